@@ -0,0 +1,326 @@
+//! `masix audit` commands and audit records for CLI-side changes.
+//!
+//! Config edits are detected by hashing the config file around the commands
+//! that may rewrite it; plugin installs are recorded by `plugins` from a diff
+//! of the installed registry.
+
+use crate::plugins::{resolve_data_dir, resolve_effective_config_path, sha256_hex};
+use crate::AuditCommands;
+use anyhow::{Context, Result};
+use masix_storage::{AuditEntry, AuditFilter, AuditRecord, Storage};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Actor recorded for changes made from the command line.
+pub(crate) fn cli_actor() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("cli:{}", user)
+}
+
+/// Append a CLI audit entry. Failures are reported but never fail the command.
+pub(crate) fn record(data_dir: &Path, kind: &str, action: &str, outcome: &str, details: Value) {
+    let appended = std::fs::create_dir_all(data_dir)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| Storage::new(data_dir.join("masix.db")))
+        .and_then(|storage| {
+            storage.append_audit(&AuditRecord {
+                kind,
+                actor: &cli_actor(),
+                account_tag: None,
+                action,
+                outcome,
+                details,
+            })
+        });
+    if let Err(e) = appended {
+        eprintln!("Warning: failed to write audit entry: {}", e);
+    }
+}
+
+/// Command line without the binary name; secrets are redacted by storage.
+pub(crate) fn command_line() -> String {
+    std::env::args().skip(1).collect::<Vec<_>>().join(" ")
+}
+
+/// `command` followed by the subcommand words given after it on the command
+/// line (`config providers add`), stopping at the first flag.
+fn subcommand_label(command: &str) -> String {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(start) = args.iter().position(|arg| arg == command) else {
+        return command.to_string();
+    };
+    args[start..]
+        .iter()
+        .take(3)
+        .take_while(|arg| !arg.starts_with('-'))
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Snapshot of the config file taken before a command that may rewrite it.
+pub(crate) struct ConfigWatch {
+    config_path: Option<String>,
+    path: Option<PathBuf>,
+    before: Option<String>,
+}
+
+impl ConfigWatch {
+    pub fn start(config_path: Option<&str>) -> Self {
+        let path = resolve_effective_config_path(config_path);
+        let before = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok());
+        Self {
+            config_path: config_path.map(|path| path.to_string()),
+            path,
+            before,
+        }
+    }
+
+    /// Record a `config_edit` entry when the file content changed. `command`
+    /// is the top-level subcommand (`config`, `plugin`, `ai`).
+    pub fn finish<T>(self, command: &str, result: &Result<T>) {
+        let Some(path) = self.path else {
+            return;
+        };
+        let after = std::fs::read_to_string(&path).ok();
+        if after == self.before {
+            return;
+        }
+        let digest = |content: &Option<String>| content.as_ref().map(sha256_hex);
+        let details = json!({
+            "path": path.display().to_string(),
+            "command": command_line(),
+            "before_sha256": digest(&self.before),
+            "after_sha256": digest(&after),
+            "changed": changed_config_paths(
+                self.before.as_deref().unwrap_or_default(),
+                after.as_deref().unwrap_or_default(),
+            ),
+        });
+        let outcome = if result.is_ok() { "ok" } else { "error" };
+        record(
+            &resolve_data_dir(self.config_path.as_deref()),
+            "config_edit",
+            &subcommand_label(command),
+            outcome,
+            details,
+        );
+    }
+}
+
+/// Dotted TOML paths whose value differs between two config files.
+/// Values are never included, only the keys.
+fn changed_config_paths(before: &str, after: &str) -> Vec<String> {
+    fn walk(
+        prefix: &str,
+        before: Option<&toml::Value>,
+        after: Option<&toml::Value>,
+    ) -> Vec<String> {
+        match (before, after) {
+            (Some(toml::Value::Table(before)), Some(toml::Value::Table(after))) => {
+                let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+                keys.into_iter()
+                    .flat_map(|key| {
+                        let path = if prefix.is_empty() {
+                            key.clone()
+                        } else {
+                            format!("{}.{}", prefix, key)
+                        };
+                        walk(&path, before.get(key), after.get(key))
+                    })
+                    .collect()
+            }
+            (before, after) if before == after => Vec::new(),
+            _ => vec![prefix.to_string()],
+        }
+    }
+
+    let parse = |content: &str| {
+        content
+            .parse::<toml::Table>()
+            .map(toml::Value::Table)
+            .unwrap_or_else(|_| toml::Value::Table(toml::Table::new()))
+    };
+    walk("", Some(&parse(before)), Some(&parse(after)))
+}
+
+pub(crate) fn handle_audit_command(
+    action: AuditCommands,
+    config_path: Option<String>,
+) -> Result<()> {
+    let data_dir = resolve_data_dir(config_path.as_deref());
+    std::fs::create_dir_all(&data_dir)?;
+    let storage = Storage::new(data_dir.join("masix.db"))?;
+
+    match action {
+        AuditCommands::List {
+            kind,
+            actor,
+            since,
+            limit,
+            json,
+        } => {
+            let entries = storage.list_audit(&AuditFilter {
+                kind,
+                actor,
+                since,
+                limit: Some(limit),
+            })?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+                return Ok(());
+            }
+            if entries.is_empty() {
+                println!("No audit entries.");
+            }
+            for entry in entries {
+                println!(
+                    "#{} {} | {} | {}{} | {} -> {}",
+                    entry.id,
+                    entry.ts,
+                    entry.kind,
+                    entry.actor,
+                    entry
+                        .account_tag
+                        .as_deref()
+                        .map(|tag| format!(" @{}", tag))
+                        .unwrap_or_default(),
+                    entry.action,
+                    entry.outcome
+                );
+            }
+        }
+        AuditCommands::Verify => {
+            let verification = storage.verify_audit_chain()?;
+            match verification.broken_at {
+                None => println!("Audit chain intact ({} entries).", verification.checked),
+                Some(id) => anyhow::bail!(
+                    "Audit chain broken at entry {} after {} valid entries: {}",
+                    id,
+                    verification.checked,
+                    verification.reason.unwrap_or_default()
+                ),
+            }
+        }
+        AuditCommands::Export {
+            format,
+            output,
+            kind,
+            since,
+        } => {
+            let entries = storage.list_audit(&AuditFilter {
+                kind,
+                since,
+                ..AuditFilter::default()
+            })?;
+            let body = match format.as_str() {
+                "jsonl" => entries
+                    .iter()
+                    .map(serde_json::to_string)
+                    .collect::<serde_json::Result<Vec<_>>>()?
+                    .join("\n"),
+                "csv" => export_csv(&entries),
+                other => anyhow::bail!("Unsupported export format '{}' (jsonl|csv)", other),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, format!("{}\n", body))
+                        .with_context(|| format!("Failed to write {}", path))?;
+                    println!("{} audit entries exported to {}", entries.len(), path);
+                }
+                None => println!("{}", body),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn export_csv(entries: &[AuditEntry]) -> String {
+    fn field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    let mut lines =
+        vec!["id,ts,kind,actor,account_tag,action,outcome,details,prev_hash,hash".to_string()];
+    for entry in entries {
+        lines.push(
+            [
+                entry.id.to_string(),
+                entry.ts.clone(),
+                entry.kind.clone(),
+                entry.actor.clone(),
+                entry.account_tag.clone().unwrap_or_default(),
+                entry.action.clone(),
+                entry.outcome.clone(),
+                entry.details.to_string(),
+                entry.prev_hash.clone(),
+                entry.hash.clone(),
+            ]
+            .iter()
+            .map(|value| field(value))
+            .collect::<Vec<_>>()
+            .join(","),
+        );
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changed_config_paths, export_csv};
+    use masix_storage::AuditEntry;
+    use serde_json::json;
+
+    #[test]
+    fn config_diff_lists_changed_keys_only() {
+        let before = r#"
+[core]
+data_dir = "~/.masix"
+[[providers.providers]]
+name = "openai"
+api_key = "sk-old"
+"#;
+        let after = r#"
+[core]
+data_dir = "~/.masix"
+global_system_prompt = "hi"
+[[providers.providers]]
+name = "openai"
+api_key = "sk-new"
+"#;
+        assert_eq!(
+            changed_config_paths(before, after),
+            vec!["core.global_system_prompt", "providers.providers"]
+        );
+        assert!(changed_config_paths(before, before).is_empty());
+    }
+
+    #[test]
+    fn csv_export_quotes_details() {
+        let entry = AuditEntry {
+            id: 1,
+            ts: "2026-01-31T10:00:00.000Z".to_string(),
+            kind: "tool_call".to_string(),
+            actor: "42".to_string(),
+            account_tag: None,
+            action: "exec".to_string(),
+            outcome: "ok".to_string(),
+            details: json!({"command": "ls, -la"}),
+            prev_hash: "0".repeat(64),
+            hash: "a".repeat(64),
+        };
+        let csv = export_csv(&[entry]);
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("1,2026-01-31T10:00:00.000Z,tool_call,42,,exec,ok,"));
+        assert!(row.contains(r#""{""command"":""ls, -la""}""#));
+    }
+}
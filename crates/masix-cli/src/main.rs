@@ -2,7 +2,9 @@
 //!
 //! Command-line interface for Masix messaging agent
 
+mod audit;
 mod logging;
+mod plugin_trust;
 mod plugins;
 
 use anyhow::{anyhow, Context, Result};
@@ -10,7 +12,8 @@ use clap::{Parser, Subcommand};
 use masix_config::Config;
 use masix_core::MasixRuntime;
 use masix_exec::{
-    is_termux_environment, manage_termux_boot, manage_termux_wake_lock, BootAction, WakeLockAction,
+    check_command, is_termux_environment, manage_termux_boot, manage_termux_wake_lock, BootAction,
+    ExecMode, WakeLockAction,
 };
 use masix_providers::{AnthropicProvider, OpenAICompatibleProvider, Provider};
 use masix_storage::Storage;
@@ -111,6 +114,7 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Start the Masix runtime (daemon mode, returns immediately)
     Start {
@@ -147,6 +151,18 @@ enum Commands {
         action: CronCommands,
     },
 
+    /// Knowledge base commands (shared_user_kb / admin_kb)
+    Kb {
+        #[command(subcommand)]
+        action: KbCommands,
+    },
+
+    /// Persisted per-user preferences (language, provider, model)
+    Prefs {
+        #[command(subcommand)]
+        action: PrefsCommands,
+    },
+
     /// Configure system startup at boot (multi-platform)
     Boot {
         #[arg(short, long)]
@@ -181,6 +197,24 @@ enum Commands {
         action: AiCommands,
     },
 
+    /// Audit log of tool calls, ACL changes, plugin installs and config edits
+    Audit {
+        #[command(subcommand)]
+        action: AuditCommands,
+    },
+
+    /// Guarded command execution policy
+    Exec {
+        #[command(subcommand)]
+        action: ExecCommands,
+    },
+
+    /// MasiX as an MCP server for other local agents
+    Mcp {
+        #[command(subcommand)]
+        action: McpRuntimeCommands,
+    },
+
     /// Show statistics
     Stats,
 
@@ -220,6 +254,12 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum McpRuntimeCommands {
+    /// Serve the builtin tools listed in [mcp.serve] over stdio (JSON-RPC on stdin/stdout)
+    Serve,
+}
+
 #[derive(Subcommand)]
 enum TestCommands {
     /// Test Telegram bot token
@@ -290,6 +330,117 @@ enum SmsCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum KbCommands {
+    /// Ingest a document or folder (PDF, Markdown, text, HTML)
+    Ingest {
+        /// File or directory (default: <data_dir>/memory/knowledge)
+        path: Option<String>,
+        /// Optional account tag scope (Telegram bot id prefix)
+        #[arg(long)]
+        account_tag: Option<String>,
+        /// Target scope: shared_user_kb | admin_kb
+        #[arg(long, default_value = "shared_user_kb")]
+        scope: String,
+    },
+    /// List indexed documents
+    List {
+        /// Optional account tag scope
+        #[arg(long)]
+        account_tag: Option<String>,
+        /// Scope: shared_user_kb | admin_kb
+        #[arg(long, default_value = "shared_user_kb")]
+        scope: String,
+    },
+    /// Search indexed documents
+    Search {
+        /// Search query
+        query: String,
+        /// Optional account tag scope
+        #[arg(long)]
+        account_tag: Option<String>,
+        /// Scope: shared_user_kb | admin_kb
+        #[arg(long, default_value = "shared_user_kb")]
+        scope: String,
+        /// Maximum number of results
+        #[arg(long, default_value_t = 5)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommands {
+    /// List audit entries (most recent last)
+    List {
+        /// Filter by kind (tool_call, acl_change, plugin, config_edit)
+        #[arg(long)]
+        kind: Option<String>,
+        /// Filter by actor (Telegram user id or cli:<user>)
+        #[arg(long)]
+        actor: Option<String>,
+        /// Only entries at or after this RFC 3339 timestamp or date (2026-01-31)
+        #[arg(long)]
+        since: Option<String>,
+        /// Maximum number of entries
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the hash chain; exits non-zero if an entry was altered or removed
+    Verify,
+    /// Export audit entries
+    Export {
+        /// Output format: jsonl|csv
+        #[arg(long, default_value = "jsonl")]
+        format: String,
+        /// Write to file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Filter by kind
+        #[arg(long)]
+        kind: Option<String>,
+        /// Only entries at or after this RFC 3339 timestamp or date
+        #[arg(long)]
+        since: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExecCommands {
+    /// Check a command against the exec allowlist and rules without running it
+    Check {
+        /// Command line, e.g. "git log --oneline -n 5"
+        command: String,
+        /// Permission level whose rules apply (admin|user|readonly)
+        #[arg(long, default_value = "user")]
+        level: String,
+        /// Check as a Termux command instead of a base command
+        #[arg(long)]
+        termux: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum PrefsCommands {
+    /// List stored preferences
+    List {
+        /// Optional account tag scope (Telegram bot id prefix)
+        #[arg(long)]
+        account_tag: Option<String>,
+    },
+    /// Export stored preferences as JSON
+    Export {
+        /// Optional account tag scope
+        #[arg(long)]
+        account_tag: Option<String>,
+        /// Write to file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 enum CronCommands {
     /// Create a new cron job
@@ -351,6 +502,7 @@ enum TermuxWakeCommands {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum ConfigCommands {
     /// Initialize configuration with interactive wizard
     Init {
@@ -524,10 +676,19 @@ enum McpCommands {
     Add {
         /// Server name
         name: String,
-        /// Command to run
-        command: String,
+        /// Command to run (omit when using --url)
+        command: Option<String>,
         /// Command arguments
         args: Vec<String>,
+        /// Remote MCP endpoint (Streamable HTTP, or legacy SSE with --sse)
+        #[arg(long)]
+        url: Option<String>,
+        /// Use the legacy HTTP+SSE transport for --url
+        #[arg(long, requires = "url")]
+        sse: bool,
+        /// Bearer token for the remote endpoint
+        #[arg(long, requires = "url")]
+        auth_token: Option<String>,
     },
     /// Remove MCP server
     Remove {
@@ -584,19 +745,32 @@ enum PluginCommands {
         /// Override platform id
         #[arg(long)]
         platform: Option<String>,
+        /// Install even if the package is unsigned or signed by an untrusted publisher
+        #[arg(long)]
+        allow_unsigned: bool,
+        /// Pin the installed version so `plugin update` skips it
+        #[arg(long)]
+        pin: bool,
     },
     /// Install a plugin package from local .pkg file (no server required)
     InstallFile {
         /// Path to local plugin .pkg file
+        #[arg(
+            long,
+            required_unless_present = "manifest",
+            conflicts_with = "manifest"
+        )]
+        file: Option<String>,
+        /// Package manifest.json (or its directory); picks the artifact for this platform
         #[arg(long)]
-        file: String,
-        /// Plugin id (e.g. discovery)
-        #[arg(long)]
-        plugin: String,
-        /// Installed version label (default: local)
+        manifest: Option<String>,
+        /// Plugin id (e.g. discovery; default: manifest plugin_id)
+        #[arg(long, required_unless_present = "manifest")]
+        plugin: Option<String>,
+        /// Installed version label (default: sibling manifest.json version, else local)
         #[arg(short, long)]
         version: Option<String>,
-        /// Package type (default: mcp_binary)
+        /// Package type (default: sibling manifest.json package_type, else mcp_binary)
         #[arg(long)]
         package_type: Option<String>,
         /// Optional entrypoint metadata
@@ -605,6 +779,23 @@ enum PluginCommands {
         /// Override platform id
         #[arg(long)]
         platform: Option<String>,
+        /// Install even if the package is unsigned or signed by an untrusted publisher
+        #[arg(long)]
+        allow_unsigned: bool,
+        /// Pin the installed version so `plugin update` skips it
+        #[arg(long)]
+        pin: bool,
+    },
+    /// Install the latest version of every package manifest under a directory
+    InstallDir {
+        /// Package root (e.g. packages/free)
+        dir: String,
+        /// Override platform id
+        #[arg(long)]
+        platform: Option<String>,
+        /// Install even if a package is unsigned or signed by an untrusted publisher
+        #[arg(long)]
+        allow_unsigned: bool,
     },
     /// Update installed plugins from the catalog
     Update {
@@ -619,6 +810,49 @@ enum PluginCommands {
         /// Override platform id
         #[arg(long)]
         platform: Option<String>,
+        /// Install even if the package is unsigned or signed by an untrusted publisher
+        #[arg(long)]
+        allow_unsigned: bool,
+    },
+    /// Sign every platform package of a manifest.json with a publisher key
+    Sign {
+        /// Path to the package manifest.json (packages must sit next to it)
+        #[arg(long)]
+        manifest: String,
+        /// File holding the base64 Ed25519 seed (e.g. `openssl rand -base64 32`)
+        #[arg(long)]
+        key_file: String,
+        /// Publisher id pinned by users in [plugins].trusted_publishers
+        #[arg(long)]
+        publisher: String,
+    },
+    /// Remove an installed plugin, its package files and generated MCP server
+    Uninstall {
+        /// Plugin id
+        plugin: String,
+    },
+    /// Switch back to a previously installed version (pins the plugin)
+    Rollback {
+        /// Plugin id
+        plugin: String,
+        /// Version to restore (default: the previous one)
+        version: Option<String>,
+    },
+    /// Exempt an installed plugin from `plugin update`
+    Pin {
+        /// Plugin id
+        plugin: String,
+    },
+    /// Let `plugin update` upgrade a pinned plugin again
+    Unpin {
+        /// Plugin id
+        plugin: String,
+    },
+    /// Delete package versions no longer needed for rollback
+    Gc {
+        /// Previous versions to keep per plugin for rollback
+        #[arg(long, default_value = "1")]
+        keep: usize,
     },
     /// Enable an installed plugin in local registry
     Enable {
@@ -838,6 +1072,37 @@ async fn main() -> Result<()> {
                     }
                     let log_manager = logging::LogManager::new(data_dir.join("logs"));
                     println!("Log: {}", log_manager.get_current_log_path().display());
+                    if let Some(snapshot) = MasixRuntime::load_mcp_status(&data_dir) {
+                        if !snapshot.servers.is_empty() {
+                            println!("MCP servers (as of {}):", snapshot.updated_at);
+                        }
+                        for server in &snapshot.servers {
+                            let pid = server
+                                .pid
+                                .map(|pid| format!(", pid {}", pid))
+                                .unwrap_or_default();
+                            println!(
+                                "  {} — {} (restarts {}{})",
+                                server.name,
+                                server.state.as_str(),
+                                server.restart_count,
+                                pid
+                            );
+                            if let Some(exit) = &server.last_exit {
+                                println!("    last exit: {}", exit);
+                            }
+                            let skip = server.last_stderr.len().saturating_sub(5);
+                            for line in server.last_stderr.iter().skip(skip) {
+                                println!("    stderr: {}", line);
+                            }
+                        }
+                        if !snapshot.tool_validation_failures.is_empty() {
+                            println!("Tool argument validation failures:");
+                        }
+                        for (tool, count) in &snapshot.tool_validation_failures {
+                            println!("  {}: {}", tool, count);
+                        }
+                    }
                 }
                 None => {
                     println!("Masix is not running");
@@ -1103,6 +1368,158 @@ async fn main() -> Result<()> {
             }
         }
 
+        Commands::Kb { action } => {
+            let config = load_config(cli.config)?;
+            let data_dir = get_data_dir(&config);
+            std::fs::create_dir_all(&data_dir)?;
+            let default_account_tag = default_telegram_account_tag(&config);
+            let storage = Storage::new(data_dir.join("masix.db"))?;
+            let runtime = MasixRuntime::new(config, storage)?;
+
+            match action {
+                KbCommands::Ingest {
+                    path,
+                    account_tag,
+                    scope,
+                } => {
+                    let account_tag = account_tag.or(default_account_tag);
+                    let root = match path {
+                        Some(path) => PathBuf::from(path),
+                        None => runtime.default_knowledge_root()?,
+                    };
+                    if !root.exists() {
+                        std::fs::create_dir_all(&root)?;
+                        println!(
+                            "Created {} - drop PDF/Markdown/text/HTML files there and rerun.",
+                            root.display()
+                        );
+                        return Ok(());
+                    }
+                    println!(
+                        "Ingesting {} into {} (account: {})",
+                        root.display(),
+                        scope,
+                        account_tag.as_deref().unwrap_or("__default__")
+                    );
+                    let results =
+                        runtime.ingest_knowledge_path(&root, account_tag.as_deref(), &scope)?;
+                    if results.is_empty() {
+                        println!("No supported documents found.");
+                    }
+                    let mut failed = 0usize;
+                    for (file, outcome) in results {
+                        match outcome {
+                            Ok(masix_core::knowledge::IngestOutcome::Added(doc)) => println!(
+                                "  + {} [{}] {} chunks",
+                                file.display(),
+                                doc.id,
+                                doc.chunks
+                            ),
+                            Ok(masix_core::knowledge::IngestOutcome::Unchanged(doc)) => {
+                                println!("  = {} [{}] already indexed", file.display(), doc.id)
+                            }
+                            Err(e) => {
+                                failed += 1;
+                                eprintln!("  ! {}: {}", file.display(), e);
+                            }
+                        }
+                    }
+                    if failed > 0 {
+                        anyhow::bail!("{} document(s) failed to ingest", failed);
+                    }
+                }
+                KbCommands::List { account_tag, scope } => {
+                    let account_tag = account_tag.or(default_account_tag);
+                    let docs = runtime.list_knowledge_documents(account_tag.as_deref(), &scope)?;
+                    if docs.is_empty() {
+                        println!("No documents indexed in {}.", scope);
+                    }
+                    for doc in docs {
+                        println!(
+                            "{} | {} | {} | {} chunks | {}",
+                            doc.id,
+                            doc.title,
+                            doc.format.as_str(),
+                            doc.chunks,
+                            doc.ingested_at
+                        );
+                        println!("  Source: {}", doc.source);
+                    }
+                }
+                KbCommands::Search {
+                    query,
+                    account_tag,
+                    scope,
+                    limit,
+                } => {
+                    let account_tag = account_tag.or(default_account_tag);
+                    let hits =
+                        runtime.search_knowledge(account_tag.as_deref(), &scope, &query, limit)?;
+                    if hits.is_empty() {
+                        println!("No results for '{}'.", query);
+                    }
+                    for hit in hits {
+                        println!(
+                            "{} #{} | {} | score {:.2}",
+                            hit.doc_id, hit.chunk_index, hit.title, hit.score
+                        );
+                        println!("  {}", hit.text.chars().take(300).collect::<String>());
+                        println!();
+                    }
+                }
+            }
+        }
+
+        Commands::Prefs { action } => {
+            let config = load_config(cli.config)?;
+            let data_dir = get_data_dir(&config);
+            let storage = Storage::new(data_dir.join("masix.db"))?;
+            let scoped = |account_tag: Option<String>| -> Result<Vec<_>> {
+                let prefix = account_tag.map(|tag| format!("{}::", tag));
+                Ok(storage
+                    .list_user_preferences()?
+                    .into_iter()
+                    .filter(|row| {
+                        prefix
+                            .as_deref()
+                            .is_none_or(|prefix| row.state_key.starts_with(prefix))
+                    })
+                    .collect())
+            };
+
+            match action {
+                PrefsCommands::List { account_tag } => {
+                    let rows = scoped(account_tag)?;
+                    if rows.is_empty() {
+                        println!("No stored user preferences.");
+                    }
+                    for row in rows {
+                        println!(
+                            "{} | language: {} | provider: {} | model: {} | updated: {}",
+                            row.state_key,
+                            row.language.as_deref().unwrap_or("-"),
+                            row.provider.as_deref().unwrap_or("-"),
+                            row.model.as_deref().unwrap_or("-"),
+                            row.updated_at
+                        );
+                    }
+                }
+                PrefsCommands::Export {
+                    account_tag,
+                    output,
+                } => {
+                    let body = serde_json::to_string_pretty(&scoped(account_tag)?)?;
+                    match output {
+                        Some(path) => {
+                            std::fs::write(&path, body)?;
+                            println!("Preferences exported to {}", path);
+                        }
+                        None => println!("{}", body),
+                    }
+                }
+            }
+        }
+
         Commands::Boot {
             enable,
             disable,
@@ -1186,144 +1603,48 @@ async fn main() -> Result<()> {
             }
         },
 
-        Commands::Config { action } => match action {
-            ConfigCommands::Init { defaults } => {
-                if defaults {
-                    create_default_config(cli.config.clone())?;
-                } else {
-                    run_config_wizard(cli.config.clone())?;
-                }
-            }
-            ConfigCommands::Show => match load_config(cli.config) {
-                Ok(config) => {
-                    println!("Current configuration:");
-                    print_redacted_config(&config)?;
-                }
-                Err(e) => eprintln!("Error loading config: {}", e),
-            },
-            ConfigCommands::Validate => match load_config(cli.config) {
-                Ok(_) => println!("Configuration is valid."),
-                Err(e) => eprintln!("Configuration is invalid: {}", e),
-            },
-            ConfigCommands::Telegram {
-                list,
-                account,
-                set_token,
-                bot_name,
-                bot_profile,
-                admins,
-                add_admins,
-                remove_admins,
-                users,
-                add_users,
-                remove_users,
-                readonly,
-                add_readonly,
-                remove_readonly,
-                allowed_chats,
-                group_mode,
-                access_mode,
-                auto_register_users,
-                notify_admin_on_new_user,
-                new_user_welcome_message,
-                start_welcome_admin,
-                start_welcome_user,
-                user_tools_mode,
-                user_allowed_tools,
-                isolated,
-                allow_self_memory_edit,
-                register_to_file,
-            } => {
-                let direct_mode = set_token.is_some()
-                    || bot_name.is_some()
-                    || bot_profile.is_some()
-                    || admins.is_some()
-                    || add_admins.is_some()
-                    || remove_admins.is_some()
-                    || users.is_some()
-                    || add_users.is_some()
-                    || remove_users.is_some()
-                    || readonly.is_some()
-                    || add_readonly.is_some()
-                    || remove_readonly.is_some()
-                    || allowed_chats.is_some()
-                    || group_mode.is_some()
-                    || access_mode.is_some()
-                    || auto_register_users.is_some()
-                    || notify_admin_on_new_user.is_some()
-                    || new_user_welcome_message.is_some()
-                    || start_welcome_admin.is_some()
-                    || start_welcome_user.is_some()
-                    || user_tools_mode.is_some()
-                    || user_allowed_tools.is_some()
-                    || isolated.is_some()
-                    || allow_self_memory_edit.is_some()
-                    || register_to_file.is_some()
-                    || account.is_some();
-                if list {
-                    run_telegram_list(cli.config.clone())?;
-                } else if direct_mode {
-                    run_telegram_non_interactive_update(
-                        cli.config.clone(),
-                        TelegramDirectUpdate {
-                            account,
-                            set_token,
-                            bot_name,
-                            bot_profile,
-                            admins,
-                            add_admins,
-                            remove_admins,
-                            users,
-                            add_users,
-                            remove_users,
-                            readonly,
-                            add_readonly,
-                            remove_readonly,
-                            allowed_chats,
-                            group_mode,
-                            access_mode,
-                            auto_register_users,
-                            notify_admin_on_new_user,
-                            new_user_welcome_message,
-                            start_welcome_admin,
-                            start_welcome_user,
-                            user_tools_mode,
-                            user_allowed_tools,
-                            isolated,
-                            allow_self_memory_edit,
-                            register_to_file,
-                        },
-                    )?;
-                } else {
-                    run_telegram_wizard(cli.config.clone())?;
-                }
-            }
-            #[cfg(feature = "sms")]
-            ConfigCommands::Sms => {
-                run_sms_wizard(cli.config.clone())?;
-            }
-            ConfigCommands::Stt => {
-                run_stt_wizard(cli.config.clone())?;
-            }
-            ConfigCommands::Provider { name } => {
-                run_provider_wizard(cli.config.clone(), name)?;
-            }
-            ConfigCommands::Providers { action } => {
-                handle_provider_command(action, cli.config.clone())?;
-            }
-            ConfigCommands::Mcp { action } => {
-                handle_mcp_command(action, cli.config.clone())?;
-            }
-        },
+        Commands::Config { action } => {
+            let config_watch = audit::ConfigWatch::start(cli.config.as_deref());
+            let result = handle_config_command(action, cli.config.clone());
+            config_watch.finish("config", &result);
+            result?;
+        }
 
         Commands::Plugin { action } => {
             plugins::handle_plugin_command(action, cli.config.clone()).await?;
         }
 
         Commands::Ai { action } => {
-            handle_ai_command(action, cli.config.clone()).await?;
+            let config_watch = audit::ConfigWatch::start(cli.config.as_deref());
+            let result = handle_ai_command(action, cli.config.clone()).await;
+            config_watch.finish("ai", &result);
+            result?;
         }
 
+        Commands::Audit { action } => {
+            audit::handle_audit_command(action, cli.config.clone())?;
+        }
+
+        Commands::Exec { action } => {
+            let exit_code = handle_exec_command(action, cli.config.clone())?;
+            std::process::exit(exit_code);
+        }
+
+        Commands::Mcp { action } => match action {
+            McpRuntimeCommands::Serve => {
+                let config = load_config(cli.config)?;
+                let data_dir = get_data_dir(&config);
+                std::fs::create_dir_all(&data_dir)?;
+                let log_dir = data_dir.join("logs");
+                std::fs::create_dir_all(&log_dir)?;
+                // stdout carries the protocol; logs go to the file and stderr only.
+                let _logging_guard = logging::init_logging(&log_dir, &cli.log_level)?;
+                let storage = Storage::new(data_dir.join("masix.db"))?;
+                let runtime = MasixRuntime::new(config, storage)?;
+                runtime.serve_mcp().await?;
+            }
+        },
+
         Commands::Stats => {
             println!("Masix Statistics");
             println!("================");
@@ -1502,6 +1823,224 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+fn handle_config_command(action: ConfigCommands, config_path: Option<String>) -> Result<()> {
+    match action {
+        ConfigCommands::Init { defaults } => {
+            if defaults {
+                create_default_config(config_path.clone())?;
+            } else {
+                run_config_wizard(config_path.clone())?;
+            }
+        }
+        ConfigCommands::Show => match load_config(config_path) {
+            Ok(config) => {
+                println!("Current configuration:");
+                print_redacted_config(&config)?;
+            }
+            Err(e) => eprintln!("Error loading config: {}", e),
+        },
+        ConfigCommands::Validate => match load_config(config_path) {
+            Ok(config) => {
+                println!("Configuration is valid.");
+                for warning in config
+                    .telegram
+                    .as_ref()
+                    .map(|telegram| telegram.legacy_memory_share_warnings())
+                    .unwrap_or_default()
+                {
+                    println!("Warning: {}", warning);
+                }
+            }
+            Err(e) => eprintln!("Configuration is invalid: {}", e),
+        },
+        ConfigCommands::Telegram {
+            list,
+            account,
+            set_token,
+            bot_name,
+            bot_profile,
+            admins,
+            add_admins,
+            remove_admins,
+            users,
+            add_users,
+            remove_users,
+            readonly,
+            add_readonly,
+            remove_readonly,
+            allowed_chats,
+            group_mode,
+            access_mode,
+            auto_register_users,
+            notify_admin_on_new_user,
+            new_user_welcome_message,
+            start_welcome_admin,
+            start_welcome_user,
+            user_tools_mode,
+            user_allowed_tools,
+            isolated,
+            allow_self_memory_edit,
+            register_to_file,
+        } => {
+            let direct_mode = set_token.is_some()
+                || bot_name.is_some()
+                || bot_profile.is_some()
+                || admins.is_some()
+                || add_admins.is_some()
+                || remove_admins.is_some()
+                || users.is_some()
+                || add_users.is_some()
+                || remove_users.is_some()
+                || readonly.is_some()
+                || add_readonly.is_some()
+                || remove_readonly.is_some()
+                || allowed_chats.is_some()
+                || group_mode.is_some()
+                || access_mode.is_some()
+                || auto_register_users.is_some()
+                || notify_admin_on_new_user.is_some()
+                || new_user_welcome_message.is_some()
+                || start_welcome_admin.is_some()
+                || start_welcome_user.is_some()
+                || user_tools_mode.is_some()
+                || user_allowed_tools.is_some()
+                || isolated.is_some()
+                || allow_self_memory_edit.is_some()
+                || register_to_file.is_some()
+                || account.is_some();
+            if list {
+                run_telegram_list(config_path.clone())?;
+            } else if direct_mode {
+                run_telegram_non_interactive_update(
+                    config_path.clone(),
+                    TelegramDirectUpdate {
+                        account,
+                        set_token,
+                        bot_name,
+                        bot_profile,
+                        admins,
+                        add_admins,
+                        remove_admins,
+                        users,
+                        add_users,
+                        remove_users,
+                        readonly,
+                        add_readonly,
+                        remove_readonly,
+                        allowed_chats,
+                        group_mode,
+                        access_mode,
+                        auto_register_users,
+                        notify_admin_on_new_user,
+                        new_user_welcome_message,
+                        start_welcome_admin,
+                        start_welcome_user,
+                        user_tools_mode,
+                        user_allowed_tools,
+                        isolated,
+                        allow_self_memory_edit,
+                        register_to_file,
+                    },
+                )?;
+            } else {
+                run_telegram_wizard(config_path.clone())?;
+            }
+        }
+        #[cfg(feature = "sms")]
+        ConfigCommands::Sms => {
+            run_sms_wizard(config_path.clone())?;
+        }
+        ConfigCommands::Stt => {
+            run_stt_wizard(config_path.clone())?;
+        }
+        ConfigCommands::Provider { name } => {
+            run_provider_wizard(config_path.clone(), name)?;
+        }
+        ConfigCommands::Providers { action } => {
+            handle_provider_command(action, config_path.clone())?;
+        }
+        ConfigCommands::Mcp { action } => {
+            handle_mcp_command(action, config_path.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the process exit code: 1 when the command would be refused.
+fn handle_exec_command(action: ExecCommands, config_path: Option<String>) -> Result<i32> {
+    match action {
+        ExecCommands::Check {
+            command,
+            level,
+            termux,
+        } => {
+            if !matches!(level.as_str(), "admin" | "user" | "readonly") {
+                anyhow::bail!("Unknown level '{}' (admin|user|readonly)", level);
+            }
+            let config_path = config_path_for_diagnostics(config_path);
+            let config = if config_path.exists() {
+                Config::load(&config_path)?
+            } else {
+                Config::default()
+            };
+            config.validate()?;
+            let policy = MasixRuntime::exec_policy_from_config(config.exec.as_ref());
+            let mode = if termux {
+                ExecMode::Termux
+            } else {
+                ExecMode::Base
+            };
+
+            println!("Command: {}", command);
+            println!(
+                "Mode: {}{}, level: {}",
+                if termux { "termux" } else { "base" },
+                if !termux && policy.pipeline.enabled {
+                    " (pipeline)"
+                } else {
+                    ""
+                },
+                level
+            );
+            if !termux && policy.rules.is_empty() {
+                println!("No exec rules configured; the program allowlist applies.");
+            }
+            let verdicts = match check_command(&policy, mode, &command, Some(&level)) {
+                Ok(verdicts) => verdicts,
+                Err(e) => {
+                    println!("  ✗ REFUSED: {}", e);
+                    return Ok(1);
+                }
+            };
+            for verdict in &verdicts {
+                if verdict.allowed {
+                    println!("  ✓ ALLOWED: {} ({})", verdict.command, verdict.reason);
+                } else {
+                    println!("  ✗ DENIED: {}", verdict.reason);
+                }
+            }
+
+            let (mode_label, flag, allowed_mode) = if termux {
+                ("Termux", "allow_termux", policy.allow_termux)
+            } else {
+                ("base", "allow_base", policy.allow_base)
+            };
+            let enabled = policy.enabled && allowed_mode;
+            if !enabled {
+                println!(
+                    "Note: {} commands are disabled (exec.enabled={}, exec.{}={}).",
+                    mode_label, policy.enabled, flag, allowed_mode
+                );
+            }
+            Ok(if enabled && verdicts.iter().all(|v| v.allowed) {
+                0
+            } else {
+                1
+            })
+        }
+    }
+}
+
 fn load_config(config_path: Option<String>) -> Result<Config> {
     if let Some(path) = config_path {
         Ok(Config::load(&path)?)
@@ -2840,6 +3379,7 @@ fn run_config_wizard(config_path: Option<String>) -> Result<()> {
                     readonly: vec![],
                     isolated: true,
                     shared_memory_with: vec![],
+                    memory_shares: vec![],
                     allow_self_memory_edit: true,
                     dm_policy: masix_config::DmPolicy::Allowlist,
                     dm_allow_from: vec![],
@@ -2970,6 +3510,7 @@ fn run_config_wizard(config_path: Option<String>) -> Result<()> {
                 base_url: Some(resolved_base_url),
                 model,
                 provider_type: Some(provider_type.to_string()),
+                supports_images: false,
             };
             let (replaced, stored_name) = upsert_provider(&mut config, provider);
             config.providers.default_provider = stored_name.clone();
@@ -3119,6 +3660,7 @@ fn run_telegram_non_interactive_update(
                 readonly: Vec::new(),
                 isolated: true,
                 shared_memory_with: Vec::new(),
+                memory_shares: Vec::new(),
                 allow_self_memory_edit: true,
                 dm_policy: masix_config::DmPolicy::Allowlist,
                 dm_allow_from: Vec::new(),
@@ -4268,6 +4810,7 @@ fn configure_custom_endpoint(config: &mut Config) -> Result<()> {
             base_url: Some(normalized_url.to_string()),
             model: None,
             provider_type: Some("openai".to_string()),
+            supports_images: false,
         };
         config.providers.providers.push(provider);
         println!("✓ Provider '{}' added", provider_id);
@@ -4959,6 +5502,7 @@ fn run_telegram_wizard(config_path: Option<String>) -> Result<()> {
         readonly: vec![],
         isolated: true,
         shared_memory_with: vec![],
+        memory_shares: vec![],
         allow_self_memory_edit: true,
         dm_policy: if auto_register {
             masix_config::DmPolicy::Pairing
@@ -5127,6 +5671,7 @@ fn run_provider_wizard(config_path: Option<String>, name: Option<String>) -> Res
         base_url: Some(resolved_base_url),
         model,
         provider_type: Some(provider_type.to_string()),
+        supports_images: false,
     };
 
     let (replaced, stored_name) = upsert_provider(&mut config, provider);
@@ -5364,6 +5909,7 @@ fn handle_provider_command(action: ProviderCommands, config_path: Option<String>
                 base_url,
                 model,
                 provider_type,
+                supports_images: false,
             };
 
             let (replaced, stored_name) = upsert_provider(&mut config, provider);
@@ -5491,7 +6037,10 @@ fn upsert_provider(config: &mut Config, provider: masix_config::ProviderConfig)
         .iter_mut()
         .find(|p| p.name == provider.name)
     {
+        // Wizards never ask about image input; keep a manually enabled flag.
+        let supports_images = existing.supports_images || provider.supports_images;
         *existing = provider;
+        existing.supports_images = supports_images;
         return (true, existing.name.clone());
     }
 
@@ -5729,6 +6278,7 @@ fn configure_known_provider_interactive(
         base_url: Some(resolved_base_url),
         model: Some(model),
         provider_type: Some((*provider_type).to_string()),
+        supports_images: false,
     };
     let (replaced, stored_name) = upsert_provider(config, provider);
     if replaced {
@@ -6486,7 +7036,13 @@ fn handle_mcp_command(action: McpCommands, config_path: Option<String>) -> Resul
                 println!("Configured MCP servers:\n");
                 for server in &mcp.servers {
                     println!("  {}", server.name);
-                    println!("    Command: {} {:?}", server.command, server.args);
+                    println!("    Endpoint: {}", server.endpoint_label());
+                    if let Some(sandbox) = &server.sandbox {
+                        println!(
+                            "    Sandbox: {}",
+                            MasixRuntime::mcp_sandbox_from_config(sandbox).summary()
+                        );
+                    }
                 }
             }
         }
@@ -6494,17 +7050,34 @@ fn handle_mcp_command(action: McpCommands, config_path: Option<String>) -> Resul
             name,
             command,
             args,
+            url,
+            sse,
+            auth_token,
         } => {
+            if url.is_some() == command.is_some() {
+                anyhow::bail!("Provide either <command> or --url");
+            }
             let mcp = config.mcp.get_or_insert_with(Default::default);
             mcp.enabled = true;
             mcp.servers.push(masix_config::McpServer {
                 name: name.clone(),
-                command,
+                command: command.unwrap_or_default(),
                 args,
                 env: std::collections::HashMap::new(),
+                transport: sse.then_some(masix_config::McpTransport::Sse),
+                url,
+                headers: HashMap::new(),
+                auth_token,
                 timeout_secs: 30,
+                tool_timeouts: HashMap::new(),
+                max_in_flight: None,
                 startup_timeout_secs: 20,
                 healthcheck_interval_secs: 60,
+                tool_cache_ttl_secs: 300,
+                max_restarts: 5,
+                restart_backoff_secs: 1,
+                restart_backoff_max_secs: 60,
+                sandbox: None,
             });
             let config_toml = toml::to_string_pretty(&config)?;
             fs::write(&config_path, config_toml)?;
@@ -7032,11 +7605,17 @@ async fn run_doctor(
             println!("✓ MCP: enabled ({} server(s))", mcp.servers.len());
             for server in &mcp.servers {
                 println!(
-                    "  - {} | timeout={}s startup={}s health={}s",
+                    "  - {} | timeout={}s startup={}s health={}s tool_cache={}s sandbox={}",
                     server.name,
                     server.timeout_secs,
                     server.startup_timeout_secs,
-                    server.healthcheck_interval_secs
+                    server.healthcheck_interval_secs,
+                    server.tool_cache_ttl_secs,
+                    server
+                        .sandbox
+                        .as_ref()
+                        .map(|sandbox| MasixRuntime::mcp_sandbox_from_config(sandbox).summary())
+                        .unwrap_or_else(|| "none".to_string())
                 );
             }
         } else {
@@ -7209,6 +7788,7 @@ mod tests {
             base_url: Some(base_url.to_string()),
             model: Some(model.to_string()),
             provider_type: Some(provider_type.to_string()),
+            supports_images: false,
         }
     }
 
@@ -7227,6 +7807,7 @@ mod tests {
             readonly: vec![],
             isolated: true,
             shared_memory_with: vec![],
+            memory_shares: vec![],
             allow_self_memory_edit: true,
             dm_policy: masix_config::DmPolicy::Allowlist,
             dm_allow_from: vec![],
@@ -0,0 +1,264 @@
+//! Publisher signatures for plugin packages.
+//!
+//! A package signature is a detached Ed25519 signature over a canonical
+//! statement that binds the manifest fields (id, version, platform, package
+//! type) to the SHA-256 of the artifact. Signatures travel as
+//! `ed25519:<publisher_id>:<base64 signature>` in the catalog entry or in the
+//! per-platform entry of a package `manifest.json`.
+
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use masix_config::PluginsConfig;
+
+const SIGNATURE_SCHEME: &str = "ed25519";
+const STATEMENT_HEADER: &str = "masix-plugin-signature-v1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageStatement<'a> {
+    pub plugin_id: &'a str,
+    pub version: &'a str,
+    pub platform: &'a str,
+    pub package_type: &'a str,
+    pub sha256: &'a str,
+}
+
+impl PackageStatement<'_> {
+    pub fn canonical(&self) -> String {
+        format!(
+            "{}\nplugin_id={}\nversion={}\nplatform={}\npackage_type={}\nsha256={}\n",
+            STATEMENT_HEADER,
+            self.plugin_id,
+            self.version,
+            self.platform,
+            self.package_type,
+            self.sha256.to_ascii_lowercase()
+        )
+    }
+}
+
+pub struct PluginTrust {
+    require_signatures: bool,
+    publishers: Vec<(String, VerifyingKey)>,
+}
+
+impl PluginTrust {
+    /// `allow_unsigned` is the `--allow-unsigned` CLI override; it never
+    /// accepts a signature that fails verification against a pinned key.
+    pub fn from_config(config: Option<&PluginsConfig>, allow_unsigned: bool) -> Result<Self> {
+        let defaults = PluginsConfig::default();
+        let config = config.unwrap_or(&defaults);
+        let mut publishers = Vec::with_capacity(config.trusted_publishers.len());
+        for publisher in &config.trusted_publishers {
+            let key = parse_verifying_key(&publisher.public_key).with_context(|| {
+                format!(
+                    "Invalid public_key for trusted plugin publisher '{}'",
+                    publisher.id
+                )
+            })?;
+            publishers.push((publisher.id.trim().to_string(), key));
+        }
+        Ok(Self {
+            require_signatures: config.require_signatures && !allow_unsigned,
+            publishers,
+        })
+    }
+
+    /// Returns the trusted publisher id that signed the package, or `None`
+    /// when an unsigned/untrusted package is accepted because signatures are
+    /// not required.
+    pub fn verify(
+        &self,
+        statement: &PackageStatement<'_>,
+        signature: Option<&str>,
+    ) -> Result<Option<String>> {
+        let Some(signature) = signature.map(str::trim).filter(|s| !s.is_empty()) else {
+            return self.accept_unverified(statement, "is not signed");
+        };
+        let (publisher_id, signature) = parse_signature(signature)
+            .with_context(|| format!("Malformed signature for plugin '{}'", statement.plugin_id))?;
+        let Some((_, key)) = self.publishers.iter().find(|(id, _)| id == publisher_id) else {
+            return self.accept_unverified(
+                statement,
+                &format!("is signed by untrusted publisher '{}'", publisher_id),
+            );
+        };
+        key.verify(statement.canonical().as_bytes(), &signature)
+            .map_err(|_| {
+                anyhow!(
+                    "Signature verification failed for plugin '{}' {} ({}): package or manifest was modified after signing by '{}'",
+                    statement.plugin_id,
+                    statement.version,
+                    statement.platform,
+                    publisher_id
+                )
+            })?;
+        Ok(Some(publisher_id.to_string()))
+    }
+
+    fn accept_unverified(
+        &self,
+        statement: &PackageStatement<'_>,
+        reason: &str,
+    ) -> Result<Option<String>> {
+        if self.require_signatures {
+            anyhow::bail!(
+                "Plugin '{}' {} {}. Pin the publisher key in [plugins].trusted_publishers or re-run with --allow-unsigned.",
+                statement.plugin_id,
+                statement.version,
+                reason
+            );
+        }
+        eprintln!(
+            "Warning: plugin '{}' {} {}; installing without signature verification.",
+            statement.plugin_id, statement.version, reason
+        );
+        Ok(None)
+    }
+}
+
+pub fn sign_statement(
+    signing_key: &SigningKey,
+    publisher_id: &str,
+    statement: &PackageStatement<'_>,
+) -> String {
+    let signature = signing_key.sign(statement.canonical().as_bytes());
+    format!(
+        "{}:{}:{}",
+        SIGNATURE_SCHEME,
+        publisher_id,
+        BASE64.encode(signature.to_bytes())
+    )
+}
+
+/// Signing keys are stored as the base64 encoding of the 32-byte seed
+/// (e.g. `openssl rand -base64 32`).
+pub fn parse_signing_key(encoded: &str) -> Result<SigningKey> {
+    let seed: [u8; 32] = decode_fixed(encoded, "signing key")?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+pub fn encode_verifying_key(key: &VerifyingKey) -> String {
+    BASE64.encode(key.to_bytes())
+}
+
+fn parse_verifying_key(encoded: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = decode_fixed(encoded, "public key")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| anyhow!("Invalid Ed25519 public key: {}", e))
+}
+
+fn parse_signature(value: &str) -> Result<(&str, Signature)> {
+    let mut parts = value.splitn(3, ':');
+    let scheme = parts.next().unwrap_or_default();
+    let publisher_id = parts.next().unwrap_or_default().trim();
+    let encoded = parts.next().unwrap_or_default();
+    if scheme != SIGNATURE_SCHEME {
+        anyhow::bail!(
+            "unsupported signature scheme '{}' (expected '{}')",
+            scheme,
+            SIGNATURE_SCHEME
+        );
+    }
+    if publisher_id.is_empty() {
+        anyhow::bail!("signature is missing the publisher id");
+    }
+    let bytes: [u8; 64] = decode_fixed(encoded, "signature")?;
+    Ok((publisher_id, Signature::from_bytes(&bytes)))
+}
+
+fn decode_fixed<const N: usize>(encoded: &str, what: &str) -> Result<[u8; N]> {
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| anyhow!("{} is not valid base64: {}", what, e))?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("{} must be {} bytes, got {}", what, N, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masix_config::PluginPublisherKey;
+
+    fn statement(sha256: &str) -> PackageStatement<'_> {
+        PackageStatement {
+            plugin_id: "discovery",
+            version: "0.2.4",
+            platform: "linux-x86_64",
+            package_type: "mcp_binary",
+            sha256,
+        }
+    }
+
+    fn trust_for(key: &SigningKey, require_signatures: bool, allow_unsigned: bool) -> PluginTrust {
+        let config = PluginsConfig {
+            require_signatures,
+            trusted_publishers: vec![PluginPublisherKey {
+                id: "masix".to_string(),
+                public_key: encode_verifying_key(&key.verifying_key()),
+            }],
+            ..PluginsConfig::default()
+        };
+        PluginTrust::from_config(Some(&config), allow_unsigned).unwrap()
+    }
+
+    #[test]
+    fn signatures_bind_the_manifest_fields_and_artifact_hash() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let trust = trust_for(&key, true, false);
+        let signature = sign_statement(&key, "masix", &statement("ab12"));
+
+        assert_eq!(
+            trust
+                .verify(&statement("AB12"), Some(&signature))
+                .unwrap()
+                .as_deref(),
+            Some("masix")
+        );
+        assert!(trust.verify(&statement("ab13"), Some(&signature)).is_err());
+        let mut other_version = statement("ab12");
+        other_version.version = "0.2.5";
+        assert!(trust.verify(&other_version, Some(&signature)).is_err());
+    }
+
+    #[test]
+    fn unsigned_and_untrusted_packages_need_an_explicit_override() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let stranger = SigningKey::from_bytes(&[9u8; 32]);
+        let untrusted = sign_statement(&stranger, "someone", &statement("ab12"));
+        let forged = sign_statement(&stranger, "masix", &statement("ab12"));
+
+        let strict = trust_for(&key, true, false);
+        assert!(strict.verify(&statement("ab12"), None).is_err());
+        assert!(strict.verify(&statement("ab12"), Some(&untrusted)).is_err());
+
+        let overridden = trust_for(&key, true, true);
+        assert_eq!(overridden.verify(&statement("ab12"), None).unwrap(), None);
+        assert_eq!(
+            overridden
+                .verify(&statement("ab12"), Some(&untrusted))
+                .unwrap(),
+            None
+        );
+        // A bad signature under a pinned publisher id is never overridable.
+        assert!(overridden
+            .verify(&statement("ab12"), Some(&forged))
+            .is_err());
+
+        let relaxed = trust_for(&key, false, false);
+        assert_eq!(relaxed.verify(&statement("ab12"), None).unwrap(), None);
+        assert!(relaxed.verify(&statement("ab12"), Some("rsa:x:y")).is_err());
+    }
+
+    #[test]
+    fn signing_key_seed_round_trips_through_base64() {
+        let key = parse_signing_key(&BASE64.encode([7u8; 32])).unwrap();
+        assert_eq!(
+            key.verifying_key(),
+            SigningKey::from_bytes(&[7u8; 32]).verifying_key()
+        );
+        assert!(parse_signing_key("c2hvcnQ=").is_err());
+    }
+}
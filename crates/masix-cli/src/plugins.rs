@@ -1,6 +1,8 @@
+use crate::audit;
+use crate::plugin_trust::{self, PackageStatement, PluginTrust};
 use crate::PluginCommands;
 use anyhow::{anyhow, Context, Result};
-use masix_config::{Config, McpServer};
+use masix_config::{Config, McpSandboxConfig, McpServer};
 use masix_exec::is_termux_environment;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -104,16 +106,196 @@ struct InstalledPluginRecord {
     admin_only: bool,
     #[serde(default)]
     package_type: Option<String>,
+    #[serde(default)]
+    sha256: Option<String>,
+    /// Trusted publisher that signed the package, `None` when installed
+    /// with `--allow-unsigned` or `require_signatures = false`.
+    #[serde(default)]
+    signed_by: Option<String>,
+    /// Exempt from `plugin update`.
+    #[serde(default)]
+    pinned: bool,
+    /// Earlier installs still on disk, newest first; `plugin rollback`
+    /// restores one of them and `plugin gc` prunes them.
+    #[serde(default)]
+    previous: Vec<InstalledPluginVersion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct InstalledPluginVersion {
+    version: String,
+    visibility: String,
+    source_server: String,
+    install_path: String,
+    #[serde(default)]
+    entrypoint: Option<String>,
+    installed_at: u64,
+    #[serde(default)]
+    admin_only: bool,
+    #[serde(default)]
+    package_type: Option<String>,
+    #[serde(default)]
+    sha256: Option<String>,
+    #[serde(default)]
+    signed_by: Option<String>,
+}
+
+impl InstalledPluginRecord {
+    fn snapshot(&self) -> InstalledPluginVersion {
+        InstalledPluginVersion {
+            version: self.version.clone(),
+            visibility: self.visibility.clone(),
+            source_server: self.source_server.clone(),
+            install_path: self.install_path.clone(),
+            entrypoint: self.entrypoint.clone(),
+            installed_at: self.installed_at,
+            admin_only: self.admin_only,
+            package_type: self.package_type.clone(),
+            sha256: self.sha256.clone(),
+            signed_by: self.signed_by.clone(),
+        }
+    }
+
+    fn restore(&mut self, version: InstalledPluginVersion) {
+        self.version = version.version;
+        self.visibility = version.visibility;
+        self.source_server = version.source_server;
+        self.install_path = version.install_path;
+        self.entrypoint = version.entrypoint;
+        self.installed_at = version.installed_at;
+        self.admin_only = version.admin_only;
+        self.package_type = version.package_type;
+        self.sha256 = version.sha256;
+        self.signed_by = version.signed_by;
+    }
+}
+
+/// `manifest.json` shipped next to the `.pkg` files of a package version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PackageManifest {
+    plugin_id: String,
+    version: String,
+    #[serde(default = "default_visibility")]
+    visibility: String,
+    #[serde(default)]
+    package_type: Option<String>,
+    #[serde(default)]
+    admin_only: bool,
+    #[serde(default)]
+    platforms: Vec<PackageManifestPlatform>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PackageManifestPlatform {
+    id: String,
+    file: String,
+    #[serde(default)]
+    sha256: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
 }
 
 fn default_visibility() -> String {
     "free".to_string()
 }
 
+/// Run a plugin subcommand and audit every change it made to the installed
+/// registry (and to the config, for MCP server sync).
 pub async fn handle_plugin_command(
     action: PluginCommands,
     config_path: Option<String>,
 ) -> Result<()> {
+    let data_dir = resolve_data_dir(config_path.as_deref());
+    let registry_path = plugin_registry_path(&plugin_root_dir(&data_dir));
+    let before = load_registry(&registry_path).unwrap_or_default();
+    let config_watch = audit::ConfigWatch::start(config_path.as_deref());
+
+    let result = run_plugin_command(action, config_path).await;
+
+    let after = load_registry(&registry_path).unwrap_or_default();
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+    for (change, details) in registry_changes(&before, &after) {
+        let action = format!(
+            "{} {}",
+            change,
+            details["plugin_id"].as_str().unwrap_or("?")
+        );
+        audit::record(&data_dir, "plugin", &action, outcome, details);
+    }
+    config_watch.finish("plugin", &result);
+    result
+}
+
+/// Per-plugin changes between two registry snapshots, as (change, details).
+fn registry_changes(
+    before: &InstalledPluginsRegistry,
+    after: &InstalledPluginsRegistry,
+) -> Vec<(&'static str, serde_json::Value)> {
+    let find = |registry: &InstalledPluginsRegistry, plugin_id: &str| {
+        registry
+            .plugins
+            .iter()
+            .find(|record| record.plugin_id == plugin_id)
+            .cloned()
+    };
+    let mut plugin_ids: Vec<&str> = before
+        .plugins
+        .iter()
+        .chain(after.plugins.iter())
+        .map(|record| record.plugin_id.as_str())
+        .collect();
+    plugin_ids.sort_unstable();
+    plugin_ids.dedup();
+
+    let mut changes = Vec::new();
+    for plugin_id in plugin_ids {
+        let old = find(before, plugin_id);
+        let new = find(after, plugin_id);
+        let mut kinds = Vec::new();
+        match (&old, &new) {
+            (None, Some(_)) => kinds.push("installed"),
+            (Some(_), None) => kinds.push("uninstalled"),
+            (Some(old), Some(new)) => {
+                if old.version != new.version || old.sha256 != new.sha256 {
+                    let restored = old.previous.iter().any(|prev| {
+                        prev.version == new.version && prev.install_path == new.install_path
+                    });
+                    kinds.push(if restored { "rolled_back" } else { "updated" });
+                } else if old.previous.len() > new.previous.len() {
+                    kinds.push("pruned");
+                }
+                if old.enabled != new.enabled {
+                    kinds.push(if new.enabled { "enabled" } else { "disabled" });
+                }
+                if old.pinned != new.pinned {
+                    kinds.push(if new.pinned { "pinned" } else { "unpinned" });
+                }
+                if kinds.is_empty()
+                    && serde_json::to_value(old).ok() != serde_json::to_value(new).ok()
+                {
+                    kinds.push("modified");
+                }
+            }
+            (None, None) => {}
+        }
+        for kind in kinds {
+            changes.push((
+                kind,
+                serde_json::json!({
+                    "plugin_id": plugin_id,
+                    "from_version": old.as_ref().map(|record| &record.version),
+                    "to_version": new.as_ref().map(|record| &record.version),
+                    "package_type": new.as_ref().or(old.as_ref()).and_then(|record| record.package_type.clone()),
+                    "sha256": new.as_ref().and_then(|record| record.sha256.clone()),
+                    "signed_by": new.as_ref().and_then(|record| record.signed_by.clone()),
+                }),
+            ));
+        }
+    }
+    changes
+}
+
+async fn run_plugin_command(action: PluginCommands, config_path: Option<String>) -> Result<()> {
     match action {
         PluginCommands::List {
             server,
@@ -208,10 +390,13 @@ pub async fn handle_plugin_command(
             key,
             server,
             platform,
+            allow_unsigned,
+            pin,
         } => {
             let platform = platform.unwrap_or_else(plugin_platform_id);
             let data_dir = resolve_data_dir(config_path.as_deref());
             let plugins_dir = plugin_root_dir(&data_dir);
+            let trust = load_plugin_trust(config_path.as_deref(), allow_unsigned)?;
             let auth_path = plugin_auth_store_path(&plugins_dir);
             let mut auth_store = load_auth_store(&auth_path)?;
             let server_url = resolve_plugin_server_url(server, auth_store.server_url.clone());
@@ -226,9 +411,13 @@ pub async fn handle_plugin_command(
                 &platform,
                 key.as_deref(),
                 &mut auth_store,
+                &trust,
             )
             .await?;
             save_auth_store(&auth_path, &auth_store)?;
+            if pin {
+                set_plugin_pinned(&plugins_dir, &install_result.plugin_id, true)?;
+            }
             let registry_path = plugin_registry_path(&plugins_dir);
             let registry = load_registry(&registry_path)?;
             sync_plugin_mcp_servers_in_config(config_path.as_deref(), &registry)?;
@@ -237,27 +426,56 @@ pub async fn handle_plugin_command(
                 install_result.plugin_id, install_result.version
             );
             println!("Package: {}", install_result.install_path);
+            print_signature_status(&install_result);
         }
         PluginCommands::InstallFile {
             file,
+            manifest,
             plugin,
             version,
             package_type,
             entrypoint,
             platform,
+            allow_unsigned,
+            pin,
         } => {
             let platform = platform.unwrap_or_else(plugin_platform_id);
             let data_dir = resolve_data_dir(config_path.as_deref());
             let plugins_dir = plugin_root_dir(&data_dir);
+            let trust = load_plugin_trust(config_path.as_deref(), allow_unsigned)?;
+            let (package_file, manifest) = match manifest {
+                Some(path) => {
+                    let manifest_path = resolve_manifest_path(Path::new(&path));
+                    let manifest = load_package_manifest(&manifest_path)?;
+                    let artifact =
+                        manifest_artifact_for_platform(&manifest_path, &manifest, &platform)?;
+                    (artifact, Some(manifest))
+                }
+                None => {
+                    let file = PathBuf::from(
+                        file.ok_or_else(|| anyhow!("--file or --manifest is required"))?,
+                    );
+                    let manifest = load_sibling_manifest(&file)?;
+                    (file, manifest)
+                }
+            };
+            let plugin = plugin
+                .or_else(|| manifest.as_ref().map(|m| m.plugin_id.clone()))
+                .ok_or_else(|| anyhow!("--plugin is required without a package manifest"))?;
             let install_result = install_plugin_from_local_file(
                 &plugins_dir,
-                Path::new(&file),
+                &package_file,
                 &plugin,
                 version.as_deref(),
                 &platform,
                 package_type.as_deref(),
                 entrypoint.as_deref(),
+                manifest.as_ref(),
+                &trust,
             )?;
+            if pin {
+                set_plugin_pinned(&plugins_dir, &install_result.plugin_id, true)?;
+            }
             let registry_path = plugin_registry_path(&plugins_dir);
             let registry = load_registry(&registry_path)?;
             sync_plugin_mcp_servers_in_config(config_path.as_deref(), &registry)?;
@@ -266,16 +484,101 @@ pub async fn handle_plugin_command(
                 install_result.plugin_id, install_result.version
             );
             println!("Package: {}", install_result.install_path);
+            print_signature_status(&install_result);
+        }
+        PluginCommands::InstallDir {
+            dir,
+            platform,
+            allow_unsigned,
+        } => {
+            let platform = platform.unwrap_or_else(plugin_platform_id);
+            let data_dir = resolve_data_dir(config_path.as_deref());
+            let plugins_dir = plugin_root_dir(&data_dir);
+            let trust = load_plugin_trust(config_path.as_deref(), allow_unsigned)?;
+            let scan = collect_latest_manifests(Path::new(&dir))?;
+            if scan.latest.is_empty() && scan.failed.is_empty() {
+                println!("No package manifests found under {}.", dir);
+                return Ok(());
+            }
+
+            let mut installed = 0usize;
+            let mut skipped = 0usize;
+            let mut failed = Vec::new();
+            for (manifest_path, e) in &scan.failed {
+                println!("- {}: failed: {:#}", manifest_path.display(), e);
+                failed.push(manifest_path.display().to_string());
+            }
+            for (manifest_path, manifest) in &scan.latest {
+                if !manifest.platforms.iter().any(|p| p.id == platform) {
+                    println!(
+                        "- {} {}: skipped (no package for {})",
+                        manifest.plugin_id, manifest.version, platform
+                    );
+                    skipped += 1;
+                    continue;
+                }
+                let result = manifest_artifact_for_platform(manifest_path, manifest, &platform)
+                    .and_then(|artifact| {
+                        install_plugin_from_local_file(
+                            &plugins_dir,
+                            &artifact,
+                            &manifest.plugin_id,
+                            None,
+                            &platform,
+                            None,
+                            None,
+                            Some(manifest),
+                            &trust,
+                        )
+                    });
+                match result {
+                    Ok(record) => {
+                        installed += 1;
+                        println!(
+                            "- {} {}: installed{}",
+                            record.plugin_id,
+                            record.version,
+                            record
+                                .signed_by
+                                .as_ref()
+                                .map(|p| format!(" (signed by '{}')", p))
+                                .unwrap_or_default()
+                        );
+                    }
+                    Err(e) => {
+                        println!(
+                            "- {} {}: failed: {:#}",
+                            manifest.plugin_id, manifest.version, e
+                        );
+                        failed.push(manifest.plugin_id.clone());
+                    }
+                }
+            }
+
+            let registry_path = plugin_registry_path(&plugins_dir);
+            let registry = load_registry(&registry_path)?;
+            sync_plugin_mcp_servers_in_config(config_path.as_deref(), &registry)?;
+            println!(
+                "Provisioning complete: {} installed, {} skipped, {} failed.",
+                installed,
+                skipped,
+                failed.len()
+            );
+            if !failed.is_empty() {
+                anyhow::bail!("Failed to install: {}", failed.join(", "));
+            }
         }
         PluginCommands::Update {
             plugin,
             key,
             server,
             platform,
+            allow_unsigned,
         } => {
             let platform = platform.unwrap_or_else(plugin_platform_id);
             let data_dir = resolve_data_dir(config_path.as_deref());
             let plugins_dir = plugin_root_dir(&data_dir);
+            let trust = load_plugin_trust(config_path.as_deref(), allow_unsigned)?;
             let auth_path = plugin_auth_store_path(&plugins_dir);
             let registry_path = plugin_registry_path(&plugins_dir);
             let mut auth_store = load_auth_store(&auth_path)?;
@@ -314,6 +617,13 @@ pub async fn handle_plugin_command(
                     println!("- {}: not installed", plugin_id);
                     continue;
                 };
+                if installed.pinned {
+                    println!(
+                        "- {}: pinned at {} (run `masix plugin unpin {}` to update)",
+                        plugin_id, installed.version, plugin_id
+                    );
+                    continue;
+                }
 
                 let candidate = select_catalog_plugin(&catalog, &plugin_id, None, &platform)?;
                 if candidate.version == installed.version {
@@ -334,6 +644,7 @@ pub async fn handle_plugin_command(
                     &platform,
                     key.as_deref(),
                     &mut auth_store,
+                    &trust,
                 )
                 .await?;
                 updated += 1;
@@ -350,6 +661,65 @@ pub async fn handle_plugin_command(
                 registry.plugins.len()
             );
         }
+        PluginCommands::Sign {
+            manifest,
+            key_file,
+            publisher,
+        } => {
+            let encoded = std::fs::read_to_string(&key_file)
+                .with_context(|| format!("Cannot read signing key file: {}", key_file))?;
+            let signing_key = plugin_trust::parse_signing_key(&encoded)?;
+            let signed = sign_package_manifest(Path::new(&manifest), &signing_key, &publisher)?;
+            println!(
+                "Signed {} platform package(s) in {} as '{}'.",
+                signed, manifest, publisher
+            );
+            println!(
+                "Publisher public key: {}",
+                plugin_trust::encode_verifying_key(&signing_key.verifying_key())
+            );
+        }
+        PluginCommands::Uninstall { plugin } => {
+            let data_dir = resolve_data_dir(config_path.as_deref());
+            let plugins_dir = plugin_root_dir(&data_dir);
+            uninstall_plugin(&plugins_dir, &plugin)?;
+            let registry = load_registry(&plugin_registry_path(&plugins_dir))?;
+            sync_plugin_mcp_servers_in_config(config_path.as_deref(), &registry)?;
+            println!("Uninstalled plugin '{}'.", plugin);
+        }
+        PluginCommands::Rollback { plugin, version } => {
+            let data_dir = resolve_data_dir(config_path.as_deref());
+            let plugins_dir = plugin_root_dir(&data_dir);
+            let record = rollback_plugin(&plugins_dir, &plugin, version.as_deref())?;
+            let registry = load_registry(&plugin_registry_path(&plugins_dir))?;
+            sync_plugin_mcp_servers_in_config(config_path.as_deref(), &registry)?;
+            println!(
+                "Rolled back plugin '{}' to {} (pinned; run `masix plugin unpin {}` to resume updates).",
+                plugin, record.version, plugin
+            );
+        }
+        PluginCommands::Pin { plugin } => {
+            let data_dir = resolve_data_dir(config_path.as_deref());
+            set_plugin_pinned(&plugin_root_dir(&data_dir), &plugin, true)?;
+            println!("Pinned plugin '{}'.", plugin);
+        }
+        PluginCommands::Unpin { plugin } => {
+            let data_dir = resolve_data_dir(config_path.as_deref());
+            set_plugin_pinned(&plugin_root_dir(&data_dir), &plugin, false)?;
+            println!("Unpinned plugin '{}'.", plugin);
+        }
+        PluginCommands::Gc { keep } => {
+            let data_dir = resolve_data_dir(config_path.as_deref());
+            let removed = gc_plugin_packages(&plugin_root_dir(&data_dir), keep)?;
+            for path in &removed {
+                println!("- removed {}", path.display());
+            }
+            println!(
+                "Plugin GC complete: {} unused version(s) removed, keeping {} previous version(s) per plugin.",
+                removed.len(),
+                keep
+            );
+        }
         PluginCommands::Enable { plugin } => {
             let data_dir = resolve_data_dir(config_path.as_deref());
             let plugins_dir = plugin_root_dir(&data_dir);
@@ -580,6 +950,7 @@ pub async fn ensure_device_key_quiet(
     Ok(new_key)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn install_plugin_from_catalog(
     plugins_dir: &Path,
     server_url: &str,
@@ -589,6 +960,7 @@ async fn install_plugin_from_catalog(
     platform: &str,
     key_override: Option<&str>,
     auth_store: &mut PluginAuthStore,
+    trust: &PluginTrust,
 ) -> Result<InstalledPluginRecord> {
     let entry = select_catalog_plugin(catalog, plugin_id, version, platform)?;
     let requires_auth = entry.visibility.eq_ignore_ascii_case("private")
@@ -621,8 +993,19 @@ async fn install_plugin_from_catalog(
     };
 
     let package_bytes =
-        download_plugin_package(server_url, &entry, platform, auth_resp.as_ref()).await?;
+        download_plugin_package(server_url, entry, platform, auth_resp.as_ref()).await?;
     verify_download_hash(&package_bytes, entry.sha256.as_deref())?;
+    let package_sha256 = sha256_hex(&package_bytes);
+    let signed_by = trust.verify(
+        &PackageStatement {
+            plugin_id: &entry.id,
+            version: &entry.version,
+            platform,
+            package_type: entry.package_type.as_deref().unwrap_or_default(),
+            sha256: &package_sha256,
+        },
+        entry.signature.as_deref(),
+    )?;
 
     let file_name = format!(
         "{}-{}-{}.pkg",
@@ -635,27 +1018,13 @@ async fn install_plugin_from_catalog(
                 .unwrap_or_else(|| entry.package_type.as_deref().unwrap_or("module"))
         )
     );
-    let package_path = plugins_dir
-        .join("packages")
-        .join(&entry.id)
-        .join(&entry.version)
-        .join(file_name);
+    let package_path = plugin_version_dir(plugins_dir, &entry.id, &entry.version)?.join(file_name);
     if let Some(parent) = package_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
     write_package_atomically(&package_path, &package_bytes)?;
-    ensure_plugin_package_permissions(&package_path, &entry)?;
+    ensure_plugin_package_permissions(&package_path, entry)?;
 
-    let registry_path = plugin_registry_path(plugins_dir);
-    let mut registry = load_registry(&registry_path)?;
-    let previous = registry
-        .plugins
-        .iter()
-        .find(|p| p.plugin_id == entry.id && p.platform == platform)
-        .cloned();
-    registry
-        .plugins
-        .retain(|p| !(p.plugin_id == entry.id && p.platform == platform));
     let record = InstalledPluginRecord {
         plugin_id: entry.id.clone(),
         version: entry.version.clone(),
@@ -665,19 +1034,18 @@ async fn install_plugin_from_catalog(
         install_path: package_path.display().to_string(),
         entrypoint: entry.entrypoint.clone(),
         installed_at: now_unix_secs(),
-        enabled: previous.as_ref().map(|p| p.enabled).unwrap_or(false),
+        enabled: false,
         admin_only: entry.admin_only,
         package_type: entry.package_type.clone(),
+        sha256: Some(package_sha256),
+        signed_by,
+        pinned: false,
+        previous: Vec::new(),
     };
-    registry.plugins.push(record.clone());
-    registry
-        .plugins
-        .sort_by(|a, b| a.plugin_id.cmp(&b.plugin_id));
-    save_registry(&registry_path, &registry)?;
-
-    Ok(record)
+    store_installed_record(plugins_dir, record)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn install_plugin_from_local_file(
     plugins_dir: &Path,
     local_file: &Path,
@@ -686,6 +1054,8 @@ fn install_plugin_from_local_file(
     platform: &str,
     package_type: Option<&str>,
     entrypoint: Option<&str>,
+    manifest: Option<&PackageManifest>,
+    trust: &PluginTrust,
 ) -> Result<InstalledPluginRecord> {
     if !local_file.exists() {
         anyhow::bail!(
@@ -710,14 +1080,67 @@ fn install_plugin_from_local_file(
         )
     })?;
 
+    let package_bytes = std::fs::read(&resolved_local_file).with_context(|| {
+        format!(
+            "Cannot read local package: {}",
+            resolved_local_file.display()
+        )
+    })?;
+    let package_sha256 = sha256_hex(&package_bytes);
+    let manifest_platform = manifest.and_then(|manifest| {
+        let file_name = resolved_local_file.file_name()?.to_str()?;
+        manifest.platforms.iter().find(|p| p.file == file_name)
+    });
+    // Manifest metadata only applies when it actually describes this file.
+    let manifest = manifest_platform.and(manifest);
+    let version = version.map(str::trim).filter(|v| !v.is_empty());
+    let package_type = package_type.map(str::trim).filter(|v| !v.is_empty());
+
+    let signed_by = match (manifest, manifest_platform) {
+        (Some(manifest), Some(entry)) => {
+            verify_against_manifest(
+                manifest,
+                entry,
+                &resolved_local_file,
+                &package_sha256,
+                plugin_id,
+                version,
+                platform,
+                package_type,
+            )?;
+            trust.verify(
+                &PackageStatement {
+                    plugin_id: &manifest.plugin_id,
+                    version: &manifest.version,
+                    platform: &entry.id,
+                    package_type: manifest.package_type.as_deref().unwrap_or_default(),
+                    sha256: &package_sha256,
+                },
+                entry.signature.as_deref(),
+            )?
+        }
+        _ => trust.verify(
+            &PackageStatement {
+                plugin_id,
+                version: version.unwrap_or("local"),
+                platform,
+                package_type: package_type.unwrap_or_default(),
+                sha256: &package_sha256,
+            },
+            None,
+        )?,
+    };
+
     let install_version = version
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
+        .or_else(|| manifest.map(|m| m.version.as_str()))
         .unwrap_or("local");
     let install_package_type = package_type
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
+        .or_else(|| manifest.and_then(|m| m.package_type.as_deref()))
         .unwrap_or("mcp_binary");
+    let install_visibility = manifest
+        .map(|m| m.visibility.clone())
+        .unwrap_or_else(default_visibility);
+    let admin_only = manifest.is_some_and(|m| m.admin_only);
     let install_entrypoint = entrypoint
         .map(str::trim)
         .filter(|v| !v.is_empty())
@@ -736,11 +1159,8 @@ fn install_plugin_from_local_file(
             )
         });
 
-    let package_path = plugins_dir
-        .join("packages")
-        .join(plugin_id)
-        .join(install_version)
-        .join(source_file_name);
+    let package_path =
+        plugin_version_dir(plugins_dir, plugin_id, install_version)?.join(source_file_name);
     if let Some(parent) = package_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
@@ -750,7 +1170,7 @@ fn install_plugin_from_local_file(
         id: plugin_id.to_string(),
         name: None,
         version: install_version.to_string(),
-        visibility: "free".to_string(),
+        visibility: install_visibility.clone(),
         requires_masix: None,
         platforms: vec![platform.to_string()],
         package_type: Some(install_package_type.to_string()),
@@ -758,42 +1178,516 @@ fn install_plugin_from_local_file(
         sha256: None,
         signature: None,
         size_bytes: None,
-        admin_only: false,
+        admin_only,
     };
     ensure_plugin_package_permissions(&package_path, &permission_entry)?;
 
-    let registry_path = plugin_registry_path(plugins_dir);
-    let mut registry = load_registry(&registry_path)?;
-    let previous = registry
-        .plugins
-        .iter()
-        .find(|p| p.plugin_id == plugin_id && p.platform == platform)
-        .cloned();
-    registry
-        .plugins
-        .retain(|p| !(p.plugin_id == plugin_id && p.platform == platform));
     let record = InstalledPluginRecord {
         plugin_id: plugin_id.to_string(),
         version: install_version.to_string(),
-        visibility: "free".to_string(),
+        visibility: install_visibility,
         platform: platform.to_string(),
         source_server: "local-file".to_string(),
         install_path: package_path.display().to_string(),
         entrypoint: install_entrypoint,
         installed_at: now_unix_secs(),
-        enabled: previous.as_ref().map(|p| p.enabled).unwrap_or(true),
-        admin_only: false,
+        enabled: true,
+        admin_only,
         package_type: Some(install_package_type.to_string()),
+        sha256: Some(package_sha256),
+        signed_by,
+        pinned: false,
+        previous: Vec::new(),
     };
+    store_installed_record(plugins_dir, record)
+}
+
+/// Replaces the registry entry for the record's plugin/platform. An
+/// existing entry keeps its enabled/pinned state and, when the version
+/// changes, moves to the front of the rollback history.
+fn store_installed_record(
+    plugins_dir: &Path,
+    mut record: InstalledPluginRecord,
+) -> Result<InstalledPluginRecord> {
+    let registry_path = plugin_registry_path(plugins_dir);
+    let mut registry = load_registry(&registry_path)?;
+    if let Some(index) = registry
+        .plugins
+        .iter()
+        .position(|p| p.plugin_id == record.plugin_id && p.platform == record.platform)
+    {
+        let current = registry.plugins.remove(index);
+        record.enabled = current.enabled;
+        record.pinned = current.pinned;
+        if current.version != record.version {
+            record.previous.push(current.snapshot());
+        }
+        record.previous.extend(current.previous);
+        let version = record.version.clone();
+        record.previous.retain(|v| v.version != version);
+    }
     registry.plugins.push(record.clone());
     registry
         .plugins
         .sort_by(|a, b| a.plugin_id.cmp(&b.plugin_id));
     save_registry(&registry_path, &registry)?;
+    Ok(record)
+}
+
+fn set_plugin_pinned(plugins_dir: &Path, plugin_id: &str, pinned: bool) -> Result<()> {
+    let registry_path = plugin_registry_path(plugins_dir);
+    let mut registry = load_registry(&registry_path)?;
+    let mut found = false;
+    for record in registry
+        .plugins
+        .iter_mut()
+        .filter(|p| p.plugin_id == plugin_id)
+    {
+        record.pinned = pinned;
+        found = true;
+    }
+    if !found {
+        anyhow::bail!("Plugin '{}' is not installed", plugin_id);
+    }
+    save_registry(&registry_path, &registry)
+}
+
+/// Makes `version` (default: the most recent previous install) current again
+/// and pins the plugin so `plugin update` does not undo the rollback.
+fn rollback_plugin(
+    plugins_dir: &Path,
+    plugin_id: &str,
+    version: Option<&str>,
+) -> Result<InstalledPluginRecord> {
+    let registry_path = plugin_registry_path(plugins_dir);
+    let mut registry = load_registry(&registry_path)?;
+    let record = registry
+        .plugins
+        .iter_mut()
+        .find(|p| p.plugin_id == plugin_id)
+        .ok_or_else(|| anyhow!("Plugin '{}' is not installed", plugin_id))?;
+    let index = match version {
+        Some(version) => record.previous.iter().position(|v| v.version == version),
+        None => (!record.previous.is_empty()).then_some(0),
+    };
+    let Some(index) = index else {
+        let available: Vec<&str> = record.previous.iter().map(|v| v.version.as_str()).collect();
+        anyhow::bail!(
+            "No retained version {}of plugin '{}' to roll back to (current: {}, available: {})",
+            version.map(|v| format!("{} ", v)).unwrap_or_default(),
+            plugin_id,
+            record.version,
+            if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
+            }
+        );
+    };
+
+    let target = record.previous[index].clone();
+    let bytes = std::fs::read(&target.install_path).with_context(|| {
+        format!(
+            "Package for {} {} is missing: {}",
+            plugin_id, target.version, target.install_path
+        )
+    })?;
+    if let Some(expected) = target.sha256.as_deref() {
+        let actual = sha256_hex(&bytes);
+        if !expected.eq_ignore_ascii_case(&actual) {
+            anyhow::bail!(
+                "SHA256 mismatch for {} {}: registry {}, file {}",
+                plugin_id,
+                target.version,
+                expected,
+                actual
+            );
+        }
+    }
 
+    record.previous.remove(index);
+    let current = record.snapshot();
+    record.previous.insert(0, current);
+    record.restore(target);
+    record.pinned = true;
+    let record = record.clone();
+    save_registry(&registry_path, &registry)?;
     Ok(record)
 }
 
+/// Removes every registry entry and package file of `plugin_id`.
+fn uninstall_plugin(plugins_dir: &Path, plugin_id: &str) -> Result<usize> {
+    let registry_path = plugin_registry_path(plugins_dir);
+    let mut registry = load_registry(&registry_path)?;
+    let before = registry.plugins.len();
+    registry.plugins.retain(|p| p.plugin_id != plugin_id);
+    let removed = before - registry.plugins.len();
+    if removed == 0 {
+        anyhow::bail!("Plugin '{}' is not installed", plugin_id);
+    }
+    let package_dir = plugin_package_dir(plugins_dir, plugin_id)?;
+    if package_dir.exists() {
+        std::fs::remove_dir_all(&package_dir)
+            .with_context(|| format!("Cannot remove {}", package_dir.display()))?;
+    }
+    save_registry(&registry_path, &registry)?;
+    Ok(removed)
+}
+
+/// Trims each plugin's rollback history to `keep` versions and deletes
+/// package directories no registry entry points at any more.
+fn gc_plugin_packages(plugins_dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let registry_path = plugin_registry_path(plugins_dir);
+    let mut registry = load_registry(&registry_path)?;
+    for record in &mut registry.plugins {
+        record.previous.truncate(keep);
+    }
+    save_registry(&registry_path, &registry)?;
+
+    let referenced: HashSet<PathBuf> = registry
+        .plugins
+        .iter()
+        .flat_map(|record| {
+            std::iter::once(record.install_path.as_str())
+                .chain(record.previous.iter().map(|v| v.install_path.as_str()))
+        })
+        .filter_map(|path| Path::new(path).parent().map(Path::to_path_buf))
+        .collect();
+
+    let packages_root = plugins_dir.join("packages");
+    let mut removed = Vec::new();
+    let Ok(plugin_dirs) = std::fs::read_dir(&packages_root) else {
+        return Ok(removed);
+    };
+    for plugin_dir in plugin_dirs {
+        let plugin_dir = plugin_dir?.path();
+        if !plugin_dir.is_dir() {
+            continue;
+        }
+        for version_dir in std::fs::read_dir(&plugin_dir)? {
+            let version_dir = version_dir?.path();
+            if version_dir.is_dir() && !referenced.contains(&version_dir) {
+                std::fs::remove_dir_all(&version_dir)
+                    .with_context(|| format!("Cannot remove {}", version_dir.display()))?;
+                removed.push(version_dir);
+            }
+        }
+        if std::fs::read_dir(&plugin_dir)?.next().is_none() {
+            std::fs::remove_dir(&plugin_dir)?;
+        }
+    }
+    Ok(removed)
+}
+
+fn plugin_package_dir(plugins_dir: &Path, plugin_id: &str) -> Result<PathBuf> {
+    if !is_plain_path_component(plugin_id) {
+        anyhow::bail!("Invalid plugin id '{}'", plugin_id);
+    }
+    Ok(plugins_dir.join("packages").join(plugin_id))
+}
+
+/// `packages/<id>/<version>`: ids and versions come from manifests and catalogs,
+/// so neither may climb out of the packages tree.
+fn plugin_version_dir(plugins_dir: &Path, plugin_id: &str, version: &str) -> Result<PathBuf> {
+    let package_dir = plugin_package_dir(plugins_dir, plugin_id)?;
+    if !is_plain_path_component(version) {
+        anyhow::bail!("Invalid plugin version '{}'", version);
+    }
+    Ok(package_dir.join(version))
+}
+
+fn is_plain_path_component(value: &str) -> bool {
+    let mut components = Path::new(value).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    )
+}
+
+fn load_plugin_trust(config_path: Option<&str>, allow_unsigned: bool) -> Result<PluginTrust> {
+    let config = match config_path {
+        Some(path) => Config::load(path).ok(),
+        None => Config::default_path().and_then(|path| Config::load(&path).ok()),
+    };
+    PluginTrust::from_config(
+        config.as_ref().and_then(|c| c.plugins.as_ref()),
+        allow_unsigned,
+    )
+}
+
+fn print_signature_status(record: &InstalledPluginRecord) {
+    match &record.signed_by {
+        Some(publisher) => println!("Signature: verified (publisher '{}')", publisher),
+        None => println!("Signature: not verified (unsigned or untrusted publisher)"),
+    }
+}
+
+fn load_sibling_manifest(package_file: &Path) -> Result<Option<PackageManifest>> {
+    let Some(dir) = package_file.parent() else {
+        return Ok(None);
+    };
+    let manifest_path = dir.join("manifest.json");
+    if !manifest_path.is_file() {
+        return Ok(None);
+    }
+    load_package_manifest(&manifest_path).map(Some)
+}
+
+fn load_package_manifest(manifest_path: &Path) -> Result<PackageManifest> {
+    let raw = std::fs::read_to_string(manifest_path)
+        .with_context(|| format!("Cannot read {}", manifest_path.display()))?;
+    serde_json::from_str(&raw)
+        .with_context(|| format!("Invalid package manifest: {}", manifest_path.display()))
+}
+
+/// Accepts either a package version directory or the manifest file itself.
+fn resolve_manifest_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join("manifest.json")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Returns the artifact path of the manifest entry built for `platform`.
+fn manifest_artifact_for_platform(
+    manifest_path: &Path,
+    manifest: &PackageManifest,
+    platform: &str,
+) -> Result<PathBuf> {
+    let entry = manifest
+        .platforms
+        .iter()
+        .find(|p| p.id == platform)
+        .ok_or_else(|| {
+            let available: Vec<&str> = manifest.platforms.iter().map(|p| p.id.as_str()).collect();
+            anyhow!(
+                "Plugin '{}' {} has no package for platform '{}' (available: {})",
+                manifest.plugin_id,
+                manifest.version,
+                platform,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            )
+        })?;
+    Ok(manifest_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(&entry.file))
+}
+
+/// Manifests found by [`collect_latest_manifests`].
+#[derive(Default)]
+struct ManifestScan {
+    latest: Vec<(PathBuf, PackageManifest)>,
+    /// Manifests that could not be loaded, with the reason.
+    failed: Vec<(PathBuf, anyhow::Error)>,
+}
+
+/// Finds `manifest.json` files under `root` (e.g. `packages/free`) and keeps
+/// the highest version of each plugin, sorted by plugin id. A malformed
+/// manifest is reported in `failed` without stopping the scan.
+fn collect_latest_manifests(root: &Path) -> Result<ManifestScan> {
+    let mut latest: BTreeMap<String, (PathBuf, PackageManifest)> = BTreeMap::new();
+    let mut failed = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .with_context(|| format!("Cannot read package directory {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if path.file_name().and_then(|n| n.to_str()) != Some("manifest.json") {
+                continue;
+            }
+            let manifest = match load_package_manifest(&path) {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    failed.push((path, e));
+                    continue;
+                }
+            };
+            let newer = latest.get(&manifest.plugin_id).is_none_or(|(_, current)| {
+                cmp_versionish(&manifest.version, &current.version).is_gt()
+            });
+            if newer {
+                latest.insert(manifest.plugin_id.clone(), (path, manifest));
+            }
+        }
+    }
+    Ok(ManifestScan {
+        latest: latest.into_values().collect(),
+        failed,
+    })
+}
+
+/// Parses a `sha256sum`-style file (`<hex>  <file>` per line).
+fn read_sha256sums(path: &Path) -> Result<BTreeMap<String, String>> {
+    let raw =
+        std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let mut sums = BTreeMap::new();
+    for line in raw.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(hash), Some(file)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        sums.insert(
+            file.trim_start_matches('*').to_string(),
+            hash.to_ascii_lowercase(),
+        );
+    }
+    Ok(sums)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn verify_against_manifest(
+    manifest: &PackageManifest,
+    entry: &PackageManifestPlatform,
+    package_file: &Path,
+    package_sha256: &str,
+    plugin_id: &str,
+    version: Option<&str>,
+    platform: &str,
+    package_type: Option<&str>,
+) -> Result<()> {
+    if manifest.plugin_id != plugin_id {
+        anyhow::bail!(
+            "Package manifest is for plugin '{}', not '{}'",
+            manifest.plugin_id,
+            plugin_id
+        );
+    }
+    if let Some(version) = version.filter(|v| *v != manifest.version) {
+        anyhow::bail!(
+            "Package manifest declares version {}, not {}",
+            manifest.version,
+            version
+        );
+    }
+    if entry.id != platform {
+        anyhow::bail!(
+            "Package {} is built for platform '{}', not '{}'",
+            entry.file,
+            entry.id,
+            platform
+        );
+    }
+    if let (Some(requested), Some(declared)) = (package_type, manifest.package_type.as_deref()) {
+        if requested != declared {
+            anyhow::bail!(
+                "Package manifest declares package type '{}', not '{}'",
+                declared,
+                requested
+            );
+        }
+    }
+    let mut checksums = 0usize;
+    if let Some(expected) = entry.sha256.as_deref() {
+        if !expected.eq_ignore_ascii_case(package_sha256) {
+            anyhow::bail!(
+                "SHA256 mismatch for {}: manifest {}, file {}",
+                entry.file,
+                expected,
+                package_sha256
+            );
+        }
+        checksums += 1;
+    }
+    let sums_path = package_file.with_file_name("SHA256SUMS");
+    if sums_path.is_file() {
+        if let Some(expected) = read_sha256sums(&sums_path)?.get(&entry.file) {
+            if expected != package_sha256 {
+                anyhow::bail!(
+                    "SHA256 mismatch for {}: SHA256SUMS {}, file {}",
+                    entry.file,
+                    expected,
+                    package_sha256
+                );
+            }
+            checksums += 1;
+        }
+    }
+    if checksums == 0 {
+        anyhow::bail!(
+            "Package manifest declares no sha256 for {} (and SHA256SUMS has no entry)",
+            entry.file
+        );
+    }
+    Ok(())
+}
+
+/// Adds a publisher signature to every platform entry of a package
+/// `manifest.json`, keeping the other manifest fields untouched.
+fn sign_package_manifest(
+    manifest_path: &Path,
+    signing_key: &ed25519_dalek::SigningKey,
+    publisher: &str,
+) -> Result<usize> {
+    let publisher = publisher.trim();
+    if publisher.is_empty() || publisher.contains(':') {
+        anyhow::bail!("Publisher id must be non-empty and cannot contain ':'");
+    }
+    let raw = std::fs::read_to_string(manifest_path)
+        .with_context(|| format!("Cannot read {}", manifest_path.display()))?;
+    let manifest: PackageManifest = serde_json::from_str(&raw)
+        .with_context(|| format!("Invalid package manifest: {}", manifest_path.display()))?;
+    let mut document: serde_json::Value = serde_json::from_str(&raw)?;
+    let dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+
+    let mut signatures = Vec::with_capacity(manifest.platforms.len());
+    for entry in &manifest.platforms {
+        let package_sha256 = sha256_hex(
+            std::fs::read(dir.join(&entry.file))
+                .with_context(|| format!("Cannot read package {}", entry.file))?,
+        );
+        if let Some(expected) = entry.sha256.as_deref() {
+            if !expected.eq_ignore_ascii_case(&package_sha256) {
+                anyhow::bail!(
+                    "SHA256 mismatch for {}: manifest {}, file {}",
+                    entry.file,
+                    expected,
+                    package_sha256
+                );
+            }
+        }
+        signatures.push(plugin_trust::sign_statement(
+            signing_key,
+            publisher,
+            &PackageStatement {
+                plugin_id: &manifest.plugin_id,
+                version: &manifest.version,
+                platform: &entry.id,
+                package_type: manifest.package_type.as_deref().unwrap_or_default(),
+                sha256: &package_sha256,
+            },
+        ));
+    }
+
+    let platforms = document
+        .get_mut("platforms")
+        .and_then(|p| p.as_array_mut())
+        .ok_or_else(|| anyhow!("Package manifest has no platforms"))?;
+    for (platform, signature) in platforms.iter_mut().zip(&signatures) {
+        if let Some(object) = platform.as_object_mut() {
+            object.insert(
+                "signature".to_string(),
+                serde_json::Value::String(signature.clone()),
+            );
+        }
+    }
+    std::fs::write(
+        manifest_path,
+        format!("{}\n", serde_json::to_string_pretty(&document)?),
+    )?;
+    Ok(signatures.len())
+}
+
 fn sync_plugin_mcp_servers_in_config(
     config_path: Option<&str>,
     registry: &InstalledPluginsRegistry,
@@ -808,6 +1702,7 @@ fn sync_plugin_mcp_servers_in_config(
     let mut config = Config::load(&config_path)
         .with_context(|| format!("Failed to load config from {}", config_path.display()))?;
 
+    let packages_root = plugin_root_dir(&data_dir_from_config(&config)).join("packages");
     let mcp_cfg = config.mcp.get_or_insert_with(Default::default);
     let managed_names: HashSet<String> = registry
         .plugins
@@ -830,7 +1725,9 @@ fn sync_plugin_mcp_servers_in_config(
         if let Some(next) = desired.get(&existing.name) {
             merged.push(merge_plugin_mcp_server(existing, next));
             seen.insert(existing.name.clone());
-        } else if managed_names.contains(&existing.name) {
+        } else if managed_names.contains(&existing.name)
+            || is_stale_plugin_server(existing, &packages_root)
+        {
             continue;
         } else {
             merged.push(existing.clone());
@@ -849,18 +1746,39 @@ fn sync_plugin_mcp_servers_in_config(
     Ok(())
 }
 
+/// A generated plugin server whose package is no longer in the registry
+/// (e.g. after `plugin uninstall`).
+fn is_stale_plugin_server(server: &McpServer, packages_root: &Path) -> bool {
+    server.name.starts_with("plugin_") && Path::new(&server.command).starts_with(packages_root)
+}
+
 fn build_plugin_mcp_server(record: &InstalledPluginRecord) -> Option<McpServer> {
     if !(record.enabled && plugin_record_is_mcp_binary(record)) {
         return None;
     }
+    let workdir = Path::new(&record.install_path)
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
     Some(McpServer {
         name: plugin_server_name(&record.plugin_id),
         command: record.install_path.clone(),
         args: vec!["serve-mcp".to_string()],
         env: std::collections::HashMap::new(),
+        url: None,
+        transport: None,
+        headers: std::collections::HashMap::new(),
+        auth_token: None,
         timeout_secs: 30,
+        tool_timeouts: std::collections::HashMap::new(),
+        max_in_flight: None,
         startup_timeout_secs: 20,
         healthcheck_interval_secs: 60,
+        tool_cache_ttl_secs: 300,
+        max_restarts: 5,
+        restart_backoff_secs: 1,
+        restart_backoff_max_secs: 60,
+        sandbox: Some(McpSandboxConfig::for_plugin(&workdir)),
     })
 }
 
@@ -880,6 +1798,16 @@ fn merge_plugin_mcp_server(existing: &McpServer, generated: &McpServer) -> McpSe
     if !existing.env.is_empty() {
         merged.env = existing.env.clone();
     }
+    merged.tool_cache_ttl_secs = existing.tool_cache_ttl_secs;
+    merged.max_restarts = existing.max_restarts;
+    merged.restart_backoff_secs = existing.restart_backoff_secs;
+    merged.restart_backoff_max_secs = existing.restart_backoff_max_secs;
+    merged.tool_timeouts = existing.tool_timeouts.clone();
+    merged.max_in_flight = existing.max_in_flight;
+    // An operator-tuned sandbox wins over the generated plugin default.
+    if existing.sandbox.is_some() {
+        merged.sandbox = existing.sandbox.clone();
+    }
     merged
 }
 
@@ -898,7 +1826,7 @@ fn plugin_server_name(plugin_id: &str) -> String {
     format!("plugin_{}", plugin_id.replace('-', "_"))
 }
 
-fn resolve_effective_config_path(config_path: Option<&str>) -> Option<PathBuf> {
+pub(crate) fn resolve_effective_config_path(config_path: Option<&str>) -> Option<PathBuf> {
     if let Some(path) = config_path {
         return Some(PathBuf::from(path));
     }
@@ -1131,7 +2059,7 @@ fn resolve_plugin_server_url(cli_override: Option<String>, stored: Option<String
         .unwrap_or_else(|| DEFAULT_PLUGIN_SERVER_URL.to_string())
 }
 
-fn resolve_data_dir(config_path: Option<&str>) -> PathBuf {
+pub(crate) fn resolve_data_dir(config_path: Option<&str>) -> PathBuf {
     if let Some(path) = config_path {
         if let Ok(config) = Config::load(path) {
             return data_dir_from_config(&config);
@@ -1216,7 +2144,7 @@ fn read_hostname_fallback() -> Option<String> {
         .filter(|s| !s.is_empty())
 }
 
-fn sha256_hex(bytes: impl AsRef<[u8]>) -> String {
+pub(crate) fn sha256_hex(bytes: impl AsRef<[u8]>) -> String {
     let mut hasher = Sha256::new();
     hasher.update(bytes.as_ref());
     let digest = hasher.finalize();
@@ -1622,8 +2550,16 @@ pub fn get_global_key(config_path: Option<&str>) -> Result<Option<String>> {
 
 #[cfg(test)]
 mod tests {
-    use super::merge_plugin_mcp_server;
-    use masix_config::McpServer;
+    use super::{
+        collect_latest_manifests, gc_plugin_packages, install_plugin_from_local_file,
+        load_registry, manifest_artifact_for_platform, merge_plugin_mcp_server,
+        plugin_registry_path, read_sha256sums, registry_changes, resolve_manifest_path,
+        rollback_plugin, set_plugin_pinned, sha256_hex, sign_package_manifest,
+        sync_plugin_mcp_servers_in_config, uninstall_plugin,
+    };
+    use crate::plugin_trust::{encode_verifying_key, PluginTrust};
+    use ed25519_dalek::SigningKey;
+    use masix_config::{McpSandboxConfig, McpServer, PluginPublisherKey, PluginsConfig};
     use std::collections::HashMap;
 
     #[test]
@@ -1636,9 +2572,23 @@ mod tests {
             command: "/old/path".to_string(),
             args: vec!["serve-mcp".to_string()],
             env: existing_env.clone(),
+            url: None,
+            transport: None,
+            headers: HashMap::new(),
+            auth_token: None,
             timeout_secs: 1800,
+            tool_timeouts: HashMap::from([("deep_search".to_string(), 3600)]),
+            max_in_flight: Some(1),
             startup_timeout_secs: 45,
             healthcheck_interval_secs: 180,
+            tool_cache_ttl_secs: 600,
+            max_restarts: 2,
+            restart_backoff_secs: 1,
+            restart_backoff_max_secs: 60,
+            sandbox: Some(McpSandboxConfig {
+                max_memory_mb: Some(2048),
+                ..McpSandboxConfig::for_plugin("/old")
+            }),
         };
 
         let generated = McpServer {
@@ -1646,9 +2596,20 @@ mod tests {
             command: "/new/path".to_string(),
             args: vec!["serve-mcp".to_string()],
             env: HashMap::new(),
+            url: None,
+            transport: None,
+            headers: HashMap::new(),
+            auth_token: None,
             timeout_secs: 30,
+            tool_timeouts: HashMap::new(),
+            max_in_flight: None,
             startup_timeout_secs: 20,
             healthcheck_interval_secs: 60,
+            tool_cache_ttl_secs: 300,
+            max_restarts: 5,
+            restart_backoff_secs: 1,
+            restart_backoff_max_secs: 60,
+            sandbox: Some(McpSandboxConfig::for_plugin("/new")),
         };
 
         let merged = merge_plugin_mcp_server(&existing, &generated);
@@ -1656,6 +2617,422 @@ mod tests {
         assert_eq!(merged.timeout_secs, 1800);
         assert_eq!(merged.startup_timeout_secs, 45);
         assert_eq!(merged.healthcheck_interval_secs, 180);
+        assert_eq!(merged.tool_cache_ttl_secs, 600);
+        assert_eq!(merged.max_restarts, 2);
+        assert_eq!(merged.tool_timeouts, existing.tool_timeouts);
+        assert_eq!(merged.max_in_flight, Some(1));
         assert_eq!(merged.env, existing_env);
+        assert_eq!(merged.sandbox, existing.sandbox);
+
+        let unsandboxed = McpServer {
+            sandbox: None,
+            ..existing.clone()
+        };
+        let merged = merge_plugin_mcp_server(&unsandboxed, &generated);
+        let sandbox = merged.sandbox.expect("plugin default sandbox");
+        assert_eq!(sandbox.workdir.as_deref(), Some("/new"));
+        assert!(sandbox
+            .env_allowlist
+            .is_some_and(|names| names.iter().any(|name| name == "PATH")));
+    }
+
+    #[test]
+    fn install_file_verifies_sibling_manifest_hashes_and_signature() {
+        let root = std::env::temp_dir().join(format!(
+            "masix-plugin-sign-{}-{}",
+            std::process::id(),
+            super::now_unix_secs()
+        ));
+        let package_dir = root.join("discovery").join("0.2.4");
+        std::fs::create_dir_all(&package_dir).unwrap();
+        let package = package_dir.join("discovery-linux-x86_64.pkg");
+        std::fs::write(&package, b"#!/bin/sh\necho discovery\n").unwrap();
+        let digest = sha256_hex(b"#!/bin/sh\necho discovery\n");
+        std::fs::write(
+            package_dir.join("SHA256SUMS"),
+            format!("{}  discovery-linux-x86_64.pkg\n", digest),
+        )
+        .unwrap();
+        let manifest_path = package_dir.join("manifest.json");
+        std::fs::write(
+            &manifest_path,
+            serde_json::json!({
+                "plugin_id": "discovery",
+                "version": "0.2.4",
+                "license": "MIT",
+                "package_type": "mcp_binary",
+                "platforms": [{
+                    "id": "linux-x86_64",
+                    "file": "discovery-linux-x86_64.pkg",
+                    "sha256": digest
+                }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let key = SigningKey::from_bytes(&[5u8; 32]);
+        let config = PluginsConfig {
+            require_signatures: true,
+            trusted_publishers: vec![PluginPublisherKey {
+                id: "masix".to_string(),
+                public_key: encode_verifying_key(&key.verifying_key()),
+            }],
+            ..PluginsConfig::default()
+        };
+        let strict = PluginTrust::from_config(Some(&config), false).unwrap();
+        let plugins_dir = root.join("plugins");
+        let install = |trust: &PluginTrust| {
+            install_plugin_from_local_file(
+                &plugins_dir,
+                &package,
+                "discovery",
+                None,
+                "linux-x86_64",
+                None,
+                None,
+                super::load_sibling_manifest(&package).unwrap().as_ref(),
+                trust,
+            )
+        };
+
+        // Unsigned manifest: refused unless explicitly overridden.
+        assert!(install(&strict).is_err());
+        let overridden = PluginTrust::from_config(Some(&config), true).unwrap();
+        let record = install(&overridden).unwrap();
+        assert_eq!(record.signed_by, None);
+        assert_eq!(record.version, "0.2.4");
+
+        assert_eq!(
+            sign_package_manifest(&manifest_path, &key, "masix").unwrap(),
+            1
+        );
+        let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+        assert!(manifest.contains("\"license\": \"MIT\""));
+        let record = install(&strict).unwrap();
+        assert_eq!(record.signed_by.as_deref(), Some("masix"));
+        assert_eq!(record.sha256.as_deref(), Some(digest.as_str()));
+        assert_eq!(record.package_type.as_deref(), Some("mcp_binary"));
+
+        // Tampered artifact no longer matches the manifest / SHA256SUMS.
+        std::fs::write(&package, b"#!/bin/sh\necho evil\n").unwrap();
+        assert!(install(&overridden).is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    fn write_package_version(
+        root: &std::path::Path,
+        plugin: &str,
+        version: &str,
+        platforms: &[&str],
+    ) {
+        let dir = root.join(plugin).join(version);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut entries = Vec::new();
+        for platform in platforms {
+            let file = format!("{}-{}.pkg", plugin, platform);
+            let body = format!("{} {} {}", plugin, version, platform);
+            std::fs::write(dir.join(&file), &body).unwrap();
+            entries.push(serde_json::json!({
+                "id": platform,
+                "file": file,
+                "sha256": sha256_hex(&body)
+            }));
+        }
+        let manifest = serde_json::json!({
+            "plugin_id": plugin,
+            "version": version,
+            "visibility": "free",
+            "admin_only": plugin == "codex-backend",
+            "package_type": "library",
+            "platforms": entries
+        });
+        std::fs::write(dir.join("manifest.json"), manifest.to_string()).unwrap();
+    }
+
+    #[test]
+    fn install_dir_picks_latest_manifest_per_plugin_and_platform() {
+        let root = std::env::temp_dir().join(format!(
+            "masix-plugin-dir-{}-{}",
+            std::process::id(),
+            super::now_unix_secs()
+        ));
+        let free = root.join("free");
+        write_package_version(&free, "codex-backend", "0.1.9", &["linux-x86_64"]);
+        write_package_version(
+            &free,
+            "codex-backend",
+            "0.1.10",
+            &["linux-x86_64", "macos-aarch64"],
+        );
+        write_package_version(&free, "termux-only", "1.0.0", &["android-aarch64-termux"]);
+        let broken = free.join("broken").join("1.0.0");
+        std::fs::create_dir_all(&broken).unwrap();
+        std::fs::write(broken.join("manifest.json"), "{").unwrap();
+
+        let scan = collect_latest_manifests(&free).unwrap();
+        assert_eq!(scan.failed.len(), 1);
+        assert_eq!(scan.failed[0].0, broken.join("manifest.json"));
+        let manifests = scan.latest;
+        let found: Vec<(&str, &str)> = manifests
+            .iter()
+            .map(|(_, m)| (m.plugin_id.as_str(), m.version.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("codex-backend", "0.1.10"), ("termux-only", "1.0.0")]
+        );
+
+        let (manifest_path, manifest) = &manifests[0];
+        let artifact =
+            manifest_artifact_for_platform(manifest_path, manifest, "macos-aarch64").unwrap();
+        assert!(artifact.ends_with("0.1.10/codex-backend-macos-aarch64.pkg"));
+        let (termux_path, termux) = &manifests[1];
+        let missing = manifest_artifact_for_platform(termux_path, termux, "linux-x86_64")
+            .unwrap_err()
+            .to_string();
+        assert!(missing.contains("available: android-aarch64-termux"));
+
+        let trust = PluginTrust::from_config(None, true).unwrap();
+        let record = install_plugin_from_local_file(
+            &root.join("plugins"),
+            &artifact,
+            &manifest.plugin_id,
+            None,
+            "macos-aarch64",
+            None,
+            None,
+            Some(manifest),
+            &trust,
+        )
+        .unwrap();
+        assert_eq!(record.version, "0.1.10");
+        assert_eq!(record.package_type.as_deref(), Some("library"));
+        assert!(record.admin_only);
+        assert_eq!(
+            resolve_manifest_path(&free.join("codex-backend").join("0.1.10")),
+            *manifest_path
+        );
+
+        // Manifest must describe the artifact being installed.
+        let wrong_platform = install_plugin_from_local_file(
+            &root.join("plugins"),
+            &artifact,
+            &manifest.plugin_id,
+            None,
+            "linux-x86_64",
+            None,
+            None,
+            Some(manifest),
+            &trust,
+        );
+        assert!(wrong_platform.is_err());
+
+        // Manifest ids and versions become directories and must stay inside packages/.
+        for (plugin_id, version, expected) in [
+            ("../escape", "0.1.10", "Invalid plugin id"),
+            ("codex-backend", "..", "Invalid plugin version"),
+        ] {
+            let mut hostile = manifest.clone();
+            hostile.plugin_id = plugin_id.to_string();
+            hostile.version = version.to_string();
+            let err = install_plugin_from_local_file(
+                &root.join("plugins"),
+                &artifact,
+                plugin_id,
+                None,
+                "macos-aarch64",
+                None,
+                None,
+                Some(&hostile),
+                &trust,
+            )
+            .unwrap_err()
+            .to_string();
+            assert!(err.contains(expected), "{}", err);
+        }
+        assert!(!root.join("escape").exists());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn bundled_free_manifests_agree_with_sha256sums() {
+        let free = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../packages/free");
+        let manifests = collect_latest_manifests(&free).unwrap().latest;
+        assert!(!manifests.is_empty());
+        for (manifest_path, manifest) in manifests {
+            let sums = read_sha256sums(&manifest_path.with_file_name("SHA256SUMS")).unwrap();
+            for platform in &manifest.platforms {
+                assert_eq!(
+                    sums.get(&platform.file).map(String::as_str),
+                    platform.sha256.as_deref(),
+                    "{} {}",
+                    manifest.plugin_id,
+                    platform.file
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rollback_gc_and_uninstall_manage_package_versions() {
+        let root = std::env::temp_dir().join(format!(
+            "masix-plugin-lifecycle-{}-{}",
+            std::process::id(),
+            super::now_unix_secs()
+        ));
+        let plugins_dir = root.join("plugins");
+        let source = root.join("source");
+        std::fs::create_dir_all(&source).unwrap();
+        let trust = PluginTrust::from_config(None, true).unwrap();
+        let install = |version: &str| {
+            let file = source.join(format!("discovery-{}.pkg", version));
+            std::fs::write(&file, format!("discovery {}", version)).unwrap();
+            install_plugin_from_local_file(
+                &plugins_dir,
+                &file,
+                "discovery",
+                Some(version),
+                "linux-x86_64",
+                None,
+                None,
+                None,
+                &trust,
+            )
+            .unwrap()
+        };
+
+        install("0.1.0");
+        install("0.2.0");
+        let current = install("0.3.0");
+        let history: Vec<&str> = current
+            .previous
+            .iter()
+            .map(|v| v.version.as_str())
+            .collect();
+        assert_eq!(history, vec!["0.2.0", "0.1.0"]);
+
+        let rolled = rollback_plugin(&plugins_dir, "discovery", None).unwrap();
+        assert_eq!(rolled.version, "0.2.0");
+        assert!(rolled.pinned);
+        assert!(rolled.install_path.contains("0.2.0"));
+        let history: Vec<&str> = rolled.previous.iter().map(|v| v.version.as_str()).collect();
+        assert_eq!(history, vec!["0.3.0", "0.1.0"]);
+        assert!(rollback_plugin(&plugins_dir, "discovery", Some("9.9.9")).is_err());
+
+        // Reinstalling keeps the pin until explicitly released.
+        assert!(install("0.3.0").pinned);
+        set_plugin_pinned(&plugins_dir, "discovery", false).unwrap();
+
+        let removed = gc_plugin_packages(&plugins_dir, 1).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].ends_with("discovery/0.1.0"));
+        let registry = load_registry(&plugin_registry_path(&plugins_dir)).unwrap();
+        assert_eq!(registry.plugins[0].previous.len(), 1);
+        assert!(plugins_dir.join("packages/discovery/0.2.0").is_dir());
+
+        // Uninstall drops the registry entry, the files and the generated server.
+        let config_path = root.join("config.toml");
+        std::fs::write(
+            &config_path,
+            format!(
+                r#"
+[core]
+data_dir = "{}"
+
+[[mcp.servers]]
+name = "operator_tools"
+command = "/usr/bin/tools"
+"#,
+                root.display()
+            ),
+        )
+        .unwrap();
+        let config_arg = config_path.to_str();
+        sync_plugin_mcp_servers_in_config(config_arg, &registry).unwrap();
+        let servers = |path: &std::path::Path| -> Vec<String> {
+            masix_config::Config::load(path)
+                .unwrap()
+                .mcp
+                .unwrap()
+                .servers
+                .into_iter()
+                .map(|s| s.name)
+                .collect()
+        };
+        assert_eq!(
+            servers(&config_path),
+            vec!["operator_tools", "plugin_discovery"]
+        );
+
+        uninstall_plugin(&plugins_dir, "discovery").unwrap();
+        let registry = load_registry(&plugin_registry_path(&plugins_dir)).unwrap();
+        assert!(registry.plugins.is_empty());
+        assert!(!plugins_dir.join("packages/discovery").exists());
+        sync_plugin_mcp_servers_in_config(config_arg, &registry).unwrap();
+        assert_eq!(servers(&config_path), vec!["operator_tools"]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn registry_diff_names_each_plugin_change() {
+        let root = std::env::temp_dir().join(format!(
+            "masix-plugin-audit-{}-{}",
+            std::process::id(),
+            super::now_unix_secs()
+        ));
+        let plugins_dir = root.join("plugins");
+        let source = root.join("source");
+        std::fs::create_dir_all(&source).unwrap();
+        let trust = PluginTrust::from_config(None, true).unwrap();
+        let registry = || load_registry(&plugin_registry_path(&plugins_dir)).unwrap();
+        let install = |version: &str| {
+            let file = source.join(format!("discovery-{}.pkg", version));
+            std::fs::write(&file, format!("discovery {}", version)).unwrap();
+            install_plugin_from_local_file(
+                &plugins_dir,
+                &file,
+                "discovery",
+                Some(version),
+                "linux-x86_64",
+                None,
+                None,
+                None,
+                &trust,
+            )
+            .unwrap();
+        };
+        let kinds = |before, after| -> Vec<&'static str> {
+            registry_changes(before, after)
+                .into_iter()
+                .map(|(kind, _)| kind)
+                .collect()
+        };
+
+        let empty = registry();
+        install("0.1.0");
+        let first = registry();
+        let changes = registry_changes(&empty, &first);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, "installed");
+        assert_eq!(changes[0].1["plugin_id"], "discovery");
+        assert_eq!(changes[0].1["to_version"], "0.1.0");
+        assert!(kinds(&first, &first).is_empty());
+
+        install("0.2.0");
+        let second = registry();
+        assert_eq!(kinds(&first, &second), vec!["updated"]);
+
+        rollback_plugin(&plugins_dir, "discovery", None).unwrap();
+        let rolled = registry();
+        assert_eq!(kinds(&second, &rolled), vec!["rolled_back", "pinned"]);
+
+        uninstall_plugin(&plugins_dir, "discovery").unwrap();
+        assert_eq!(kinds(&rolled, &registry()), vec!["uninstalled"]);
+
+        let _ = std::fs::remove_dir_all(&root);
     }
 }
@@ -0,0 +1,169 @@
+//! Grammar of the argument-level exec rules, shared by config validation
+//! and the `masix-exec` checks.
+//!
+//! A rule is `program arg...`, split like a shell command. Each argument
+//! pattern matches one argument: a literal, a glob (`*`, `?`) or a regex
+//! between slashes (`/^[0-9]+$/`, anchored); `**` matches any number of
+//! arguments. The program may be a glob too, so `* **` matches everything.
+
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+enum ArgPattern {
+    Literal(String),
+    Pattern(Regex),
+    /// `**`
+    Rest,
+}
+
+impl ArgPattern {
+    fn parse(token: &str) -> Result<Self> {
+        if token == "**" {
+            return Ok(Self::Rest);
+        }
+        if token.len() >= 2 && token.starts_with('/') && token.ends_with('/') {
+            let inner = &token[1..token.len() - 1];
+            let regex = Regex::new(&format!("^(?:{})$", inner))
+                .map_err(|e| anyhow!("Invalid regex '{}': {}", token, e))?;
+            return Ok(Self::Pattern(regex));
+        }
+        if token.contains(['*', '?']) {
+            let mut pattern = String::from("^");
+            for c in token.chars() {
+                match c {
+                    '*' => pattern.push_str(".*"),
+                    '?' => pattern.push('.'),
+                    c => pattern.push_str(&regex::escape(&c.to_string())),
+                }
+            }
+            pattern.push('$');
+            return Ok(Self::Pattern(Regex::new(&pattern)?));
+        }
+        Ok(Self::Literal(token.to_string()))
+    }
+
+    fn matches_one(&self, value: &str) -> bool {
+        match self {
+            Self::Literal(literal) => literal == value,
+            Self::Pattern(regex) => regex.is_match(value),
+            Self::Rest => true,
+        }
+    }
+}
+
+/// One allow or deny entry, e.g. `git log --oneline -n *`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ExecRule {
+    source: String,
+    program: ArgPattern,
+    args: Vec<ArgPattern>,
+}
+
+impl ExecRule {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = shlex::split(source).ok_or_else(|| anyhow!("Invalid rule syntax"))?;
+        let Some((program, args)) = tokens.split_first() else {
+            bail!("Empty exec rule");
+        };
+        let program = ArgPattern::parse(program)?;
+        if matches!(program, ArgPattern::Rest) {
+            bail!("Exec rule '{}' must start with a program", source);
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            program,
+            args: args
+                .iter()
+                .map(|token| ArgPattern::parse(token))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn matches(&self, program: &str, args: &[String]) -> bool {
+        self.program.matches_one(program) && match_args(&self.args, args)
+    }
+}
+
+fn match_args(patterns: &[ArgPattern], args: &[String]) -> bool {
+    match patterns.split_first() {
+        None => args.is_empty(),
+        Some((ArgPattern::Rest, rest)) => {
+            (0..=args.len()).any(|skip| match_args(rest, &args[skip..]))
+        }
+        Some((pattern, rest)) => args.split_first().is_some_and(|(arg, remaining)| {
+            pattern.matches_one(arg) && match_args(rest, remaining)
+        }),
+    }
+}
+
+impl TryFrom<String> for ExecRule {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        Self::parse(&source)
+    }
+}
+
+impl From<ExecRule> for String {
+    fn from(rule: ExecRule) -> Self {
+        rule.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecRule;
+
+    fn rule_matches(rule: &str, command: &str) -> bool {
+        let mut args = shlex::split(command).unwrap();
+        let program = args.remove(0);
+        ExecRule::parse(rule).unwrap().matches(&program, &args)
+    }
+
+    #[test]
+    fn rules_match_argument_vectors() {
+        assert!(rule_matches("git status", "git status"));
+        assert!(!rule_matches("git status", "git status --porcelain"));
+        assert!(!rule_matches("git status", "git stash"));
+        assert!(rule_matches(
+            "git log --oneline -n *",
+            "git log --oneline -n 20"
+        ));
+        assert!(!rule_matches("git log --oneline -n *", "git log --oneline"));
+        assert!(rule_matches(
+            "git log --oneline -n '/[0-9]+/'",
+            "git log --oneline -n 5"
+        ));
+        assert!(!rule_matches(
+            "git log --oneline -n '/[0-9]+/'",
+            "git log --oneline -n 5x"
+        ));
+        assert!(rule_matches("git diff **", "git diff"));
+        assert!(rule_matches("git diff **", "git diff --stat HEAD~1"));
+        assert!(rule_matches(
+            "git ** --force",
+            "git push origin main --force"
+        ));
+        assert!(!rule_matches("git ** --force", "git push --force origin"));
+        assert!(rule_matches(
+            "systemctl --user status masix",
+            "systemctl --user status masix"
+        ));
+        assert!(rule_matches(
+            "journal* -u masix*",
+            "journalctl -u masix.service"
+        ));
+        assert!(rule_matches("* **", "anything at all"));
+
+        assert!(ExecRule::parse("").is_err());
+        assert!(ExecRule::parse("** status").is_err());
+        assert!(ExecRule::parse("git '/(/'").is_err());
+    }
+}
@@ -2,6 +2,10 @@
 //!
 //! TOML configuration loading with environment variable support
 
+mod exec_rule;
+
+pub use exec_rule::ExecRule;
+
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
@@ -21,6 +25,7 @@ pub struct Config {
     pub bots: Option<BotsConfig>,
     pub exec: Option<ExecConfig>,
     pub policy: Option<PolicyConfig>,
+    pub plugins: Option<PluginsConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -41,6 +46,10 @@ pub struct CoreConfig {
     pub streaming: CoreStreamingConfig,
     #[serde(default)]
     pub cron: CoreCronConfig,
+    #[serde(default)]
+    pub fact_extraction: CoreFactExtractionConfig,
+    #[serde(default)]
+    pub tool_approval: CoreToolApprovalConfig,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -167,6 +176,62 @@ impl Default for CoreCronConfig {
     }
 }
 
+/// Post-turn extraction of durable user facts into `user_private` memory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreFactExtractionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_fact_extraction_max_facts_per_turn")]
+    pub max_facts_per_turn: u8,
+    #[serde(default = "default_fact_extraction_max_stored_facts")]
+    pub max_stored_facts: usize,
+    #[serde(default = "default_true")]
+    pub inject_into_context: bool,
+}
+
+impl Default for CoreFactExtractionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_facts_per_turn: default_fact_extraction_max_facts_per_turn(),
+            max_stored_facts: default_fact_extraction_max_stored_facts(),
+            inject_into_context: true,
+        }
+    }
+}
+
+/// Tool calls held until an admin approves them from an inline-keyboard card.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreToolApprovalConfig {
+    /// Tool names (`exec`, `write_file`, ...). A trailing `*` matches a
+    /// prefix, e.g. `mcp_github_*`.
+    #[serde(default)]
+    pub requires_confirmation: Vec<String>,
+    #[serde(default = "default_tool_approval_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for CoreToolApprovalConfig {
+    fn default() -> Self {
+        Self {
+            requires_confirmation: Vec::new(),
+            timeout_secs: default_tool_approval_timeout_secs(),
+        }
+    }
+}
+
+impl CoreToolApprovalConfig {
+    pub fn requires_confirmation(&self, tool_name: &str) -> bool {
+        self.requires_confirmation
+            .iter()
+            .map(|entry| entry.trim())
+            .any(|entry| match entry.strip_suffix('*') {
+                Some(prefix) => tool_name.starts_with(prefix),
+                None => entry == tool_name,
+            })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdatesConfig {
     #[serde(default = "default_true")]
@@ -193,6 +258,36 @@ impl Default for UpdatesConfig {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryShareScope {
+    UserPrivate,
+    SharedUserKb,
+    AdminKb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryShareAccess {
+    #[default]
+    ReadOnly,
+    ReadWrite,
+}
+
+/// Memory scopes an account exposes to another account tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryShareConfig {
+    pub account: String,
+    #[serde(default = "default_memory_share_scopes")]
+    pub scopes: Vec<MemoryShareScope>,
+    #[serde(default)]
+    pub access: MemoryShareAccess,
+}
+
+fn default_memory_share_scopes() -> Vec<MemoryShareScope> {
+    vec![MemoryShareScope::SharedUserKb]
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum DmPolicy {
@@ -282,6 +377,90 @@ pub struct TelegramConfig {
     pub accounts: Vec<TelegramAccount>,
 }
 
+impl TelegramConfig {
+    /// Access that account `owner` grants to account `grantee` on `scope`, if any.
+    /// Isolated accounts never share nor receive memory.
+    pub fn memory_share_access(
+        &self,
+        owner: &str,
+        grantee: &str,
+        scope: MemoryShareScope,
+    ) -> Option<MemoryShareAccess> {
+        if owner == grantee {
+            return None;
+        }
+        let find = |tag: &str| {
+            self.accounts
+                .iter()
+                .find(|account| telegram_account_tag(&account.bot_token) == tag)
+        };
+        let owner_account = find(owner)?;
+        let grantee_account = find(grantee)?;
+        if owner_account.isolated || grantee_account.isolated {
+            return None;
+        }
+
+        let mut access = None;
+        if scope == MemoryShareScope::SharedUserKb
+            && owner_account
+                .shared_memory_with
+                .iter()
+                .any(|tag| tag.trim() == grantee)
+        {
+            access = Some(MemoryShareAccess::ReadOnly);
+        }
+        for share in &owner_account.memory_shares {
+            if share.account.trim() == grantee && share.scopes.contains(&scope) {
+                access = Some(match (access, share.access) {
+                    (Some(MemoryShareAccess::ReadWrite), _) | (_, MemoryShareAccess::ReadWrite) => {
+                        MemoryShareAccess::ReadWrite
+                    }
+                    _ => MemoryShareAccess::ReadOnly,
+                });
+            }
+        }
+        access
+    }
+
+    /// Legacy `shared_memory_with` entries that have no effect (isolated,
+    /// unknown or self accounts). Reported as warnings so existing configs
+    /// keep loading.
+    pub fn legacy_memory_share_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for account in &self.accounts {
+            let owner = telegram_account_tag(&account.bot_token);
+            if account.shared_memory_with.is_empty() {
+                continue;
+            }
+            if account.isolated {
+                warnings.push(format!(
+                    "Telegram account '{}' sets shared_memory_with but is isolated; ignored (set isolated = false to share)",
+                    owner
+                ));
+                continue;
+            }
+            for target in &account.shared_memory_with {
+                let target = target.trim();
+                let grantee = self
+                    .accounts
+                    .iter()
+                    .find(|a| telegram_account_tag(&a.bot_token) == target);
+                let problem = match grantee {
+                    _ if target == owner => "itself",
+                    None => "an unknown account",
+                    Some(grantee) if grantee.isolated => "an isolated account",
+                    Some(_) => continue,
+                };
+                warnings.push(format!(
+                    "Telegram account '{}' shared_memory_with '{}' names {}; ignored",
+                    owner, target, problem
+                ));
+            }
+        }
+        warnings
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TelegramAccount {
     pub bot_token: String,
@@ -298,8 +477,11 @@ pub struct TelegramAccount {
     pub readonly: Vec<i64>,
     #[serde(default = "default_true")]
     pub isolated: bool,
+    /// Legacy form: read-only share of `shared_user_kb` with these account tags.
     #[serde(default)]
     pub shared_memory_with: Vec<String>,
+    #[serde(default)]
+    pub memory_shares: Vec<MemoryShareConfig>,
     #[serde(default = "default_true")]
     pub allow_self_memory_edit: bool,
     #[serde(default)]
@@ -430,21 +612,210 @@ pub struct McpConfig {
     pub enabled: bool,
     #[serde(default)]
     pub servers: Vec<McpServer>,
+    /// `masix mcp serve`: builtin tools offered to other agents over stdio.
+    #[serde(default)]
+    pub serve: Option<McpServeConfig>,
+}
+
+/// Builtin tools that `masix mcp serve` can expose.
+pub const MCP_SERVABLE_TOOLS: &[&str] = &[
+    "cron",
+    "memory_read",
+    "memory_write",
+    "telegram_send",
+    "chat_context",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServeConfig {
+    /// Subset of `MCP_SERVABLE_TOOLS`; defaults to all of them.
+    #[serde(default = "default_mcp_serve_tools")]
+    pub tools: Vec<String>,
+    /// Telegram account (bot id prefix) the calls act for. Default: first account.
+    #[serde(default)]
+    pub account_tag: Option<String>,
+    /// Chat used as context for cron jobs and `chat_context`.
+    #[serde(default)]
+    pub chat_id: Option<i64>,
+    /// Telegram user the calls are attributed to (memory scopes).
+    #[serde(default)]
+    pub user_id: Option<i64>,
+    #[serde(default)]
+    pub permission: McpServePermission,
+}
+
+impl Default for McpServeConfig {
+    fn default() -> Self {
+        Self {
+            tools: default_mcp_serve_tools(),
+            account_tag: None,
+            chat_id: None,
+            user_id: None,
+            permission: McpServePermission::default(),
+        }
+    }
+}
+
+/// Permission level granted to MCP clients of `masix mcp serve`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum McpServePermission {
+    Admin,
+    #[default]
+    User,
+    Readonly,
+}
+
+impl McpServePermission {
+    pub fn level(self) -> PermissionLevel {
+        match self {
+            Self::Admin => PermissionLevel::Admin,
+            Self::User => PermissionLevel::User,
+            Self::Readonly => PermissionLevel::Readonly,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum McpTransport {
+    /// Local process over stdin/stdout.
+    Stdio,
+    /// MCP Streamable HTTP (single endpoint, JSON or SSE responses).
+    Http,
+    /// Legacy HTTP+SSE transport (GET event stream, POST to announced endpoint).
+    Sse,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServer {
     pub name: String,
+    #[serde(default)]
     pub command: String,
+    #[serde(default)]
     pub args: Vec<String>,
     #[serde(default)]
     pub env: std::collections::HashMap<String, String>,
+    /// Remote endpoint; when set the server is reached over HTTP instead of spawning `command`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Defaults to `http` when `url` is set, `stdio` otherwise.
+    #[serde(default)]
+    pub transport: Option<McpTransport>,
+    #[serde(default)]
+    pub headers: std::collections::HashMap<String, String>,
+    /// Sent as `Authorization: Bearer <token>` on remote transports.
+    #[serde(default)]
+    pub auth_token: Option<String>,
     #[serde(default = "default_mcp_timeout")]
     pub timeout_secs: u64,
+    /// `tools/call` timeout overrides by tool name (server-side name), in seconds.
+    #[serde(default)]
+    pub tool_timeouts: std::collections::HashMap<String, u64>,
+    /// Requests allowed in flight at once; further calls wait for a free slot.
+    #[serde(default)]
+    pub max_in_flight: Option<usize>,
     #[serde(default = "default_mcp_startup_timeout")]
     pub startup_timeout_secs: u64,
     #[serde(default = "default_mcp_healthcheck_interval")]
     pub healthcheck_interval_secs: u64,
+    /// How long the `tools/list` result is reused; 0 queries the server on every message.
+    #[serde(default = "default_mcp_tool_cache_ttl")]
+    pub tool_cache_ttl_secs: u64,
+    /// Restarts allowed after the process exits; with 0 an exited server is
+    /// marked failed right away instead of being restarted.
+    #[serde(default = "default_mcp_max_restarts")]
+    pub max_restarts: u32,
+    #[serde(default = "default_mcp_restart_backoff")]
+    pub restart_backoff_secs: u64,
+    #[serde(default = "default_mcp_restart_backoff_max")]
+    pub restart_backoff_max_secs: u64,
+    /// Process sandbox for stdio servers; `None` runs the server unrestricted.
+    #[serde(default)]
+    pub sandbox: Option<McpSandboxConfig>,
+}
+
+/// `[mcp.servers.sandbox]`: limits applied to a stdio server process.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct McpSandboxConfig {
+    #[serde(default)]
+    pub workdir: Option<String>,
+    /// Parent environment variables passed through; unset inherits everything.
+    #[serde(default)]
+    pub env_allowlist: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_cpu_secs: Option<u64>,
+    #[serde(default)]
+    pub max_memory_mb: Option<u64>,
+    #[serde(default)]
+    pub max_open_files: Option<u64>,
+    #[serde(default = "default_true")]
+    pub no_new_privileges: bool,
+    /// Linux Landlock: filesystem read-only except `workdir` and `writable_paths`.
+    #[serde(default)]
+    pub landlock: bool,
+    #[serde(default)]
+    pub writable_paths: Vec<String>,
+    /// Linux: private user + network namespace (no network access).
+    #[serde(default)]
+    pub isolate_network: bool,
+}
+
+impl Default for McpSandboxConfig {
+    fn default() -> Self {
+        Self {
+            workdir: None,
+            env_allowlist: None,
+            max_cpu_secs: None,
+            max_memory_mb: None,
+            max_open_files: None,
+            no_new_privileges: true,
+            landlock: false,
+            writable_paths: Vec::new(),
+            isolate_network: false,
+        }
+    }
+}
+
+/// Variables plugin-installed servers inherit by default.
+pub const MCP_PLUGIN_ENV_ALLOWLIST: &[&str] = &[
+    "PATH", "HOME", "USER", "LANG", "LC_ALL", "TERM", "TMPDIR", "PREFIX",
+];
+
+impl McpSandboxConfig {
+    /// Default sandbox for a plugin-installed server living in `workdir`.
+    pub fn for_plugin(workdir: &str) -> Self {
+        Self {
+            workdir: Some(workdir.to_string()),
+            env_allowlist: Some(
+                MCP_PLUGIN_ENV_ALLOWLIST
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+            ),
+            max_open_files: Some(1024),
+            ..Self::default()
+        }
+    }
+}
+
+impl McpServer {
+    pub fn effective_transport(&self) -> McpTransport {
+        match self.transport {
+            Some(transport) => transport,
+            None if self.url.is_some() => McpTransport::Http,
+            None => McpTransport::Stdio,
+        }
+    }
+
+    /// Human-readable target: the command line for stdio, the URL for remote servers.
+    pub fn endpoint_label(&self) -> String {
+        match self.effective_transport() {
+            McpTransport::Stdio => format!("{} {:?}", self.command, self.args),
+            McpTransport::Http => format!("http {}", self.url.as_deref().unwrap_or("")),
+            McpTransport::Sse => format!("sse {}", self.url.as_deref().unwrap_or("")),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -463,6 +834,10 @@ pub struct ProviderConfig {
     pub model: Option<String>,
     #[serde(default)]
     pub provider_type: Option<String>,
+    /// OpenAI-compatible only: the model accepts image input, so images returned by
+    /// tools are forwarded to it (Anthropic always receives them).
+    #[serde(default)]
+    pub supports_images: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -513,6 +888,99 @@ pub struct ExecConfig {
     pub base_allowlist: Vec<String>,
     #[serde(default)]
     pub termux_allowlist: Vec<String>,
+    /// Sandboxed pipeline mode for base commands; unset keeps single commands.
+    #[serde(default)]
+    pub pipeline: Option<ExecPipelineConfig>,
+    /// Argument-level allow/deny rules for base commands.
+    #[serde(default)]
+    pub rules: Option<ExecRulesConfig>,
+    /// Background jobs started with `/exec --bg` or the `exec_bg` tool.
+    #[serde(default)]
+    pub jobs: Option<ExecJobsConfig>,
+}
+
+/// `[exec.jobs]`: long-running commands in the background, with output
+/// streamed to the chat. Unset fields use the `masix-exec` defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ExecJobsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    pub max_running: Option<usize>,
+    pub max_runtime_secs: Option<u64>,
+    pub max_log_bytes: Option<u64>,
+    /// Finished jobs kept in `/jobs` (and their logs).
+    pub keep_finished: Option<usize>,
+    /// Seconds between edits of the job message in the chat.
+    pub update_interval_secs: Option<u64>,
+}
+
+/// `[exec.rules]`: entries like `git log --oneline -n *` matched against the
+/// argument vector (`*`/`?` globs, `/regex/`, `**` for any remaining
+/// arguments). Deny rules win over allow rules; with no allow rules the
+/// program allowlist applies.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ExecRulesConfig {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Per permission level: `allow` replaces the rules above, `deny` adds to them.
+    pub admin: Option<ExecRuleSetConfig>,
+    pub user: Option<ExecRuleSetConfig>,
+    pub readonly: Option<ExecRuleSetConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ExecRuleSetConfig {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl ExecRulesConfig {
+    /// `(section, allow, deny)` for the default set and each configured level.
+    pub fn sets(&self) -> Vec<(&'static str, &[String], &[String])> {
+        let mut sets = vec![("default", self.allow.as_slice(), self.deny.as_slice())];
+        for (level, set) in [
+            ("admin", &self.admin),
+            ("user", &self.user),
+            ("readonly", &self.readonly),
+        ] {
+            if let Some(set) = set {
+                sets.push((level, set.allow.as_slice(), set.deny.as_slice()));
+            }
+        }
+        sets
+    }
+}
+
+/// Syntax check for one exec rule with the parser `masix-exec` uses too, so
+/// config validation and the runtime accept exactly the same rules.
+fn validate_exec_rule(rule: &str) -> anyhow::Result<()> {
+    ExecRule::parse(rule).map(|_| ())
+}
+
+/// `[exec.pipeline]`: base commands may chain allowlisted programs with pipes
+/// and redirect into the workdir, inside a sandbox. Unset fields use the
+/// `masix-exec` defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ExecPipelineConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Extra programs allowed only in pipeline mode (besides `base_allowlist`).
+    pub allowlist: Option<Vec<String>>,
+    /// Absolute paths the stages may read.
+    #[serde(default)]
+    pub read_only_paths: Vec<String>,
+    pub max_stages: Option<usize>,
+    pub max_cpu_secs: Option<u64>,
+    pub max_memory_mb: Option<u64>,
+    pub max_file_size_mb: Option<u64>,
+    /// Linux: stages run in a private network namespace unless true.
+    pub allow_network: Option<bool>,
+    /// Linux Landlock filesystem ruleset (default true).
+    pub landlock: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -527,6 +995,89 @@ pub struct RateLimitConfig {
     pub messages_per_minute: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// Refuse packages without a valid signature from a trusted publisher
+    /// unless the install is run with `--allow-unsigned`. Default: true.
+    #[serde(default = "default_true")]
+    pub require_signatures: bool,
+    #[serde(default)]
+    pub trusted_publishers: Vec<PluginPublisherKey>,
+    #[serde(default)]
+    pub wasm: WasmPluginsConfig,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            require_signatures: true,
+            trusted_publishers: Vec::new(),
+            wasm: WasmPluginsConfig::default(),
+        }
+    }
+}
+
+/// Limits and host capabilities for `wasm_tool` plugins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmPluginsConfig {
+    /// Fuel (instruction budget) per tool call. Default: 50_000_000.
+    #[serde(default = "default_wasm_fuel")]
+    pub fuel: u64,
+    /// Linear memory cap per instance. Default: 32.
+    #[serde(default = "default_wasm_max_memory_mb")]
+    pub max_memory_mb: u32,
+    /// Wall-clock limit per tool call. Default: 30.
+    #[serde(default = "default_wasm_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Capabilities per plugin id; plugins without an entry get none.
+    #[serde(default)]
+    pub grants: HashMap<String, WasmPluginGrant>,
+}
+
+impl Default for WasmPluginsConfig {
+    fn default() -> Self {
+        Self {
+            fuel: default_wasm_fuel(),
+            max_memory_mb: default_wasm_max_memory_mb(),
+            timeout_secs: default_wasm_timeout_secs(),
+            grants: HashMap::new(),
+        }
+    }
+}
+
+fn default_wasm_fuel() -> u64 {
+    50_000_000
+}
+
+fn default_wasm_max_memory_mb() -> u32 {
+    32
+}
+
+fn default_wasm_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WasmPluginGrant {
+    /// Hosts reachable via `http_fetch`; `*.example.com` includes subdomains.
+    #[serde(default)]
+    pub http_allow: Vec<String>,
+    /// Read/write files under `<bot workdir>/plugins/<plugin_id>`.
+    #[serde(default)]
+    pub files: bool,
+    /// Persistent key/value storage under the data dir.
+    #[serde(default)]
+    pub kv: bool,
+}
+
+/// Pinned Ed25519 publisher key. `public_key` is the base64 encoding of the
+/// 32-byte verifying key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginPublisherKey {
+    pub id: String,
+    pub public_key: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PermissionLevel {
     Admin,
@@ -698,10 +1249,12 @@ impl TelegramAccount {
         };
 
         let user_perm = self.get_permission_level(user_id);
-        if require_mention && !is_bot_tagged && user_perm != PermissionLevel::Admin {
-            if !(self.group_allow_known_untagged && user_perm != PermissionLevel::None) {
-                return PermissionLevel::None;
-            }
+        if require_mention
+            && !is_bot_tagged
+            && user_perm != PermissionLevel::Admin
+            && !(self.group_allow_known_untagged && user_perm != PermissionLevel::None)
+        {
+            return PermissionLevel::None;
         }
 
         match effective_group_policy {
@@ -797,6 +1350,29 @@ fn default_mcp_healthcheck_interval() -> u64 {
     60
 }
 
+fn default_mcp_tool_cache_ttl() -> u64 {
+    300
+}
+
+fn default_mcp_serve_tools() -> Vec<String> {
+    MCP_SERVABLE_TOOLS
+        .iter()
+        .map(|tool| tool.to_string())
+        .collect()
+}
+
+fn default_mcp_max_restarts() -> u32 {
+    5
+}
+
+fn default_mcp_restart_backoff() -> u64 {
+    1
+}
+
+fn default_mcp_restart_backoff_max() -> u64 {
+    60
+}
+
 fn default_pairing_pending_ttl_secs() -> u64 {
     3600
 }
@@ -841,6 +1417,18 @@ fn default_cron_delivery_retry_backoff_secs() -> u64 {
     10
 }
 
+fn default_fact_extraction_max_facts_per_turn() -> u8 {
+    3
+}
+
+fn default_fact_extraction_max_stored_facts() -> usize {
+    200
+}
+
+fn default_tool_approval_timeout_secs() -> u64 {
+    120
+}
+
 impl Config {
     pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
         let content = std::fs::read_to_string(path.as_ref())?;
@@ -1092,6 +1680,70 @@ impl Config {
                     );
                 }
             }
+
+            // Legacy `shared_memory_with` is not validated here: older configs
+            // set it on isolated accounts, see `legacy_memory_share_warnings`.
+            for account in &telegram.accounts {
+                let owner = telegram_account_tag(&account.bot_token);
+                let mut targets: Vec<&str> = Vec::new();
+                let mut seen_scopes = HashSet::new();
+                for share in &account.memory_shares {
+                    if share.scopes.is_empty() {
+                        anyhow::bail!(
+                            "Telegram account '{}' memory share with '{}' has no scopes",
+                            owner,
+                            share.account
+                        );
+                    }
+                    for scope in &share.scopes {
+                        if !seen_scopes.insert((share.account.trim(), *scope)) {
+                            anyhow::bail!(
+                                "Telegram account '{}' shares scope {:?} with '{}' more than once",
+                                owner,
+                                scope,
+                                share.account
+                            );
+                        }
+                    }
+                    targets.push(share.account.as_str());
+                }
+                if targets.is_empty() {
+                    continue;
+                }
+                if account.isolated {
+                    anyhow::bail!(
+                        "Telegram account '{}' shares memory but is isolated (set isolated = false)",
+                        owner
+                    );
+                }
+                for target in targets {
+                    let target = target.trim();
+                    if target == owner {
+                        anyhow::bail!(
+                            "Telegram account '{}' cannot share memory with itself",
+                            owner
+                        );
+                    }
+                    let Some(grantee) = telegram
+                        .accounts
+                        .iter()
+                        .find(|a| telegram_account_tag(&a.bot_token) == target)
+                    else {
+                        anyhow::bail!(
+                            "Telegram account '{}' shares memory with unknown account tag '{}'",
+                            owner,
+                            target
+                        );
+                    };
+                    if grantee.isolated {
+                        anyhow::bail!(
+                            "Telegram account '{}' shares memory with isolated account '{}'",
+                            owner,
+                            target
+                        );
+                    }
+                }
+            }
         }
 
         if let Some(exec) = &self.exec {
@@ -1158,10 +1810,106 @@ impl Config {
             anyhow::bail!("core.cron.delivery_retry_backoff_secs must be > 0");
         }
 
-        if let Some(mcp) = &self.mcp {
-            for server in &mcp.servers {
-                if server.timeout_secs == 0 {
-                    anyhow::bail!("mcp.servers['{}'].timeout_secs must be > 0", server.name);
+        if self.core.fact_extraction.enabled {
+            if self.core.fact_extraction.max_facts_per_turn == 0 {
+                anyhow::bail!("core.fact_extraction.max_facts_per_turn must be > 0");
+            }
+            if self.core.fact_extraction.max_stored_facts == 0 {
+                anyhow::bail!("core.fact_extraction.max_stored_facts must be > 0");
+            }
+        }
+
+        if self.core.tool_approval.timeout_secs == 0 {
+            anyhow::bail!("core.tool_approval.timeout_secs must be > 0");
+        }
+        for entry in &self.core.tool_approval.requires_confirmation {
+            let pattern = entry.trim();
+            let name = pattern.strip_suffix('*').unwrap_or(pattern);
+            if name.is_empty() || name.contains('*') {
+                anyhow::bail!(
+                    "core.tool_approval.requires_confirmation entry '{}' must be a tool name with an optional trailing '*'",
+                    entry
+                );
+            }
+        }
+
+        if let Some(pipeline) = self.exec.as_ref().and_then(|exec| exec.pipeline.as_ref()) {
+            if pipeline.max_stages == Some(0) {
+                anyhow::bail!("exec.pipeline.max_stages must be > 0");
+            }
+            if let Some(path) = pipeline
+                .read_only_paths
+                .iter()
+                .find(|path| !Path::new(path).is_absolute())
+            {
+                anyhow::bail!(
+                    "exec.pipeline.read_only_paths entry '{}' must be absolute",
+                    path
+                );
+            }
+        }
+
+        if let Some(jobs) = self.exec.as_ref().and_then(|exec| exec.jobs.as_ref()) {
+            if jobs.max_running == Some(0) {
+                anyhow::bail!("exec.jobs.max_running must be > 0");
+            }
+            if jobs.max_runtime_secs == Some(0) {
+                anyhow::bail!("exec.jobs.max_runtime_secs must be > 0");
+            }
+            if jobs.update_interval_secs == Some(0) {
+                anyhow::bail!("exec.jobs.update_interval_secs must be > 0");
+            }
+        }
+
+        if let Some(rules) = self.exec.as_ref().and_then(|exec| exec.rules.as_ref()) {
+            for (section, allow, deny) in rules.sets() {
+                for (kind, rule) in allow
+                    .iter()
+                    .map(|rule| ("allow", rule))
+                    .chain(deny.iter().map(|rule| ("deny", rule)))
+                {
+                    validate_exec_rule(rule).map_err(|e| {
+                        anyhow::anyhow!("exec.rules ({}) {} entry '{}': {}", section, kind, rule, e)
+                    })?;
+                }
+            }
+        }
+
+        if let Some(mcp) = &self.mcp {
+            for server in &mcp.servers {
+                match server.effective_transport() {
+                    McpTransport::Stdio => {
+                        if server.command.trim().is_empty() {
+                            anyhow::bail!(
+                                "mcp.servers['{}'] needs `command` (or `url` for a remote server)",
+                                server.name
+                            );
+                        }
+                        if server.url.is_some() {
+                            anyhow::bail!(
+                                "mcp.servers['{}'] uses transport = \"stdio\" but sets `url`",
+                                server.name
+                            );
+                        }
+                    }
+                    McpTransport::Http | McpTransport::Sse => {
+                        let url = server.url.as_deref().unwrap_or("").trim();
+                        if !(url.starts_with("http://") || url.starts_with("https://")) {
+                            anyhow::bail!(
+                                "mcp.servers['{}'] needs an http(s) `url` for remote transports",
+                                server.name
+                            );
+                        }
+                        if !server.command.trim().is_empty() {
+                            anyhow::bail!(
+                                "mcp.servers['{}'] sets both `command` and `url`; pick one",
+                                server.name
+                            );
+                        }
+                    }
+                }
+                if server.timeout_secs == 0 {
+                    anyhow::bail!("mcp.servers['{}'].timeout_secs must be > 0", server.name);
                 }
                 if server.startup_timeout_secs == 0 {
                     anyhow::bail!(
@@ -1169,12 +1917,62 @@ impl Config {
                         server.name
                     );
                 }
+                if let Some((tool, _)) = server.tool_timeouts.iter().find(|(_, secs)| **secs == 0) {
+                    anyhow::bail!(
+                        "mcp.servers['{}'].tool_timeouts['{}'] must be > 0",
+                        server.name,
+                        tool
+                    );
+                }
+                if server.max_in_flight == Some(0) {
+                    anyhow::bail!("mcp.servers['{}'].max_in_flight must be > 0", server.name);
+                }
                 if server.healthcheck_interval_secs == 0 {
                     anyhow::bail!(
                         "mcp.servers['{}'].healthcheck_interval_secs must be > 0",
                         server.name
                     );
                 }
+                if let Some(sandbox) = &server.sandbox {
+                    if server.effective_transport() != McpTransport::Stdio {
+                        anyhow::bail!(
+                            "mcp.servers['{}'].sandbox applies only to stdio servers",
+                            server.name
+                        );
+                    }
+                    if [
+                        sandbox.max_cpu_secs,
+                        sandbox.max_memory_mb,
+                        sandbox.max_open_files,
+                    ]
+                    .contains(&Some(0))
+                    {
+                        anyhow::bail!(
+                            "mcp.servers['{}'].sandbox limits must be > 0 when set",
+                            server.name
+                        );
+                    }
+                }
+                if server.max_restarts > 0
+                    && (server.restart_backoff_secs == 0
+                        || server.restart_backoff_max_secs < server.restart_backoff_secs)
+                {
+                    anyhow::bail!(
+                        "mcp.servers['{}'] needs restart_backoff_secs > 0 and restart_backoff_max_secs >= restart_backoff_secs",
+                        server.name
+                    );
+                }
+            }
+            if let Some(serve) = &mcp.serve {
+                for tool in &serve.tools {
+                    if !MCP_SERVABLE_TOOLS.contains(&tool.as_str()) {
+                        anyhow::bail!(
+                            "mcp.serve.tools: '{}' cannot be served (allowed: {})",
+                            tool,
+                            MCP_SERVABLE_TOOLS.join(", ")
+                        );
+                    }
+                }
             }
         }
 
@@ -1232,13 +2030,55 @@ impl Config {
             }
         }
 
+        if let Some(plugins) = &self.plugins {
+            let mut publisher_ids = HashSet::new();
+            for publisher in &plugins.trusted_publishers {
+                let id = publisher.id.trim();
+                if id.is_empty() || id.contains(':') {
+                    anyhow::bail!(
+                        "plugins.trusted_publishers id '{}' must be non-empty and cannot contain ':'",
+                        publisher.id
+                    );
+                }
+                if !publisher_ids.insert(id.to_string()) {
+                    anyhow::bail!("Duplicate plugins.trusted_publishers id '{}'", id);
+                }
+                if publisher.public_key.trim().is_empty() {
+                    anyhow::bail!(
+                        "plugins.trusted_publishers '{}' has an empty public_key",
+                        id
+                    );
+                }
+            }
+
+            let wasm = &plugins.wasm;
+            if wasm.fuel == 0 || wasm.max_memory_mb == 0 || wasm.timeout_secs == 0 {
+                anyhow::bail!("plugins.wasm fuel, max_memory_mb and timeout_secs must be > 0");
+            }
+            for (plugin_id, grant) in &wasm.grants {
+                if let Some(host) = grant.http_allow.iter().find(|host| {
+                    let host = host.trim().trim_start_matches("*.");
+                    host.is_empty() || host.contains(['/', ':', '*'])
+                }) {
+                    anyhow::bail!(
+                        "plugins.wasm.grants.{} has an invalid http_allow host '{}' (use 'example.com' or '*.example.com')",
+                        plugin_id,
+                        host
+                    );
+                }
+            }
+        }
+
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{AccessMode, Config, DmPolicy, GroupPolicy, PermissionLevel, TelegramAccount};
+    use super::{
+        AccessMode, Config, DmPolicy, GroupPolicy, McpServePermission, McpTransport,
+        MemoryShareAccess, MemoryShareScope, PermissionLevel, TelegramAccount, MCP_SERVABLE_TOOLS,
+    };
     use std::time::{SystemTime, UNIX_EPOCH};
 
     fn parse_config(input: &str) -> Config {
@@ -1355,6 +2195,7 @@ api_key = "k"
             readonly: vec![3],
             isolated: true,
             shared_memory_with: Vec::new(),
+            memory_shares: Vec::new(),
             allow_self_memory_edit: true,
             dm_policy: DmPolicy::Allowlist,
             dm_allow_from: vec![],
@@ -1731,6 +2572,106 @@ timeout_secs = 0
         assert!(cfg.validate().is_err());
     }
 
+    #[test]
+    fn mcp_tool_timeouts_and_in_flight_limit() {
+        let base = r#"
+[core]
+
+[providers]
+default_provider = "openai"
+
+[[providers.providers]]
+name = "openai"
+api_key = "k"
+
+[mcp]
+[[mcp.servers]]
+name = "test"
+command = "echo"
+max_in_flight = 2
+
+[mcp.servers.tool_timeouts]
+deep_research = 600
+"#;
+        let cfg = parse_config(base);
+        cfg.validate().unwrap();
+        let server = &cfg.mcp.as_ref().unwrap().servers[0];
+        assert_eq!(server.tool_timeouts.get("deep_research"), Some(&600));
+        assert_eq!(server.max_in_flight, Some(2));
+
+        let zero_timeout = parse_config(&base.replace("= 600", "= 0"));
+        assert!(zero_timeout.validate().is_err());
+        let zero_slots = parse_config(&base.replace("max_in_flight = 2", "max_in_flight = 0"));
+        assert!(zero_slots.validate().is_err());
+    }
+
+    #[test]
+    fn plugin_trusted_publishers_are_validated() {
+        let base = r#"
+[core]
+
+[providers]
+default_provider = "openai"
+
+[[providers.providers]]
+name = "openai"
+api_key = "k"
+
+[plugins]
+[[plugins.trusted_publishers]]
+id = "masix"
+public_key = "ZmFrZQ=="
+"#;
+        let cfg = parse_config(base);
+        cfg.validate().unwrap();
+        let plugins = cfg.plugins.as_ref().unwrap();
+        assert!(plugins.require_signatures);
+        assert_eq!(plugins.trusted_publishers[0].id, "masix");
+
+        let duplicated = format!(
+            "{}\n[[plugins.trusted_publishers]]\nid = \"masix\"\npublic_key = \"b3RoZXI=\"\n",
+            base
+        );
+        assert!(parse_config(&duplicated).validate().is_err());
+        let bad_id = parse_config(&base.replace("id = \"masix\"", "id = \"ma:six\""));
+        assert!(bad_id.validate().is_err());
+    }
+
+    #[test]
+    fn plugin_wasm_grants_default_to_nothing_and_are_validated() {
+        let base = r#"
+[core]
+
+[providers]
+default_provider = "openai"
+
+[[providers.providers]]
+name = "openai"
+api_key = "k"
+
+[plugins.wasm]
+fuel = 1000
+
+[plugins.wasm.grants.weather]
+http_allow = ["api.weather.io", "*.example.com"]
+kv = true
+"#;
+        let cfg = parse_config(base);
+        cfg.validate().unwrap();
+        let wasm = &cfg.plugins.as_ref().unwrap().wasm;
+        assert_eq!(wasm.fuel, 1000);
+        assert_eq!(wasm.max_memory_mb, 32);
+        let grant = &wasm.grants["weather"];
+        assert!(grant.kv && !grant.files);
+        assert!(!wasm.grants.contains_key("other"));
+        assert!(super::PluginsConfig::default().wasm.grants.is_empty());
+
+        let bad_host = parse_config(&base.replace("api.weather.io", "https://api.weather.io/"));
+        assert!(bad_host.validate().is_err());
+        let zero_fuel = parse_config(&base.replace("fuel = 1000", "fuel = 0"));
+        assert!(zero_fuel.validate().is_err());
+    }
+
     #[test]
     fn validate_rejects_zero_agent_loop_auto_continue_max() {
         let cfg = parse_config(
@@ -1790,6 +2731,323 @@ api_key = "k"
         assert!(cfg.validate().is_err());
     }
 
+    fn memory_share_config(account_a_extra: &str, account_b_extra: &str) -> Config {
+        parse_config(&format!(
+            r#"
+[providers]
+default_provider = "openai"
+
+[[providers.providers]]
+name = "openai"
+api_key = "k"
+
+[telegram]
+[[telegram.accounts]]
+bot_token = "111:AAA"
+{}
+
+[[telegram.accounts]]
+bot_token = "222:BBB"
+{}
+"#,
+            account_a_extra, account_b_extra
+        ))
+    }
+
+    #[test]
+    fn validate_memory_share_combinations() {
+        let valid = [
+            ("isolated = false\nshared_memory_with = [\"222\"]", "isolated = false"),
+            (
+                "isolated = false\nmemory_shares = [{ account = \"222\", scopes = [\"admin_kb\", \"user_private\"], access = \"read_write\" }]",
+                "isolated = false",
+            ),
+            ("", ""),
+        ];
+        for (a, b) in valid {
+            assert!(
+                memory_share_config(a, b).validate().is_ok(),
+                "expected valid: {} / {}",
+                a,
+                b
+            );
+        }
+
+        let invalid = [
+            // owner isolated (default)
+            ("memory_shares = [{ account = \"222\" }]", "isolated = false"),
+            // grantee isolated
+            (
+                "isolated = false\nmemory_shares = [{ account = \"222\" }]",
+                "isolated = true",
+            ),
+            // unknown account tag
+            (
+                "isolated = false\nmemory_shares = [{ account = \"999\" }]",
+                "isolated = false",
+            ),
+            // self share
+            (
+                "isolated = false\nmemory_shares = [{ account = \"111\" }]",
+                "isolated = false",
+            ),
+            // empty scope list
+            (
+                "isolated = false\nmemory_shares = [{ account = \"222\", scopes = [] }]",
+                "isolated = false",
+            ),
+            // duplicated scope for the same grantee
+            (
+                "isolated = false\nmemory_shares = [{ account = \"222\" }, { account = \"222\", access = \"read_write\" }]",
+                "isolated = false",
+            ),
+        ];
+        for (a, b) in invalid {
+            assert!(
+                memory_share_config(a, b).validate().is_err(),
+                "expected invalid: {} / {}",
+                a,
+                b
+            );
+        }
+
+        // Legacy shares without effect only warn, so older configs still load.
+        let legacy = [
+            (
+                "shared_memory_with = [\"222\"]",
+                "isolated = false",
+                "is isolated",
+            ),
+            (
+                "isolated = false\nshared_memory_with = [\"222\"]",
+                "isolated = true",
+                "an isolated account",
+            ),
+            (
+                "isolated = false\nshared_memory_with = [\"999\"]",
+                "isolated = false",
+                "an unknown account",
+            ),
+            (
+                "isolated = false\nshared_memory_with = [\"111\"]",
+                "isolated = false",
+                "itself",
+            ),
+        ];
+        for (a, b, warning) in legacy {
+            let cfg = memory_share_config(a, b);
+            assert!(cfg.validate().is_ok(), "expected valid: {} / {}", a, b);
+            let warnings = cfg
+                .telegram
+                .as_ref()
+                .unwrap()
+                .legacy_memory_share_warnings();
+            assert_eq!(warnings.len(), 1, "{:?}", warnings);
+            assert!(warnings[0].contains(warning), "{:?}", warnings);
+        }
+        let shared = memory_share_config(
+            "isolated = false\nshared_memory_with = [\"222\"]",
+            "isolated = false",
+        );
+        assert!(shared
+            .telegram
+            .as_ref()
+            .unwrap()
+            .legacy_memory_share_warnings()
+            .is_empty());
+    }
+
+    #[test]
+    fn memory_share_access_resolution() {
+        let cfg = memory_share_config(
+            "isolated = false\nshared_memory_with = [\"222\"]\nmemory_shares = [{ account = \"222\", scopes = [\"admin_kb\"], access = \"read_write\" }]",
+            "isolated = false",
+        );
+        let telegram = cfg.telegram.as_ref().unwrap();
+        assert_eq!(
+            telegram.memory_share_access("111", "222", MemoryShareScope::SharedUserKb),
+            Some(MemoryShareAccess::ReadOnly)
+        );
+        assert_eq!(
+            telegram.memory_share_access("111", "222", MemoryShareScope::AdminKb),
+            Some(MemoryShareAccess::ReadWrite)
+        );
+        assert_eq!(
+            telegram.memory_share_access("111", "222", MemoryShareScope::UserPrivate),
+            None
+        );
+        // Shares are directional and never apply to the owner itself.
+        assert_eq!(
+            telegram.memory_share_access("222", "111", MemoryShareScope::SharedUserKb),
+            None
+        );
+        assert_eq!(
+            telegram.memory_share_access("111", "111", MemoryShareScope::SharedUserKb),
+            None
+        );
+
+        let isolated = memory_share_config("shared_memory_with = [\"222\"]", "isolated = false");
+        assert_eq!(
+            isolated.telegram.as_ref().unwrap().memory_share_access(
+                "111",
+                "222",
+                MemoryShareScope::SharedUserKb
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn validate_rejects_zero_fact_extraction_limits_when_enabled() {
+        let cfg = parse_config(
+            r#"
+[core]
+[core.fact_extraction]
+enabled = true
+max_facts_per_turn = 0
+
+[providers]
+default_provider = "openai"
+
+[[providers.providers]]
+name = "openai"
+api_key = "k"
+"#,
+        );
+        assert!(cfg.validate().is_err());
+
+        let disabled = parse_config(
+            r#"
+[core]
+[core.fact_extraction]
+enabled = false
+max_facts_per_turn = 0
+"#,
+        );
+        assert!(disabled.validate().is_ok());
+        assert!(disabled.core.fact_extraction.inject_into_context);
+    }
+
+    #[test]
+    fn tool_approval_matches_names_and_prefixes() {
+        let cfg = parse_config(
+            r#"
+[core]
+[core.tool_approval]
+requires_confirmation = ["exec", "mcp_github_*"]
+"#,
+        );
+        assert!(cfg.validate().is_ok());
+        let approval = &cfg.core.tool_approval;
+        assert_eq!(approval.timeout_secs, 120);
+        assert!(approval.requires_confirmation("exec"));
+        assert!(approval.requires_confirmation("mcp_github_create_issue"));
+        assert!(!approval.requires_confirmation("exec_bg"));
+        assert!(!approval.requires_confirmation("write_file"));
+        assert!(!parse_config("[core]\n")
+            .core
+            .tool_approval
+            .requires_confirmation("exec"));
+
+        for bad in [
+            "[core.tool_approval]\nrequires_confirmation = [\"*\"]",
+            "[core.tool_approval]\nrequires_confirmation = [\"mcp_*_issue\"]",
+            "[core.tool_approval]\ntimeout_secs = 0",
+        ] {
+            let cfg = parse_config(&format!("[core]\n{}\n", bad));
+            assert!(cfg.validate().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn exec_pipeline_section_parses_and_validates() {
+        let cfg = parse_config(
+            r#"
+[core]
+[exec]
+enabled = true
+allow_base = true
+[exec.pipeline]
+enabled = true
+allowlist = ["grep", "sort"]
+read_only_paths = ["/var/log"]
+max_memory_mb = 128
+"#,
+        );
+        assert!(cfg.validate().is_ok());
+        let pipeline = cfg.exec.unwrap().pipeline.unwrap();
+        assert!(pipeline.enabled);
+        assert_eq!(pipeline.allowlist.unwrap(), vec!["grep", "sort"]);
+        assert_eq!(pipeline.max_memory_mb, Some(128));
+        assert_eq!(pipeline.allow_network, None);
+
+        for bad in ["max_stages = 0", "read_only_paths = [\"logs\"]"] {
+            let cfg = parse_config(&format!("[core]\n[exec.pipeline]\n{}\n", bad));
+            assert!(cfg.validate().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn exec_jobs_section_parses_and_validates() {
+        let cfg = parse_config(
+            r#"
+[core]
+[exec.jobs]
+enabled = true
+max_runtime_secs = 1800
+"#,
+        );
+        assert!(cfg.validate().is_ok());
+        let jobs = cfg.exec.unwrap().jobs.unwrap();
+        assert!(jobs.enabled);
+        assert_eq!(jobs.max_runtime_secs, Some(1800));
+        assert_eq!(jobs.max_running, None);
+
+        for bad in [
+            "max_running = 0",
+            "max_runtime_secs = 0",
+            "update_interval_secs = 0",
+        ] {
+            let cfg = parse_config(&format!("[core]\n[exec.jobs]\n{}\n", bad));
+            assert!(cfg.validate().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn exec_rules_section_parses_and_validates() {
+        let cfg = parse_config(
+            r#"
+[core]
+[exec.rules]
+deny = ["git push **"]
+[exec.rules.admin]
+allow = ["git status", "git log --oneline -n *", "systemctl --user status masix"]
+[exec.rules.readonly]
+deny = ["* **"]
+"#,
+        );
+        assert!(cfg.validate().is_ok());
+        let rules = cfg.exec.unwrap().rules.unwrap();
+        assert!(rules.allow.is_empty());
+        assert_eq!(rules.admin.as_ref().unwrap().allow.len(), 3);
+        assert!(rules.user.is_none());
+        let sections: Vec<&str> = rules.sets().iter().map(|set| set.0).collect();
+        assert_eq!(sections, vec!["default", "admin", "readonly"]);
+
+        for bad in [
+            "allow = [\"\"]",
+            "deny = [\"** x\"]",
+            "allow = [\"git '/(/'\"]",
+        ] {
+            let cfg = parse_config(&format!("[core]\n[exec.rules]\n{}\n", bad));
+            assert!(cfg.validate().is_err(), "{}", bad);
+        }
+        // Errors come from the runtime parser in masix-exec.
+        let cfg = parse_config("[core]\n[exec.rules]\nallow = [\"git '/(/'\"]\n");
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("Invalid regex '/(/'"), "{}", err);
+    }
+
     #[test]
     fn validate_rejects_zero_mcp_startup_timeout() {
         let cfg = parse_config(
@@ -1815,4 +3073,150 @@ healthcheck_interval_secs = 60
         );
         assert!(cfg.validate().is_err());
     }
+
+    #[test]
+    fn mcp_restart_policy_defaults_and_validation() {
+        let base = r#"
+[core]
+
+[providers]
+default_provider = "openai"
+
+[[providers.providers]]
+name = "openai"
+api_key = "k"
+
+[mcp]
+[[mcp.servers]]
+name = "test"
+command = "echo"
+args = []
+"#;
+        let cfg = parse_config(base);
+        cfg.validate().unwrap();
+        let server = &cfg.mcp.as_ref().unwrap().servers[0];
+        assert_eq!(server.max_restarts, 5);
+        assert_eq!(server.restart_backoff_secs, 1);
+        assert_eq!(server.restart_backoff_max_secs, 60);
+        assert_eq!(server.tool_cache_ttl_secs, 300);
+
+        let inverted = parse_config(&format!(
+            "{}restart_backoff_secs = 30\nrestart_backoff_max_secs = 10\n",
+            base
+        ));
+        assert!(inverted.validate().is_err());
+
+        let disabled = parse_config(&format!(
+            "{}max_restarts = 0\nrestart_backoff_secs = 0\n",
+            base
+        ));
+        disabled.validate().unwrap();
+    }
+
+    #[test]
+    fn mcp_serve_defaults_and_tool_allowlist() {
+        let base = r#"
+[core]
+
+[providers]
+default_provider = "openai"
+
+[[providers.providers]]
+name = "openai"
+api_key = "k"
+
+[mcp.serve]
+"#;
+        let cfg = parse_config(base);
+        cfg.validate().unwrap();
+        let serve = cfg.mcp.as_ref().unwrap().serve.as_ref().unwrap();
+        assert_eq!(serve.tools.len(), MCP_SERVABLE_TOOLS.len());
+        assert_eq!(serve.permission, McpServePermission::User);
+
+        let narrowed = parse_config(&format!(
+            "{}tools = [\"cron\"]\npermission = \"admin\"\nchat_id = 42\n",
+            base
+        ));
+        narrowed.validate().unwrap();
+        let serve = narrowed.mcp.as_ref().unwrap().serve.as_ref().unwrap();
+        assert_eq!(serve.tools, vec!["cron".to_string()]);
+        assert_eq!(serve.permission.level(), PermissionLevel::Admin);
+        assert_eq!(serve.chat_id, Some(42));
+
+        let exec = parse_config(&format!("{}tools = [\"exec\"]\n", base));
+        assert!(exec.validate().is_err());
+    }
+
+    #[test]
+    fn mcp_sandbox_parses_and_validates() {
+        let base = r#"
+[core]
+
+[providers]
+default_provider = "openai"
+
+[[providers.providers]]
+name = "openai"
+api_key = "k"
+
+[mcp]
+[[mcp.servers]]
+name = "test"
+command = "echo"
+
+[mcp.servers.sandbox]
+workdir = "/tmp/mcp-test"
+env_allowlist = ["PATH"]
+max_memory_mb = 512
+"#;
+        let cfg = parse_config(base);
+        cfg.validate().unwrap();
+        let sandbox = cfg.mcp.as_ref().unwrap().servers[0]
+            .sandbox
+            .clone()
+            .unwrap();
+        assert_eq!(sandbox.workdir.as_deref(), Some("/tmp/mcp-test"));
+        assert_eq!(sandbox.env_allowlist, Some(vec!["PATH".to_string()]));
+        assert_eq!(sandbox.max_memory_mb, Some(512));
+        assert!(sandbox.no_new_privileges);
+        assert!(!sandbox.landlock);
+
+        let zero = parse_config(&format!("{}max_open_files = 0\n", base));
+        assert!(zero.validate().is_err());
+
+        let remote = parse_config(&base.replace("command = \"echo\"", "url = \"https://x\""));
+        assert!(remote.validate().is_err());
+    }
+
+    #[test]
+    fn mcp_remote_transport_validation() {
+        let server = |body: &str| {
+            parse_config(&format!(
+                "[core]\n[providers]\ndefault_provider = \"openai\"\n[[providers.providers]]\nname = \"openai\"\napi_key = \"k\"\n[mcp]\n[[mcp.servers]]\nname = \"remote\"\n{}",
+                body
+            ))
+        };
+
+        let http = server("url = \"https://mcp.example.com/mcp\"\nauth_token = \"t\"\n");
+        http.validate().unwrap();
+        let entry = &http.mcp.as_ref().unwrap().servers[0];
+        assert_eq!(entry.effective_transport(), McpTransport::Http);
+        assert_eq!(entry.endpoint_label(), "http https://mcp.example.com/mcp");
+
+        let sse = server("url = \"http://127.0.0.1:9000/sse\"\ntransport = \"sse\"\n");
+        sse.validate().unwrap();
+        assert_eq!(
+            sse.mcp.as_ref().unwrap().servers[0].effective_transport(),
+            McpTransport::Sse
+        );
+
+        assert!(server("").validate().is_err());
+        assert!(server("url = \"ftp://host/mcp\"\n").validate().is_err());
+        assert!(server("url = \"https://host/mcp\"\ncommand = \"npx\"\n")
+            .validate()
+            .is_err());
+        assert!(server("transport = \"sse\"\ncommand = \"npx\"\n")
+            .validate()
+            .is_err());
+    }
 }
@@ -18,7 +18,7 @@ pub fn get_builtin_tool_definitions() -> Vec<ToolDefinition> {
             tool_type: "function".to_string(),
             function: masix_providers::FunctionDefinition {
                 name: "exec".to_string(),
-                description: "Execute a shell command in the workdir. Only safe commands are allowed (pwd, ls, whoami, date, uname, uptime, df, du, free, head, tail, wc).".to_string(),
+                description: "Execute a shell command in the workdir. Only safe commands are allowed (pwd, ls, whoami, date, uname, uptime, df, du, free, head, tail, wc). If the sandboxed pipeline mode is enabled, allowlisted programs can be chained with | and redirected with <, >, >> and 2>&1 inside the workdir (no shell: no variables or globbing).".to_string(),
                 parameters: serde_json::json!({
                     "type": "object",
                     "properties": {
@@ -31,6 +31,23 @@ pub fn get_builtin_tool_definitions() -> Vec<ToolDefinition> {
                 }),
             },
         },
+        ToolDefinition {
+            tool_type: "function".to_string(),
+            function: masix_providers::FunctionDefinition {
+                name: "exec_bg".to_string(),
+                description: "Start a long-running command (build, backup) as a background job with the same rules as exec. Returns the job id at once; output is streamed to the chat and the admin can use /jobs tail <id> or /jobs kill <id>.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "command": {
+                            "type": "string",
+                            "description": "The command to run in the background (e.g., 'cargo build --release')"
+                        }
+                    },
+                    "required": ["command"]
+                }),
+            },
+        },
         ToolDefinition {
             tool_type: "function".to_string(),
             function: masix_providers::FunctionDefinition {
@@ -122,6 +139,10 @@ pub fn get_builtin_tool_definitions() -> Vec<ToolDefinition> {
                         "target_user_id": {
                             "type": "integer",
                             "description": "Optional target user id for admin reads in user_private scope"
+                        },
+                        "account": {
+                            "type": "string",
+                            "description": "Optional owner account tag, for scopes another account shares with this bot"
                         }
                     },
                     "required": ["scope"]
@@ -151,12 +172,79 @@ pub fn get_builtin_tool_definitions() -> Vec<ToolDefinition> {
                         "target_user_id": {
                             "type": "integer",
                             "description": "Optional target user id for admin writes in user_private scope"
+                        },
+                        "account": {
+                            "type": "string",
+                            "description": "Optional owner account tag, for scopes another account shares with this bot"
                         }
                     },
                     "required": ["scope", "content"]
                 }),
             },
         },
+        ToolDefinition {
+            tool_type: "function".to_string(),
+            function: masix_providers::FunctionDefinition {
+                name: "kb_search".to_string(),
+                description: "Search ingested knowledge base documents (PDF, Markdown, text, HTML). Returns the most relevant chunks with document ids.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Keywords to search for"
+                        },
+                        "scope": {
+                            "type": "string",
+                            "description": "Optional scope: shared_user_kb | admin_kb (defaults to all readable scopes)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of chunks to return (1-10, default 5)"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            },
+        },
+        ToolDefinition {
+            tool_type: "function".to_string(),
+            function: masix_providers::FunctionDefinition {
+                name: "mcp_resource_list".to_string(),
+                description: "List resources (files, records, documents) exposed by connected MCP servers. Returns server, uri, name and mime type.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "server": {
+                            "type": "string",
+                            "description": "Optional MCP server name to restrict the listing"
+                        }
+                    },
+                    "required": []
+                }),
+            },
+        },
+        ToolDefinition {
+            tool_type: "function".to_string(),
+            function: masix_providers::FunctionDefinition {
+                name: "mcp_resource_read".to_string(),
+                description: "Read an MCP resource by uri. Text is returned inline; binary content is saved to the workdir and its path reported.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "server": {
+                            "type": "string",
+                            "description": "MCP server name (from mcp_resource_list)"
+                        },
+                        "uri": {
+                            "type": "string",
+                            "description": "Resource uri (from mcp_resource_list)"
+                        }
+                    },
+                    "required": ["server", "uri"]
+                }),
+            },
+        },
         ToolDefinition {
             tool_type: "function".to_string(),
             function: masix_providers::FunctionDefinition {
@@ -358,6 +446,7 @@ pub async fn execute_builtin_tool(
     arguments: Value,
     exec_policy: &ExecPolicy,
     workdir: &Path,
+    permission_level: &str,
 ) -> Result<String> {
     match tool_name {
         "exec" => {
@@ -369,7 +458,15 @@ pub async fn execute_builtin_tool(
                 return Ok("Exec commands are disabled. Enable in config with exec.enabled=true and exec.allow_base=true".to_string());
             }
 
-            match run_command(exec_policy, ExecMode::Base, command, workdir).await {
+            match run_command(
+                exec_policy,
+                ExecMode::Base,
+                command,
+                workdir,
+                Some(permission_level),
+            )
+            .await
+            {
                 Ok(result) => Ok(result.format_for_chat()),
                 Err(e) => Ok(format!("Error: {}", e)),
             }
@@ -390,7 +487,7 @@ pub async fn execute_builtin_tool(
                 return Ok("Termux commands are disabled. Enable in config with exec.enabled=true and exec.allow_termux=true".to_string());
             }
 
-            match run_command(exec_policy, ExecMode::Termux, command, workdir).await {
+            match run_command(exec_policy, ExecMode::Termux, command, workdir, None).await {
                 Ok(result) => Ok(result.format_for_chat()),
                 Err(e) => Ok(format!("Error: {}", e)),
             }
@@ -498,6 +595,14 @@ pub async fn execute_builtin_tool(
             "memory_write requires runtime context and is executed by the runtime coordinator."
                 .to_string(),
         ),
+        "kb_search" => Ok(
+            "kb_search requires runtime context and is executed by the runtime coordinator."
+                .to_string(),
+        ),
+        "mcp_resource_list" | "mcp_resource_read" => Ok(format!(
+            "{} requires the MCP client and is executed by the runtime coordinator.",
+            tool_name
+        )),
         "web_fetch" => {
             let url = arguments["url"]
                 .as_str()
@@ -521,6 +626,10 @@ pub async fn execute_builtin_tool(
             "chat_context tool requires runtime envelope context and is executed by the runtime coordinator."
                 .to_string(),
         ),
+        "exec_bg" => Ok(
+            "exec_bg requires runtime chat/outbound context and is executed by the runtime coordinator."
+                .to_string(),
+        ),
         "telegram_send" => Ok(
             "telegram_send requires runtime envelope/outbound context and is executed by the runtime coordinator."
                 .to_string(),
@@ -556,12 +665,16 @@ pub fn is_builtin_tool(tool_name: &str) -> bool {
     let core_tools = matches!(
         tool_name,
         "exec"
+            | "exec_bg"
             | "termux"
             | "read_file"
             | "write_file"
             | "list_dir"
             | "memory_read"
             | "memory_write"
+            | "kb_search"
+            | "mcp_resource_list"
+            | "mcp_resource_read"
             | "web_fetch"
             | "device_info"
             | "cron"
@@ -665,6 +778,7 @@ async fn get_device_info() -> Result<String> {
             ExecMode::Termux,
             "termux-battery-status",
             Path::new("."),
+            None,
         )
         .await
         {
@@ -0,0 +1,600 @@
+//! Knowledge base ingestion
+//!
+//! Text extraction (PDF, Markdown, text, HTML), chunking and keyword search
+//! for documents stored inside the shared/admin KB memory scopes.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE: &str = "index.json";
+const CHUNKS_DIR: &str = "chunks";
+const FILES_DIR: &str = "files";
+const CHUNK_MAX_CHARS: usize = 1200;
+const CHUNK_OVERLAP_CHARS: usize = 150;
+pub const MAX_DOCUMENT_BYTES: usize = 20 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KnowledgeFormat {
+    Pdf,
+    Markdown,
+    Text,
+    Html,
+}
+
+impl KnowledgeFormat {
+    /// Detect the format from file extension first, then from mime type.
+    pub fn detect(file_name: Option<&str>, mime_type: Option<&str>) -> Option<Self> {
+        let extension = file_name
+            .and_then(|name| Path::new(name).extension())
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        let by_extension = match extension.as_deref() {
+            Some("pdf") => Some(Self::Pdf),
+            Some("md") | Some("markdown") => Some(Self::Markdown),
+            Some("txt") | Some("text") | Some("log") | Some("csv") => Some(Self::Text),
+            Some("html") | Some("htm") | Some("xhtml") => Some(Self::Html),
+            _ => None,
+        };
+        if by_extension.is_some() {
+            return by_extension;
+        }
+
+        let mime = mime_type?.split(';').next()?.trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "application/pdf" => Some(Self::Pdf),
+            "text/markdown" | "text/x-markdown" => Some(Self::Markdown),
+            "text/html" | "application/xhtml+xml" => Some(Self::Html),
+            "text/plain" | "text/csv" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Markdown => "markdown",
+            Self::Text => "text",
+            Self::Html => "html",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Markdown => "md",
+            Self::Text => "txt",
+            Self::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeDocument {
+    pub id: String,
+    pub title: String,
+    pub source: String,
+    pub format: KnowledgeFormat,
+    pub sha256: String,
+    pub chars: usize,
+    pub chunks: usize,
+    pub ingested_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KnowledgeIndex {
+    #[serde(default)]
+    documents: Vec<KnowledgeDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KnowledgeChunk {
+    doc_id: String,
+    index: usize,
+    text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct KnowledgeHit {
+    pub doc_id: String,
+    pub title: String,
+    pub chunk_index: usize,
+    pub score: f64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum IngestOutcome {
+    Added(KnowledgeDocument),
+    Unchanged(KnowledgeDocument),
+}
+
+impl IngestOutcome {
+    pub fn document(&self) -> &KnowledgeDocument {
+        match self {
+            Self::Added(doc) | Self::Unchanged(doc) => doc,
+        }
+    }
+}
+
+pub fn extract_text(bytes: &[u8], format: KnowledgeFormat) -> Result<String> {
+    let raw = match format {
+        // pdf-extract can panic on malformed files; surface that as an error.
+        KnowledgeFormat::Pdf => {
+            std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(bytes))
+                .map_err(|_| anyhow!("PDF text extraction failed: malformed document"))?
+                .map_err(|e| anyhow!("PDF text extraction failed: {}", e))?
+        }
+        KnowledgeFormat::Html => html_to_text(&String::from_utf8_lossy(bytes)),
+        KnowledgeFormat::Markdown | KnowledgeFormat::Text => {
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+    };
+    Ok(normalize_text(&raw))
+}
+
+fn html_to_text(html: &str) -> String {
+    const SKIPPED: &[&str] = &["script", "style", "noscript", "template", "head"];
+    const BLOCKS: &[&str] = &[
+        "p",
+        "div",
+        "section",
+        "article",
+        "li",
+        "tr",
+        "br",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "pre",
+        "blockquote",
+        "table",
+    ];
+
+    let document = scraper::Html::parse_document(html);
+    let mut out = String::new();
+    for node in document.tree.root().descendants() {
+        let Some(text) = node.value().as_text() else {
+            continue;
+        };
+        let mut skipped = false;
+        let mut block = false;
+        for ancestor in node.ancestors() {
+            if let Some(element) = ancestor.value().as_element() {
+                if SKIPPED.contains(&element.name()) {
+                    skipped = true;
+                    break;
+                }
+                if !block && BLOCKS.contains(&element.name()) {
+                    block = true;
+                }
+            }
+        }
+        if skipped {
+            continue;
+        }
+        out.push_str(text);
+        out.push_str(if block { "\n\n" } else { " " });
+    }
+    out
+}
+
+fn normalize_text(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut blank_run = 0usize;
+    for line in raw.replace("\r\n", "\n").replace('\r', "\n").lines() {
+        let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            blank_run += 1;
+            if blank_run == 1 && !out.is_empty() {
+                out.push('\n');
+            }
+            continue;
+        }
+        blank_run = 0;
+        out.push_str(&collapsed);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+/// Split text into paragraph-aligned chunks of at most `max_chars`, carrying a
+/// short overlap from the previous chunk so matches at boundaries keep context.
+pub fn chunk_text(text: &str, max_chars: usize, overlap_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut pieces = Vec::new();
+    for paragraph in text.split("\n\n") {
+        let paragraph = paragraph.trim();
+        if paragraph.is_empty() {
+            continue;
+        }
+        if paragraph.chars().count() <= max_chars {
+            pieces.push(paragraph.to_string());
+            continue;
+        }
+        let mut current = String::new();
+        for word in paragraph.split_whitespace() {
+            let needed = current.chars().count() + word.chars().count() + 1;
+            if !current.is_empty() && needed > max_chars {
+                pieces.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        if !current.is_empty() {
+            pieces.push(current);
+        }
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for piece in pieces {
+        let needed = current.chars().count() + piece.chars().count() + 2;
+        if !current.is_empty() && needed > max_chars {
+            let tail = overlap_tail(&current, overlap_chars);
+            chunks.push(std::mem::take(&mut current));
+            if !tail.is_empty() && tail.chars().count() + piece.chars().count() + 2 <= max_chars {
+                current = tail;
+            }
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(&piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn overlap_tail(text: &str, overlap_chars: usize) -> String {
+    if overlap_chars == 0 {
+        return String::new();
+    }
+    let total = text.chars().count();
+    if total <= overlap_chars {
+        return text.to_string();
+    }
+    let tail: String = text.chars().skip(total - overlap_chars).collect();
+    // Start at a word boundary.
+    match tail.find(char::is_whitespace) {
+        Some(pos) => tail[pos..].trim().to_string(),
+        None => tail,
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.chars().count() >= 2)
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+fn load_index(kb_dir: &Path) -> Result<KnowledgeIndex> {
+    let path = kb_dir.join(INDEX_FILE);
+    if !path.exists() {
+        return Ok(KnowledgeIndex::default());
+    }
+    let raw = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&raw).map_err(|e| anyhow!("Invalid KB index {}: {}", path.display(), e))
+}
+
+fn save_index(kb_dir: &Path, index: &KnowledgeIndex) -> Result<()> {
+    std::fs::create_dir_all(kb_dir)?;
+    let path = kb_dir.join(INDEX_FILE);
+    let tmp = kb_dir.join(format!("{}.tmp", INDEX_FILE));
+    std::fs::write(&tmp, serde_json::to_string_pretty(index)?)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+fn chunks_path(kb_dir: &Path, doc_id: &str) -> PathBuf {
+    kb_dir.join(CHUNKS_DIR).join(format!("{}.jsonl", doc_id))
+}
+
+fn load_chunks(kb_dir: &Path, doc_id: &str) -> Vec<KnowledgeChunk> {
+    let Ok(raw) = std::fs::read_to_string(chunks_path(kb_dir, doc_id)) else {
+        return Vec::new();
+    };
+    raw.lines()
+        .filter_map(|line| serde_json::from_str::<KnowledgeChunk>(line).ok())
+        .collect()
+}
+
+fn sanitize_file_stem(title: &str) -> String {
+    let stem = Path::new(title)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(title);
+    let cleaned: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect();
+    if cleaned.is_empty() {
+        "document".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// Extract, chunk and index a document. Documents are deduplicated by content
+/// hash; the original bytes are kept under `files/` for later re-indexing.
+pub fn ingest_document(
+    kb_dir: &Path,
+    title: &str,
+    source: &str,
+    bytes: &[u8],
+    format: KnowledgeFormat,
+) -> Result<IngestOutcome> {
+    if bytes.len() > MAX_DOCUMENT_BYTES {
+        anyhow::bail!(
+            "Document too large ({} bytes, max {})",
+            bytes.len(),
+            MAX_DOCUMENT_BYTES
+        );
+    }
+
+    let sha256 = format!("{:x}", Sha256::digest(bytes));
+    let mut index = load_index(kb_dir)?;
+    if let Some(existing) = index.documents.iter().find(|doc| doc.sha256 == sha256) {
+        return Ok(IngestOutcome::Unchanged(existing.clone()));
+    }
+
+    let text = extract_text(bytes, format)?;
+    if text.is_empty() {
+        anyhow::bail!("No extractable text in '{}'", title);
+    }
+    let chunks = chunk_text(&text, CHUNK_MAX_CHARS, CHUNK_OVERLAP_CHARS);
+    let doc_id = sha256[..12].to_string();
+
+    std::fs::create_dir_all(kb_dir.join(CHUNKS_DIR))?;
+    let mut jsonl = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        jsonl.push_str(&serde_json::to_string(&KnowledgeChunk {
+            doc_id: doc_id.clone(),
+            index: i,
+            text: chunk.clone(),
+        })?);
+        jsonl.push('\n');
+    }
+    std::fs::write(chunks_path(kb_dir, &doc_id), jsonl)?;
+
+    std::fs::create_dir_all(kb_dir.join(FILES_DIR))?;
+    let file_name = format!(
+        "{}-{}.{}",
+        doc_id,
+        sanitize_file_stem(title),
+        format.extension()
+    );
+    std::fs::write(kb_dir.join(FILES_DIR).join(file_name), bytes)?;
+
+    let document = KnowledgeDocument {
+        id: doc_id,
+        title: title.to_string(),
+        source: source.to_string(),
+        format,
+        sha256,
+        chars: text.chars().count(),
+        chunks: chunks.len(),
+        ingested_at: chrono::Utc::now().to_rfc3339(),
+    };
+    index.documents.push(document.clone());
+    save_index(kb_dir, &index)?;
+    Ok(IngestOutcome::Added(document))
+}
+
+pub fn list_documents(kb_dir: &Path) -> Result<Vec<KnowledgeDocument>> {
+    Ok(load_index(kb_dir)?.documents)
+}
+
+pub fn remove_document(kb_dir: &Path, doc_id: &str) -> Result<Option<KnowledgeDocument>> {
+    let mut index = load_index(kb_dir)?;
+    let Some(pos) = index.documents.iter().position(|doc| doc.id == doc_id) else {
+        return Ok(None);
+    };
+    let removed = index.documents.remove(pos);
+    let _ = std::fs::remove_file(chunks_path(kb_dir, &removed.id));
+    if let Ok(entries) = std::fs::read_dir(kb_dir.join(FILES_DIR)) {
+        let prefix = format!("{}-", removed.id);
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+    save_index(kb_dir, &index)?;
+    Ok(Some(removed))
+}
+
+/// BM25 keyword search over all indexed chunks.
+pub fn search(kb_dir: &Path, query: &str, limit: usize) -> Result<Vec<KnowledgeHit>> {
+    const K1: f64 = 1.2;
+    const B: f64 = 0.75;
+
+    let terms: HashSet<String> = tokenize(query).into_iter().collect();
+    if terms.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let index = load_index(kb_dir)?;
+    let mut entries: Vec<(
+        &KnowledgeDocument,
+        KnowledgeChunk,
+        HashMap<String, usize>,
+        usize,
+    )> = Vec::new();
+    for doc in &index.documents {
+        for chunk in load_chunks(kb_dir, &doc.id) {
+            let tokens = tokenize(&chunk.text);
+            let len = tokens.len();
+            let mut tf: HashMap<String, usize> = HashMap::new();
+            for token in tokens {
+                if terms.contains(&token) {
+                    *tf.entry(token).or_default() += 1;
+                }
+            }
+            entries.push((doc, chunk, tf, len));
+        }
+    }
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let n = entries.len() as f64;
+    let avg_len = entries
+        .iter()
+        .map(|(_, _, _, len)| *len as f64)
+        .sum::<f64>()
+        / n;
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    for (_, _, tf, _) in &entries {
+        for term in tf.keys() {
+            *df.entry(term.as_str()).or_default() += 1;
+        }
+    }
+    let idf: HashMap<String, f64> = df
+        .into_iter()
+        .map(|(term, count)| {
+            let count = count as f64;
+            (
+                term.to_string(),
+                ((n - count + 0.5) / (count + 0.5) + 1.0).ln(),
+            )
+        })
+        .collect();
+
+    let mut hits: Vec<KnowledgeHit> = entries
+        .into_iter()
+        .filter_map(|(doc, chunk, tf, len)| {
+            let norm = 1.0 - B + B * (len as f64 / avg_len.max(1.0));
+            let score: f64 = tf
+                .iter()
+                .map(|(term, count)| {
+                    let count = *count as f64;
+                    idf.get(term).copied().unwrap_or_default() * count * (K1 + 1.0)
+                        / (count + K1 * norm)
+                })
+                .sum();
+            (score > 0.0).then(|| KnowledgeHit {
+                doc_id: doc.id.clone(),
+                title: doc.title.clone(),
+                chunk_index: chunk.index,
+                score,
+                text: chunk.text,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// Collect ingestable files under `path` (recursively for directories).
+/// Hidden files and README files are skipped.
+pub fn collect_ingestable_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        anyhow::bail!("Path not found: {}", path.display());
+    }
+
+    let mut files = Vec::new();
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            let entry_path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            if entry_path.is_dir() {
+                stack.push(entry_path);
+                continue;
+            }
+            if name.to_ascii_lowercase().starts_with("readme") {
+                continue;
+            }
+            if KnowledgeFormat::detect(Some(&name), None).is_some() {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn knowledge_chunking_and_format_detection() {
+        assert_eq!(
+            KnowledgeFormat::detect(Some("Manual.PDF"), None),
+            Some(KnowledgeFormat::Pdf)
+        );
+        assert_eq!(
+            KnowledgeFormat::detect(None, Some("text/html; charset=utf-8")),
+            Some(KnowledgeFormat::Html)
+        );
+        assert_eq!(KnowledgeFormat::detect(Some("archive.zip"), None), None);
+
+        let text = (0..40)
+            .map(|i| format!("Paragraph {} talks about topic number {}.", i, i))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let chunks = chunk_text(&text, 200, 40);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 200));
+        assert!(chunks[0].starts_with("Paragraph 0 "));
+        assert!(chunks.last().unwrap().contains("Paragraph 39 "));
+    }
+
+    #[test]
+    fn knowledge_ingest_dedupes_and_searches_html() {
+        let kb_dir = std::env::temp_dir().join(format!(
+            "masix-test-kb-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let html = b"<html><head><style>.x{}</style></head><body><h1>Router setup</h1>\
+            <p>Reset the router by holding the button for ten seconds.</p>\
+            <script>var secret = 1;</script><p>Wifi password is on the label.</p></body></html>";
+
+        let first = ingest_document(&kb_dir, "router.html", "test", html, KnowledgeFormat::Html)
+            .expect("ingest");
+        assert!(matches!(first, IngestOutcome::Added(_)));
+        let second = ingest_document(&kb_dir, "copy.html", "test", html, KnowledgeFormat::Html)
+            .expect("ingest again");
+        assert!(matches!(second, IngestOutcome::Unchanged(_)));
+        assert_eq!(list_documents(&kb_dir).unwrap().len(), 1);
+
+        let hits = search(&kb_dir, "reset router", 3).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].text.contains("ten seconds"));
+        assert!(!hits[0].text.contains("secret"));
+        assert!(search(&kb_dir, "secret", 3).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&kb_dir).ok();
+    }
+}
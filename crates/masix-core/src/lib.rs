@@ -3,29 +3,44 @@
 //! Main runtime orchestration with MCP + Cron + LLM support
 
 mod builtin_tools;
+pub mod knowledge;
+mod mcp_serve;
+mod tool_approval;
+mod tool_registry;
+mod tool_validation;
+mod wasm_tools;
 
 use anyhow::{anyhow, Result};
 use base64::Engine;
 use builtin_tools::{execute_builtin_tool, get_builtin_tool_definitions, is_builtin_tool};
+use knowledge::{IngestOutcome, KnowledgeDocument, KnowledgeFormat, KnowledgeHit};
 #[cfg(feature = "stt")]
 use masix_config::SttConfig;
 use masix_config::{
     AccessMode, AgentLoopContinuationDetection, Config, CoreCronConfig, CoreToolProgressConfig,
-    GroupPolicy, PermissionLevel, RetryPolicyConfig, StreamingMode, ToolProgressMode,
-    UserToolsMode,
+    GroupPolicy, McpTransport, MemoryShareAccess, MemoryShareScope, PermissionLevel,
+    RetryPolicyConfig, StreamingMode, ToolProgressMode, UserToolsMode,
 };
 use masix_exec::{
     is_termux_environment, manage_termux_boot, manage_termux_wake_lock, run_command, BootAction,
-    ExecMode, ExecPolicy, WakeLockAction,
+    ExecJobs, ExecMode, ExecPolicy, ExecRuleSet, ExecRules, JobInfo, JobOwner, JobStatus,
+    WakeLockAction,
+};
+use masix_ipc::{
+    CallbackAnswer, DeliveryReceipt, Envelope, EventBus, MessageKind, OutboundMessage,
+    OutboundPhoto,
+};
+use masix_mcp::{
+    McpCallLimits, McpCallOptions, McpClient, McpHttpOptions, McpProgress, McpRestartPolicy,
+    McpSandbox, McpServer, McpServerState, McpServerStatus, PromptResult, ResourceContents,
 };
-use masix_ipc::{Envelope, EventBus, MessageKind, OutboundMessage};
-use masix_mcp::McpClient;
 use masix_policy::PolicyEngine;
 use masix_providers::{
-    AnthropicProvider, ChatMessage, OpenAICompatibleProvider, Provider, ProviderRouter,
+    AnthropicProvider, ChatMessage, ImagePart, OpenAICompatibleProvider, Provider, ProviderRouter,
     RetryPolicy, ToolCall, ToolDefinition,
 };
-use masix_storage::Storage;
+use masix_storage::{AuditRecord, Storage};
+use mcp_serve::McpServeHandler;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
@@ -35,8 +50,15 @@ use std::time::Instant;
 use tokio::fs::{self, OpenOptions};
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command as TokioCommand;
-use tokio::sync::{broadcast, Mutex, Semaphore};
+use tokio::sync::{broadcast, mpsc, watch, Mutex, Semaphore};
+use tool_approval::{ApprovalDecision, ApprovalOutcome, ToolApprovalGate};
+pub use tool_registry::{
+    RegisteredTool, ToolCallContext, ToolCatalog, ToolCost, ToolDenial, ToolMetadata, ToolOutput,
+    ToolProvider, ToolRegistry, ToolSource,
+};
+use tool_validation::ToolValidationMetrics;
 use tracing::{debug, error, info, warn};
+pub use wasm_tools::WasmToolProvider;
 
 use masix_telegram::menu::Language;
 
@@ -46,8 +68,41 @@ use std::hash::{Hash, Hasher};
 const DEFAULT_MAX_TOOL_ITERATIONS: usize = 25;
 const MEMORY_MAX_CONTEXT_ENTRIES: usize = 12;
 const MAX_INBOUND_CONCURRENCY: usize = 8;
+const PROFILE_FACTS_FILE: &str = "profile_facts.json";
+const PROFILE_FACT_MAX_CHARS: usize = 280;
+const KB_DIR_NAME: &str = "knowledge";
+const DEFAULT_CHAT_SESSION: &str = "default";
+const CHAT_SESSION_NAME_MAX_CHARS: usize = 32;
+const KB_SEARCH_DEFAULT_LIMIT: usize = 5;
+const KB_SEARCH_MAX_LIMIT: usize = 10;
+const MCP_STATUS_FILE: &str = "mcp_status.json";
+const MCP_STATUS_INTERVAL_SECS: u64 = 15;
+const MCP_RESOURCE_LIST_LIMIT: usize = 100;
+const MCP_RESOURCE_TEXT_LIMIT: usize = 10_000;
+const MCP_RESOURCE_BLOB_MAX_BYTES: usize = 10 * 1024 * 1024;
+const MCP_RESOURCE_DIR: &str = "mcp_resources";
+/// Largest MCP image forwarded to chat and model (Anthropic's per-image limit).
+const MCP_IMAGE_MAX_BYTES: usize = 5 * 1024 * 1024;
+const AUDIT_RESULT_PREVIEW_CHARS: usize = 500;
+/// Output shown in a job message (Telegram caps messages at 4096 chars).
+const EXEC_JOB_TAIL_BYTES: usize = 3000;
+const EXEC_JOBS_LIST_LIMIT: usize = 20;
 const DEFAULT_PLUGIN_SERVER_URL: &str = "https://masix.wellanet.dev";
 
+type InboundScopeLocks = Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>;
+/// `/stop` switches of the turns currently running, by inbound scope key.
+type ActiveTurns = Arc<Mutex<HashMap<String, watch::Sender<bool>>>>;
+
+/// Supervisor state of MCP servers, written by the runtime for `masix status`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct McpStatusSnapshot {
+    pub updated_at: String,
+    pub servers: Vec<McpServerStatus>,
+    /// Tool calls rejected by argument validation since start, per tool.
+    #[serde(default)]
+    pub tool_validation_failures: BTreeMap<String, u64>,
+}
+
 #[derive(Debug, Clone, serde::Deserialize, Default)]
 struct PluginCatalog {
     #[serde(default)]
@@ -146,6 +201,11 @@ fn is_admin_only_tool(tool_name: &str, admin_only_modules: &HashSet<String>) ->
     false
 }
 
+/// Check if an MCP server (and so all of its resources) belongs to an admin-only module.
+fn is_admin_only_server(server_name: &str, admin_only_modules: &HashSet<String>) -> bool {
+    is_admin_only_tool(&format!("{}_", server_name), admin_only_modules)
+}
+
 /// Load admin-only plugin IDs from the installed plugin registry.
 fn load_admin_only_modules(data_dir: &Path) -> HashSet<String> {
     let registry_path = data_dir.join("plugins").join("installed.json");
@@ -182,13 +242,6 @@ fn load_admin_only_modules(data_dir: &Path) -> HashSet<String> {
         .unwrap_or_default()
 }
 
-#[async_trait::async_trait]
-pub trait ToolProvider: Send + Sync {
-    fn namespace(&self) -> &str;
-    async fn list_tools(&self) -> Result<Vec<ToolDefinition>>;
-    async fn call_tool(&self, tool_name: &str, arguments: serde_json::Value) -> Result<String>;
-}
-
 #[async_trait::async_trait]
 pub trait ChannelAdapter: Send + Sync {
     fn channel_name(&self) -> &str;
@@ -236,33 +289,28 @@ impl RuntimeToolAccess {
             }
         }
     }
-
-    fn filter_tools(&self, tools: Vec<ToolDefinition>) -> Vec<ToolDefinition> {
-        match self {
-            Self::None => Vec::new(),
-            Self::All => tools,
-            Self::Selected(allowed) => tools
-                .into_iter()
-                .filter(|tool| allowed.contains(&tool.function.name.trim().to_lowercase()))
-                .collect(),
-        }
-    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{is_admin_only_tool, load_admin_only_modules, MasixRuntime};
+    use super::{
+        is_admin_only_server, is_admin_only_tool, load_admin_only_modules, ActiveTurns,
+        ApprovalDecision, MasixRuntime, RuntimeToolAccess, ToolApprovalGate, ToolDenial,
+        ToolMetadata, ToolProvider, ToolRegistry, ToolSource,
+    };
     use masix_config::{
         AccessMode, AgentLoopContinuationDetection, Config, CoreToolProgressConfig, DmPolicy,
         GroupPolicy, PermissionLevel, TelegramAccount, TelegramConfig, ToolProgressMode,
     };
     use masix_ipc::{Envelope, MessageKind};
+    use masix_policy::PolicyEngine;
     use masix_providers::{ChatMessage, FunctionDefinition, ToolDefinition};
     use masix_storage::Storage;
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
+    use std::path::PathBuf;
     use std::sync::Arc;
     use std::time::{SystemTime, UNIX_EPOCH};
-    use tokio::sync::{broadcast, Mutex};
+    use tokio::sync::{broadcast, watch, Mutex};
 
     fn make_account(token: &str) -> TelegramAccount {
         TelegramAccount {
@@ -275,6 +323,7 @@ mod tests {
             readonly: vec![],
             isolated: true,
             shared_memory_with: vec![],
+            memory_shares: vec![],
             allow_self_memory_edit: true,
             dm_policy: DmPolicy::Allowlist,
             dm_allow_from: vec![],
@@ -387,6 +436,9 @@ mod tests {
         // Builtin tools are never admin-only
         assert!(!is_admin_only_tool("shell", &admin_modules));
         assert!(!is_admin_only_tool("web", &admin_modules));
+        assert!(is_admin_only_server("codex-backend", &admin_modules));
+        assert!(is_admin_only_server("plugin_codex_backend", &admin_modules));
+        assert!(!is_admin_only_server("discovery", &admin_modules));
     }
 
     #[test]
@@ -469,6 +521,284 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn stop_command_cancels_the_running_turn_of_its_chat() {
+        let message = |text: &str| {
+            Envelope::new(
+                "telegram",
+                MessageKind::Message {
+                    from: "123".to_string(),
+                    text: text.to_string(),
+                },
+            )
+            .with_chat_id(-100)
+            .with_payload(serde_json::json!({ "account_tag": "bot" }))
+        };
+        assert!(MasixRuntime::is_stop_command(&message("/stop")));
+        assert!(MasixRuntime::is_stop_command(&message("/stop@masix_bot")));
+        assert!(!MasixRuntime::is_stop_command(&message("/stopwatch")));
+        assert!(!MasixRuntime::is_stop_command(&message("please /stop")));
+
+        let policy = PolicyEngine::new(None);
+        let (outbound, mut replies) = broadcast::channel(4);
+        let active_turns: ActiveTurns = Arc::new(Mutex::new(HashMap::new()));
+        let stop = message("/stop");
+
+        MasixRuntime::handle_stop_command(&stop, &policy, &active_turns, &outbound).await;
+        assert!(replies.try_recv().unwrap().text.starts_with("Nessuna"));
+
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let scope_key = MasixRuntime::inbound_processing_scope_key(&stop);
+        active_turns.lock().await.insert(scope_key, stop_tx);
+        MasixRuntime::handle_stop_command(&stop, &policy, &active_turns, &outbound).await;
+        assert!(*stop_rx.borrow());
+        assert!(replies.try_recv().unwrap().text.starts_with("⏹️"));
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            MasixRuntime::wait_for_stop(&stop_rx),
+        )
+        .await
+        .expect("stop observed");
+    }
+
+    #[tokio::test]
+    async fn approval_callbacks_from_admins_release_the_held_call() {
+        let mut account = make_account("111:AAA");
+        account.admins = vec![7];
+        let config = Config {
+            telegram: Some(TelegramConfig {
+                poll_timeout_secs: Some(60),
+                client_recreate_interval_secs: Some(60),
+                default_policy: None,
+                accounts: vec![account],
+            }),
+            ..Config::default()
+        };
+        let gate = ToolApprovalGate::default();
+        let (approval_id, mut decision) = gate.register(Some("111"), "exec");
+        let click = |user_id: i64, data: String| {
+            Envelope::new(
+                "telegram",
+                MessageKind::Callback {
+                    query_id: "q1".to_string(),
+                    data,
+                },
+            )
+            .with_chat_id(user_id)
+            .with_message_id(55)
+            .with_payload(serde_json::json!({ "account_tag": "111", "from_user_id": user_id }))
+        };
+        let (outbound, mut replies) = broadcast::channel(4);
+        let user_languages = Arc::new(Mutex::new(HashMap::new()));
+        user_languages.lock().await.insert(
+            MasixRuntime::user_state_key(Some("111"), Some("7"), None),
+            masix_telegram::menu::Language::English,
+        );
+
+        let intruder = click(8, format!("approve:{}:y", approval_id));
+        assert!(MasixRuntime::is_tool_approval_callback(&intruder));
+        assert!(!MasixRuntime::is_tool_approval_callback(&click(
+            7,
+            "menu:admin".to_string()
+        )));
+        MasixRuntime::handle_tool_approval_callback(
+            &intruder,
+            &config,
+            &gate,
+            &user_languages,
+            &outbound,
+        )
+        .await;
+        let refused = replies.try_recv().unwrap();
+        assert!(refused.text.is_empty());
+        assert_eq!(refused.edit_message_id, None);
+        let answer = refused.callback_answer.unwrap();
+        assert_eq!(answer.query_id, "q1");
+        assert!(answer.text.unwrap().contains("admin"));
+        assert!(decision.try_recv().is_err());
+
+        let admin = click(7, format!("approve:{}:n", approval_id));
+        MasixRuntime::handle_tool_approval_callback(
+            &admin,
+            &config,
+            &gate,
+            &user_languages,
+            &outbound,
+        )
+        .await;
+        let card = replies.try_recv().unwrap();
+        assert_eq!(card.edit_message_id, Some(55));
+        assert_eq!(card.text, "❌ `exec` rejected by 7.");
+        assert_eq!(card.callback_answer.unwrap().query_id, "q1");
+        assert_eq!(
+            decision.try_recv().unwrap(),
+            ApprovalDecision {
+                approved: false,
+                approver: 7
+            }
+        );
+
+        MasixRuntime::handle_tool_approval_callback(
+            &admin,
+            &config,
+            &gate,
+            &user_languages,
+            &outbound,
+        )
+        .await;
+        assert!(replies.try_recv().unwrap().text.contains("already handled"));
+    }
+
+    struct FakeProvider {
+        namespace: &'static str,
+        tools: Vec<&'static str>,
+        admin_only: bool,
+    }
+
+    impl FakeProvider {
+        fn new(namespace: &'static str, tools: &[&'static str]) -> Arc<Self> {
+            Arc::new(Self {
+                namespace,
+                tools: tools.to_vec(),
+                admin_only: false,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ToolProvider for FakeProvider {
+        fn namespace(&self) -> &str {
+            self.namespace
+        }
+
+        async fn list_tools(&self) -> anyhow::Result<Vec<ToolDefinition>> {
+            Ok(self
+                .tools
+                .iter()
+                .map(|name| ToolDefinition {
+                    tool_type: "function".to_string(),
+                    function: FunctionDefinition {
+                        name: name.to_string(),
+                        description: "Current weather".to_string(),
+                        parameters: serde_json::json!({
+                            "type": "object",
+                            "properties": { "city": { "type": "string" } },
+                            "required": ["city"]
+                        }),
+                    },
+                })
+                .collect())
+        }
+
+        async fn call_tool(
+            &self,
+            tool_name: &str,
+            _arguments: serde_json::Value,
+        ) -> anyhow::Result<String> {
+            Ok(format!("{}:{}", self.namespace, tool_name))
+        }
+
+        fn tool_metadata(&self, _tool_name: &str) -> ToolMetadata {
+            ToolMetadata {
+                admin_only: self.admin_only,
+                ..ToolMetadata::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_registry_resolves_namespaces_and_collisions() {
+        let mut registry = ToolRegistry::with_builtins(false);
+        registry
+            .register(FakeProvider::new(
+                "discovery",
+                &["web_search", "extra_fetch"],
+            ))
+            .unwrap();
+        registry
+            .register(FakeProvider::new("disc", &["overy_web_search"]))
+            .unwrap();
+        registry
+            .register(FakeProvider::new("discovery_extra", &["fetch"]))
+            .unwrap();
+        registry
+            .register(FakeProvider::new("read", &["file", "dir"]))
+            .unwrap();
+        assert!(registry
+            .register(FakeProvider::new("discovery", &["other"]))
+            .is_err());
+        assert!(registry
+            .register(FakeProvider::new("bad name", &["x"]))
+            .is_err());
+        assert!(registry.register(FakeProvider::new("", &["x"])).is_err());
+
+        let catalog = registry.catalog().await;
+        let resolved = |name: &str| {
+            let tool = catalog.get(name).expect("resolved");
+            (tool.namespace.as_str(), tool.local_name.as_str())
+        };
+        // Longest namespace wins whatever the registration order.
+        assert_eq!(
+            resolved("discovery_web_search"),
+            ("discovery", "web_search")
+        );
+        assert_eq!(
+            resolved("discovery_extra_fetch"),
+            ("discovery_extra", "fetch")
+        );
+        // Builtins are never shadowed.
+        assert_eq!(resolved("read_file"), ("", "read_file"));
+        assert_eq!(
+            catalog.get("read_file").unwrap().source,
+            ToolSource::Builtin
+        );
+        assert_eq!(resolved("read_dir"), ("read", "dir"));
+        assert!(catalog.get("mcp_resource_list").is_none());
+
+        let exec = catalog.get("exec").unwrap();
+        assert!(exec.metadata.side_effecting && !exec.metadata.admin_only);
+        assert!(catalog.get("exec_bg").unwrap().metadata.side_effecting);
+        assert!(!catalog.get("read_file").unwrap().metadata.side_effecting);
+
+        let call = |arguments: &str| masix_providers::ToolCall {
+            id: "call_1".to_string(),
+            tool_type: "function".to_string(),
+            function: masix_providers::FunctionCall {
+                name: "discovery_web_search".to_string(),
+                arguments: arguments.to_string(),
+            },
+        };
+        assert!(MasixRuntime::validate_tool_call_arguments(&catalog, &call("{}")).is_err());
+        assert!(
+            MasixRuntime::validate_tool_call_arguments(&catalog, &call(r#"{"city":"Roma"}"#))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn mcp_progress_is_formatted_for_chat_updates() {
+        let update = masix_mcp::McpProgress {
+            server: "docs".to_string(),
+            tool: "index".to_string(),
+            progress: 3.0,
+            total: Some(4.0),
+            message: Some("embedding".to_string()),
+        };
+        assert_eq!(
+            MasixRuntime::format_mcp_progress(&update, true),
+            "Quick update: index 75% - embedding"
+        );
+        let open_ended = masix_mcp::McpProgress {
+            total: None,
+            message: None,
+            ..update
+        };
+        assert_eq!(
+            MasixRuntime::format_mcp_progress(&open_ended, false),
+            "Quick update: still working (3)"
+        );
+    }
+
     #[test]
     fn users_only_group_denial_keeps_unauthorized_response() {
         let mut account = make_account("111:AAA");
@@ -555,52 +885,114 @@ mod tests {
     fn memory_scope_permissions_follow_rbac() {
         assert!(MasixRuntime::can_access_memory_scope(
             PermissionLevel::Admin,
+            super::MemoryNamespace::Own,
             super::MemoryScope::AdminKb,
             1,
             None
         ));
         assert!(!MasixRuntime::can_access_memory_scope(
             PermissionLevel::User,
+            super::MemoryNamespace::Own,
             super::MemoryScope::AdminKb,
             10,
             None
         ));
         assert!(MasixRuntime::can_access_memory_scope(
             PermissionLevel::User,
+            super::MemoryNamespace::Own,
             super::MemoryScope::SharedUserKb,
             10,
             None
         ));
         assert!(MasixRuntime::can_access_memory_scope(
             PermissionLevel::User,
+            super::MemoryNamespace::Own,
             super::MemoryScope::UserPrivate,
             10,
             Some(10)
         ));
         assert!(!MasixRuntime::can_access_memory_scope(
             PermissionLevel::User,
+            super::MemoryNamespace::Own,
             super::MemoryScope::UserPrivate,
             10,
             Some(99)
         ));
     }
 
+    #[test]
+    fn self_memory_edit_flag_gates_user_private_writes() {
+        use super::{MemoryNamespace, MemoryScope};
+        let write = |permission, scope, allow_self_edit| {
+            MasixRuntime::can_write_memory_scope(
+                permission,
+                MemoryNamespace::Own,
+                scope,
+                10,
+                Some(10),
+                allow_self_edit,
+            )
+        };
+        assert!(write(PermissionLevel::User, MemoryScope::UserPrivate, true));
+        assert!(!write(
+            PermissionLevel::User,
+            MemoryScope::UserPrivate,
+            false
+        ));
+        assert!(write(
+            PermissionLevel::User,
+            MemoryScope::SharedUserKb,
+            false
+        ));
+        assert!(write(
+            PermissionLevel::Admin,
+            MemoryScope::UserPrivate,
+            false
+        ));
+
+        let mut locked = make_account("111:AAA");
+        locked.allow_self_memory_edit = false;
+        let config = Config {
+            telegram: Some(TelegramConfig {
+                poll_timeout_secs: Some(60),
+                client_recreate_interval_secs: Some(60),
+                default_policy: None,
+                accounts: vec![locked, make_account("222:BBB")],
+            }),
+            ..Config::default()
+        };
+        assert!(!MasixRuntime::self_memory_edit_allowed(
+            &config,
+            Some("111")
+        ));
+        assert!(MasixRuntime::self_memory_edit_allowed(&config, Some("222")));
+        assert!(MasixRuntime::self_memory_edit_allowed(
+            &Config::default(),
+            Some("111")
+        ));
+    }
+
     #[test]
     fn readonly_cannot_write_memory_scopes() {
         assert!(!MasixRuntime::can_write_memory_scope(
             PermissionLevel::Readonly,
+            super::MemoryNamespace::Own,
             super::MemoryScope::UserPrivate,
             10,
-            Some(10)
+            Some(10),
+            true
         ));
         assert!(!MasixRuntime::can_write_memory_scope(
             PermissionLevel::Readonly,
+            super::MemoryNamespace::Own,
             super::MemoryScope::SharedUserKb,
             10,
-            None
+            None,
+            true
         ));
         assert!(MasixRuntime::can_access_memory_scope(
             PermissionLevel::Readonly,
+            super::MemoryNamespace::Own,
             super::MemoryScope::UserPrivate,
             10,
             Some(10)
@@ -608,113 +1000,522 @@ mod tests {
     }
 
     #[test]
-    fn capabilities_query_detection_matches_common_prompts() {
-        assert!(MasixRuntime::is_capabilities_query("what can you do"));
-        assert!(MasixRuntime::is_capabilities_query("cosa puoi fare"));
-        assert!(MasixRuntime::is_capabilities_query("quali strumenti hai"));
-        assert!(!MasixRuntime::is_capabilities_query("/capabilities"));
-        assert!(!MasixRuntime::is_capabilities_query("scrivi un file"));
+    fn shared_memory_grants_gate_reads_and_writes() {
+        use super::{MemoryGrant, MemoryNamespace, MemoryScope};
+        let grant = |scope, writable| MemoryGrant {
+            owner_account: "111".to_string(),
+            owner_memory_dir: PathBuf::from("/data/accounts/111/memory"),
+            scope,
+            writable,
+        };
+        let read_only = grant(MemoryScope::SharedUserKb, false);
+        let read_write = grant(MemoryScope::SharedUserKb, true);
+        let admin_rw = grant(MemoryScope::AdminKb, true);
+
+        // (permission, namespace, scope, can read, can write)
+        let cases = [
+            (
+                PermissionLevel::User,
+                MemoryNamespace::Own,
+                MemoryScope::SharedUserKb,
+                true,
+                true,
+            ),
+            (
+                PermissionLevel::User,
+                MemoryNamespace::Shared(&read_only),
+                MemoryScope::SharedUserKb,
+                true,
+                false,
+            ),
+            (
+                PermissionLevel::User,
+                MemoryNamespace::Shared(&read_write),
+                MemoryScope::SharedUserKb,
+                true,
+                true,
+            ),
+            (
+                PermissionLevel::User,
+                MemoryNamespace::Shared(&read_write),
+                MemoryScope::AdminKb,
+                false,
+                false,
+            ),
+            (
+                PermissionLevel::User,
+                MemoryNamespace::Shared(&admin_rw),
+                MemoryScope::AdminKb,
+                false,
+                false,
+            ),
+            (
+                PermissionLevel::Admin,
+                MemoryNamespace::Shared(&read_only),
+                MemoryScope::SharedUserKb,
+                true,
+                false,
+            ),
+            (
+                PermissionLevel::Admin,
+                MemoryNamespace::Shared(&admin_rw),
+                MemoryScope::AdminKb,
+                true,
+                true,
+            ),
+            (
+                PermissionLevel::Admin,
+                MemoryNamespace::Shared(&read_only),
+                MemoryScope::AdminKb,
+                false,
+                false,
+            ),
+            (
+                PermissionLevel::Readonly,
+                MemoryNamespace::Shared(&read_write),
+                MemoryScope::SharedUserKb,
+                true,
+                false,
+            ),
+            (
+                PermissionLevel::None,
+                MemoryNamespace::Shared(&read_write),
+                MemoryScope::SharedUserKb,
+                false,
+                false,
+            ),
+        ];
+        for (idx, (permission, namespace, scope, read, write)) in cases.into_iter().enumerate() {
+            assert_eq!(
+                MasixRuntime::can_access_memory_scope(permission, namespace, scope, 10, None),
+                read,
+                "read case {}",
+                idx
+            );
+            assert_eq!(
+                MasixRuntime::can_write_memory_scope(permission, namespace, scope, 10, None, true),
+                write,
+                "write case {}",
+                idx
+            );
+        }
     }
 
     #[test]
-    fn group_tag_response_requires_tag_for_capabilities_command() {
-        let mut account = make_account("111:AAA");
-        account.bot_name = Some("MyBot".to_string());
-        account.access_mode = Some(AccessMode::GroupTagResponse);
-        let config = Config {
-            telegram: Some(TelegramConfig {
-                poll_timeout_secs: Some(60),
-                client_recreate_interval_secs: Some(60),
-                default_policy: None,
-                accounts: vec![account],
-            }),
-            ..Config::default()
+    fn exec_job_messages_show_status_and_tail() {
+        use masix_exec::{JobInfo, JobStatus};
+        let mut job = JobInfo {
+            id: 7,
+            command: "cargo build --release".to_string(),
+            account_tag: Some("111".to_string()),
+            chat_id: Some(42),
+            started_by: "42".to_string(),
+            started_at: 1_000,
+            finished_at: Some(1_075),
+            status: JobStatus::Exited,
+            exit_code: Some(0),
+            error: None,
         };
-
-        let tagged = MasixRuntime::get_permission_level(
-            &config,
-            Some("111"),
-            "telegram",
-            "1001",
-            1001,
-            -100777,
-            "/capabilities @MyBot",
+        assert_eq!(
+            MasixRuntime::format_exec_job(&job, "Finished release\n"),
+            "✅ Job #7 exited 0 (1m 15s)\n`cargo build --release`\n```text\nFinished release\n```"
         );
-        assert_eq!(tagged, PermissionLevel::User);
 
-        let untagged = MasixRuntime::get_permission_level(
-            &config,
-            Some("111"),
-            "telegram",
-            "1001",
-            1001,
-            -100777,
-            "/capabilities",
+        job.exit_code = Some(101);
+        assert!(MasixRuntime::format_exec_job(&job, "error[E0308]")
+            .starts_with("❌ Job #7 exited 101 (1m 15s)"));
+
+        job.status = JobStatus::TimedOut;
+        job.exit_code = None;
+        job.error = Some("timed out after 60s".to_string());
+        assert_eq!(
+            MasixRuntime::format_exec_job(&job, ""),
+            "⚠️ Job #7 timed_out (1m 15s)\n`cargo build --release`\nError: timed out after 60s\nNo output."
         );
-        assert_eq!(untagged, PermissionLevel::None);
     }
 
     #[test]
-    fn non_admin_tool_visibility_hides_admin_only_modules() {
-        let tools = vec![
-            ToolDefinition {
-                tool_type: "function".to_string(),
-                function: FunctionDefinition {
-                    name: "plugin_codex_backend_run_task".to_string(),
-                    description: "codex".to_string(),
-                    parameters: serde_json::json!({}),
-                },
-            },
-            ToolDefinition {
-                tool_type: "function".to_string(),
-                function: FunctionDefinition {
-                    name: "plugin_discovery_web_search".to_string(),
-                    description: "discovery".to_string(),
-                    parameters: serde_json::json!({}),
-                },
-            },
-        ];
-        let mut admin_only_modules = HashSet::new();
-        admin_only_modules.insert("codex-backend".to_string());
+    fn shared_memory_namespace_resolves_owner_paths() {
+        use super::{MemoryGrant, MemoryNamespace, MemoryScope};
+        let context = super::BotContext {
+            profile_name: "test".to_string(),
+            workdir: PathBuf::from("/data/accounts/222"),
+            memory_dir: PathBuf::from("/data/accounts/222/memory"),
+            memory_file: PathBuf::from("/data/accounts/222/MEMORY.md"),
+            provider_chain: vec!["openai".to_string()],
+            vision_provider: None,
+            retry_policy: masix_providers::RetryPolicy::default(),
+            exec_policy: masix_exec::ExecPolicy::default(),
+            exec_jobs: None,
+            memory_grants: vec![MemoryGrant {
+                owner_account: "111".to_string(),
+                owner_memory_dir: PathBuf::from("/data/accounts/111/memory"),
+                scope: MemoryScope::SharedUserKb,
+                writable: false,
+            }],
+        };
+        let tag = Some("222");
 
-        let user_visible = MasixRuntime::filter_visible_tools_by_permission(
-            tools.clone(),
-            &admin_only_modules,
-            PermissionLevel::User,
-        );
-        assert_eq!(user_visible.len(), 1);
-        assert_eq!(user_visible[0].function.name, "plugin_discovery_web_search");
+        for owner in [None, Some(""), Some("222")] {
+            let namespace =
+                MasixRuntime::resolve_memory_namespace(&context, tag, owner, MemoryScope::AdminKb);
+            assert!(matches!(namespace, Some(MemoryNamespace::Own)));
+        }
+        assert!(MasixRuntime::resolve_memory_namespace(
+            &context,
+            tag,
+            Some("111"),
+            MemoryScope::AdminKb
+        )
+        .is_none());
+        assert!(MasixRuntime::resolve_memory_namespace(
+            &context,
+            tag,
+            Some("333"),
+            MemoryScope::SharedUserKb
+        )
+        .is_none());
 
-        let admin_visible = MasixRuntime::filter_visible_tools_by_permission(
-            tools,
-            &admin_only_modules,
-            PermissionLevel::Admin,
+        let shared = MasixRuntime::resolve_memory_namespace(
+            &context,
+            tag,
+            Some("111"),
+            MemoryScope::SharedUserKb,
+        )
+        .expect("granted");
+        assert_eq!(
+            MasixRuntime::memory_scope_base_dir(
+                &context,
+                tag,
+                shared,
+                MemoryScope::SharedUserKb,
+                None,
+                10
+            ),
+            PathBuf::from("/data/accounts/111/memory/accounts/111/scopes/shared_user_kb")
+        );
+        assert_eq!(
+            MasixRuntime::memory_scope_base_dir(
+                &context,
+                tag,
+                MemoryNamespace::Own,
+                MemoryScope::UserPrivate,
+                None,
+                10
+            ),
+            PathBuf::from("/data/accounts/222/memory/accounts/222/scopes/user_private/10")
         );
-        assert_eq!(admin_visible.len(), 2);
     }
 
     #[test]
-    fn telegram_streaming_is_only_dm_or_tagged_group() {
-        let mut account = make_account("111:AAA");
-        account.bot_name = Some("MyBot".to_string());
-        let config = Config {
-            telegram: Some(TelegramConfig {
-                poll_timeout_secs: Some(60),
-                client_recreate_interval_secs: Some(60),
-                default_policy: None,
-                accounts: vec![account],
-            }),
-            ..Config::default()
-        };
+    fn fact_extraction_response_parsing_accepts_fenced_json() {
+        let raw = "```json\n[{\"category\":\"preference\",\"fact\":\"Prefers answers in Italian\"},\"Has a dog named Rex\",{\"fact\":\"\"},42]\n```";
+        let facts = MasixRuntime::parse_fact_extraction_response(raw, 5);
+        assert_eq!(
+            facts,
+            vec![
+                (
+                    "preference".to_string(),
+                    "Prefers answers in Italian".to_string()
+                ),
+                ("other".to_string(), "Has a dog named Rex".to_string()),
+            ]
+        );
+        assert_eq!(
+            MasixRuntime::parse_fact_extraction_response(raw, 1).len(),
+            1
+        );
+        assert!(MasixRuntime::parse_fact_extraction_response("nothing here", 3).is_empty());
+    }
 
-        let dm = Envelope::new(
+    #[test]
+    fn merge_profile_facts_dedupes_and_keeps_provenance() {
+        let mut facts = Vec::new();
+        let added = MasixRuntime::merge_profile_facts(
+            &mut facts,
+            vec![
+                ("identity".to_string(), "Name is Marco.".to_string()),
+                ("identity".to_string(), "name is  marco".to_string()),
+                ("routine".to_string(), "Runs every morning".to_string()),
+                ("task".to_string(), "Works on the MasiX repo".to_string()),
+            ],
+            "# Notes\n- works on the masix repo\n",
+            "trace-1",
             "telegram",
-            MessageKind::Message {
-                from: "1001".to_string(),
-                text: "hello".to_string(),
-            },
-        )
-        .with_chat_id(1001)
+            Some(42),
+            "2026-01-01T00:00:00Z",
+            10,
+        );
+        assert_eq!(added, 2);
+        assert_eq!(facts[0].id, 1);
+        assert_eq!(facts[1].id, 2);
+        assert_eq!(facts[1].text, "Runs every morning");
+        assert_eq!(facts[0].trace_id, "trace-1");
+        assert_eq!(facts[0].chat_id, Some(42));
+
+        let added = MasixRuntime::merge_profile_facts(
+            &mut facts,
+            vec![
+                ("routine".to_string(), "RUNS every morning!".to_string()),
+                ("preference".to_string(), "Likes espresso".to_string()),
+            ],
+            "",
+            "trace-2",
+            "telegram",
+            Some(42),
+            "2026-01-02T00:00:00Z",
+            2,
+        );
+        assert_eq!(added, 1);
+        assert_eq!(facts.len(), 2);
+        assert_eq!(facts[1].id, 3);
+        assert_eq!(facts[1].trace_id, "trace-2");
+
+        let added = MasixRuntime::merge_profile_facts(
+            &mut facts,
+            vec![
+                ("preference".to_string(), "Likes tea".to_string()),
+                ("preference".to_string(), "Dislikes coffee".to_string()),
+            ],
+            "- Dislikes tea\n- dislikes coffee\n",
+            "trace-3",
+            "telegram",
+            Some(42),
+            "2026-01-03T00:00:00Z",
+            10,
+        );
+        assert_eq!(added, 1);
+        assert_eq!(facts.last().unwrap().text, "Likes tea");
+    }
+
+    #[tokio::test]
+    async fn named_sessions_keep_separate_histories() {
+        let memory_dir = std::env::temp_dir().join(format!(
+            "masix-test-sessions-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let context = super::BotContext {
+            profile_name: "test".to_string(),
+            workdir: memory_dir.clone(),
+            memory_dir: memory_dir.clone(),
+            memory_file: memory_dir.join("MEMORY.md"),
+            provider_chain: vec!["openai".to_string()],
+            vision_provider: None,
+            retry_policy: masix_providers::RetryPolicy::default(),
+            exec_policy: masix_exec::ExecPolicy::default(),
+            exec_jobs: None,
+            memory_grants: Vec::new(),
+        };
+        let (tag, user, chat) = (Some("bot"), Some("42"), Some(42));
+        let history = |ctx: &super::BotContext| {
+            let ctx = ctx.clone();
+            async move {
+                MasixRuntime::load_chat_memory_history(&ctx, tag, user, chat, 10)
+                    .await
+                    .into_iter()
+                    .filter_map(|msg| msg.content)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        MasixRuntime::append_chat_memory(&context, tag, user, chat, "user", "main thread")
+            .await
+            .unwrap();
+        let reply =
+            MasixRuntime::handle_session_chat_command("/session new Work", &context, tag, user, 42)
+                .await;
+        assert!(reply.contains("'work'"));
+        assert!(history(&context).await.is_empty());
+        MasixRuntime::append_chat_memory(&context, tag, user, chat, "user", "work thread")
+            .await
+            .unwrap();
+        assert_eq!(history(&context).await, vec!["work thread".to_string()]);
+
+        MasixRuntime::handle_session_chat_command("/session archive", &context, tag, user, 42)
+            .await;
+        assert_eq!(history(&context).await, vec!["main thread".to_string()]);
+        let reply = MasixRuntime::handle_session_chat_command(
+            "/session switch work",
+            &context,
+            tag,
+            user,
+            42,
+        )
+        .await;
+        assert!(reply.contains("restored"));
+        assert_eq!(history(&context).await, vec!["work thread".to_string()]);
+
+        // A corrupt state file is reported, never replaced by an empty list.
+        let state_path = MasixRuntime::chat_session_state_path(&context, tag, user, chat);
+        std::fs::write(&state_path, "{not json").unwrap();
+        let reply = MasixRuntime::handle_session_chat_command(
+            "/session new other",
+            &context,
+            tag,
+            user,
+            42,
+        )
+        .await;
+        assert!(reply.contains("corrupt session state"));
+        assert_eq!(std::fs::read_to_string(&state_path).unwrap(), "{not json");
+        assert!(
+            MasixRuntime::append_chat_memory(&context, tag, user, chat, "user", "lost")
+                .await
+                .is_err()
+        );
+
+        std::fs::remove_dir_all(&memory_dir).ok();
+    }
+
+    #[test]
+    fn session_names_map_to_distinct_directories() {
+        let accepted = ["work", "work-2", "my_work", "Big  Plan"]
+            .iter()
+            .map(|raw| MasixRuntime::normalize_chat_session_name(raw).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(accepted, ["work", "work-2", "my_work", "big--plan"]);
+        for name in &accepted {
+            assert_eq!(&MasixRuntime::sanitize_scope_component(name), name);
+        }
+        for rejected in [
+            "_work",
+            "work_",
+            "工作",
+            "работа",
+            "wörk",
+            "a/b",
+            "",
+            "x".repeat(33).as_str(),
+        ] {
+            assert_eq!(MasixRuntime::normalize_chat_session_name(rejected), None);
+        }
+    }
+
+    #[test]
+    fn capabilities_query_detection_matches_common_prompts() {
+        assert!(MasixRuntime::is_capabilities_query("what can you do"));
+        assert!(MasixRuntime::is_capabilities_query("cosa puoi fare"));
+        assert!(MasixRuntime::is_capabilities_query("quali strumenti hai"));
+        assert!(!MasixRuntime::is_capabilities_query("/capabilities"));
+        assert!(!MasixRuntime::is_capabilities_query("scrivi un file"));
+    }
+
+    #[test]
+    fn group_tag_response_requires_tag_for_capabilities_command() {
+        let mut account = make_account("111:AAA");
+        account.bot_name = Some("MyBot".to_string());
+        account.access_mode = Some(AccessMode::GroupTagResponse);
+        let config = Config {
+            telegram: Some(TelegramConfig {
+                poll_timeout_secs: Some(60),
+                client_recreate_interval_secs: Some(60),
+                default_policy: None,
+                accounts: vec![account],
+            }),
+            ..Config::default()
+        };
+
+        let tagged = MasixRuntime::get_permission_level(
+            &config,
+            Some("111"),
+            "telegram",
+            "1001",
+            1001,
+            -100777,
+            "/capabilities @MyBot",
+        );
+        assert_eq!(tagged, PermissionLevel::User);
+
+        let untagged = MasixRuntime::get_permission_level(
+            &config,
+            Some("111"),
+            "telegram",
+            "1001",
+            1001,
+            -100777,
+            "/capabilities",
+        );
+        assert_eq!(untagged, PermissionLevel::None);
+    }
+
+    #[tokio::test]
+    async fn non_admin_tool_visibility_hides_admin_only_modules() {
+        let mut registry = ToolRegistry::default();
+        registry
+            .register(Arc::new(FakeProvider {
+                namespace: "plugin_codex_backend",
+                tools: vec!["run_task"],
+                admin_only: true,
+            }))
+            .unwrap();
+        registry
+            .register(FakeProvider::new("plugin_discovery", &["web_search"]))
+            .unwrap();
+        let catalog = registry.catalog().await;
+
+        let user_visible =
+            catalog.visible_definitions(PermissionLevel::User, &RuntimeToolAccess::All);
+        assert_eq!(user_visible.len(), 1);
+        assert_eq!(user_visible[0].function.name, "plugin_discovery_web_search");
+        assert_eq!(
+            catalog.check_access(
+                "plugin_codex_backend_run_task",
+                PermissionLevel::User,
+                &RuntimeToolAccess::All
+            ),
+            Err(ToolDenial::AdminOnly)
+        );
+
+        let admin_visible =
+            catalog.visible_definitions(PermissionLevel::Admin, &RuntimeToolAccess::All);
+        assert_eq!(admin_visible.len(), 2);
+
+        let selected = RuntimeToolAccess::Selected(
+            ["plugin_codex_backend_run_task".to_string()]
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(
+            catalog.check_access(
+                "plugin_discovery_web_search",
+                PermissionLevel::Admin,
+                &selected
+            ),
+            Err(ToolDenial::Policy)
+        );
+        assert_eq!(
+            catalog
+                .visible_definitions(PermissionLevel::Admin, &selected)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn telegram_streaming_is_only_dm_or_tagged_group() {
+        let mut account = make_account("111:AAA");
+        account.bot_name = Some("MyBot".to_string());
+        let config = Config {
+            telegram: Some(TelegramConfig {
+                poll_timeout_secs: Some(60),
+                client_recreate_interval_secs: Some(60),
+                default_policy: None,
+                accounts: vec![account],
+            }),
+            ..Config::default()
+        };
+
+        let dm = Envelope::new(
+            "telegram",
+            MessageKind::Message {
+                from: "1001".to_string(),
+                text: "hello".to_string(),
+            },
+        )
+        .with_chat_id(1001)
         .with_payload(serde_json::json!({
             "account_tag": "111",
             "from_user_id": 1001
@@ -1003,6 +1804,7 @@ mod tests {
                 tool_calls: None,
                 tool_call_id: Some("1".to_string()),
                 name: Some("plugin_discovery_web_search".to_string()),
+                images: Vec::new(),
             },
             ChatMessage {
                 role: "tool".to_string(),
@@ -1010,6 +1812,7 @@ mod tests {
                 tool_calls: None,
                 tool_call_id: Some("2".to_string()),
                 name: Some("plugin_discovery_web_search".to_string()),
+                images: Vec::new(),
             },
         ];
 
@@ -1018,22 +1821,212 @@ mod tests {
         assert!(out.contains("Result A"));
     }
 
+    #[tokio::test]
+    async fn mcp_resource_contents_render_text_and_save_binary_blobs() {
+        let workdir = std::env::temp_dir().join(format!(
+            "masix-test-resources-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let contents: Vec<masix_mcp::ResourceContents> =
+            serde_json::from_value(serde_json::json!([
+                { "uri": "file:///notes.md", "mimeType": "text/markdown", "text": "# Notes" },
+                { "uri": "file:///data.json", "mimeType": "application/json", "blob": "eyJhIjoxfQ==" },
+                { "uri": "file:///img/logo.png", "mimeType": "image/png", "blob": "iVBORw0KGgo=" },
+                { "uri": "file:///broken", "blob": "***" }
+            ]))
+            .expect("contents");
+
+        let rendered = MasixRuntime::render_mcp_resource_contents(&contents, Some(&workdir))
+            .await
+            .expect("rendered");
+        assert!(rendered.contains("[file:///notes.md] (text/markdown)\n# Notes"));
+        assert!(rendered.contains("[file:///data.json] (application/json)\n{\"a\":1}"));
+        assert!(rendered.contains("Invalid base64 blob"));
+
+        let saved = std::fs::read_dir(workdir.join(super::MCP_RESOURCE_DIR))
+            .expect("resource dir")
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(saved.len(), 1);
+        let file_name = saved[0].file_name().unwrap().to_string_lossy().to_string();
+        assert!(file_name.ends_with("-logo.png"));
+        assert!(rendered.contains(&format!(
+            "Binary resource (8 bytes) saved to mcp_resources/{}",
+            file_name
+        )));
+        assert_eq!(
+            std::fs::read(&saved[0]).unwrap()[..4],
+            [0x89, b'P', b'N', b'G']
+        );
+
+        let described = MasixRuntime::render_mcp_resource_contents(&contents[2..3], None)
+            .await
+            .expect("described");
+        assert!(described.ends_with("Binary resource (8 bytes)."));
+
+        std::fs::remove_dir_all(&workdir).ok();
+    }
+
     #[test]
-    fn resolve_mcp_tool_name_prefers_longest_prefix() {
-        let names = vec![
-            "disc".to_string(),
-            "discovery".to_string(),
-            "discovery_extra".to_string(),
-        ];
-        let resolved =
-            MasixRuntime::resolve_mcp_tool_name(&names, "discovery_web_search").expect("resolved");
-        assert_eq!(resolved.0, "discovery");
-        assert_eq!(resolved.1, "web_search");
+    fn mcp_image_content_is_decoded_and_bounded() {
+        use base64::Engine;
+        let content: masix_mcp::ToolContent = serde_json::from_value(serde_json::json!({
+            "type": "image",
+            "data": "iVBORw0KGgo=",
+            "mimeType": "image/png"
+        }))
+        .expect("image content");
+        let masix_mcp::ToolContent::Image { data, mime_type } = content else {
+            panic!("expected image content");
+        };
+        let image = MasixRuntime::decode_tool_image(&data, &mime_type).expect("image");
+        assert_eq!(image.mime_type, "image/png");
+        assert_eq!(image.data, "iVBORw0KGgo=");
+
+        assert!(MasixRuntime::decode_tool_image("***", "image/png").is_err());
+        assert!(MasixRuntime::decode_tool_image("iVBORw0KGgo=", "text/plain").is_err());
+        let oversized =
+            base64::engine::general_purpose::STANDARD
+                .encode(vec![0u8; super::MCP_IMAGE_MAX_BYTES + 1]);
+        assert!(MasixRuntime::decode_tool_image(&oversized, "image/png")
+            .unwrap_err()
+            .starts_with("too large"));
+    }
+
+    #[tokio::test]
+    async fn mcp_serve_handler_runs_served_tools_as_configured_identity() {
+        use masix_mcp::server::ToolHandler;
+        use masix_mcp::ToolContent;
+
+        let path = temp_db_path("mcp-serve");
+        let storage = Arc::new(Mutex::new(Storage::new(&path).expect("storage")));
+        let serve = masix_config::McpServeConfig {
+            tools: vec!["cron".to_string(), "telegram_send".to_string()],
+            account_tag: Some("bot_a".to_string()),
+            chat_id: Some(4242),
+            user_id: Some(7),
+            permission: masix_config::McpServePermission::User,
+        };
+        let handler = super::McpServeHandler {
+            config: Config::default(),
+            storage: Arc::clone(&storage),
+            bot_context: MasixRuntime::resolve_bot_context(
+                &Arc::new(std::collections::HashMap::new()),
+                None,
+            ),
+            account_tag: serve.account_tag.clone(),
+            user_id: 7,
+            envelope: super::McpServeHandler::identity_envelope(Some("bot_a"), serve.chat_id, 7),
+            permission: serve.permission.level(),
+            tools: super::McpServeHandler::served_tools(&serve),
+            telegram: None,
+            tool_validation: Default::default(),
+        };
+
+        let names: Vec<String> = handler
+            .list_tools()
+            .await
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect();
+        assert_eq!(names, vec!["cron".to_string(), "telegram_send".to_string()]);
+
+        let text_of = |result: masix_mcp::ToolResult| match &result.content[0] {
+            ToolContent::Text { text } => (text.clone(), result.is_error),
+            other => panic!("unexpected content {:?}", other),
+        };
+
+        let (text, is_error) = text_of(
+            handler
+                .call_tool(
+                    "cron",
+                    serde_json::json!({"command": "domani alle 9 \"Meeting\""}),
+                )
+                .await
+                .expect("cron"),
+        );
+        assert!(!is_error, "{}", text);
+        let jobs = storage
+            .lock()
+            .await
+            .list_enabled_cron_jobs_for_account_recipient("bot_a", "4242")
+            .expect("jobs");
+        assert_eq!(jobs.len(), 1);
+
+        let (text, is_error) = text_of(
+            handler
+                .call_tool("cron", serde_json::json!({"command": 9}))
+                .await
+                .expect("rejected"),
+        );
+        assert!(is_error);
+        assert!(text.starts_with("Invalid arguments for tool 'cron'"));
+        assert_eq!(handler.tool_validation.snapshot().get("cron"), Some(&1));
+
+        let (text, _) = text_of(
+            handler
+                .call_tool(
+                    "telegram_send",
+                    serde_json::json!({"chat_id": "4242", "text": "ciao"}),
+                )
+                .await
+                .expect("telegram_send"),
+        );
+        assert_eq!(text, "telegram_send is admin-only.");
+
+        let audited = storage
+            .lock()
+            .await
+            .list_audit(&masix_storage::AuditFilter {
+                kind: Some("tool_call".to_string()),
+                ..Default::default()
+            })
+            .expect("audit");
+        let calls: Vec<(&str, &str)> = audited
+            .iter()
+            .map(|entry| (entry.action.as_str(), entry.outcome.as_str()))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                ("cron", "ok"),
+                ("cron", "invalid_arguments"),
+                ("telegram_send", "ok")
+            ]
+        );
+        assert!(audited.iter().all(|entry| entry.actor == "7"
+            && entry.account_tag.as_deref() == Some("bot_a")
+            && entry.details["source"] == "mcp_serve"));
 
-        let resolved_extra =
-            MasixRuntime::resolve_mcp_tool_name(&names, "discovery_extra_fetch").expect("resolved");
-        assert_eq!(resolved_extra.0, "discovery_extra");
-        assert_eq!(resolved_extra.1, "fetch");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn prompt_command_arguments_and_rendering() {
+        let arguments =
+            MasixRuntime::parse_prompt_arguments("lang=rust  topic=a=b empty=").expect("parsed");
+        assert_eq!(arguments.get("lang").map(String::as_str), Some("rust"));
+        assert_eq!(arguments.get("topic").map(String::as_str), Some("a=b"));
+        assert_eq!(arguments.get("empty").map(String::as_str), Some(""));
+        assert!(MasixRuntime::parse_prompt_arguments("loose").is_err());
+        assert!(MasixRuntime::parse_prompt_arguments("=value").is_err());
+
+        let result: masix_mcp::PromptResult = serde_json::from_value(serde_json::json!({
+            "messages": [
+                { "role": "user", "content": { "type": "text", "text": "Review this" } },
+                { "role": "assistant", "content": { "type": "text", "text": "Sure" } },
+                { "role": "user", "content": { "type": "image", "data": "", "mimeType": "image/png" } },
+                { "role": "user", "content": { "type": "resource", "resource": { "uri": "file:///a", "text": "fn main() {}" } } }
+            ]
+        }))
+        .expect("prompt result");
+        assert_eq!(
+            MasixRuntime::render_prompt_messages(&result),
+            "Review this\n\n[assistant] Sure\n\nfn main() {}"
+        );
     }
 }
 
@@ -1047,6 +2040,33 @@ struct BotContext {
     vision_provider: Option<String>,
     retry_policy: RetryPolicy,
     exec_policy: ExecPolicy,
+    /// Background exec jobs, shared by all accounts; only set by `run`.
+    exec_jobs: Option<Arc<ExecJobs>>,
+    /// Memory scopes other accounts share with this one.
+    memory_grants: Vec<MemoryGrant>,
+}
+
+/// A scope owned by another account that this account may read (and write if `writable`).
+#[derive(Debug, Clone)]
+struct MemoryGrant {
+    owner_account: String,
+    owner_memory_dir: PathBuf,
+    scope: MemoryScope,
+    writable: bool,
+}
+
+/// Which account's scoped memory a request targets.
+#[derive(Debug, Clone, Copy)]
+enum MemoryNamespace<'a> {
+    Own,
+    Shared(&'a MemoryGrant),
+}
+
+/// Result of a single tool call: its output, or how a held call ended when
+/// it was not approved.
+enum ToolCallOutcome {
+    Completed(String),
+    Declined(ApprovalOutcome),
 }
 
 /// Result of the LLM tool execution loop
@@ -1062,6 +2082,17 @@ struct LlmLoopOptions {
     auto_continue_max: usize,
     continuation_detection: AgentLoopContinuationDetection,
     tool_progress: CoreToolProgressConfig,
+    /// Menu language of the requester.
+    language: Language,
+    user_languages: Arc<Mutex<HashMap<String, Language>>>,
+}
+
+/// Outcome of a `/prompts` or `/prompt` command
+enum PromptCommandOutcome {
+    /// Reply directly to the chat
+    Reply(String),
+    /// Use the expanded prompt text as the user turn
+    Expand(String),
 }
 
 /// Context for LLM message building
@@ -1078,6 +2109,23 @@ struct ChatMemoryEntry {
     ts: String,
 }
 
+/// Named sessions of one user in one chat. `active = None` is the default session.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct ChatSessionState {
+    #[serde(default)]
+    active: Option<String>,
+    #[serde(default)]
+    sessions: Vec<ChatSessionInfo>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct ChatSessionInfo {
+    name: String,
+    created_at: String,
+    #[serde(default)]
+    archived_at: Option<String>,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct UserMemoryMeta {
     account_tag: String,
@@ -1096,6 +2144,21 @@ struct UserMemoryMeta {
     display_names: Vec<String>,
 }
 
+/// Durable fact about a user, extracted post-turn and kept in `user_private` memory.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct UserProfileFact {
+    id: u64,
+    text: String,
+    #[serde(default)]
+    category: String,
+    trace_id: String,
+    ts: String,
+    #[serde(default)]
+    channel: String,
+    #[serde(default)]
+    chat_id: Option<i64>,
+}
+
 #[derive(Debug, Clone)]
 struct ObservedTelegramUser {
     user_id: i64,
@@ -1128,6 +2191,14 @@ impl MemoryScope {
             Self::AdminKb => "admin_kb",
         }
     }
+
+    fn share_scope(self) -> MemoryShareScope {
+        match self {
+            Self::UserPrivate => MemoryShareScope::UserPrivate,
+            Self::SharedUserKb => MemoryShareScope::SharedUserKb,
+            Self::AdminKb => MemoryShareScope::AdminKb,
+        }
+    }
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -1162,6 +2233,8 @@ pub struct MasixRuntime {
     user_languages: Arc<Mutex<HashMap<String, Language>>>,
     user_providers: Arc<Mutex<HashMap<String, String>>>,
     user_models: Arc<Mutex<HashMap<String, String>>>,
+    tool_validation: Arc<ToolValidationMetrics>,
+    tool_providers: ToolRegistry,
 }
 
 impl MasixRuntime {
@@ -1180,12 +2253,15 @@ impl MasixRuntime {
                     provider_config.base_url.clone(),
                     provider_config.model.clone(),
                 )),
-                _ => Box::new(OpenAICompatibleProvider::new(
-                    provider_config.name.clone(),
-                    provider_config.api_key.clone(),
-                    provider_config.base_url.clone(),
-                    provider_config.model.clone(),
-                )),
+                _ => Box::new(
+                    OpenAICompatibleProvider::new(
+                        provider_config.name.clone(),
+                        provider_config.api_key.clone(),
+                        provider_config.base_url.clone(),
+                        provider_config.model.clone(),
+                    )
+                    .with_image_input(provider_config.supports_images),
+                ),
             };
             provider_router.add_provider(provider);
         }
@@ -1215,9 +2291,45 @@ impl MasixRuntime {
             user_languages: Arc::new(Mutex::new(HashMap::new())),
             user_providers: Arc::new(Mutex::new(HashMap::new())),
             user_models: Arc::new(Mutex::new(HashMap::new())),
+            tool_validation: Arc::new(ToolValidationMetrics::default()),
+            tool_providers: ToolRegistry::default(),
         })
     }
 
+    /// Expose extra tools to the LLM as `{namespace}_{tool}`. Fails when the
+    /// namespace is invalid or already taken. Installed `wasm_tool` plugins
+    /// are added automatically when the runtime starts.
+    pub fn register_tool_provider(&mut self, provider: Arc<dyn ToolProvider>) -> Result<()> {
+        self.tool_providers.register(provider)
+    }
+
+    /// Builtins, then one provider per started MCP server, then application
+    /// and plugin providers.
+    async fn build_tool_registry(
+        &self,
+        data_dir: &Path,
+        admin_only_modules: &HashSet<String>,
+    ) -> ToolRegistry {
+        let mut registry = ToolRegistry::with_builtins(self.mcp_client.is_some());
+        if let Some(client) = &self.mcp_client {
+            let client = client.lock().await;
+            for server_name in client.server_names() {
+                if let Some(server) = client.server(&server_name) {
+                    let admin_only = is_admin_only_server(&server_name, admin_only_modules);
+                    registry.register_mcp_server(server, admin_only);
+                }
+            }
+        }
+        let plugins = wasm_tools::load_wasm_tool_providers(data_dir, self.config.plugins.as_ref());
+        for provider in self.tool_providers.plugin_providers().chain(plugins) {
+            let namespace = provider.namespace().to_string();
+            if let Err(e) = registry.register(provider) {
+                warn!("Tool provider '{}' not registered: {}", namespace, e);
+            }
+        }
+        registry
+    }
+
     fn load_soul(config: &Config) -> Option<String> {
         let soul_path = config.core.soul_file.as_ref()?;
 
@@ -1252,12 +2364,23 @@ impl MasixRuntime {
 
     pub async fn run(&self) -> Result<()> {
         info!("Masix runtime starting...");
+        if let Some(telegram) = &self.config.telegram {
+            for warning in telegram.legacy_memory_share_warnings() {
+                warn!("{}", warning);
+            }
+        }
 
         self.init_mcp_servers().await;
 
         let outbound_sender = self.event_bus.outbound_sender();
         let base_data_dir = self.get_data_dir()?;
-        let bot_contexts = Arc::new(self.build_bot_contexts(&base_data_dir)?);
+        self.spawn_mcp_status_writer(&base_data_dir);
+        let mut bot_contexts = self.build_bot_contexts(&base_data_dir)?;
+        let exec_jobs = Arc::new(ExecJobs::load(base_data_dir.join("exec_jobs")));
+        for context in bot_contexts.values_mut() {
+            context.exec_jobs = Some(Arc::clone(&exec_jobs));
+        }
+        let bot_contexts = Arc::new(bot_contexts);
 
         // Load admin-only module IDs from plugin registry
         let admin_only_modules = Arc::new(load_admin_only_modules(&base_data_dir));
@@ -1268,6 +2391,13 @@ impl MasixRuntime {
             );
         }
 
+        let tool_registry = Arc::new(
+            self.build_tool_registry(&base_data_dir, &admin_only_modules)
+                .await,
+        );
+
+        self.load_persisted_user_preferences().await;
+
         self.start_telegram_adapters(Arc::clone(&bot_contexts))
             .await?;
         self.start_sms_adapter().await?;
@@ -1277,18 +2407,21 @@ impl MasixRuntime {
         let provider_router = Arc::clone(&self.provider_router);
         let storage_for_processor = Arc::clone(&self.storage);
         let mcp_client = self.mcp_client.clone();
+        let tool_registry_for_processor = Arc::clone(&tool_registry);
         let system_prompt = self.system_prompt.clone();
         let policy = self.policy.clone();
         let rate_state: Arc<Mutex<HashMap<String, (i64, u32)>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let inbound_scope_locks: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>> =
-            Arc::new(Mutex::new(HashMap::new()));
+        let inbound_scope_locks: InboundScopeLocks = Arc::new(Mutex::new(HashMap::new()));
+        let active_turns: ActiveTurns = Arc::new(Mutex::new(HashMap::new()));
+        let tool_approvals = Arc::new(ToolApprovalGate::default());
         let inbound_semaphore = Arc::new(Semaphore::new(MAX_INBOUND_CONCURRENCY));
         let bot_contexts_for_processor = Arc::clone(&bot_contexts);
         let default_cron_account_tag = self.default_telegram_account_tag();
         let user_languages_for_processor = Arc::clone(&self.user_languages);
         let user_providers_for_processor = Arc::clone(&self.user_providers);
         let user_models_for_processor = Arc::clone(&self.user_models);
+        let tool_validation_for_processor = Arc::clone(&self.tool_validation);
         let config_for_processor = self.config.clone();
         let admin_only_modules_for_processor = Arc::clone(&admin_only_modules);
         let cron_cfg = self.config.core.cron.clone();
@@ -1302,10 +2435,34 @@ impl MasixRuntime {
                     result = inbound_rx.recv() => {
                         match result {
                             Ok(envelope) => {
+                                // `/stop` must not queue behind the turn it interrupts.
+                                if Self::is_stop_command(&envelope) {
+                                    Self::handle_stop_command(
+                                        &envelope,
+                                        &policy,
+                                        &active_turns,
+                                        &outbound_for_processor,
+                                    )
+                                    .await;
+                                    continue;
+                                }
+                                // Approval clicks release a turn that holds the chat scope lock.
+                                if Self::is_tool_approval_callback(&envelope) {
+                                    Self::handle_tool_approval_callback(
+                                        &envelope,
+                                        &config_for_processor,
+                                        &tool_approvals,
+                                        &user_languages_for_processor,
+                                        &outbound_for_processor,
+                                    )
+                                    .await;
+                                    continue;
+                                }
                                 let outbound = outbound_for_processor.clone();
                                 let provider_router = Arc::clone(&provider_router);
                                 let storage = Arc::clone(&storage_for_processor);
                                 let mcp_client = mcp_client.clone();
+                                let tool_registry = Arc::clone(&tool_registry_for_processor);
                                 let system_prompt = system_prompt.clone();
                                 let policy = policy.clone();
                                 let rate_state = Arc::clone(&rate_state);
@@ -1313,10 +2470,13 @@ impl MasixRuntime {
                                 let user_languages = Arc::clone(&user_languages_for_processor);
                                 let user_providers = Arc::clone(&user_providers_for_processor);
                                 let user_models = Arc::clone(&user_models_for_processor);
+                                let tool_validation = Arc::clone(&tool_validation_for_processor);
                                 let config = config_for_processor.clone();
                                 let admin_only_modules = Arc::clone(&admin_only_modules_for_processor);
                                 let semaphore = Arc::clone(&inbound_semaphore);
                                 let scope_locks = Arc::clone(&inbound_scope_locks);
+                                let active_turns = Arc::clone(&active_turns);
+                                let tool_approvals = Arc::clone(&tool_approvals);
                                 let scope_key = Self::inbound_processing_scope_key(&envelope);
                                 let trace_id = envelope.trace_id.clone();
 
@@ -1338,13 +2498,16 @@ impl MasixRuntime {
                                     )
                                     .await;
                                     let _scope_guard = scope_lock.lock_owned().await;
+                                    let (stop_tx, stop_rx) = watch::channel(false);
+                                    active_turns.lock().await.insert(scope_key.clone(), stop_tx);
 
-                                    if let Err(e) = Self::process_inbound_message(
+                                    let outcome = Self::process_inbound_message(
                                         envelope,
                                         outbound,
-                                        provider_router.as_ref(),
+                                        &provider_router,
                                         &storage,
                                         &mcp_client,
+                                        &tool_registry,
                                         &system_prompt,
                                         &policy,
                                         &rate_state,
@@ -1354,9 +2517,13 @@ impl MasixRuntime {
                                         &user_models,
                                         &config,
                                         &admin_only_modules,
+                                        &tool_validation,
+                                        &tool_approvals,
+                                        &stop_rx,
                                     )
-                                    .await
-                                    {
+                                    .await;
+                                    active_turns.lock().await.remove(&scope_key);
+                                    if let Err(e) = outcome {
                                         error!(
                                             "Error processing inbound message (trace_id={}): {}",
                                             trace_id, e
@@ -1438,6 +2605,9 @@ impl MasixRuntime {
                     edit_message_id: None,
                     inline_keyboard: None,
                     chat_action: None,
+                    photo: None,
+                    delivered: None,
+                    callback_answer: None,
                 };
                 let mut success = false;
                 for attempt in 0..=cron_cfg.delivery_retry_count {
@@ -1560,24 +2730,75 @@ impl MasixRuntime {
         Ok(())
     }
 
+    pub fn mcp_sandbox_from_config(sandbox: &masix_config::McpSandboxConfig) -> McpSandbox {
+        McpSandbox {
+            workdir: sandbox.workdir.as_ref().map(PathBuf::from),
+            env_allowlist: sandbox.env_allowlist.clone(),
+            max_cpu_secs: sandbox.max_cpu_secs,
+            max_memory_mb: sandbox.max_memory_mb,
+            max_open_files: sandbox.max_open_files,
+            no_new_privileges: sandbox.no_new_privileges,
+            landlock: sandbox.landlock,
+            writable_paths: sandbox.writable_paths.iter().map(PathBuf::from).collect(),
+            isolate_network: sandbox.isolate_network,
+        }
+    }
+
+    fn mcp_call_limits_from_config(server: &masix_config::McpServer) -> McpCallLimits {
+        McpCallLimits {
+            tool_timeouts: server.tool_timeouts.clone(),
+            max_in_flight: server.max_in_flight,
+        }
+    }
+
     async fn init_mcp_servers(&self) {
         if let Some(mcp_client) = &self.mcp_client {
             if let Some(mcp_config) = &self.config.mcp {
                 if mcp_config.enabled {
                     let mut client = mcp_client.lock().await;
                     for server in &mcp_config.servers {
-                        match client
-                            .add_server(
-                                server.name.clone(),
-                                server.command.clone(),
-                                server.args.clone(),
-                                server.env.clone(),
-                                server.timeout_secs,
-                                server.startup_timeout_secs,
-                                server.healthcheck_interval_secs,
-                            )
-                            .await
-                        {
+                        let result = match server.effective_transport() {
+                            McpTransport::Stdio => {
+                                client
+                                    .add_server(
+                                        server.name.clone(),
+                                        server.command.clone(),
+                                        server.args.clone(),
+                                        server.env.clone(),
+                                        server.timeout_secs,
+                                        server.startup_timeout_secs,
+                                        server.healthcheck_interval_secs,
+                                        server.tool_cache_ttl_secs,
+                                        McpRestartPolicy {
+                                            max_restarts: server.max_restarts,
+                                            backoff_secs: server.restart_backoff_secs,
+                                            backoff_max_secs: server.restart_backoff_max_secs,
+                                        },
+                                        server.sandbox.as_ref().map(Self::mcp_sandbox_from_config),
+                                        Self::mcp_call_limits_from_config(server),
+                                    )
+                                    .await
+                            }
+                            transport @ (McpTransport::Http | McpTransport::Sse) => {
+                                client
+                                    .add_remote_server(
+                                        server.name.clone(),
+                                        McpHttpOptions {
+                                            url: server.url.clone().unwrap_or_default(),
+                                            headers: server.headers.clone(),
+                                            auth_token: server.auth_token.clone(),
+                                            legacy_sse: transport == McpTransport::Sse,
+                                        },
+                                        server.timeout_secs,
+                                        server.startup_timeout_secs,
+                                        server.healthcheck_interval_secs,
+                                        server.tool_cache_ttl_secs,
+                                        Self::mcp_call_limits_from_config(server),
+                                    )
+                                    .await
+                            }
+                        };
+                        match result {
                             Ok(_) => info!("MCP server '{}' started", server.name),
                             Err(e) => error!("Failed to start MCP server '{}': {}", server.name, e),
                         }
@@ -1587,6 +2808,154 @@ impl MasixRuntime {
         }
     }
 
+    fn spawn_mcp_status_writer(&self, data_dir: &Path) {
+        let Some(mcp_client) = self.mcp_client.clone() else {
+            return;
+        };
+        if !self.config.mcp.as_ref().is_some_and(|mcp| mcp.enabled) {
+            return;
+        }
+        let path = data_dir.join(MCP_STATUS_FILE);
+        let tool_validation = Arc::clone(&self.tool_validation);
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(MCP_STATUS_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                let servers = mcp_client.lock().await.server_statuses().await;
+                let snapshot = McpStatusSnapshot {
+                    updated_at: chrono::Utc::now().to_rfc3339(),
+                    servers,
+                    tool_validation_failures: tool_validation.snapshot(),
+                };
+                let result = match serde_json::to_vec_pretty(&snapshot) {
+                    Ok(bytes) => fs::write(&path, bytes).await.map_err(anyhow::Error::from),
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = result {
+                    warn!("Failed to write MCP status to {}: {}", path.display(), e);
+                }
+            }
+        });
+    }
+
+    /// Serve the `[mcp.serve]` builtin tools over MCP stdio until stdin is closed.
+    pub async fn serve_mcp(&self) -> Result<()> {
+        let serve = self
+            .config
+            .mcp
+            .as_ref()
+            .and_then(|mcp| mcp.serve.clone())
+            .unwrap_or_default();
+        let base_data_dir = self.get_data_dir()?;
+        let bot_contexts = Arc::new(self.build_bot_contexts(&base_data_dir)?);
+        let account_tag = serve
+            .account_tag
+            .clone()
+            .or_else(|| self.default_telegram_account_tag());
+        let bot_context = Self::resolve_bot_context(&bot_contexts, account_tag.as_deref());
+
+        let telegram = self.config.telegram.as_ref().and_then(|telegram| {
+            telegram
+                .accounts
+                .iter()
+                .find(|account| {
+                    account_tag.as_deref()
+                        == Some(Self::account_tag_from_token(&account.bot_token).as_str())
+                })
+                .map(|account| {
+                    masix_telegram::TelegramAdapter::new(
+                        account,
+                        bot_context.workdir.clone(),
+                        telegram.poll_timeout_secs,
+                        telegram.client_recreate_interval_secs,
+                    )
+                })
+        });
+        if telegram.is_none() && serve.tools.iter().any(|tool| tool == "telegram_send") {
+            warn!(
+                "mcp serve: no Telegram account matches '{}', telegram_send will fail",
+                account_tag.as_deref().unwrap_or("-")
+            );
+        }
+
+        let user_id = serve.user_id.unwrap_or_default();
+        let handler = McpServeHandler {
+            config: self.config.clone(),
+            storage: Arc::clone(&self.storage),
+            envelope: McpServeHandler::identity_envelope(
+                account_tag.as_deref(),
+                serve.chat_id,
+                user_id,
+            ),
+            bot_context,
+            account_tag,
+            user_id,
+            permission: serve.permission.level(),
+            tools: McpServeHandler::served_tools(&serve),
+            telegram,
+            tool_validation: ToolValidationMetrics::default(),
+        };
+        let server_info = masix_mcp::server::ServerInfo {
+            name: "masix".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            instructions: Some(
+                "MasiX builtin tools: schedule reminders (cron), read/write memory, post to Telegram."
+                    .to_string(),
+            ),
+        };
+        info!(
+            "mcp serve: exposing {:?} as {:?} (account={}, chat_id={:?})",
+            serve.tools,
+            serve.permission,
+            handler.account_tag.as_deref().unwrap_or("-"),
+            serve.chat_id
+        );
+        masix_mcp::server::serve_stdio(&server_info, &handler).await
+    }
+
+    /// Last MCP supervisor snapshot written by a running instance, if any.
+    pub fn load_mcp_status(data_dir: &Path) -> Option<McpStatusSnapshot> {
+        let raw = std::fs::read(data_dir.join(MCP_STATUS_FILE)).ok()?;
+        serde_json::from_slice(&raw).ok()
+    }
+
+    fn format_mcp_server_status(status: &McpServerStatus, stderr_lines: usize) -> Vec<String> {
+        let mut details = Vec::new();
+        if let Some(pid) = status.pid {
+            details.push(format!("pid {}", pid));
+        }
+        details.push(format!("restarts {}", status.restart_count));
+        if let Some(count) = status.tool_count {
+            details.push(format!("tools {}", count));
+        }
+        if let Some(version) = &status.protocol_version {
+            details.push(format!("protocol {}", version));
+        }
+        let icon = match status.state {
+            McpServerState::Running => "🟢",
+            McpServerState::Restarting => "🟡",
+            McpServerState::Failed => "🔴",
+        };
+        let mut lines = vec![format!(
+            "{} {} — {} ({})",
+            icon,
+            status.name,
+            status.state.as_str(),
+            details.join(", ")
+        )];
+        if status.state != McpServerState::Running {
+            if let Some(exit) = &status.last_exit {
+                lines.push(format!("    last exit: {}", exit));
+            }
+            let skip = status.last_stderr.len().saturating_sub(stderr_lines);
+            for line in status.last_stderr.iter().skip(skip) {
+                lines.push(format!("    stderr: {}", line));
+            }
+        }
+        lines
+    }
+
     async fn start_telegram_adapters(
         &self,
         bot_contexts: Arc<HashMap<String, BotContext>>,
@@ -1764,6 +3133,8 @@ impl MasixRuntime {
                             vision_provider: profile.vision_provider.clone(),
                             retry_policy: Self::retry_policy_from_config(profile.retry.as_ref()),
                             exec_policy: Self::exec_policy_from_config(self.config.exec.as_ref()),
+                            exec_jobs: None,
+                            memory_grants: Vec::new(),
                         }
                     } else {
                         self.default_account_bot_context(base_data_dir, &account_tag)?
@@ -1775,6 +3146,39 @@ impl MasixRuntime {
                 Self::ensure_bot_context_dirs(&context)?;
                 contexts.insert(account_tag, context);
             }
+
+            let tags: Vec<String> = contexts
+                .keys()
+                .filter(|tag| tag.as_str() != "__default__")
+                .cloned()
+                .collect();
+            for grantee in &tags {
+                let mut grants = Vec::new();
+                for owner in &tags {
+                    let Some(owner_context) = contexts.get(owner) else {
+                        continue;
+                    };
+                    for scope in [
+                        MemoryScope::UserPrivate,
+                        MemoryScope::SharedUserKb,
+                        MemoryScope::AdminKb,
+                    ] {
+                        if let Some(access) =
+                            telegram.memory_share_access(owner, grantee, scope.share_scope())
+                        {
+                            grants.push(MemoryGrant {
+                                owner_account: owner.clone(),
+                                owner_memory_dir: owner_context.memory_dir.clone(),
+                                scope,
+                                writable: access == MemoryShareAccess::ReadWrite,
+                            });
+                        }
+                    }
+                }
+                if let Some(context) = contexts.get_mut(grantee) {
+                    context.memory_grants = grants;
+                }
+            }
         }
 
         Ok(contexts)
@@ -1796,6 +3200,8 @@ impl MasixRuntime {
             vision_provider: None,
             retry_policy: RetryPolicy::default(),
             exec_policy: Self::exec_policy_from_config(self.config.exec.as_ref()),
+            exec_jobs: None,
+            memory_grants: Vec::new(),
         };
         Self::ensure_bot_context_dirs(&context)?;
         Ok(context)
@@ -1816,6 +3222,8 @@ impl MasixRuntime {
             vision_provider: None,
             retry_policy: RetryPolicy::default(),
             exec_policy: Self::exec_policy_from_config(self.config.exec.as_ref()),
+            exec_jobs: None,
+            memory_grants: Vec::new(),
         };
         Self::ensure_bot_context_dirs(&context)?;
         Ok(context)
@@ -1885,7 +3293,7 @@ impl MasixRuntime {
         }
     }
 
-    fn exec_policy_from_config(config: Option<&masix_config::ExecConfig>) -> ExecPolicy {
+    pub fn exec_policy_from_config(config: Option<&masix_config::ExecConfig>) -> ExecPolicy {
         let mut policy = ExecPolicy::default();
         if let Some(cfg) = config {
             policy.enabled = cfg.enabled.unwrap_or(policy.enabled);
@@ -1899,10 +3307,68 @@ impl MasixRuntime {
             if !cfg.termux_allowlist.is_empty() {
                 policy.termux_allowlist = cfg.termux_allowlist.clone();
             }
+            if let Some(pipeline_cfg) = &cfg.pipeline {
+                let pipeline = &mut policy.pipeline;
+                pipeline.enabled = pipeline_cfg.enabled;
+                if let Some(allowlist) = &pipeline_cfg.allowlist {
+                    pipeline.allowlist = allowlist.clone();
+                }
+                pipeline.read_only_paths = pipeline_cfg
+                    .read_only_paths
+                    .iter()
+                    .map(PathBuf::from)
+                    .collect();
+                pipeline.max_stages = pipeline_cfg.max_stages.unwrap_or(pipeline.max_stages);
+                pipeline.max_cpu_secs = pipeline_cfg.max_cpu_secs.unwrap_or(pipeline.max_cpu_secs);
+                pipeline.max_memory_mb =
+                    pipeline_cfg.max_memory_mb.unwrap_or(pipeline.max_memory_mb);
+                pipeline.max_file_size_mb = pipeline_cfg
+                    .max_file_size_mb
+                    .unwrap_or(pipeline.max_file_size_mb);
+                pipeline.allow_network =
+                    pipeline_cfg.allow_network.unwrap_or(pipeline.allow_network);
+                pipeline.landlock = pipeline_cfg.landlock.unwrap_or(pipeline.landlock);
+            }
+            if let Some(jobs_cfg) = &cfg.jobs {
+                let jobs = &mut policy.jobs;
+                jobs.enabled = jobs_cfg.enabled;
+                jobs.max_running = jobs_cfg.max_running.unwrap_or(jobs.max_running);
+                jobs.max_runtime_secs = jobs_cfg.max_runtime_secs.unwrap_or(jobs.max_runtime_secs);
+                jobs.max_log_bytes = jobs_cfg.max_log_bytes.unwrap_or(jobs.max_log_bytes);
+                jobs.keep_finished = jobs_cfg.keep_finished.unwrap_or(jobs.keep_finished);
+                jobs.update_interval_secs = jobs_cfg
+                    .update_interval_secs
+                    .unwrap_or(jobs.update_interval_secs);
+            }
+            if let Some(rules_cfg) = &cfg.rules {
+                match Self::exec_rules_from_config(rules_cfg) {
+                    Ok(rules) => policy.rules = rules,
+                    Err(e) => {
+                        // Never run with a partial rule set: a dropped deny
+                        // rule would widen the policy.
+                        error!("Invalid exec rules, exec disabled: {}", e);
+                        policy.enabled = false;
+                    }
+                }
+            }
         }
         policy
     }
 
+    fn exec_rules_from_config(cfg: &masix_config::ExecRulesConfig) -> Result<ExecRules> {
+        let mut rules = ExecRules::default();
+        for (section, allow, deny) in cfg.sets() {
+            let set = ExecRuleSet::parse(allow, deny)
+                .map_err(|e| anyhow!("exec.rules ({}): {}", section, e))?;
+            if section == "default" {
+                rules.default = set;
+            } else {
+                rules.levels.insert(section.to_string(), set);
+            }
+        }
+        Ok(rules)
+    }
+
     fn account_tag_from_token(token: &str) -> String {
         token.split(':').next().unwrap_or("default").to_string()
     }
@@ -1928,8 +3394,163 @@ impl MasixRuntime {
         format!("{}:{}:{}", envelope.channel, account_tag, scope)
     }
 
+    fn is_stop_command(envelope: &Envelope) -> bool {
+        let MessageKind::Message { text, .. } = &envelope.kind else {
+            return false;
+        };
+        let command = text.split_whitespace().next().unwrap_or_default();
+        command == "/stop" || command.starts_with("/stop@")
+    }
+
+    /// Cancel the turn running in the sender's scope: the LLM loop stops and
+    /// in-flight MCP calls are sent `notifications/cancelled`.
+    async fn handle_stop_command(
+        envelope: &Envelope,
+        policy: &PolicyEngine,
+        active_turns: &ActiveTurns,
+        outbound_sender: &broadcast::Sender<OutboundMessage>,
+    ) {
+        let MessageKind::Message { from, .. } = &envelope.kind else {
+            return;
+        };
+        let sender_id = envelope
+            .chat_id
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| from.clone());
+        if !policy.is_allowed(&sender_id) {
+            warn!("Blocked /stop by policy from {}", sender_id);
+            return;
+        }
+
+        let scope_key = Self::inbound_processing_scope_key(envelope);
+        let stopped = match active_turns.lock().await.get(&scope_key) {
+            Some(stop) => {
+                stop.send_replace(true);
+                true
+            }
+            None => false,
+        };
+        info!("/stop in scope {} (running turn: {})", scope_key, stopped);
+        if let Some(chat_id) = envelope.chat_id {
+            let text = if stopped {
+                "⏹️ Interruzione richiesta: annullo le operazioni in corso."
+            } else {
+                "Nessuna operazione in corso da interrompere."
+            };
+            Self::send_outbound_text(
+                outbound_sender,
+                &envelope.channel,
+                envelope
+                    .payload
+                    .get("account_tag")
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.to_string()),
+                chat_id,
+                text,
+                envelope.message_id,
+            );
+        }
+    }
+
+    fn is_tool_approval_callback(envelope: &Envelope) -> bool {
+        matches!(
+            &envelope.kind,
+            MessageKind::Callback { data, .. }
+                if data.starts_with(tool_approval::APPROVAL_CALLBACK_PREFIX)
+        )
+    }
+
+    /// Deliver an admin's ✅/❌ to the held tool call, close the card and
+    /// answer the button press.
+    async fn handle_tool_approval_callback(
+        envelope: &Envelope,
+        config: &Config,
+        tool_approvals: &ToolApprovalGate,
+        user_languages: &Arc<Mutex<HashMap<String, Language>>>,
+        outbound_sender: &broadcast::Sender<OutboundMessage>,
+    ) {
+        let MessageKind::Callback { query_id, data } = &envelope.kind else {
+            return;
+        };
+        let account_tag = envelope
+            .payload
+            .get("account_tag")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string());
+        let user_id = envelope
+            .payload
+            .get("from_user_id")
+            .and_then(|v| v.as_i64())
+            .unwrap_or_default();
+        let lang = user_languages
+            .lock()
+            .await
+            .get(&Self::user_state_key(
+                account_tag.as_deref(),
+                Some(&user_id.to_string()),
+                None,
+            ))
+            .copied()
+            .unwrap_or_default();
+        let reply = |text: String, toast: &str| {
+            if let Some(chat_id) = envelope.chat_id {
+                let _ = outbound_sender.send(OutboundMessage {
+                    channel: envelope.channel.clone(),
+                    account_tag: account_tag.clone(),
+                    chat_id,
+                    edit_message_id: (!text.is_empty()).then_some(envelope.message_id).flatten(),
+                    text,
+                    reply_to: None,
+                    inline_keyboard: None,
+                    chat_action: None,
+                    photo: None,
+                    delivered: None,
+                    callback_answer: Some(CallbackAnswer {
+                        query_id: query_id.clone(),
+                        text: (!toast.is_empty()).then(|| toast.to_string()),
+                    }),
+                });
+            }
+        };
+        let Some((approval_id, approved)) = tool_approval::parse_callback(data) else {
+            reply(String::new(), "");
+            return;
+        };
+        let is_admin = envelope.channel == "telegram"
+            && Self::get_telegram_account(config, account_tag.as_deref()).is_some_and(|account| {
+                let dynamic_acl = Self::load_dynamic_acl_for_account(account);
+                Self::telegram_user_permission(account, &dynamic_acl, user_id)
+                    == PermissionLevel::Admin
+            });
+        if !is_admin {
+            warn!(
+                "Ignored tool approval {} from non-admin user {}",
+                approval_id, user_id
+            );
+            reply(String::new(), tool_approval::admin_only_notice(lang));
+            return;
+        }
+
+        let decision = ApprovalDecision {
+            approved,
+            approver: user_id,
+        };
+        let tool_name = tool_approvals.resolve(approval_id, account_tag.as_deref(), decision);
+        info!(
+            "Tool approval {} answered by {}: {}",
+            approval_id,
+            user_id,
+            if approved { "approve" } else { "reject" }
+        );
+        // The edited card shows the result, so the answer only stops the spinner.
+        reply(
+            tool_approval::decision_text(tool_name.as_deref(), approved, user_id, lang),
+            "",
+        );
+    }
+
     async fn get_or_create_inbound_scope_lock(
-        scope_locks: &Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+        scope_locks: &InboundScopeLocks,
         scope_key: &str,
     ) -> Arc<Mutex<()>> {
         let mut locks = scope_locks.lock().await;
@@ -1986,6 +3607,8 @@ impl MasixRuntime {
                 vision_provider: None,
                 retry_policy: RetryPolicy::default(),
                 exec_policy: ExecPolicy::default(),
+                exec_jobs: None,
+                memory_grants: Vec::new(),
             })
     }
 
@@ -2029,6 +3652,7 @@ impl MasixRuntime {
             tool_calls: None,
             tool_call_id: None,
             name: None,
+            images: Vec::new(),
         }];
 
         // Build user message with media enrichment
@@ -2092,6 +3716,7 @@ impl MasixRuntime {
             tool_calls: None,
             tool_call_id: None,
             name: None,
+            images: Vec::new(),
         });
 
         Ok(LlmMessagesResult {
@@ -2101,29 +3726,6 @@ impl MasixRuntime {
         })
     }
 
-    async fn get_mcp_tools(mcp_client: &Option<Arc<Mutex<McpClient>>>) -> Vec<ToolDefinition> {
-        let mut tools = get_builtin_tool_definitions();
-
-        if let Some(client) = mcp_client {
-            let mcp = client.lock().await;
-            let mcp_tools = mcp.list_all_tools().await;
-
-            for (server_name, tool) in mcp_tools {
-                let tool_def = ToolDefinition {
-                    tool_type: "function".to_string(),
-                    function: masix_providers::FunctionDefinition {
-                        name: format!("{}_{}", server_name, tool.name),
-                        description: tool.description,
-                        parameters: tool.input_schema,
-                    },
-                };
-                tools.push(tool_def);
-            }
-        }
-
-        tools
-    }
-
     fn build_tool_call_guidance(tools: &[ToolDefinition]) -> String {
         let mut builtin_names: Vec<String> = tools
             .iter()
@@ -2249,6 +3851,60 @@ impl MasixRuntime {
         }
     }
 
+    /// Post a progress line to the Telegram chat of `envelope`; false when nothing was sent.
+    fn send_tool_progress(
+        outbound_sender: Option<&broadcast::Sender<OutboundMessage>>,
+        envelope: &Envelope,
+        account_tag: Option<&str>,
+        text: &str,
+    ) -> bool {
+        let (Some(sender), Some(chat_id)) = (outbound_sender, envelope.chat_id) else {
+            return false;
+        };
+        if envelope.channel != "telegram" {
+            return false;
+        }
+        Self::send_outbound_text(
+            sender,
+            &envelope.channel,
+            account_tag.map(|s| s.to_string()),
+            chat_id,
+            text,
+            None,
+        );
+        debug!("Sent tool progress message: {}", text);
+        true
+    }
+
+    fn format_mcp_progress(update: &McpProgress, include_tool_name: bool) -> String {
+        let amount = match update.total {
+            Some(total) if total > 0.0 => {
+                format!(
+                    "{:.0}%",
+                    (update.progress / total * 100.0).clamp(0.0, 100.0)
+                )
+            }
+            _ => format!("{}", update.progress),
+        };
+        let mut text = if include_tool_name {
+            format!("Quick update: {} {}", update.tool, amount)
+        } else {
+            format!("Quick update: still working ({})", amount)
+        };
+        if let Some(message) = update.message.as_deref().filter(|m| !m.trim().is_empty()) {
+            text.push_str(&format!(" - {}", message.trim()));
+        }
+        text
+    }
+
+    /// Resolves once `/stop` is sent for the running turn.
+    async fn wait_for_stop(stop: &watch::Receiver<bool>) {
+        let mut stop = stop.clone();
+        if stop.wait_for(|stopped| *stopped).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+
     fn build_relaxed_web_search_args(arguments: &serde_json::Value) -> Option<serde_json::Value> {
         let query = arguments.get("query").and_then(|v| v.as_str())?;
         let relaxed = Self::relax_search_query(query)?;
@@ -2370,19 +4026,6 @@ impl MasixRuntime {
             .count()
     }
 
-    fn resolve_mcp_tool_name(server_names: &[String], tool_name: &str) -> Option<(String, String)> {
-        // Resolve by longest prefix first so "discovery" wins over "disc"
-        // for tool names like "discovery_web_search".
-        let mut sorted = server_names.to_vec();
-        sorted.sort_by(|a, b| b.len().cmp(&a.len()));
-        sorted.into_iter().find_map(|server| {
-            let prefix = format!("{}_", server);
-            tool_name
-                .strip_prefix(prefix.as_str())
-                .map(|rest| (server, rest.to_string()))
-        })
-    }
-
     fn sanitize_false_search_unavailable_claims(response: &str) -> (String, bool) {
         let mut changed = false;
         let mut kept = Vec::new();
@@ -2469,41 +4112,350 @@ impl MasixRuntime {
         ))
     }
 
-    #[allow(clippy::too_many_arguments)]
-    async fn execute_tool_call(
-        mcp_client: &Option<Arc<Mutex<McpClient>>>,
+    /// Parse the call arguments and check them against the advertised schema.
+    /// On failure returns the message handed back to the model instead of a result.
+    fn validate_tool_call_arguments(
+        catalog: &ToolCatalog,
         tool_call: &ToolCall,
-        outbound_sender: Option<&broadcast::Sender<OutboundMessage>>,
-        exec_policy: &ExecPolicy,
-        workdir: &Path,
-        storage: &Arc<Mutex<Storage>>,
-        envelope: &Envelope,
-        account_tag: Option<&str>,
-        vision_analysis: Option<&str>,
-        config: &Config,
-        bot_context: &BotContext,
-        permission: PermissionLevel,
-    ) -> Result<String> {
+    ) -> std::result::Result<serde_json::Value, String> {
         let tool_name = &tool_call.function.name;
-        let arguments: serde_json::Value = serde_json::from_str(&tool_call.function.arguments)
-            .unwrap_or_else(|_| serde_json::json!({}));
+        let schema = catalog
+            .get(tool_name)
+            .map(|tool| &tool.definition.function.parameters);
+        let arguments = tool_validation::parse_arguments(&tool_call.function.arguments)
+            .map_err(|reason| tool_validation::format_rejection(tool_name, &reason, schema))?;
+        if let Some(schema) = schema {
+            tool_validation::validate_arguments(schema, &arguments).map_err(|reason| {
+                tool_validation::format_rejection(tool_name, &reason, Some(schema))
+            })?;
+        }
+        Ok(arguments)
+    }
 
-        if tool_name == "cron" {
-            return Self::execute_cron_tool(arguments, storage, envelope, account_tag, permission)
+    /// Validate and dispatch one call. Tools listed in
+    /// `core.tool_approval.requires_confirmation` wait for an admin first.
+    async fn execute_tool_call(
+        catalog: &ToolCatalog,
+        tool_call: &ToolCall,
+        ctx: &ToolCallContext<'_>,
+        tool_approvals: &ToolApprovalGate,
+        stop: &watch::Receiver<bool>,
+        tool_validation: &ToolValidationMetrics,
+        tool_images: &mut Vec<ImagePart>,
+    ) -> Result<ToolCallOutcome> {
+        let tool_name = &tool_call.function.name;
+        let arguments = match Self::validate_tool_call_arguments(catalog, tool_call) {
+            Ok(arguments) => arguments,
+            Err(rejection) => {
+                let failures = tool_validation.record_failure(tool_name);
+                warn!(
+                    "Rejected call to tool '{}' before dispatch (validation failures: {}): {}",
+                    tool_name,
+                    failures,
+                    rejection.lines().next().unwrap_or_default()
+                );
+                Self::audit_tool_call(
+                    ctx,
+                    tool_name,
+                    serde_json::Value::String(tool_call.function.arguments.clone()),
+                    "invalid_arguments",
+                    None,
+                    &rejection,
+                )
                 .await;
-        }
-        if tool_name == "telegram_send" {
-            return Self::execute_telegram_send_tool(
-                arguments,
-                outbound_sender,
-                envelope,
+                return Ok(ToolCallOutcome::Completed(rejection));
+            }
+        };
+        let mut approval_details = None;
+        if ctx
+            .config
+            .core
+            .tool_approval
+            .requires_confirmation(tool_name)
+        {
+            let started = Instant::now();
+            let approval =
+                Self::await_tool_approval(tool_approvals, tool_name, &arguments, ctx, stop).await;
+            let details = serde_json::json!({
+                "decision": approval.as_str(),
+                "approver": approval.approver(),
+                "waited_ms": started.elapsed().as_millis() as u64,
+            });
+            if !matches!(approval, ApprovalOutcome::Approved { .. }) {
+                Self::audit_tool_call(
+                    ctx,
+                    tool_name,
+                    arguments,
+                    approval.as_str(),
+                    Some(details),
+                    "",
+                )
+                .await;
+                return Ok(ToolCallOutcome::Declined(approval));
+            }
+            approval_details = Some(details);
+        }
+        let output = catalog.call(tool_name, arguments.clone(), ctx).await;
+        let (outcome, result) = match &output {
+            Ok(output) => ("ok", output.text.clone()),
+            Err(e) => ("error", e.to_string()),
+        };
+        Self::audit_tool_call(
+            ctx,
+            tool_name,
+            arguments,
+            outcome,
+            approval_details,
+            &result,
+        )
+        .await;
+        let output = output?;
+        tool_images.extend(output.images);
+        Ok(ToolCallOutcome::Completed(output.text))
+    }
+
+    /// Append a `tool_call` entry to the audit log; storage redacts secrets
+    /// in the arguments and the result preview.
+    async fn audit_tool_call(
+        ctx: &ToolCallContext<'_>,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        outcome: &str,
+        approval: Option<serde_json::Value>,
+        result: &str,
+    ) {
+        let mut details = Self::tool_call_audit_details(ctx.envelope, arguments, result);
+        if let Some(approval) = approval {
+            details["approval"] = approval;
+        }
+        Self::append_tool_call_audit(
+            ctx.storage,
+            ctx.envelope,
+            ctx.account_tag,
+            tool_name,
+            outcome,
+            details,
+        )
+        .await;
+    }
+
+    /// Details of a `tool_call` audit entry; `source` marks calls that did not
+    /// come from chat (e.g. `mcp_serve`).
+    pub(crate) fn tool_call_audit_details(
+        envelope: &Envelope,
+        arguments: serde_json::Value,
+        result: &str,
+    ) -> serde_json::Value {
+        let mut details = serde_json::json!({
+            "arguments": arguments,
+            "channel": envelope.channel,
+            "chat_id": envelope.chat_id,
+            "trace_id": envelope.trace_id,
+            "result_chars": result.chars().count(),
+            "result": Self::truncate_for_prompt(result, AUDIT_RESULT_PREVIEW_CHARS),
+        });
+        if let Some(source) = envelope.payload.get("source").and_then(|v| v.as_str()) {
+            details["source"] = serde_json::Value::String(source.to_string());
+        }
+        details
+    }
+
+    pub(crate) async fn append_tool_call_audit(
+        storage: &Mutex<Storage>,
+        envelope: &Envelope,
+        account_tag: Option<&str>,
+        tool_name: &str,
+        outcome: &str,
+        details: serde_json::Value,
+    ) {
+        let actor = Self::envelope_actor(envelope);
+        let record = AuditRecord {
+            kind: "tool_call",
+            actor: &actor,
+            account_tag,
+            action: tool_name,
+            outcome,
+            details,
+        };
+        if let Err(e) = storage.lock().await.append_audit(&record) {
+            warn!("Failed to audit call to tool '{}': {}", tool_name, e);
+        }
+    }
+
+    /// Telegram user id of the sender when known, otherwise the sender field.
+    fn envelope_actor(envelope: &Envelope) -> String {
+        envelope
+            .payload
+            .get("from_user_id")
+            .and_then(|v| v.as_i64())
+            .map(|id| id.to_string())
+            .or_else(|| match &envelope.kind {
+                MessageKind::Message { from, .. } => Some(from.clone()),
+                _ => None,
+            })
+            .unwrap_or_else(|| "?".to_string())
+    }
+
+    /// Send the approval card to the account admins and wait for a decision,
+    /// the timeout or `/stop`.
+    async fn await_tool_approval(
+        tool_approvals: &ToolApprovalGate,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+        ctx: &ToolCallContext<'_>,
+        stop: &watch::Receiver<bool>,
+    ) -> ApprovalOutcome {
+        let Some(outbound_sender) = ctx.outbound_sender else {
+            return ApprovalOutcome::Unavailable;
+        };
+        if ctx.envelope.channel != "telegram" {
+            return ApprovalOutcome::Unavailable;
+        }
+        let Some(account) = Self::get_telegram_account(ctx.config, ctx.account_tag) else {
+            return ApprovalOutcome::Unavailable;
+        };
+        let admins = Self::telegram_account_admin_ids(account);
+        if admins.is_empty() {
+            return ApprovalOutcome::Unavailable;
+        }
+
+        let Some(rendered_arguments) = tool_approval::card_arguments(arguments) else {
+            warn!(
+                "Tool call '{}' refused: arguments too long for an approval card",
+                tool_name
+            );
+            return ApprovalOutcome::TooLarge;
+        };
+
+        let timeout_secs = ctx.config.core.tool_approval.timeout_secs;
+        let requester = Self::tool_approval_requester(ctx.envelope);
+        let (approval_id, decision) = tool_approvals.register(ctx.account_tag, tool_name);
+        for admin_id in &admins {
+            let admin_key =
+                Self::user_state_key(ctx.account_tag, Some(&admin_id.to_string()), None);
+            let lang = ctx
+                .user_languages
+                .lock()
+                .await
+                .get(&admin_key)
+                .copied()
+                .unwrap_or_default();
+            let (text, keyboard) = tool_approval::approval_card(
+                &approval_id,
+                tool_name,
+                &rendered_arguments,
+                &requester,
+                ctx.envelope.chat_id,
+                timeout_secs,
+                lang,
+            );
+            let _ = outbound_sender.send(OutboundMessage {
+                channel: ctx.envelope.channel.clone(),
+                account_tag: ctx.account_tag.map(|tag| tag.to_string()),
+                chat_id: *admin_id,
+                text,
+                reply_to: None,
+                edit_message_id: None,
+                inline_keyboard: Some(keyboard),
+                chat_action: None,
+                photo: None,
+                delivered: None,
+                callback_answer: None,
+            });
+        }
+        if let Some(chat_id) = ctx.envelope.chat_id.filter(|id| !admins.contains(id)) {
+            Self::send_outbound_text(
+                outbound_sender,
+                &ctx.envelope.channel,
+                ctx.account_tag.map(|tag| tag.to_string()),
+                chat_id,
+                &tool_approval::pending_notice(tool_name, timeout_secs, ctx.language),
+                None,
+            );
+        }
+        info!(
+            "Tool call '{}' held for approval {} ({} admin(s))",
+            tool_name,
+            approval_id,
+            admins.len()
+        );
+
+        let outcome = tokio::select! {
+            result = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), decision) => {
+                match result {
+                    Ok(Ok(decision)) if decision.approved => ApprovalOutcome::Approved {
+                        approver: decision.approver,
+                    },
+                    Ok(Ok(decision)) => ApprovalOutcome::Denied {
+                        approver: decision.approver,
+                    },
+                    Ok(Err(_)) | Err(_) => ApprovalOutcome::TimedOut,
+                }
+            }
+            _ = Self::wait_for_stop(stop) => ApprovalOutcome::Stopped,
+        };
+        tool_approvals.cancel(&approval_id);
+        outcome
+    }
+
+    fn tool_approval_requester(envelope: &Envelope) -> String {
+        let user = Self::envelope_actor(envelope);
+        match envelope
+            .payload
+            .get("from_username")
+            .and_then(|v| v.as_str())
+        {
+            Some(username) => format!("@{} ({})", username, user),
+            None => format!("user {}", user),
+        }
+    }
+
+    /// Builtin tools run with the runtime state of the turn.
+    pub(crate) async fn execute_builtin_tool_call(
+        tool_name: &str,
+        arguments: serde_json::Value,
+        ctx: &ToolCallContext<'_>,
+    ) -> Result<String> {
+        let ToolCallContext {
+            outbound_sender,
+            exec_policy,
+            workdir,
+            storage,
+            envelope,
+            account_tag,
+            vision_analysis,
+            config,
+            bot_context,
+            permission,
+            admin_only_modules,
+            mcp_client,
+            ..
+        } = *ctx;
+
+        if tool_name == "cron" {
+            return Self::execute_cron_tool(arguments, storage, envelope, account_tag, permission)
+                .await;
+        }
+        if tool_name == "telegram_send" {
+            return Self::execute_telegram_send_tool(
+                arguments,
+                outbound_sender,
+                envelope,
                 account_tag,
                 permission,
             )
             .await;
         }
         if tool_name == "admin_acl" {
-            return Self::execute_admin_acl_tool(
+            let action = format!(
+                "admin_acl {}",
+                arguments
+                    .get("action")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("list")
+            );
+            let acl_before = Self::dynamic_acl_snapshot(config, account_tag);
+            let result = Self::execute_admin_acl_tool(
                 arguments,
                 config,
                 account_tag,
@@ -2511,6 +4463,16 @@ impl MasixRuntime {
                 permission,
             )
             .await;
+            Self::audit_acl_change(
+                storage,
+                config,
+                account_tag,
+                &Self::envelope_actor(envelope),
+                &action,
+                acl_before,
+            )
+            .await;
+            return result;
         }
         if tool_name == "vision" {
             return Self::execute_vision_tool(arguments, envelope, vision_analysis);
@@ -2540,6 +4502,21 @@ impl MasixRuntime {
             )
             .await;
         }
+        if tool_name == "kb_search" {
+            let caller_user_id = envelope
+                .payload
+                .get("from_user_id")
+                .and_then(|v| v.as_i64())
+                .unwrap_or_default();
+            return Self::execute_kb_search_tool(
+                arguments,
+                bot_context,
+                account_tag,
+                caller_user_id,
+                permission,
+            )
+            .await;
+        }
         if tool_name == "memory_write" {
             let caller_user_id = envelope
                 .payload
@@ -2552,104 +4529,196 @@ impl MasixRuntime {
                 account_tag,
                 caller_user_id,
                 permission,
+                Self::self_memory_edit_allowed(config, account_tag),
             )
             .await;
         }
 
-        // Check if it's a builtin tool
-        if is_builtin_tool(tool_name) {
-            return execute_builtin_tool(tool_name, arguments, exec_policy, workdir).await;
+        if tool_name == "mcp_resource_list" {
+            return Self::execute_mcp_resource_list_tool(
+                arguments,
+                mcp_client,
+                admin_only_modules,
+                permission,
+            )
+            .await;
+        }
+        if tool_name == "mcp_resource_read" {
+            return Self::execute_mcp_resource_read_tool(
+                arguments,
+                mcp_client,
+                admin_only_modules,
+                permission,
+                workdir,
+            )
+            .await;
         }
 
-        // Otherwise, it's an MCP tool.
-        // Server names may contain underscores (e.g. "codex-tools", "discovery"),
-        // so we resolve the server by longest prefix match instead of splitn.
-        if mcp_client.is_none() {
-            return Err(anyhow::anyhow!(
-                "No MCP client available for tool: {}",
-                tool_name
+        let permission_level = match permission {
+            PermissionLevel::Admin => "admin",
+            PermissionLevel::User => "user",
+            PermissionLevel::Readonly => "readonly",
+            PermissionLevel::None => "none",
+        };
+        if tool_name == "exec_bg" {
+            return Ok(Self::execute_exec_bg_tool(
+                arguments,
+                bot_context,
+                outbound_sender,
+                envelope,
+                account_tag,
+                permission,
+                permission_level,
             ));
         }
-        let (server_name, mcp_tool_name) = {
-            let mcp_guard = mcp_client.as_ref().unwrap().lock().await;
-            let names = mcp_guard.server_names();
-            drop(mcp_guard);
-            Self::resolve_mcp_tool_name(&names, tool_name)
-                .ok_or_else(|| anyhow::anyhow!("No MCP server found for tool: {}", tool_name))?
-        };
-        let server_name = server_name.as_str();
-        let mcp_tool_name = mcp_tool_name.as_str();
+        execute_builtin_tool(tool_name, arguments, exec_policy, workdir, permission_level).await
+    }
 
-        if let Some(client) = mcp_client {
-            let mcp = client.lock().await;
-            let mut result = match mcp
-                .call_tool(server_name, mcp_tool_name, arguments.clone())
-                .await
-            {
-                Ok(res) => res,
-                Err(e) => {
-                    if mcp_tool_name == "web_search" {
-                        if let Some(relaxed_args) = Self::build_relaxed_web_search_args(&arguments)
-                        {
-                            warn!(
-                                    "MCP web_search failed on first attempt (server='{}'): {}. Retrying with relaxed query.",
-                                    server_name, e
-                                );
-                            mcp.call_tool(server_name, mcp_tool_name, relaxed_args)
-                                .await?
-                        } else {
-                            return Err(e.into());
-                        }
+    /// Call one MCP server tool without holding the client lock: slow tools
+    /// must not block other chats, and `max_in_flight` is enforced per server.
+    pub(crate) async fn call_mcp_server_tool(
+        server: &McpServer,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        mcp_call: &McpCallOptions,
+        tool_images: &mut Vec<ImagePart>,
+    ) -> Result<String> {
+        let mut result = match server
+            .call_tool_with(tool_name, arguments.clone(), mcp_call.clone())
+            .await
+        {
+            Ok(res) => res,
+            Err(e) => {
+                let stopped = mcp_call
+                    .cancel
+                    .as_ref()
+                    .is_some_and(|cancel| *cancel.borrow());
+                if tool_name == "web_search" && !stopped {
+                    if let Some(relaxed_args) = Self::build_relaxed_web_search_args(&arguments) {
+                        warn!(
+                                "MCP web_search failed on first attempt (server='{}'): {}. Retrying with relaxed query.",
+                                server.name(), e
+                            );
+                        server
+                            .call_tool_with(tool_name, relaxed_args, mcp_call.clone())
+                            .await?
                     } else {
-                        return Err(e.into());
+                        return Err(e);
                     }
+                } else {
+                    return Err(e);
                 }
-            };
+            }
+        };
 
-            if result.is_error && mcp_tool_name == "web_search" {
-                if let Some(relaxed_args) = Self::build_relaxed_web_search_args(&arguments) {
-                    warn!(
-                        "MCP web_search returned error payload (server='{}'), retrying with relaxed query.",
-                        server_name
-                    );
-                    if let Ok(retry_result) = mcp
-                        .call_tool(server_name, mcp_tool_name, relaxed_args)
-                        .await
-                    {
-                        if !retry_result.is_error {
-                            result = retry_result;
-                        }
+        if result.is_error && tool_name == "web_search" {
+            if let Some(relaxed_args) = Self::build_relaxed_web_search_args(&arguments) {
+                warn!(
+                    "MCP web_search returned error payload (server='{}'), retrying with relaxed query.",
+                    server.name()
+                );
+                if let Ok(retry_result) = server
+                    .call_tool_with(tool_name, relaxed_args, mcp_call.clone())
+                    .await
+                {
+                    if !retry_result.is_error {
+                        result = retry_result;
                     }
                 }
             }
+        }
 
-            let mut content_parts = Vec::new();
-            for item in &result.content {
-                match item {
-                    masix_mcp::ToolContent::Text { text } => content_parts.push(text.clone()),
-                    masix_mcp::ToolContent::Image { mime_type, .. } => {
-                        content_parts.push(format!("[image content: {}]", mime_type));
+        let mut content_parts = Vec::new();
+        for item in &result.content {
+            match item {
+                masix_mcp::ToolContent::Text { text } => content_parts.push(text.clone()),
+                masix_mcp::ToolContent::Image { data, mime_type } => {
+                    match Self::decode_tool_image(data, mime_type) {
+                        Ok(image) => {
+                            content_parts
+                                .push(format!("[image content: {}, shown to the user]", mime_type));
+                            tool_images.push(image);
+                        }
+                        Err(reason) => content_parts
+                            .push(format!("[image content: {}, {}]", mime_type, reason)),
                     }
                 }
             }
+        }
 
-            if content_parts.is_empty() {
-                return Ok(serde_json::to_string(&result)?);
-            }
+        if content_parts.is_empty() {
+            return Ok(serde_json::to_string(&result)?);
+        }
 
-            let joined = content_parts.join("\n");
-            if result.is_error {
-                warn!(
-                    "MCP tool '{}' error payload (first 320 chars): {}",
-                    tool_call.function.name,
-                    joined.chars().take(320).collect::<String>()
-                );
-                Ok(format!("Tool error: {}", joined))
-            } else {
-                Ok(joined)
-            }
+        let joined = content_parts.join("\n");
+        if result.is_error {
+            warn!(
+                "MCP tool '{}_{}' error payload (first 320 chars): {}",
+                server.name(),
+                tool_name,
+                joined.chars().take(320).collect::<String>()
+            );
+            Ok(format!("Tool error: {}", joined))
         } else {
-            Err(anyhow::anyhow!("No MCP client available"))
+            Ok(joined)
+        }
+    }
+
+    /// Validate base64 image content returned by an MCP tool.
+    fn decode_tool_image(data: &str, mime_type: &str) -> Result<ImagePart, String> {
+        if !mime_type.starts_with("image/") {
+            return Err("unsupported type".to_string());
+        }
+        let data = data.trim();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|_| "invalid base64".to_string())?;
+        if bytes.is_empty() {
+            return Err("empty".to_string());
+        }
+        if bytes.len() > MCP_IMAGE_MAX_BYTES {
+            return Err(format!("too large ({} bytes)", bytes.len()));
+        }
+        Ok(ImagePart {
+            mime_type: mime_type.to_string(),
+            data: data.to_string(),
+        })
+    }
+
+    /// Forward tool images to the originating Telegram chat as photos.
+    fn send_tool_images(
+        outbound_sender: &broadcast::Sender<OutboundMessage>,
+        envelope: &Envelope,
+        account_tag: Option<&str>,
+        tool_name: &str,
+        images: &[ImagePart],
+    ) {
+        if envelope.channel != "telegram" {
+            return;
+        }
+        let Some(chat_id) = envelope.chat_id else {
+            return;
+        };
+        for image in images {
+            let Ok(data) = base64::engine::general_purpose::STANDARD.decode(&image.data) else {
+                continue;
+            };
+            let _ = outbound_sender.send(OutboundMessage {
+                channel: envelope.channel.clone(),
+                account_tag: account_tag.map(|s| s.to_string()),
+                chat_id,
+                text: format!("🖼️ {}", tool_name),
+                reply_to: None,
+                edit_message_id: None,
+                inline_keyboard: None,
+                chat_action: None,
+                photo: Some(OutboundPhoto {
+                    mime_type: image.mime_type.clone(),
+                    data,
+                }),
+                delivered: None,
+                callback_answer: None,
+            });
         }
     }
 
@@ -2670,6 +4739,7 @@ impl MasixRuntime {
         runtime_tool_access: &RuntimeToolAccess,
         sender_id: &str,
         mcp_client: &Option<Arc<Mutex<McpClient>>>,
+        catalog: &ToolCatalog,
         exec_policy: &ExecPolicy,
         workdir: &Path,
         storage: &Arc<Mutex<Storage>>,
@@ -2680,6 +4750,9 @@ impl MasixRuntime {
         permission: PermissionLevel,
         loop_options: &LlmLoopOptions,
         outbound_sender: Option<&broadcast::Sender<OutboundMessage>>,
+        tool_validation: &ToolValidationMetrics,
+        tool_approvals: &ToolApprovalGate,
+        stop: &watch::Receiver<bool>,
     ) -> Result<LlmLoopResult> {
         let mut final_response = String::new();
         let mut iterations = 0;
@@ -2692,6 +4765,8 @@ impl MasixRuntime {
         let mut auto_continue_count = 0usize;
         let mut progress_updates_sent: u8 = 0;
         let mut last_progress_emit: Option<Instant> = None;
+        let mut stopped = false;
+        let mut declined: Option<(String, ApprovalOutcome)> = None;
         let mut pre_search_memory_context_injected = false;
         let pre_search_memory_digest = Self::build_pre_search_memory_digest(bot_context).await;
 
@@ -2703,7 +4778,7 @@ impl MasixRuntime {
                 break;
             }
 
-            let (response, provider_used) = Self::chat_with_fallback_chain(
+            let chat = Self::chat_with_fallback_chain(
                 provider_router,
                 messages.clone(),
                 tools.clone(),
@@ -2712,8 +4787,14 @@ impl MasixRuntime {
                 preferred_model.as_deref(),
                 retry_policy,
                 profile_name,
-            )
-            .await?;
+            );
+            let (response, provider_used) = tokio::select! {
+                result = chat => result?,
+                _ = Self::wait_for_stop(stop) => {
+                    stopped = true;
+                    break;
+                }
+            };
             selected_provider = Some(provider_used);
 
             if let Some(content) = &response.content {
@@ -2733,6 +4814,7 @@ impl MasixRuntime {
                     tool_calls: Some(tool_calls.clone()),
                     tool_call_id: None,
                     name: None,
+                    images: Vec::new(),
                 };
                 messages.push(assistant_message);
 
@@ -2761,6 +4843,7 @@ impl MasixRuntime {
                             tool_calls: None,
                             tool_call_id: Some(tool_call.id.clone()),
                             name: Some(tool_call.function.name.clone()),
+                            images: Vec::new(),
                         });
                         if tool_call.function.name == "cron" {
                             force_finalize_after_guard = true;
@@ -2792,46 +4875,31 @@ impl MasixRuntime {
                             tool_calls: None,
                             tool_call_id: Some(tool_call.id.clone()),
                             name: Some(tool_call.function.name.clone()),
+                            images: Vec::new(),
                         });
                         continue;
                     }
 
-                    if !runtime_tool_access.allows_tool(&tool_call.function.name) {
+                    if let Err(denial) = catalog.check_access(
+                        &tool_call.function.name,
+                        permission,
+                        runtime_tool_access,
+                    ) {
                         warn!(
-                            "Tool execution denied for sender '{}' (tool='{}')",
-                            sender_id, tool_call.function.name
+                            "Tool execution denied for sender '{}' (tool='{}', reason={:?})",
+                            sender_id, tool_call.function.name, denial
                         );
                         messages.push(ChatMessage {
                             role: "tool".to_string(),
-                            content: Some("Tool execution denied by role/tool policy.".to_string()),
+                            content: Some(denial.message().to_string()),
                             tool_calls: None,
                             tool_call_id: Some(tool_call.id.clone()),
                             name: Some(tool_call.function.name.clone()),
+                            images: Vec::new(),
                         });
                         continue;
                     }
 
-                    // Check if tool belongs to an admin-only module
-                    if is_admin_only_tool(&tool_call.function.name, admin_only_modules) {
-                        if permission != PermissionLevel::Admin {
-                            warn!(
-                                "Admin-only tool '{}' denied for non-admin sender '{}'",
-                                tool_call.function.name, sender_id
-                            );
-                            messages.push(ChatMessage {
-                                role: "tool".to_string(),
-                                content: Some(
-                                    "Tool execution denied: this tool requires admin privileges."
-                                        .to_string(),
-                                ),
-                                tool_calls: None,
-                                tool_call_id: Some(tool_call.id.clone()),
-                                name: Some(tool_call.function.name.clone()),
-                            });
-                            continue;
-                        }
-                    }
-
                     if Self::is_web_search_tool_name(&tool_call.function.name)
                         && !pre_search_memory_context_injected
                     {
@@ -2845,6 +4913,7 @@ impl MasixRuntime {
                                 tool_calls: None,
                                 tool_call_id: None,
                                 name: None,
+                                images: Vec::new(),
                             });
                             pre_search_memory_context_injected = true;
                             debug!(
@@ -2854,9 +4923,13 @@ impl MasixRuntime {
                         }
                     }
 
-                    let tool_result = match Self::execute_tool_call(
-                        mcp_client,
-                        tool_call,
+                    let mut tool_images = Vec::new();
+                    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel::<McpProgress>();
+                    let mcp_call = McpCallOptions {
+                        cancel: Some(stop.clone()),
+                        progress: Some(progress_tx),
+                    };
+                    let tool_ctx = ToolCallContext {
                         outbound_sender,
                         exec_policy,
                         workdir,
@@ -2867,14 +4940,72 @@ impl MasixRuntime {
                         config,
                         bot_context,
                         permission,
-                    )
-                    .await
-                    {
-                        Ok(result) => result,
+                        admin_only_modules,
+                        mcp_client,
+                        mcp_call: &mcp_call,
+                        language: loop_options.language,
+                        user_languages: &loop_options.user_languages,
+                    };
+                    let outcome = {
+                        let call = Self::execute_tool_call(
+                            catalog,
+                            tool_call,
+                            &tool_ctx,
+                            tool_approvals,
+                            stop,
+                            tool_validation,
+                            &mut tool_images,
+                        );
+                        tokio::pin!(call);
+                        // Server progress notifications go through the regular progress throttle.
+                        loop {
+                            let update = tokio::select! {
+                                outcome = &mut call => break outcome,
+                                Some(update) = progress_rx.recv() => update,
+                            };
+                            let text = Self::format_mcp_progress(
+                                &update,
+                                loop_options.tool_progress.include_tool_names,
+                            );
+                            if Self::should_emit_tool_progress(
+                                &loop_options.tool_progress,
+                                iterations,
+                                progress_updates_sent,
+                                last_progress_emit,
+                            ) && Self::send_tool_progress(
+                                outbound_sender,
+                                envelope,
+                                account_tag,
+                                &text,
+                            ) {
+                                progress_updates_sent = progress_updates_sent.saturating_add(1);
+                                last_progress_emit = Some(Instant::now());
+                            }
+                        }
+                    };
+                    let tool_result = match outcome {
+                        Ok(ToolCallOutcome::Completed(result)) => result,
+                        Ok(ToolCallOutcome::Declined(approval)) => {
+                            if approval == ApprovalOutcome::Stopped {
+                                stopped = true;
+                            } else {
+                                declined = Some((tool_call.function.name.clone(), approval));
+                            }
+                            approval.tool_message(&tool_call.function.name)
+                        }
                         Err(e) => format!("Error: {}", e),
                     };
-
-                    if tool_call.function.name == "plugin_discovery_web_search"
+                    if let Some(sender) = outbound_sender {
+                        Self::send_tool_images(
+                            sender,
+                            envelope,
+                            account_tag,
+                            &tool_call.function.name,
+                            &tool_images,
+                        );
+                    }
+
+                    if tool_call.function.name == "plugin_discovery_web_search"
                         && Self::count_search_results_from_tool_payload(&tool_result) > 0
                     {
                         successful_discovery_search_calls += 1;
@@ -2886,10 +5017,20 @@ impl MasixRuntime {
                         tool_calls: None,
                         tool_call_id: Some(tool_call.id.clone()),
                         name: Some(tool_call.function.name.clone()),
+                        images: tool_images,
                     };
                     messages.push(tool_message);
+                    if *stop.borrow() {
+                        stopped = true;
+                    }
+                    if stopped || declined.is_some() {
+                        break;
+                    }
                 }
 
+                if stopped || declined.is_some() {
+                    break;
+                }
                 if force_finalize_after_guard {
                     debug!("Breaking tool loop early due to cron guard; finalizing response.");
                     break;
@@ -2901,39 +5042,30 @@ impl MasixRuntime {
                     progress_updates_sent,
                     last_progress_emit,
                 ) {
-                    if let Some(sender) = outbound_sender {
-                        if envelope.channel == "telegram" {
-                            if let Some(chat_id) = envelope.chat_id {
-                                let progress_text = if loop_options.tool_progress.include_tool_names
-                                    && !used_tools.is_empty()
-                                {
-                                    let tool_names: Vec<&str> =
-                                        used_tools.iter().map(|s| s.as_str()).collect();
-                                    format!(
-                                        "Quick update ({}/{}): checking {}.",
-                                        iterations,
-                                        loop_options.max_iterations,
-                                        tool_names.join(", ")
-                                    )
-                                } else {
-                                    format!(
-                                        "Quick update ({}/{}): still working, running tools...",
-                                        iterations, loop_options.max_iterations
-                                    )
-                                };
-                                Self::send_outbound_text(
-                                    sender,
-                                    &envelope.channel,
-                                    account_tag.map(|s| s.to_string()),
-                                    chat_id,
-                                    &progress_text,
-                                    None,
-                                );
-                                debug!("Sent tool progress message: {}", progress_text);
-                                progress_updates_sent = progress_updates_sent.saturating_add(1);
-                                last_progress_emit = Some(Instant::now());
-                            }
-                        }
+                    let progress_text = if loop_options.tool_progress.include_tool_names
+                        && !used_tools.is_empty()
+                    {
+                        let tool_names: Vec<&str> = used_tools.iter().map(|s| s.as_str()).collect();
+                        format!(
+                            "Quick update ({}/{}): checking {}.",
+                            iterations,
+                            loop_options.max_iterations,
+                            tool_names.join(", ")
+                        )
+                    } else {
+                        format!(
+                            "Quick update ({}/{}): still working, running tools...",
+                            iterations, loop_options.max_iterations
+                        )
+                    };
+                    if Self::send_tool_progress(
+                        outbound_sender,
+                        envelope,
+                        account_tag,
+                        &progress_text,
+                    ) {
+                        progress_updates_sent = progress_updates_sent.saturating_add(1);
+                        last_progress_emit = Some(Instant::now());
                     }
                 }
             } else {
@@ -2960,6 +5092,7 @@ impl MasixRuntime {
                         tool_calls: None,
                         tool_call_id: None,
                         name: None,
+                        images: Vec::new(),
                     });
                     messages.push(ChatMessage {
                         role: "user".to_string(),
@@ -2970,6 +5103,7 @@ impl MasixRuntime {
                         tool_calls: None,
                         tool_call_id: None,
                         name: None,
+                        images: Vec::new(),
                     });
                     continue;
                 }
@@ -2977,6 +5111,27 @@ impl MasixRuntime {
             }
         }
 
+        if stopped {
+            info!("Turn stopped by user after {} iteration(s)", iterations);
+            return Ok(LlmLoopResult {
+                final_response: "⏹️ Operazione interrotta su richiesta (/stop).".to_string(),
+                used_tools,
+                successful_discovery_search_calls,
+            });
+        }
+        if let Some((tool_name, approval)) = declined {
+            info!(
+                "Turn aborted: call to '{}' {}",
+                tool_name,
+                approval.as_str()
+            );
+            return Ok(LlmLoopResult {
+                final_response: approval.user_message(&tool_name, loop_options.language),
+                used_tools,
+                successful_discovery_search_calls,
+            });
+        }
+
         if final_response.trim().is_empty() && !used_tools.is_empty() {
             let mut finalize_messages = messages.clone();
             finalize_messages.push(ChatMessage {
@@ -2988,6 +5143,7 @@ impl MasixRuntime {
                 tool_calls: None,
                 tool_call_id: None,
                 name: None,
+                images: Vec::new(),
             });
 
             match Self::chat_with_fallback_chain(
@@ -3016,7 +5172,7 @@ impl MasixRuntime {
         }
 
         if final_response.trim().is_empty() && !used_tools.is_empty() {
-            if let Some(synthesized) = Self::synthesize_from_tool_messages(&messages) {
+            if let Some(synthesized) = Self::synthesize_from_tool_messages(messages) {
                 final_response = synthesized;
             }
         }
@@ -3036,9 +5192,10 @@ impl MasixRuntime {
     async fn process_inbound_message(
         envelope: Envelope,
         outbound_sender: broadcast::Sender<OutboundMessage>,
-        provider_router: &ProviderRouter,
+        provider_router: &Arc<ProviderRouter>,
         storage: &Arc<Mutex<Storage>>,
         mcp_client: &Option<Arc<Mutex<McpClient>>>,
+        tool_registry: &ToolRegistry,
         system_prompt: &str,
         policy: &PolicyEngine,
         rate_state: &Arc<Mutex<HashMap<String, (i64, u32)>>>,
@@ -3048,6 +5205,9 @@ impl MasixRuntime {
         user_models: &Arc<Mutex<HashMap<String, String>>>,
         config: &Config,
         admin_only_modules: &HashSet<String>,
+        tool_validation: &ToolValidationMetrics,
+        tool_approvals: &ToolApprovalGate,
+        stop: &watch::Receiver<bool>,
     ) -> Result<()> {
         let account_tag = envelope
             .payload
@@ -3233,6 +5393,7 @@ impl MasixRuntime {
                     user_languages,
                     user_providers,
                     user_models,
+                    storage,
                     &bot_context,
                     user_scope_id.as_deref(),
                     config,
@@ -3240,7 +5401,8 @@ impl MasixRuntime {
                     from_user_id,
                     permission,
                     mcp_client,
-                    admin_only_modules,
+                    tool_registry,
+                    tool_validation,
                 )
                 .await?
                 {
@@ -3273,17 +5435,60 @@ impl MasixRuntime {
                     return Ok(());
                 }
 
-                let tools = if allow_runtime_tools {
-                    let all_tools = Self::get_mcp_tools(mcp_client).await;
-                    let filtered = runtime_tool_access.filter_tools(all_tools);
-                    Self::filter_visible_tools_by_permission(
-                        filtered,
-                        admin_only_modules,
-                        permission,
-                    )
+                let expanded_prompt;
+                let text = match Self::handle_prompt_command(
+                    text,
+                    mcp_client,
+                    &runtime_tool_access,
+                    admin_only_modules,
+                    permission,
+                )
+                .await
+                {
+                    Some(PromptCommandOutcome::Reply(reply)) => {
+                        Self::send_outbound_text(
+                            &outbound_sender,
+                            &envelope.channel,
+                            account_tag.clone(),
+                            chat_id,
+                            &reply,
+                            envelope.message_id,
+                        );
+                        return Ok(());
+                    }
+                    Some(PromptCommandOutcome::Expand(expanded)) => {
+                        expanded_prompt = expanded;
+                        expanded_prompt.as_str()
+                    }
+                    None => text,
+                };
+
+                if let Some(reply) = Self::handle_resources_command(
+                    text,
+                    mcp_client,
+                    &runtime_tool_access,
+                    admin_only_modules,
+                    permission,
+                )
+                .await
+                {
+                    Self::send_outbound_text(
+                        &outbound_sender,
+                        &envelope.channel,
+                        account_tag.clone(),
+                        chat_id,
+                        &reply,
+                        envelope.message_id,
+                    );
+                    return Ok(());
+                }
+
+                let catalog = if allow_runtime_tools {
+                    tool_registry.catalog().await
                 } else {
-                    Vec::new()
+                    ToolCatalog::default()
                 };
+                let tools = catalog.visible_definitions(permission, &runtime_tool_access);
                 let has_tools = !tools.is_empty();
                 let builtin_tools_count = tools
                     .iter()
@@ -3339,6 +5544,24 @@ impl MasixRuntime {
                 let user_message = llm_msgs.user_message;
                 let vision_analysis = llm_msgs.vision_analysis;
 
+                if config.core.fact_extraction.inject_into_context {
+                    let facts_path = Self::profile_facts_path(
+                        &bot_context,
+                        account_tag.as_deref(),
+                        from_user_id,
+                    );
+                    let facts = Self::load_profile_facts(&facts_path).await;
+                    if let (Some(section), Some(system)) = (
+                        Self::render_profile_facts_context(&facts),
+                        messages.first_mut(),
+                    ) {
+                        system
+                            .content
+                            .get_or_insert_with(String::new)
+                            .push_str(&section);
+                    }
+                }
+
                 let preferred_provider = user_providers.lock().await.get(&user_state_key).cloned();
                 let preferred_model = user_models.lock().await.get(&user_state_key).cloned();
                 let max_tool_iterations = config
@@ -3353,6 +5576,13 @@ impl MasixRuntime {
                     auto_continue_max: usize::from(config.core.agent_loop.auto_continue_max),
                     continuation_detection: config.core.agent_loop.continuation_detection,
                     tool_progress: config.core.tool_progress.clone(),
+                    language: user_languages
+                        .lock()
+                        .await
+                        .get(&user_state_key)
+                        .copied()
+                        .unwrap_or_default(),
+                    user_languages: Arc::clone(user_languages),
                 };
 
                 // Execute LLM loop with tool calling
@@ -3370,6 +5600,7 @@ impl MasixRuntime {
                     &runtime_tool_access,
                     from,
                     mcp_client,
+                    &catalog,
                     &bot_context.exec_policy,
                     &bot_context.workdir,
                     storage,
@@ -3380,6 +5611,9 @@ impl MasixRuntime {
                     permission,
                     &loop_options,
                     Some(&outbound_sender),
+                    tool_validation,
+                    tool_approvals,
+                    stop,
                 )
                 .await?;
 
@@ -3447,6 +5681,63 @@ impl MasixRuntime {
                     }),
                 )
                 .await;
+
+                if config.core.fact_extraction.enabled
+                    && Self::can_write_memory_scope(
+                        permission,
+                        MemoryNamespace::Own,
+                        MemoryScope::UserPrivate,
+                        from_user_id,
+                        None,
+                        Self::self_memory_edit_allowed(config, account_tag.as_deref()),
+                    )
+                {
+                    // The extra LLM round-trip runs after the reply and outside the
+                    // chat scope lock, so the next message does not wait for it.
+                    let provider_router = Arc::clone(provider_router);
+                    let bot_context = bot_context.clone();
+                    let config = config.clone();
+                    let envelope = envelope.clone();
+                    let account_tag = account_tag.clone();
+                    tokio::spawn(async move {
+                        match Self::extract_profile_facts(
+                            &provider_router,
+                            &bot_context,
+                            account_tag.as_deref(),
+                            from_user_id,
+                            &envelope,
+                            &user_message,
+                            &final_response,
+                            &config,
+                        )
+                        .await
+                        {
+                            Ok(0) => {}
+                            Ok(added) => {
+                                debug!(
+                                    "Stored {} new profile fact(s) for user {}",
+                                    added, from_user_id
+                                );
+                                let _ = Self::append_runtime_event(
+                                    &bot_context.workdir,
+                                    "profile_facts_extracted",
+                                    &envelope,
+                                    serde_json::json!({
+                                        "user_id": from_user_id,
+                                        "added": added,
+                                    }),
+                                )
+                                .await;
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "Profile fact extraction failed for user {}: {}",
+                                    from_user_id, e
+                                );
+                            }
+                        }
+                    });
+                }
             }
             MessageKind::Callback { query_id, data } => {
                 info!("Processing callback {}: {}", query_id, data);
@@ -3465,6 +5756,14 @@ impl MasixRuntime {
                                 .lock()
                                 .await
                                 .insert(user_state_key.clone(), new_lang);
+                            Self::persist_user_preferences(
+                                storage,
+                                &user_state_key,
+                                user_languages,
+                                user_providers,
+                                user_models,
+                            )
+                            .await;
                             let (_text, keyboard) = masix_telegram::menu::settings_menu(new_lang);
                             let msg = OutboundMessage {
                                 channel: envelope.channel.clone(),
@@ -3475,6 +5774,9 @@ impl MasixRuntime {
                                 edit_message_id: envelope.message_id,
                                 inline_keyboard: Some(keyboard),
                                 chat_action: None,
+                                photo: None,
+                                delivered: None,
+                                callback_answer: None,
                             };
                             let _ = outbound_sender.send(msg);
                             return Ok(());
@@ -3618,6 +5920,9 @@ impl MasixRuntime {
             edit_message_id: None,
             inline_keyboard: None,
             chat_action: None,
+            photo: None,
+            delivered: None,
+            callback_answer: None,
         })
     }
 
@@ -4795,13 +7100,12 @@ impl MasixRuntime {
                 let raw_user = arguments
                     .get("user")
                     .or_else(|| arguments.get("user_id"))
-                    .map(|v| {
+                    .and_then(|v| {
                         v.as_str()
                             .map(str::trim)
                             .map(str::to_string)
                             .or_else(|| v.as_i64().map(|n| n.to_string()))
                     })
-                    .flatten()
                     .unwrap_or_default();
                 if raw_user.is_empty() {
                     return Ok(
@@ -4980,6 +7284,7 @@ impl MasixRuntime {
         user_languages: &Arc<Mutex<HashMap<String, Language>>>,
         user_providers: &Arc<Mutex<HashMap<String, String>>>,
         user_models: &Arc<Mutex<HashMap<String, String>>>,
+        storage: &Arc<Mutex<Storage>>,
         bot_context: &BotContext,
         user_scope_id: Option<&str>,
         config: &Config,
@@ -4987,7 +7292,8 @@ impl MasixRuntime {
         from_user_id: i64,
         permission: PermissionLevel,
         mcp_client: &Option<Arc<Mutex<McpClient>>>,
-        admin_only_modules: &HashSet<String>,
+        tool_registry: &ToolRegistry,
+        tool_validation: &ToolValidationMetrics,
     ) -> Result<bool> {
         let Some(chat_id) = envelope.chat_id else {
             return Ok(false);
@@ -5047,6 +7353,9 @@ impl MasixRuntime {
                 edit_message_id: None,
                 inline_keyboard: Some(keyboard),
                 chat_action: None,
+                photo: None,
+                delivered: None,
+                callback_answer: None,
             };
             if let Err(e) = outbound_sender.send(msg) {
                 error!("Failed to send menu: {}", e);
@@ -5117,8 +7426,7 @@ impl MasixRuntime {
                 account_tag,
                 permission,
                 &envelope.channel,
-                mcp_client,
-                admin_only_modules,
+                tool_registry,
             )
             .await;
             Self::send_outbound_text(
@@ -5170,6 +7478,65 @@ impl MasixRuntime {
             return Ok(true);
         }
 
+        if text.starts_with("/facts") {
+            info!("Processing /facts");
+            let response =
+                Self::handle_facts_chat_command(text, bot_context, account_tag, from_user_id).await;
+            Self::send_outbound_text(
+                outbound_sender,
+                &envelope.channel,
+                account_tag_owned.clone(),
+                chat_id,
+                &response,
+                None,
+            );
+            return Ok(true);
+        }
+
+        if text == "/session" || text.starts_with("/session ") {
+            info!("Processing /session");
+            let response = Self::handle_session_chat_command(
+                text,
+                bot_context,
+                account_tag,
+                user_scope_id,
+                chat_id,
+            )
+            .await;
+            Self::send_outbound_text(
+                outbound_sender,
+                &envelope.channel,
+                account_tag_owned.clone(),
+                chat_id,
+                &response,
+                None,
+            );
+            return Ok(true);
+        }
+
+        if text == "/kb" || text.starts_with("/kb ") {
+            info!("Processing /kb");
+            let response = Self::handle_kb_chat_command(
+                text,
+                envelope,
+                config,
+                bot_context,
+                account_tag,
+                from_user_id,
+                permission,
+            )
+            .await;
+            Self::send_outbound_text(
+                outbound_sender,
+                &envelope.channel,
+                account_tag_owned.clone(),
+                chat_id,
+                &response,
+                None,
+            );
+            return Ok(true);
+        }
+
         if text.starts_with("/language") {
             info!("Processing /language");
             let lang = user_languages
@@ -5188,6 +7555,9 @@ impl MasixRuntime {
                 edit_message_id: None,
                 inline_keyboard: Some(keyboard),
                 chat_action: None,
+                photo: None,
+                delivered: None,
+                callback_answer: None,
             };
             let _ = outbound_sender.send(msg);
             return Ok(true);
@@ -5198,6 +7568,14 @@ impl MasixRuntime {
             let response =
                 Self::handle_provider_chat_command(text, user_state_key, config, user_providers)
                     .await;
+            Self::persist_user_preferences(
+                storage,
+                user_state_key,
+                user_languages,
+                user_providers,
+                user_models,
+            )
+            .await;
             Self::send_outbound_text(
                 outbound_sender,
                 &envelope.channel,
@@ -5219,6 +7597,39 @@ impl MasixRuntime {
                 user_models,
             )
             .await;
+            Self::persist_user_preferences(
+                storage,
+                user_state_key,
+                user_languages,
+                user_providers,
+                user_models,
+            )
+            .await;
+            Self::send_outbound_text(
+                outbound_sender,
+                &envelope.channel,
+                account_tag_owned.clone(),
+                chat_id,
+                &response,
+                None,
+            );
+            return Ok(true);
+        }
+
+        if text.starts_with("/admin prefs") {
+            info!("Processing /admin prefs");
+            let response = Self::handle_admin_prefs_command(
+                text,
+                config,
+                account_tag,
+                permission,
+                bot_context,
+                storage,
+                user_languages,
+                user_providers,
+                user_models,
+            )
+            .await;
             Self::send_outbound_text(
                 outbound_sender,
                 &envelope.channel,
@@ -5232,6 +7643,7 @@ impl MasixRuntime {
 
         if text.starts_with("/admin") {
             info!("Processing /admin");
+            let acl_before = Self::dynamic_acl_snapshot(config, account_tag);
             let response = Self::handle_admin_command(
                 text,
                 config,
@@ -5241,6 +7653,15 @@ impl MasixRuntime {
                 bot_context,
             )
             .await;
+            Self::audit_acl_change(
+                storage,
+                config,
+                account_tag,
+                &from_user_id.to_string(),
+                text,
+                acl_before,
+            )
+            .await;
             Self::send_outbound_text(
                 outbound_sender,
                 &envelope.channel,
@@ -5294,7 +7715,7 @@ impl MasixRuntime {
                 return Ok(true);
             }
             info!("Processing /mcp");
-            let response = Self::handle_mcp_chat_command(text, config).await;
+            let response = Self::handle_mcp_chat_command(text, config, mcp_client).await;
             Self::send_outbound_text(
                 outbound_sender,
                 &envelope.channel,
@@ -5319,7 +7740,7 @@ impl MasixRuntime {
                 return Ok(true);
             }
             info!("Processing /tools");
-            let response = Self::handle_tools_chat_command(mcp_client).await;
+            let response = Self::handle_tools_chat_command(tool_registry, tool_validation).await;
             Self::send_outbound_text(
                 outbound_sender,
                 &envelope.channel,
@@ -5513,17 +7934,49 @@ impl MasixRuntime {
                     &envelope.channel,
                     account_tag.clone(),
                     chat_id,
-                    "Usage: `/exec <command>`\nExample: `/exec ls -la`\nExecutes only allowlisted commands in bot workdir.",
+                    "Usage: `/exec <command>`\nExample: `/exec ls -la`\nExecutes only allowlisted commands in bot workdir.\nWith `[exec.pipeline]` enabled: `/exec grep error app.log | sort | uniq -c > report.txt` (sandboxed).\nWith `[exec.jobs]` enabled: `/exec --bg <command>` runs in the background (see `/jobs`).",
                     envelope.message_id,
                 );
                 return Ok(true);
             }
 
+            if command == "--bg" || command.starts_with("--bg ") {
+                let bg_command = command.strip_prefix("--bg").unwrap_or("").trim();
+                let error = if bg_command.is_empty() {
+                    Some("Usage: `/exec --bg <command>`".to_string())
+                } else {
+                    Self::start_exec_job(
+                        bot_context,
+                        outbound_sender,
+                        envelope,
+                        account_tag.clone(),
+                        chat_id,
+                        bg_command,
+                        "admin",
+                    )
+                    .err()
+                    .map(|e| format!("Exec error: {}", e))
+                };
+                // On success the job message itself is the reply.
+                if let Some(error) = error {
+                    Self::send_outbound_text(
+                        outbound_sender,
+                        &envelope.channel,
+                        account_tag.clone(),
+                        chat_id,
+                        &error,
+                        envelope.message_id,
+                    );
+                }
+                return Ok(true);
+            }
+
             let response = match run_command(
                 &bot_context.exec_policy,
                 ExecMode::Base,
                 command,
                 &bot_context.workdir,
+                Some("admin"),
             )
             .await
             {
@@ -5542,6 +7995,27 @@ impl MasixRuntime {
             return Ok(true);
         }
 
+        if trimmed == "/jobs" || trimmed.starts_with("/jobs ") {
+            let response = if permission != PermissionLevel::Admin {
+                "Admin only command.".to_string()
+            } else {
+                Self::exec_jobs_command_response(
+                    bot_context,
+                    account_tag.as_deref(),
+                    trimmed.strip_prefix("/jobs").unwrap_or("").trim(),
+                )
+            };
+            Self::send_outbound_text(
+                outbound_sender,
+                &envelope.channel,
+                account_tag.clone(),
+                chat_id,
+                &response,
+                envelope.message_id,
+            );
+            return Ok(true);
+        }
+
         if trimmed == "/termux" || trimmed.starts_with("/termux ") {
             let rest = trimmed.strip_prefix("/termux").unwrap_or("");
             let command = rest.trim();
@@ -5717,6 +8191,7 @@ impl MasixRuntime {
                 ExecMode::Termux,
                 &mapped_command,
                 &bot_context.workdir,
+                None,
             )
             .await
             {
@@ -5738,21 +8213,259 @@ impl MasixRuntime {
         Ok(false)
     }
 
-    async fn handle_admin_command(
-        text: &str,
-        config: &Config,
+    /// Start a background exec job and stream its output to `chat_id`.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_exec_bg_tool(
+        arguments: serde_json::Value,
+        bot_context: &BotContext,
+        outbound_sender: Option<&broadcast::Sender<OutboundMessage>>,
+        envelope: &Envelope,
         account_tag: Option<&str>,
-        _from_user_id: i64,
         permission: PermissionLevel,
-        bot_context: &BotContext,
+        permission_level: &str,
     ) -> String {
+        // Jobs are managed with the admin-only /jobs command.
         if permission != PermissionLevel::Admin {
-            return "Admin only command.".to_string();
+            return "exec_bg is reserved to admins.".to_string();
         }
-
-        let parts: Vec<&str> = text.split_whitespace().collect();
-        if parts.len() < 2 {
-            return "🛡️ Admin commands\n\
+        let command = arguments
+            .get("command")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim();
+        if command.is_empty() {
+            return "exec_bg requires `command`.".to_string();
+        }
+        let policy = &bot_context.exec_policy;
+        if !policy.enabled || !policy.allow_base {
+            return "Exec commands are disabled. Enable in config with exec.enabled=true and exec.allow_base=true".to_string();
+        }
+        let (Some(outbound_sender), Some(chat_id)) = (outbound_sender, envelope.chat_id) else {
+            return "exec_bg needs a chat to stream the job output to.".to_string();
+        };
+        match Self::start_exec_job(
+            bot_context,
+            outbound_sender,
+            envelope,
+            account_tag.map(str::to_string),
+            chat_id,
+            command,
+            permission_level,
+        ) {
+            Ok(job) => format!(
+                "Job #{} started: `{}`. Output is streamed to the chat; use `/jobs tail {}` or `/jobs kill {}`.",
+                job.id, job.command, job.id, job.id
+            ),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    fn start_exec_job(
+        bot_context: &BotContext,
+        outbound_sender: &broadcast::Sender<OutboundMessage>,
+        envelope: &Envelope,
+        account_tag: Option<String>,
+        chat_id: i64,
+        command: &str,
+        level: &str,
+    ) -> Result<JobInfo> {
+        let jobs = bot_context
+            .exec_jobs
+            .as_ref()
+            .ok_or_else(|| anyhow!("background jobs are only available in the running bot"))?;
+        let job = jobs.start(
+            &bot_context.exec_policy,
+            ExecMode::Base,
+            command,
+            &bot_context.workdir,
+            Some(level),
+            JobOwner {
+                account_tag: account_tag.clone(),
+                chat_id: Some(chat_id),
+                started_by: Self::message_sender_id(envelope).to_string(),
+            },
+        )?;
+        Self::spawn_exec_job_stream(
+            Arc::clone(jobs),
+            job.clone(),
+            bot_context.exec_policy.jobs.update_interval_secs,
+            outbound_sender.clone(),
+            envelope.channel.clone(),
+            account_tag,
+            chat_id,
+            envelope.message_id,
+        );
+        Ok(job)
+    }
+
+    /// Post the job message, then edit it with the latest output every
+    /// `interval_secs` until the job ends. Channels that do not report the
+    /// message id only get the final message.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_exec_job_stream(
+        jobs: Arc<ExecJobs>,
+        job: JobInfo,
+        interval_secs: u64,
+        outbound_sender: broadcast::Sender<OutboundMessage>,
+        channel: String,
+        account_tag: Option<String>,
+        chat_id: i64,
+        reply_to: Option<i64>,
+    ) {
+        tokio::spawn(async move {
+            let message =
+                |text: &str, edit_message_id: Option<i64>, delivered: Option<DeliveryReceipt>| {
+                    OutboundMessage {
+                        channel: channel.clone(),
+                        account_tag: account_tag.clone(),
+                        chat_id,
+                        text: text.to_string(),
+                        reply_to: if edit_message_id.is_none() {
+                            reply_to
+                        } else {
+                            None
+                        },
+                        edit_message_id,
+                        inline_keyboard: None,
+                        chat_action: None,
+                        photo: None,
+                        delivered,
+                        callback_answer: None,
+                    }
+                };
+            let mut last_text = Self::format_exec_job(&job, "");
+            let (receipt, delivered) = DeliveryReceipt::new();
+            let _ = outbound_sender.send(message(&last_text, None, Some(receipt)));
+            let message_id = tokio::time::timeout(tokio::time::Duration::from_secs(10), delivered)
+                .await
+                .ok()
+                .and_then(|id| id.ok());
+
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs.max(1))).await;
+                let Some(current) = jobs.get(job.id) else {
+                    break;
+                };
+                let tail = jobs.tail(job.id, EXEC_JOB_TAIL_BYTES).unwrap_or_default();
+                let text = Self::format_exec_job(&current, &tail);
+                let running = current.is_running();
+                if text != last_text && (message_id.is_some() || !running) {
+                    let _ = outbound_sender.send(message(&text, message_id, None));
+                    last_text = text;
+                }
+                if !running {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn format_exec_job(job: &JobInfo, tail: &str) -> String {
+        let secs = job.elapsed_secs();
+        let elapsed = if secs >= 60 {
+            format!("{}m {}s", secs / 60, secs % 60)
+        } else {
+            format!("{}s", secs)
+        };
+        let header = match (job.status, job.exit_code) {
+            (JobStatus::Running, _) => format!("⏳ Job #{} running ({})", job.id, elapsed),
+            (JobStatus::Exited, Some(0)) => format!("✅ Job #{} exited 0 ({})", job.id, elapsed),
+            (JobStatus::Exited, code) => format!(
+                "❌ Job #{} exited {} ({})",
+                job.id,
+                code.map(|code| code.to_string())
+                    .unwrap_or_else(|| "by signal".to_string()),
+                elapsed
+            ),
+            (status, _) => format!("⚠️ Job #{} {} ({})", job.id, status.as_str(), elapsed),
+        };
+        let mut lines = vec![header, format!("`{}`", job.command)];
+        if let Some(error) = &job.error {
+            lines.push(format!("Error: {}", error));
+        }
+        let tail = tail.trim_end();
+        if !tail.is_empty() {
+            lines.push(format!("```text\n{}\n```", tail));
+        } else if !job.is_running() {
+            lines.push("No output.".to_string());
+        }
+        lines.join("\n")
+    }
+
+    /// `/jobs`, `/jobs tail <id>`, `/jobs kill <id>` for the jobs of this account.
+    fn exec_jobs_command_response(
+        bot_context: &BotContext,
+        account_tag: Option<&str>,
+        args: &str,
+    ) -> String {
+        let Some(jobs) = &bot_context.exec_jobs else {
+            return "Background jobs are only available in the running bot.".to_string();
+        };
+        let usage = "Usage: `/jobs`, `/jobs tail <id>`, `/jobs kill <id>`";
+        let mut parts = args.split_whitespace();
+        let action = parts.next();
+        let job = parts
+            .next()
+            .and_then(|id| id.trim_start_matches('#').parse::<u64>().ok())
+            .and_then(|id| jobs.get(id))
+            .filter(|job| job.account_tag.as_deref() == account_tag);
+
+        match (action, job) {
+            (None | Some("list"), _) => {
+                let mut listed: Vec<JobInfo> = jobs
+                    .list()
+                    .into_iter()
+                    .filter(|job| job.account_tag.as_deref() == account_tag)
+                    .collect();
+                if listed.is_empty() {
+                    return "No background jobs.".to_string();
+                }
+                listed.reverse();
+                listed.truncate(EXEC_JOBS_LIST_LIMIT);
+                let mut lines = vec!["Background jobs:".to_string()];
+                for job in listed {
+                    let status = match (job.status, job.exit_code) {
+                        (JobStatus::Exited, Some(code)) => format!("exited {}", code),
+                        (status, _) => status.as_str().to_string(),
+                    };
+                    lines.push(format!(
+                        "#{} {} ({}s) `{}`",
+                        job.id,
+                        status,
+                        job.elapsed_secs(),
+                        job.command
+                    ));
+                }
+                lines.join("\n")
+            }
+            (Some("tail"), Some(job)) => {
+                let tail = jobs.tail(job.id, EXEC_JOB_TAIL_BYTES).unwrap_or_default();
+                Self::format_exec_job(&job, &tail)
+            }
+            (Some("kill"), Some(job)) => match jobs.kill(job.id) {
+                Ok(()) => format!("Stopping job #{}.", job.id),
+                Err(e) => format!("Jobs error: {}", e),
+            },
+            (Some("tail" | "kill"), None) => format!("Job not found. {}", usage),
+            _ => usage.to_string(),
+        }
+    }
+
+    async fn handle_admin_command(
+        text: &str,
+        config: &Config,
+        account_tag: Option<&str>,
+        _from_user_id: i64,
+        permission: PermissionLevel,
+        bot_context: &BotContext,
+    ) -> String {
+        if permission != PermissionLevel::Admin {
+            return "Admin only command.".to_string();
+        }
+
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        if parts.len() < 2 {
+            return "🛡️ Admin commands\n\
 /admin list\n\
 /admin users [chat_id]\n\
 /admin contacts\n\
@@ -5768,7 +8481,8 @@ impl MasixRuntime {
 /admin tools user mode <none|selected>\n\
 /admin tools user allow <tool_name>\n\
 /admin tools user deny <tool_name>\n\
-/admin tools user clear"
+/admin tools user clear\n\
+/admin prefs <user_id|@username> [language|provider|model|reset]"
                 .to_string();
         }
 
@@ -6146,7 +8860,7 @@ impl MasixRuntime {
                 continue;
             };
             if let Some(chat_id) = chat_filter {
-                if !meta.chat_ids.iter().any(|cid| *cid == chat_id) {
+                if !meta.chat_ids.contains(&chat_id) {
                     continue;
                 }
             }
@@ -6458,6 +9172,58 @@ impl MasixRuntime {
         }
     }
 
+    /// Dynamic ACL of the account as sorted JSON, for audit diffs.
+    fn dynamic_acl_snapshot(
+        config: &Config,
+        account_tag: Option<&str>,
+    ) -> Option<serde_json::Value> {
+        let account = Self::get_telegram_account(config, account_tag)?;
+        let acl = Self::load_dynamic_acl_for_account(account);
+        let sorted_ids = |ids: &HashSet<i64>| {
+            let mut ids: Vec<i64> = ids.iter().copied().collect();
+            ids.sort_unstable();
+            ids
+        };
+        let allowed_tools = acl.user_allowed_tools.as_ref().map(|tools| {
+            let mut tools: Vec<&String> = tools.iter().collect();
+            tools.sort();
+            tools
+        });
+        Some(serde_json::json!({
+            "admins": sorted_ids(&acl.admins),
+            "users": sorted_ids(&acl.users),
+            "readonly": sorted_ids(&acl.readonly),
+            "user_tools_mode": acl.user_tools_mode.map(Self::user_tools_mode_label),
+            "user_allowed_tools": allowed_tools,
+        }))
+    }
+
+    /// Append an `acl_change` audit entry when `action` modified the dynamic ACL.
+    async fn audit_acl_change(
+        storage: &Arc<Mutex<Storage>>,
+        config: &Config,
+        account_tag: Option<&str>,
+        actor: &str,
+        action: &str,
+        before: Option<serde_json::Value>,
+    ) {
+        let after = Self::dynamic_acl_snapshot(config, account_tag);
+        if after == before {
+            return;
+        }
+        let record = AuditRecord {
+            kind: "acl_change",
+            actor,
+            account_tag,
+            action,
+            outcome: "ok",
+            details: serde_json::json!({ "before": before, "after": after }),
+        };
+        if let Err(e) = storage.lock().await.append_audit(&record) {
+            warn!("Failed to audit ACL change '{}': {}", action, e);
+        }
+    }
+
     fn sorted_csv(values: &HashSet<i64>) -> String {
         let mut list: Vec<i64> = values.iter().copied().collect();
         list.sort_unstable();
@@ -7376,14 +10142,52 @@ impl MasixRuntime {
             .join("scopes")
     }
 
+    fn memory_namespace_root(
+        context: &BotContext,
+        account_tag: Option<&str>,
+        namespace: MemoryNamespace<'_>,
+    ) -> PathBuf {
+        match namespace {
+            MemoryNamespace::Own => Self::memory_scopes_root(context, account_tag),
+            MemoryNamespace::Shared(grant) => grant
+                .owner_memory_dir
+                .join("accounts")
+                .join(Self::sanitize_scope_component(&grant.owner_account))
+                .join("scopes"),
+        }
+    }
+
+    /// Resolve which account's memory `owner_account` points to. `None` means the
+    /// owner does not share `scope` with this account.
+    fn resolve_memory_namespace<'a>(
+        context: &'a BotContext,
+        account_tag: Option<&str>,
+        owner_account: Option<&str>,
+        scope: MemoryScope,
+    ) -> Option<MemoryNamespace<'a>> {
+        match owner_account
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+        {
+            None => Some(MemoryNamespace::Own),
+            Some(owner) if owner == Self::account_scope(account_tag) => Some(MemoryNamespace::Own),
+            Some(owner) => context
+                .memory_grants
+                .iter()
+                .find(|grant| grant.owner_account == owner && grant.scope == scope)
+                .map(MemoryNamespace::Shared),
+        }
+    }
+
     fn memory_scope_base_dir(
         context: &BotContext,
         account_tag: Option<&str>,
+        namespace: MemoryNamespace<'_>,
         scope: MemoryScope,
         target_user_id: Option<i64>,
         caller_user_id: i64,
     ) -> PathBuf {
-        let root = Self::memory_scopes_root(context, account_tag);
+        let root = Self::memory_namespace_root(context, account_tag, namespace);
         match scope {
             MemoryScope::UserPrivate => {
                 let effective_user = target_user_id.unwrap_or(caller_user_id);
@@ -7397,10 +10201,16 @@ impl MasixRuntime {
 
     fn can_access_memory_scope(
         permission: PermissionLevel,
+        namespace: MemoryNamespace<'_>,
         scope: MemoryScope,
         caller_user_id: i64,
         target_user_id: Option<i64>,
     ) -> bool {
+        if let MemoryNamespace::Shared(grant) = namespace {
+            if grant.scope != scope {
+                return false;
+            }
+        }
         match permission {
             PermissionLevel::Admin => true,
             PermissionLevel::User => match scope {
@@ -7421,18 +10231,36 @@ impl MasixRuntime {
         }
     }
 
+    /// `allow_self_memory_edit` of the account (missing account: allowed).
+    fn self_memory_edit_allowed(config: &Config, account_tag: Option<&str>) -> bool {
+        Self::get_telegram_account(config, account_tag)
+            .is_none_or(|account| account.allow_self_memory_edit)
+    }
+
+    /// `allow_self_edit` gates users writing their own `user_private` memory;
+    /// admins keep full access.
     fn can_write_memory_scope(
         permission: PermissionLevel,
+        namespace: MemoryNamespace<'_>,
         scope: MemoryScope,
         caller_user_id: i64,
         target_user_id: Option<i64>,
+        allow_self_edit: bool,
     ) -> bool {
+        if let MemoryNamespace::Shared(grant) = namespace {
+            if grant.scope != scope || !grant.writable {
+                return false;
+            }
+        }
         match permission {
             PermissionLevel::Admin => true,
             PermissionLevel::User => match scope {
-                MemoryScope::UserPrivate => target_user_id
-                    .map(|target| target == caller_user_id)
-                    .unwrap_or(true),
+                MemoryScope::UserPrivate => {
+                    allow_self_edit
+                        && target_user_id
+                            .map(|target| target == caller_user_id)
+                            .unwrap_or(true)
+                }
                 MemoryScope::SharedUserKb => true,
                 MemoryScope::AdminKb => false,
             },
@@ -7471,7 +10299,23 @@ impl MasixRuntime {
                 anyhow!("memory_read requires scope: user_private|shared_user_kb|admin_kb")
             })?;
         let target_user_id = arguments.get("target_user_id").and_then(|v| v.as_i64());
-        if !Self::can_access_memory_scope(permission, scope, caller_user_id, target_user_id) {
+        let owner_account = arguments.get("account").and_then(|v| v.as_str());
+        let Some(namespace) =
+            Self::resolve_memory_namespace(bot_context, account_tag, owner_account, scope)
+        else {
+            return Ok(format!(
+                "Access denied: account '{}' does not share '{}' with this account.",
+                owner_account.unwrap_or_default().trim(),
+                scope.as_str()
+            ));
+        };
+        if !Self::can_access_memory_scope(
+            permission,
+            namespace,
+            scope,
+            caller_user_id,
+            target_user_id,
+        ) {
             return Ok(format!("Access denied for scope '{}'.", scope.as_str()));
         }
         let relative =
@@ -7479,6 +10323,7 @@ impl MasixRuntime {
         let base = Self::memory_scope_base_dir(
             bot_context,
             account_tag,
+            namespace,
             scope,
             target_user_id,
             caller_user_id,
@@ -7506,6 +10351,7 @@ impl MasixRuntime {
         account_tag: Option<&str>,
         caller_user_id: i64,
         permission: PermissionLevel,
+        allow_self_edit: bool,
     ) -> Result<String> {
         let scope = arguments
             .get("scope")
@@ -7519,7 +10365,24 @@ impl MasixRuntime {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow!("memory_write requires `content`"))?;
         let target_user_id = arguments.get("target_user_id").and_then(|v| v.as_i64());
-        if !Self::can_write_memory_scope(permission, scope, caller_user_id, target_user_id) {
+        let owner_account = arguments.get("account").and_then(|v| v.as_str());
+        let Some(namespace) =
+            Self::resolve_memory_namespace(bot_context, account_tag, owner_account, scope)
+        else {
+            return Ok(format!(
+                "Access denied: account '{}' does not share '{}' with this account.",
+                owner_account.unwrap_or_default().trim(),
+                scope.as_str()
+            ));
+        };
+        if !Self::can_write_memory_scope(
+            permission,
+            namespace,
+            scope,
+            caller_user_id,
+            target_user_id,
+            allow_self_edit,
+        ) {
             return Ok(format!("Access denied for scope '{}'.", scope.as_str()));
         }
         let relative =
@@ -7527,6 +10390,7 @@ impl MasixRuntime {
         let base = Self::memory_scope_base_dir(
             bot_context,
             account_tag,
+            namespace,
             scope,
             target_user_id,
             caller_user_id,
@@ -7536,7 +10400,7 @@ impl MasixRuntime {
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent).await?;
         }
-        let backup_root = Self::memory_scopes_root(bot_context, account_tag)
+        let backup_root = Self::memory_namespace_root(bot_context, account_tag, namespace)
             .join(".backups")
             .join(scope.as_str());
         let backup = Self::backup_memory_file(&full_path, &backup_root).await?;
@@ -7582,9 +10446,16 @@ impl MasixRuntime {
         account_tag: Option<&str>,
         user_scope_id: Option<&str>,
         chat_id: Option<i64>,
+        session: Option<&str>,
     ) -> PathBuf {
-        Self::user_memory_dir(context, account_tag, user_scope_id, chat_id)
-            .join(format!("chat_{}.jsonl", Self::chat_scope_label(chat_id)))
+        match session {
+            Some(name) => {
+                Self::chat_session_dir(context, account_tag, user_scope_id, chat_id, name)
+                    .join("chat.jsonl")
+            }
+            None => Self::user_memory_dir(context, account_tag, user_scope_id, chat_id)
+                .join(format!("chat_{}.jsonl", Self::chat_scope_label(chat_id))),
+        }
     }
 
     fn scoped_summary_path(
@@ -7592,15 +10463,275 @@ impl MasixRuntime {
         account_tag: Option<&str>,
         user_scope_id: Option<&str>,
         chat_id: Option<i64>,
+        session: Option<&str>,
     ) -> PathBuf {
-        Self::user_memory_dir(context, account_tag, user_scope_id, chat_id)
-            .join(format!("summary_{}.md", Self::chat_scope_label(chat_id)))
+        match session {
+            Some(name) => {
+                Self::chat_session_dir(context, account_tag, user_scope_id, chat_id, name)
+                    .join("summary.md")
+            }
+            None => Self::user_memory_dir(context, account_tag, user_scope_id, chat_id)
+                .join(format!("summary_{}.md", Self::chat_scope_label(chat_id))),
+        }
     }
 
     fn legacy_chat_memory_path(context: &BotContext, chat_id: Option<i64>) -> Option<PathBuf> {
         chat_id.map(|id| context.memory_dir.join(format!("chat_{}.jsonl", id)))
     }
 
+    fn chat_session_dir(
+        context: &BotContext,
+        account_tag: Option<&str>,
+        user_scope_id: Option<&str>,
+        chat_id: Option<i64>,
+        session: &str,
+    ) -> PathBuf {
+        Self::user_memory_dir(context, account_tag, user_scope_id, chat_id)
+            .join("sessions")
+            .join(Self::chat_scope_label(chat_id))
+            .join(Self::sanitize_scope_component(session))
+    }
+
+    fn chat_session_state_path(
+        context: &BotContext,
+        account_tag: Option<&str>,
+        user_scope_id: Option<&str>,
+        chat_id: Option<i64>,
+    ) -> PathBuf {
+        Self::user_memory_dir(context, account_tag, user_scope_id, chat_id)
+            .join(format!("sessions_{}.json", Self::chat_scope_label(chat_id)))
+    }
+
+    /// A missing file means no named sessions; an unreadable one is an error so
+    /// it is never overwritten with an empty list.
+    async fn load_chat_session_state(path: &Path) -> Result<ChatSessionState> {
+        match fs::read_to_string(path).await {
+            Ok(raw) => serde_json::from_str::<ChatSessionState>(&raw)
+                .map_err(|e| anyhow!("corrupt session state {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ChatSessionState::default()),
+            Err(e) => Err(anyhow!("failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    async fn save_chat_session_state(path: &Path, state: &ChatSessionState) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, serde_json::to_string_pretty(state)?).await?;
+        Ok(())
+    }
+
+    /// Active named session for this user/chat, `None` for the default session.
+    async fn active_chat_session(
+        context: &BotContext,
+        account_tag: Option<&str>,
+        user_scope_id: Option<&str>,
+        chat_id: Option<i64>,
+    ) -> Result<Option<String>> {
+        let path = Self::chat_session_state_path(context, account_tag, user_scope_id, chat_id);
+        Ok(Self::load_chat_session_state(&path).await?.active)
+    }
+
+    /// Session names are `[a-z0-9-]` with `_` only between other characters, so
+    /// `sanitize_scope_component` keeps them as they are and two names never
+    /// share a directory.
+    fn normalize_chat_session_name(raw: &str) -> Option<String> {
+        let name: String = raw
+            .trim()
+            .to_ascii_lowercase()
+            .chars()
+            .map(|ch| if ch.is_whitespace() { '-' } else { ch })
+            .collect();
+        let valid = !name.is_empty()
+            && name.len() <= CHAT_SESSION_NAME_MAX_CHARS
+            && !name.starts_with('_')
+            && !name.ends_with('_')
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_');
+        valid.then_some(name)
+    }
+
+    async fn count_chat_session_messages(path: &Path) -> usize {
+        fs::read_to_string(path)
+            .await
+            .map(|raw| raw.lines().filter(|line| !line.trim().is_empty()).count())
+            .unwrap_or(0)
+    }
+
+    async fn handle_session_chat_command(
+        text: &str,
+        bot_context: &BotContext,
+        account_tag: Option<&str>,
+        user_scope_id: Option<&str>,
+        chat_id: i64,
+    ) -> String {
+        let chat = Some(chat_id);
+        let rest = text.strip_prefix("/session").unwrap_or("").trim();
+        let (action, args) = rest
+            .split_once(char::is_whitespace)
+            .map(|(action, args)| (action, args.trim()))
+            .unwrap_or((rest, ""));
+        let state_path =
+            Self::chat_session_state_path(bot_context, account_tag, user_scope_id, chat);
+        let mut state = match Self::load_chat_session_state(&state_path).await {
+            Ok(state) => state,
+            Err(err) => return format!("❌ Sessions unavailable: {:#}", err),
+        };
+        let now = chrono::Utc::now().to_rfc3339();
+
+        match action.to_ascii_lowercase().as_str() {
+            "" | "list" | "ls" => {
+                let active = state.active.as_deref();
+                let marker = |selected: bool| if selected { "▶" } else { "•" };
+                let mut lines = vec!["🗂️ *Sessions*".to_string(), String::new()];
+                let default_path = Self::scoped_chat_memory_path(
+                    bot_context,
+                    account_tag,
+                    user_scope_id,
+                    chat,
+                    None,
+                );
+                lines.push(format!(
+                    "{} {} ({} messages)",
+                    marker(active.is_none()),
+                    DEFAULT_CHAT_SESSION,
+                    Self::count_chat_session_messages(&default_path).await
+                ));
+                let mut archived = Vec::new();
+                for session in &state.sessions {
+                    if session.archived_at.is_some() {
+                        archived.push(session.name.clone());
+                        continue;
+                    }
+                    let path = Self::scoped_chat_memory_path(
+                        bot_context,
+                        account_tag,
+                        user_scope_id,
+                        chat,
+                        Some(&session.name),
+                    );
+                    lines.push(format!(
+                        "{} {} ({} messages)",
+                        marker(active == Some(session.name.as_str())),
+                        session.name,
+                        Self::count_chat_session_messages(&path).await
+                    ));
+                }
+                if !archived.is_empty() {
+                    lines.push(String::new());
+                    lines.push(format!("Archived: {}", archived.join(", ")));
+                }
+                lines.push(String::new());
+                lines.push("/session new <name> - Start a new session".to_string());
+                lines.push("/session switch <name> - Switch session".to_string());
+                lines.push("/session archive [name] - Archive a session".to_string());
+                lines.join("\n")
+            }
+            "help" => [
+                "🗂️ *Session Commands*".to_string(),
+                String::new(),
+                "/session list - List sessions in this chat".to_string(),
+                "/session new <name> - Start a new session and switch to it".to_string(),
+                "/session switch <name> - Switch session (`default` for the main one)".to_string(),
+                "/session archive [name] - Archive a session (history is kept)".to_string(),
+                "/new - Reset the history of the active session".to_string(),
+            ]
+            .join("\n"),
+            "new" | "create" => {
+                let Some(name) = Self::normalize_chat_session_name(args) else {
+                    return format!(
+                        "Usage: /session new <name> (a-z, 0-9, - or _ inside, max {} chars)",
+                        CHAT_SESSION_NAME_MAX_CHARS
+                    );
+                };
+                if name == DEFAULT_CHAT_SESSION {
+                    return "❌ `default` is reserved. Use /session switch default.".to_string();
+                }
+                if let Some(existing) = state.sessions.iter().find(|s| s.name == name) {
+                    return if existing.archived_at.is_some() {
+                        format!(
+                            "❌ Session '{}' is archived. Use /session switch {} to restore it.",
+                            name, name
+                        )
+                    } else {
+                        format!(
+                            "❌ Session '{}' already exists. Use /session switch {}.",
+                            name, name
+                        )
+                    };
+                }
+                state.sessions.push(ChatSessionInfo {
+                    name: name.clone(),
+                    created_at: now,
+                    archived_at: None,
+                });
+                state.active = Some(name.clone());
+                match Self::save_chat_session_state(&state_path, &state).await {
+                    Ok(()) => format!("✅ New session '{}' started.", name),
+                    Err(err) => format!("Failed to create session: {}", err),
+                }
+            }
+            "switch" | "use" => {
+                let Some(name) = Self::normalize_chat_session_name(args) else {
+                    return "Usage: /session switch <name>".to_string();
+                };
+                let mut restored = false;
+                if name == DEFAULT_CHAT_SESSION {
+                    state.active = None;
+                } else {
+                    let Some(session) = state.sessions.iter_mut().find(|s| s.name == name) else {
+                        return format!(
+                            "❌ Session '{}' not found. Use /session new {}.",
+                            name, name
+                        );
+                    };
+                    restored = session.archived_at.take().is_some();
+                    state.active = Some(name.clone());
+                }
+                match Self::save_chat_session_state(&state_path, &state).await {
+                    Ok(()) if restored => {
+                        format!("✅ Session '{}' restored from archive and active.", name)
+                    }
+                    Ok(()) => format!("✅ Switched to session '{}'.", name),
+                    Err(err) => format!("Failed to switch session: {}", err),
+                }
+            }
+            "archive" => {
+                let target = if args.is_empty() {
+                    state.active.clone()
+                } else {
+                    Self::normalize_chat_session_name(args)
+                };
+                let Some(name) = target.filter(|name| name != DEFAULT_CHAT_SESSION) else {
+                    return "❌ The default session cannot be archived. Use /new to reset it."
+                        .to_string();
+                };
+                let Some(session) = state
+                    .sessions
+                    .iter_mut()
+                    .find(|s| s.name == name && s.archived_at.is_none())
+                else {
+                    return format!("❌ Session '{}' not found.", name);
+                };
+                session.archived_at = Some(now);
+                let was_active = state.active.as_deref() == Some(name.as_str());
+                if was_active {
+                    state.active = None;
+                }
+                match Self::save_chat_session_state(&state_path, &state).await {
+                    Ok(()) if was_active => format!(
+                        "📦 Session '{}' archived. Back to the default session.",
+                        name
+                    ),
+                    Ok(()) => format!("📦 Session '{}' archived.", name),
+                    Err(err) => format!("Failed to archive session: {}", err),
+                }
+            }
+            _ => "Usage: /session [list|new <name>|switch <name>|archive [name]]".to_string(),
+        }
+    }
+
     async fn record_user_catalog(
         context: &BotContext,
         account_tag: Option<&str>,
@@ -7699,10 +10830,27 @@ impl MasixRuntime {
         chat_id: Option<i64>,
         max_entries: usize,
     ) -> Vec<ChatMessage> {
-        let path = Self::scoped_chat_memory_path(context, account_tag, user_scope_id, chat_id);
-        let content = match fs::read_to_string(&path).await {
-            Ok(c) => c,
-            Err(_) => {
+        let session =
+            match Self::active_chat_session(context, account_tag, user_scope_id, chat_id).await {
+                Ok(session) => session,
+                Err(e) => {
+                    warn!("Chat history unavailable: {:#}", e);
+                    return Vec::new();
+                }
+            };
+        let path = Self::scoped_chat_memory_path(
+            context,
+            account_tag,
+            user_scope_id,
+            chat_id,
+            session.as_deref(),
+        );
+        let content = match fs::read_to_string(&path).await {
+            Ok(c) => c,
+            Err(_) => {
+                if session.is_some() {
+                    return Vec::new();
+                }
                 let Some(legacy_path) = Self::legacy_chat_memory_path(context, chat_id) else {
                     return Vec::new();
                 };
@@ -7733,6 +10881,7 @@ impl MasixRuntime {
                 tool_calls: None,
                 tool_call_id: None,
                 name: None,
+                images: Vec::new(),
             })
             .collect()
     }
@@ -7748,7 +10897,15 @@ impl MasixRuntime {
         if content.trim().is_empty() {
             return Ok(());
         }
-        let path = Self::scoped_chat_memory_path(context, account_tag, user_scope_id, chat_id);
+        let session =
+            Self::active_chat_session(context, account_tag, user_scope_id, chat_id).await?;
+        let path = Self::scoped_chat_memory_path(
+            context,
+            account_tag,
+            user_scope_id,
+            chat_id,
+            session.as_deref(),
+        );
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
         }
@@ -7774,19 +10931,36 @@ impl MasixRuntime {
         user_scope_id: Option<&str>,
         chat_id: Option<i64>,
     ) {
-        let path = Self::scoped_chat_memory_path(context, account_tag, user_scope_id, chat_id);
+        let session =
+            match Self::active_chat_session(context, account_tag, user_scope_id, chat_id).await {
+                Ok(session) => session,
+                Err(e) => {
+                    warn!("Chat memory not cleared: {:#}", e);
+                    return;
+                }
+            };
+        let path = Self::scoped_chat_memory_path(
+            context,
+            account_tag,
+            user_scope_id,
+            chat_id,
+            session.as_deref(),
+        );
         if path.exists() {
             let _ = std::fs::remove_file(&path);
         }
-        if let Some(legacy_path) = Self::legacy_chat_memory_path(context, chat_id) {
-            if legacy_path.exists() {
-                let _ = std::fs::remove_file(legacy_path);
+        if session.is_none() {
+            if let Some(legacy_path) = Self::legacy_chat_memory_path(context, chat_id) {
+                if legacy_path.exists() {
+                    let _ = std::fs::remove_file(legacy_path);
+                }
             }
         }
         info!(
-            "Cleared scoped chat memory for user={} chat={}",
+            "Cleared scoped chat memory for user={} chat={} session={}",
             Self::normalized_user_id(user_scope_id, chat_id),
-            Self::chat_scope_label(chat_id)
+            Self::chat_scope_label(chat_id),
+            session.as_deref().unwrap_or(DEFAULT_CHAT_SESSION)
         );
     }
 
@@ -7801,13 +10975,17 @@ impl MasixRuntime {
         if history.is_empty() {
             return Ok(());
         }
+        let session =
+            Self::active_chat_session(context, account_tag, user_scope_id, chat_id).await?;
 
         let user_label = Self::normalized_user_id(user_scope_id, chat_id);
         let chat_label = Self::chat_scope_label(chat_id);
         let mut lines = vec![
             format!(
-                "# Chat Summary (user: {}, chat: {})",
-                user_label, chat_label
+                "# Chat Summary (user: {}, chat: {}, session: {})",
+                user_label,
+                chat_label,
+                session.as_deref().unwrap_or(DEFAULT_CHAT_SESSION)
             ),
             format!("Updated: {}", chrono::Utc::now().to_rfc3339()),
             String::new(),
@@ -7824,12 +11002,946 @@ impl MasixRuntime {
             lines.push(format!("- {}: {}", role, shortened.replace('\n', " ")));
         }
 
-        let path = Self::scoped_summary_path(context, account_tag, user_scope_id, chat_id);
+        let path = Self::scoped_summary_path(
+            context,
+            account_tag,
+            user_scope_id,
+            chat_id,
+            session.as_deref(),
+        );
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, lines.join("\n")).await?;
+        Ok(())
+    }
+
+    fn profile_facts_path(
+        context: &BotContext,
+        account_tag: Option<&str>,
+        user_id: i64,
+    ) -> PathBuf {
+        Self::memory_scope_base_dir(
+            context,
+            account_tag,
+            MemoryNamespace::Own,
+            MemoryScope::UserPrivate,
+            None,
+            user_id,
+        )
+        .join(PROFILE_FACTS_FILE)
+    }
+
+    async fn load_profile_facts(path: &Path) -> Vec<UserProfileFact> {
+        match fs::read_to_string(path).await {
+            Ok(raw) => serde_json::from_str::<Vec<UserProfileFact>>(&raw).unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    async fn save_profile_facts(path: &Path, facts: &[UserProfileFact]) -> Result<()> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
         }
-        fs::write(path, lines.join("\n")).await?;
-        Ok(())
+        let body = serde_json::to_string_pretty(facts)?;
+        fs::write(path, body).await?;
+        Ok(())
+    }
+
+    fn normalize_fact_text(text: &str) -> String {
+        text.chars()
+            .map(|ch| {
+                if ch.is_alphanumeric() {
+                    ch.to_lowercase().next().unwrap_or(ch)
+                } else {
+                    ' '
+                }
+            })
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Parses the extractor reply: a JSON array of strings or `{category, fact}` objects,
+    /// optionally wrapped in a Markdown code fence.
+    fn parse_fact_extraction_response(raw: &str, max_facts: usize) -> Vec<(String, String)> {
+        let (Some(start), Some(end)) = (raw.find('['), raw.rfind(']')) else {
+            return Vec::new();
+        };
+        if end <= start {
+            return Vec::new();
+        }
+        let Ok(items) = serde_json::from_str::<Vec<serde_json::Value>>(&raw[start..=end]) else {
+            return Vec::new();
+        };
+
+        let mut facts = Vec::new();
+        for item in items {
+            let (category, text) = match &item {
+                serde_json::Value::String(text) => ("other".to_string(), text.clone()),
+                serde_json::Value::Object(obj) => {
+                    let text = obj
+                        .get("fact")
+                        .or_else(|| obj.get("text"))
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    let category = obj
+                        .get("category")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.trim().to_ascii_lowercase())
+                        .filter(|v| !v.is_empty())
+                        .unwrap_or_else(|| "other".to_string());
+                    (category, text)
+                }
+                _ => continue,
+            };
+            let text = text.trim().replace('\n', " ");
+            if text.is_empty() || text.chars().count() > PROFILE_FACT_MAX_CHARS {
+                continue;
+            }
+            facts.push((category, text));
+            if facts.len() >= max_facts {
+                break;
+            }
+        }
+        facts
+    }
+
+    /// Appends candidates that are not already known (stored facts or `user_private`
+    /// memory text), trimming the oldest entries above `max_stored`. Returns added count.
+    #[allow(clippy::too_many_arguments)]
+    fn merge_profile_facts(
+        facts: &mut Vec<UserProfileFact>,
+        candidates: Vec<(String, String)>,
+        private_memory: &str,
+        trace_id: &str,
+        channel: &str,
+        chat_id: Option<i64>,
+        now: &str,
+        max_stored: usize,
+    ) -> usize {
+        // Whole lines only: "likes tea" must not match "dislikes tea".
+        let mut known: HashSet<String> = facts
+            .iter()
+            .map(|fact| Self::normalize_fact_text(&fact.text))
+            .chain(private_memory.lines().map(Self::normalize_fact_text))
+            .collect();
+        let mut next_id = facts.iter().map(|fact| fact.id).max().unwrap_or(0) + 1;
+        let mut added = 0;
+
+        for (category, text) in candidates {
+            let normalized = Self::normalize_fact_text(&text);
+            if normalized.is_empty() || known.contains(&normalized) {
+                continue;
+            }
+            known.insert(normalized);
+            facts.push(UserProfileFact {
+                id: next_id,
+                text,
+                category,
+                trace_id: trace_id.to_string(),
+                ts: now.to_string(),
+                channel: channel.to_string(),
+                chat_id,
+            });
+            next_id += 1;
+            added += 1;
+        }
+
+        if facts.len() > max_stored {
+            let overflow = facts.len() - max_stored;
+            facts.drain(..overflow);
+        }
+        added
+    }
+
+    async fn load_user_private_memory_text(
+        context: &BotContext,
+        account_tag: Option<&str>,
+        user_id: i64,
+    ) -> String {
+        let base = Self::memory_scope_base_dir(
+            context,
+            account_tag,
+            MemoryNamespace::Own,
+            MemoryScope::UserPrivate,
+            None,
+            user_id,
+        );
+        let mut combined = String::new();
+        let Ok(mut entries) = fs::read_dir(&base).await else {
+            return combined;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let is_text = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| matches!(ext, "md" | "txt"))
+                .unwrap_or(false);
+            if !is_text {
+                continue;
+            }
+            if let Ok(content) = fs::read_to_string(&path).await {
+                combined.push_str(&content);
+                combined.push('\n');
+            }
+        }
+        combined
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn extract_profile_facts(
+        provider_router: &ProviderRouter,
+        bot_context: &BotContext,
+        account_tag: Option<&str>,
+        user_id: i64,
+        envelope: &Envelope,
+        user_message: &str,
+        assistant_message: &str,
+        config: &Config,
+    ) -> Result<usize> {
+        let settings = &config.core.fact_extraction;
+        let path = Self::profile_facts_path(bot_context, account_tag, user_id);
+        let facts = Self::load_profile_facts(&path).await;
+
+        let known = if facts.is_empty() {
+            "(none)".to_string()
+        } else {
+            facts
+                .iter()
+                .map(|fact| format!("- {}", fact.text))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let instructions = format!(
+            "You extract durable facts about the user from one conversation turn.\n\
+             Return ONLY a JSON array (max {} items) of objects {{\"category\": \"preference|identity|routine|task|other\", \"fact\": \"...\"}}.\n\
+             Keep only stable facts the user states about themselves (preferences, names, recurring tasks). \
+             Skip one-off requests, questions, secrets/credentials and anything about the assistant.\n\
+             Write each fact as a short third-person sentence in the user's language. Return [] when nothing qualifies.\n\n\
+             Known facts (do not repeat):\n{}",
+            settings.max_facts_per_turn, known
+        );
+        let messages = vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: Some(instructions),
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+                images: Vec::new(),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: Some(format!(
+                    "User:\n{}\n\nAssistant:\n{}",
+                    Self::truncate_for_prompt(user_message, 4000),
+                    Self::truncate_for_prompt(assistant_message, 2000)
+                )),
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+                images: Vec::new(),
+            },
+        ];
+
+        let (response, _provider_used) = Self::chat_with_fallback_chain(
+            provider_router,
+            messages,
+            None,
+            &bot_context.provider_chain,
+            None,
+            None,
+            &bot_context.retry_policy,
+            &bot_context.profile_name,
+        )
+        .await?;
+        let candidates = Self::parse_fact_extraction_response(
+            response.content.as_deref().unwrap_or_default(),
+            usize::from(settings.max_facts_per_turn),
+        );
+        if candidates.is_empty() {
+            return Ok(0);
+        }
+
+        // Runs outside the chat scope lock: reload so facts stored meanwhile
+        // (another turn, `/facts delete`) are not overwritten.
+        let mut facts = Self::load_profile_facts(&path).await;
+        let private_memory =
+            Self::load_user_private_memory_text(bot_context, account_tag, user_id).await;
+        let added = Self::merge_profile_facts(
+            &mut facts,
+            candidates,
+            &private_memory,
+            &envelope.trace_id,
+            &envelope.channel,
+            envelope.chat_id,
+            &chrono::Utc::now().to_rfc3339(),
+            settings.max_stored_facts,
+        );
+        if added > 0 {
+            Self::save_profile_facts(&path, &facts).await?;
+        }
+        Ok(added)
+    }
+
+    fn render_profile_facts_context(facts: &[UserProfileFact]) -> Option<String> {
+        if facts.is_empty() {
+            return None;
+        }
+        let mut lines = vec![
+            "\n\n# User Profile Facts".to_string(),
+            "Durable facts previously learned about this user:".to_string(),
+        ];
+        for fact in facts {
+            lines.push(format!("- {}", fact.text));
+        }
+        Some(lines.join("\n"))
+    }
+
+    async fn handle_facts_chat_command(
+        text: &str,
+        bot_context: &BotContext,
+        account_tag: Option<&str>,
+        user_id: i64,
+    ) -> String {
+        let rest = text.strip_prefix("/facts").unwrap_or("").trim();
+        let path = Self::profile_facts_path(bot_context, account_tag, user_id);
+        let mut facts = Self::load_profile_facts(&path).await;
+
+        if rest.is_empty() || rest.eq_ignore_ascii_case("list") {
+            if facts.is_empty() {
+                return "🧠 No stored facts about you.".to_string();
+            }
+            let mut lines = vec![
+                format!("🧠 *Stored facts* ({})", facts.len()),
+                String::new(),
+            ];
+            for fact in &facts {
+                let day = fact.ts.get(..10).unwrap_or(fact.ts.as_str());
+                lines.push(format!(
+                    "#{} [{}] {} ({}, trace {})",
+                    fact.id, fact.category, fact.text, day, fact.trace_id
+                ));
+            }
+            lines.push(String::new());
+            lines.push("/facts delete <id> - Delete one fact".to_string());
+            lines.push("/facts clear - Delete all facts".to_string());
+            return lines.join("\n");
+        }
+
+        if rest.eq_ignore_ascii_case("help") {
+            return [
+                "🧠 *Fact Commands*".to_string(),
+                String::new(),
+                "/facts - List stored facts about you".to_string(),
+                "/facts delete <id> - Delete one fact".to_string(),
+                "/facts clear - Delete all facts".to_string(),
+            ]
+            .join("\n");
+        }
+
+        if rest.eq_ignore_ascii_case("clear") {
+            if facts.is_empty() {
+                return "🧠 No stored facts about you.".to_string();
+            }
+            let count = facts.len();
+            return match Self::save_profile_facts(&path, &[]).await {
+                Ok(()) => format!("✅ Deleted {} fact(s).", count),
+                Err(err) => format!("Failed to delete facts: {}", err),
+            };
+        }
+
+        let Some(raw_id) = rest
+            .strip_prefix("delete")
+            .or_else(|| rest.strip_prefix("del"))
+            .or_else(|| rest.strip_prefix("rm"))
+        else {
+            return "Usage: /facts [list|delete <id>|clear]".to_string();
+        };
+        let Ok(id) = raw_id.trim().trim_start_matches('#').parse::<u64>() else {
+            return "Usage: /facts delete <id>".to_string();
+        };
+        let before = facts.len();
+        facts.retain(|fact| fact.id != id);
+        if facts.len() == before {
+            return format!("❌ Fact #{} not found.", id);
+        }
+        match Self::save_profile_facts(&path, &facts).await {
+            Ok(()) => format!("✅ Fact #{} deleted.", id),
+            Err(err) => format!("Failed to delete fact: {}", err),
+        }
+    }
+
+    fn knowledge_dir(
+        bot_context: &BotContext,
+        account_tag: Option<&str>,
+        scope: MemoryScope,
+    ) -> PathBuf {
+        Self::memory_scope_base_dir(
+            bot_context,
+            account_tag,
+            MemoryNamespace::Own,
+            scope,
+            None,
+            0,
+        )
+        .join(KB_DIR_NAME)
+    }
+
+    fn parse_kb_scope(raw: &str) -> Option<MemoryScope> {
+        match Self::parse_memory_scope(raw)? {
+            MemoryScope::UserPrivate => None,
+            scope => Some(scope),
+        }
+    }
+
+    fn readable_kb_scopes(permission: PermissionLevel, caller_user_id: i64) -> Vec<MemoryScope> {
+        [MemoryScope::SharedUserKb, MemoryScope::AdminKb]
+            .into_iter()
+            .filter(|scope| {
+                Self::can_access_memory_scope(
+                    permission,
+                    MemoryNamespace::Own,
+                    *scope,
+                    caller_user_id,
+                    None,
+                )
+            })
+            .collect()
+    }
+
+    async fn search_knowledge_scopes(
+        bot_context: &BotContext,
+        account_tag: Option<&str>,
+        scopes: &[MemoryScope],
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<(MemoryScope, KnowledgeHit)>> {
+        let dirs: Vec<(MemoryScope, PathBuf)> = scopes
+            .iter()
+            .map(|scope| {
+                (
+                    *scope,
+                    Self::knowledge_dir(bot_context, account_tag, *scope),
+                )
+            })
+            .collect();
+        let query = query.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut hits = Vec::new();
+            for (scope, dir) in dirs {
+                for hit in knowledge::search(&dir, &query, limit)? {
+                    hits.push((scope, hit));
+                }
+            }
+            hits.sort_by(|a, b| b.1.score.total_cmp(&a.1.score));
+            hits.truncate(limit);
+            Ok(hits)
+        })
+        .await?
+    }
+
+    async fn execute_mcp_resource_list_tool(
+        arguments: serde_json::Value,
+        mcp_client: &Option<Arc<Mutex<McpClient>>>,
+        admin_only_modules: &HashSet<String>,
+        permission: PermissionLevel,
+    ) -> Result<String> {
+        let Some(client) = mcp_client else {
+            return Ok("MCP is disabled.".to_string());
+        };
+        let server_filter = arguments
+            .get("server")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        let resources: Vec<serde_json::Value> = client
+            .lock()
+            .await
+            .list_all_resources()
+            .await
+            .into_iter()
+            .filter(|(server, _)| server_filter.is_none_or(|name| name == server))
+            .filter(|(server, _)| {
+                permission == PermissionLevel::Admin
+                    || !is_admin_only_server(server, admin_only_modules)
+            })
+            .take(MCP_RESOURCE_LIST_LIMIT)
+            .map(|(server, resource)| {
+                serde_json::json!({
+                    "server": server,
+                    "uri": resource.uri,
+                    "name": resource.name,
+                    "description": resource.description,
+                    "mime_type": resource.mime_type,
+                })
+            })
+            .collect();
+        if resources.is_empty() {
+            return Ok("No MCP resources available.".to_string());
+        }
+        Ok(serde_json::to_string_pretty(&resources)?)
+    }
+
+    async fn execute_mcp_resource_read_tool(
+        arguments: serde_json::Value,
+        mcp_client: &Option<Arc<Mutex<McpClient>>>,
+        admin_only_modules: &HashSet<String>,
+        permission: PermissionLevel,
+        workdir: &Path,
+    ) -> Result<String> {
+        let Some(client) = mcp_client else {
+            return Ok("MCP is disabled.".to_string());
+        };
+        let server = arguments
+            .get("server")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| anyhow!("mcp_resource_read requires `server`"))?;
+        let uri = arguments
+            .get("uri")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| anyhow!("mcp_resource_read requires `uri`"))?;
+        if permission != PermissionLevel::Admin && is_admin_only_server(server, admin_only_modules)
+        {
+            return Ok("Access denied: this MCP server requires admin privileges.".to_string());
+        }
+        // Release the client lock before the read: a slow server must not block other chats.
+        let mcp_server = client
+            .lock()
+            .await
+            .server(server)
+            .ok_or_else(|| anyhow!("Server not found: {}", server))?;
+        let contents = mcp_server.read_resource_contents(uri).await?;
+        Self::render_mcp_resource_contents(&contents, Some(workdir)).await
+    }
+
+    /// Render `resources/read` contents as text. Blobs that are not valid text are
+    /// saved under `<workdir>/mcp_resources/` when a workdir is given, otherwise described.
+    async fn render_mcp_resource_contents(
+        contents: &[ResourceContents],
+        workdir: Option<&Path>,
+    ) -> Result<String> {
+        if contents.is_empty() {
+            return Ok("Resource is empty.".to_string());
+        }
+        let mut parts = Vec::new();
+        for item in contents {
+            let mime = item.mime_type.as_deref().unwrap_or("unknown");
+            let header = format!("[{}] ({})", item.uri, mime);
+            if let Some(text) = &item.text {
+                parts.push(format!(
+                    "{}\n{}",
+                    header,
+                    Self::truncate_resource_text(text)
+                ));
+                continue;
+            }
+            let Some(blob) = &item.blob else {
+                parts.push(format!("{}\n(no content)", header));
+                continue;
+            };
+            let bytes = match base64::engine::general_purpose::STANDARD.decode(blob.trim()) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    parts.push(format!("{}\nInvalid base64 blob: {}", header, e));
+                    continue;
+                }
+            };
+            if bytes.len() > MCP_RESOURCE_BLOB_MAX_BYTES {
+                parts.push(format!(
+                    "{}\nBinary resource too large ({} bytes, limit {}).",
+                    header,
+                    bytes.len(),
+                    MCP_RESOURCE_BLOB_MAX_BYTES
+                ));
+                continue;
+            }
+            let textual = mime.starts_with("text/")
+                || mime.ends_with("json")
+                || mime.ends_with("xml")
+                || mime.ends_with("yaml");
+            if textual {
+                if let Ok(text) = std::str::from_utf8(&bytes) {
+                    parts.push(format!(
+                        "{}\n{}",
+                        header,
+                        Self::truncate_resource_text(text)
+                    ));
+                    continue;
+                }
+            }
+            match workdir {
+                Some(workdir) => {
+                    let relative = Path::new(MCP_RESOURCE_DIR)
+                        .join(Self::mcp_resource_file_name(&item.uri, &bytes));
+                    let target = workdir.join(&relative);
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent).await?;
+                    }
+                    fs::write(&target, &bytes).await?;
+                    parts.push(format!(
+                        "{}\nBinary resource ({} bytes) saved to {}",
+                        header,
+                        bytes.len(),
+                        relative.display()
+                    ));
+                }
+                None => parts.push(format!(
+                    "{}\nBinary resource ({} bytes).",
+                    header,
+                    bytes.len()
+                )),
+            }
+        }
+        Ok(parts.join("\n\n"))
+    }
+
+    fn truncate_resource_text(text: &str) -> String {
+        if text.chars().count() <= MCP_RESOURCE_TEXT_LIMIT {
+            return text.to_string();
+        }
+        let truncated: String = text.chars().take(MCP_RESOURCE_TEXT_LIMIT).collect();
+        format!(
+            "{}\n\n... [truncated, {} bytes total]",
+            truncated,
+            text.len()
+        )
+    }
+
+    /// Content-addressed file name keeping the last uri segment for readability.
+    fn mcp_resource_file_name(uri: &str, bytes: &[u8]) -> String {
+        use sha2::{Digest, Sha256};
+        let digest = format!("{:x}", Sha256::digest(bytes));
+        let stem: String = uri
+            .rsplit(['/', ':'])
+            .find(|segment| !segment.is_empty())
+            .unwrap_or("resource")
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(64)
+            .collect();
+        format!("{}-{}", &digest[..12], stem.trim_start_matches('.'))
+    }
+
+    async fn execute_kb_search_tool(
+        arguments: serde_json::Value,
+        bot_context: &BotContext,
+        account_tag: Option<&str>,
+        caller_user_id: i64,
+        permission: PermissionLevel,
+    ) -> Result<String> {
+        let query = arguments
+            .get("query")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| anyhow!("kb_search requires `query`"))?;
+        let scopes = match arguments.get("scope").and_then(|v| v.as_str()) {
+            Some(raw) => {
+                let scope = Self::parse_kb_scope(raw)
+                    .ok_or_else(|| anyhow!("kb_search scope must be shared_user_kb|admin_kb"))?;
+                if !Self::can_access_memory_scope(
+                    permission,
+                    MemoryNamespace::Own,
+                    scope,
+                    caller_user_id,
+                    None,
+                ) {
+                    return Ok(format!("Access denied for scope '{}'.", scope.as_str()));
+                }
+                vec![scope]
+            }
+            None => Self::readable_kb_scopes(permission, caller_user_id),
+        };
+        if scopes.is_empty() {
+            return Ok("Access denied for knowledge base scopes.".to_string());
+        }
+        let limit = arguments
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .unwrap_or(KB_SEARCH_DEFAULT_LIMIT)
+            .clamp(1, KB_SEARCH_MAX_LIMIT);
+
+        let hits =
+            Self::search_knowledge_scopes(bot_context, account_tag, &scopes, query, limit).await?;
+        if hits.is_empty() {
+            return Ok(format!("No knowledge base results for '{}'.", query));
+        }
+        let mut blocks = Vec::with_capacity(hits.len());
+        for (scope, hit) in hits {
+            blocks.push(format!(
+                "[{} | {} | doc {} chunk {} | score {:.2}]\n{}",
+                scope.as_str(),
+                hit.title,
+                hit.doc_id,
+                hit.chunk_index,
+                hit.score,
+                hit.text
+            ));
+        }
+        Ok(blocks.join("\n\n---\n\n"))
+    }
+
+    fn format_kb_document_line(doc: &KnowledgeDocument) -> String {
+        let day = doc
+            .ingested_at
+            .get(..10)
+            .unwrap_or(doc.ingested_at.as_str());
+        format!(
+            "`{}` {} ({}, {} chunks, {})",
+            doc.id,
+            doc.title,
+            doc.format.as_str(),
+            doc.chunks,
+            day
+        )
+    }
+
+    fn kb_media_reference(envelope: &Envelope) -> Option<&serde_json::Value> {
+        ["reply_media", "media"].into_iter().find_map(|key| {
+            envelope.payload.get(key).filter(|media| {
+                matches!(
+                    media.get("kind").and_then(|v| v.as_str()),
+                    Some("document") | Some("image_document")
+                )
+            })
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_kb_chat_command(
+        text: &str,
+        envelope: &Envelope,
+        config: &Config,
+        bot_context: &BotContext,
+        account_tag: Option<&str>,
+        from_user_id: i64,
+        permission: PermissionLevel,
+    ) -> String {
+        let rest = text.strip_prefix("/kb").unwrap_or("").trim();
+        let (action, args) = rest
+            .split_once(char::is_whitespace)
+            .map(|(action, args)| (action, args.trim()))
+            .unwrap_or((rest, ""));
+        let is_admin = permission == PermissionLevel::Admin;
+
+        match action.to_ascii_lowercase().as_str() {
+            "" | "help" => {
+                let mut lines = vec![
+                    "📚 *Knowledge Base*".to_string(),
+                    String::new(),
+                    "/kb list [shared|admin] - List indexed documents".to_string(),
+                    "/kb search <query> - Search indexed documents".to_string(),
+                ];
+                if is_admin {
+                    lines.push(
+                        "/kb add [shared|admin] - Ingest the replied document (PDF, MD, TXT, HTML)"
+                            .to_string(),
+                    );
+                    lines.push("/kb remove <id> [shared|admin] - Remove a document".to_string());
+                }
+                lines.join("\n")
+            }
+            "list" | "ls" => {
+                let scopes = if args.is_empty() {
+                    Self::readable_kb_scopes(permission, from_user_id)
+                } else {
+                    match Self::parse_kb_scope(args) {
+                        Some(scope)
+                            if Self::can_access_memory_scope(
+                                permission,
+                                MemoryNamespace::Own,
+                                scope,
+                                from_user_id,
+                                None,
+                            ) =>
+                        {
+                            vec![scope]
+                        }
+                        Some(scope) => {
+                            return format!("Access denied for scope '{}'.", scope.as_str())
+                        }
+                        None => return "Usage: /kb list [shared|admin]".to_string(),
+                    }
+                };
+                let mut lines = vec!["📚 *Knowledge Base*".to_string()];
+                for scope in scopes {
+                    let dir = Self::knowledge_dir(bot_context, account_tag, scope);
+                    let docs = knowledge::list_documents(&dir).unwrap_or_default();
+                    lines.push(String::new());
+                    lines.push(format!("*{}* ({})", scope.as_str(), docs.len()));
+                    for doc in &docs {
+                        lines.push(Self::format_kb_document_line(doc));
+                    }
+                }
+                lines.join("\n")
+            }
+            "search" | "find" => {
+                if args.is_empty() {
+                    return "Usage: /kb search <query>".to_string();
+                }
+                let scopes = Self::readable_kb_scopes(permission, from_user_id);
+                match Self::search_knowledge_scopes(
+                    bot_context,
+                    account_tag,
+                    &scopes,
+                    args,
+                    KB_SEARCH_DEFAULT_LIMIT,
+                )
+                .await
+                {
+                    Ok(hits) if hits.is_empty() => format!("📚 No results for '{}'.", args),
+                    Ok(hits) => {
+                        let mut lines = vec![format!("📚 *Results for* '{}'", args)];
+                        for (scope, hit) in hits {
+                            let snippet: String = hit.text.chars().take(400).collect();
+                            lines.push(String::new());
+                            lines.push(format!(
+                                "`{}` {} [{}] #{}",
+                                hit.doc_id,
+                                hit.title,
+                                scope.as_str(),
+                                hit.chunk_index
+                            ));
+                            lines.push(snippet);
+                        }
+                        lines.join("\n")
+                    }
+                    Err(err) => format!("Knowledge base search failed: {}", err),
+                }
+            }
+            "add" | "ingest" => {
+                if !is_admin {
+                    return "Admin only command.".to_string();
+                }
+                let scope = if args.is_empty() {
+                    MemoryScope::SharedUserKb
+                } else {
+                    match Self::parse_kb_scope(args) {
+                        Some(scope) => scope,
+                        None => return "Usage: /kb add [shared|admin]".to_string(),
+                    }
+                };
+                let Some(media) = Self::kb_media_reference(envelope) else {
+                    return "Reply to a document (PDF, MD, TXT, HTML) with /kb add.".to_string();
+                };
+                let Some(file_id) = media.get("file_id").and_then(|v| v.as_str()) else {
+                    return "Document has no file id.".to_string();
+                };
+                let file_name = media.get("file_name").and_then(|v| v.as_str());
+                let mime_type = media.get("mime_type").and_then(|v| v.as_str());
+                if media
+                    .get("file_size")
+                    .and_then(|v| v.as_u64())
+                    .is_some_and(|size| size as usize > knowledge::MAX_DOCUMENT_BYTES)
+                {
+                    return format!(
+                        "❌ Document too large (max {} MB).",
+                        knowledge::MAX_DOCUMENT_BYTES / (1024 * 1024)
+                    );
+                }
+                let declared_format = KnowledgeFormat::detect(file_name, mime_type);
+                if declared_format.is_none() && file_name.is_some() {
+                    return "❌ Unsupported document type. Supported: PDF, Markdown, text, HTML."
+                        .to_string();
+                }
+
+                let (bytes, detected_mime) =
+                    match Self::fetch_telegram_media_bytes(config, account_tag, file_id).await {
+                        Ok(result) => result,
+                        Err(err) => return format!("Failed to download document: {}", err),
+                    };
+                let Some(format) = declared_format
+                    .or_else(|| KnowledgeFormat::detect(None, detected_mime.as_deref()))
+                else {
+                    return "❌ Unsupported document type. Supported: PDF, Markdown, text, HTML."
+                        .to_string();
+                };
+                let title = file_name.map(str::to_string).unwrap_or_else(|| {
+                    format!("telegram-{}", file_id.chars().take(12).collect::<String>())
+                });
+                let source = format!(
+                    "telegram:{}:{}",
+                    envelope.chat_id.unwrap_or_default(),
+                    media
+                        .get("message_id")
+                        .and_then(|v| v.as_i64())
+                        .or(envelope.message_id)
+                        .unwrap_or_default()
+                );
+                let dir = Self::knowledge_dir(bot_context, account_tag, scope);
+                let task_title = title.clone();
+                let outcome = tokio::task::spawn_blocking(move || {
+                    knowledge::ingest_document(&dir, &task_title, &source, &bytes, format)
+                })
+                .await;
+                match outcome {
+                    Ok(Ok(IngestOutcome::Added(doc))) => format!(
+                        "✅ Added '{}' to {} ({} chunks, id `{}`).",
+                        doc.title,
+                        scope.as_str(),
+                        doc.chunks,
+                        doc.id
+                    ),
+                    Ok(Ok(IngestOutcome::Unchanged(doc))) => format!(
+                        "ℹ️ '{}' is already indexed in {} (id `{}`).",
+                        doc.title,
+                        scope.as_str(),
+                        doc.id
+                    ),
+                    Ok(Err(err)) => format!("❌ Ingestion of '{}' failed: {}", title, err),
+                    Err(err) => format!("❌ Ingestion of '{}' failed: {}", title, err),
+                }
+            }
+            "remove" | "rm" | "delete" => {
+                if !is_admin {
+                    return "Admin only command.".to_string();
+                }
+                let mut parts = args.split_whitespace();
+                let Some(doc_id) = parts.next() else {
+                    return "Usage: /kb remove <id> [shared|admin]".to_string();
+                };
+                let scope = match parts.next() {
+                    Some(raw) => match Self::parse_kb_scope(raw) {
+                        Some(scope) => scope,
+                        None => return "Usage: /kb remove <id> [shared|admin]".to_string(),
+                    },
+                    None => MemoryScope::SharedUserKb,
+                };
+                let dir = Self::knowledge_dir(bot_context, account_tag, scope);
+                let task_doc_id = doc_id.trim_matches('`').to_string();
+                let outcome = tokio::task::spawn_blocking(move || {
+                    knowledge::remove_document(&dir, &task_doc_id)
+                })
+                .await;
+                match outcome {
+                    Ok(Ok(Some(doc))) => {
+                        format!("✅ Removed '{}' from {}.", doc.title, scope.as_str())
+                    }
+                    Ok(Ok(None)) => {
+                        format!("❌ Document `{}` not found in {}.", doc_id, scope.as_str())
+                    }
+                    Ok(Err(err)) => format!("Failed to remove document: {}", err),
+                    Err(err) => format!("Failed to remove document: {}", err),
+                }
+            }
+            _ => "Usage: /kb [list|search <query>|add|remove <id>]".to_string(),
+        }
     }
 
     async fn check_and_update_rate_limit(
@@ -7873,9 +11985,22 @@ impl MasixRuntime {
             edit_message_id: None,
             inline_keyboard: None,
             chat_action: None,
+            photo: None,
+            delivered: None,
+            callback_answer: None,
         });
     }
 
+    /// Static and dynamic-ACL admins of a Telegram account.
+    fn telegram_account_admin_ids(account: &masix_config::TelegramAccount) -> Vec<i64> {
+        let dynamic_acl = Self::load_dynamic_acl_for_account(account);
+        let mut admins: Vec<i64> = account.admins.clone();
+        admins.extend(dynamic_acl.admins.iter().copied());
+        admins.sort_unstable();
+        admins.dedup();
+        admins
+    }
+
     fn notify_admins_new_user_auto_registered(
         config: &Config,
         account_tag: Option<&str>,
@@ -7891,11 +12016,7 @@ impl MasixRuntime {
             return;
         }
 
-        let dynamic_acl = Self::load_dynamic_acl_for_account(account);
-        let mut admins: Vec<i64> = account.admins.clone();
-        admins.extend(dynamic_acl.admins.iter().copied());
-        admins.sort_unstable();
-        admins.dedup();
+        let admins = Self::telegram_account_admin_ids(account);
         if admins.is_empty() {
             return;
         }
@@ -8022,6 +12143,9 @@ impl MasixRuntime {
                     edit_message_id: None,
                     inline_keyboard: None,
                     chat_action: Some("typing".to_string()),
+                    photo: None,
+                    delivered: None,
+                    callback_answer: None,
                 });
                 tokio::time::sleep(tokio::time::Duration::from_secs(4)).await;
             }
@@ -8050,6 +12174,69 @@ impl MasixRuntime {
         }
     }
 
+    /// Default folder scanned by `masix kb ingest` when no path is given.
+    pub fn default_knowledge_root(&self) -> Result<PathBuf> {
+        Ok(self.get_data_dir()?.join("memory").join(KB_DIR_NAME))
+    }
+
+    fn knowledge_dir_for_account(&self, account_tag: Option<&str>, scope: &str) -> Result<PathBuf> {
+        let scope = Self::parse_kb_scope(scope)
+            .ok_or_else(|| anyhow!("Invalid KB scope '{}': use shared_user_kb|admin_kb", scope))?;
+        let data_dir = self.get_data_dir()?;
+        let contexts = self.build_bot_contexts(&data_dir)?;
+        let key = Self::account_scope(account_tag);
+        let context = contexts
+            .get(&key)
+            .ok_or_else(|| anyhow!("Unknown account tag '{}'", key))?;
+        Ok(Self::knowledge_dir(context, account_tag, scope))
+    }
+
+    /// Ingest a file, or every supported file below a directory, into a KB scope.
+    pub fn ingest_knowledge_path(
+        &self,
+        path: &Path,
+        account_tag: Option<&str>,
+        scope: &str,
+    ) -> Result<Vec<(PathBuf, Result<IngestOutcome>)>> {
+        let dir = self.knowledge_dir_for_account(account_tag, scope)?;
+        let mut results = Vec::new();
+        for file in knowledge::collect_ingestable_files(path)? {
+            let outcome = (|| {
+                let file_name = file.file_name().and_then(|name| name.to_str());
+                let format = KnowledgeFormat::detect(file_name, None)
+                    .ok_or_else(|| anyhow!("Unsupported document type"))?;
+                let bytes = std::fs::read(&file)?;
+                let title = file_name.unwrap_or("document").to_string();
+                let source = format!("file:{}", file.display());
+                knowledge::ingest_document(&dir, &title, &source, &bytes, format)
+            })();
+            results.push((file, outcome));
+        }
+        Ok(results)
+    }
+
+    pub fn list_knowledge_documents(
+        &self,
+        account_tag: Option<&str>,
+        scope: &str,
+    ) -> Result<Vec<KnowledgeDocument>> {
+        knowledge::list_documents(&self.knowledge_dir_for_account(account_tag, scope)?)
+    }
+
+    pub fn search_knowledge(
+        &self,
+        account_tag: Option<&str>,
+        scope: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeHit>> {
+        knowledge::search(
+            &self.knowledge_dir_for_account(account_tag, scope)?,
+            query,
+            limit,
+        )
+    }
+
     pub async fn storage(&self) -> tokio::sync::MutexGuard<'_, Storage> {
         self.storage.lock().await
     }
@@ -8149,6 +12336,158 @@ impl MasixRuntime {
         }
     }
 
+    async fn load_persisted_user_preferences(&self) {
+        let rows = match self.storage.lock().await.list_user_preferences() {
+            Ok(rows) => rows,
+            Err(e) => {
+                warn!("Failed to load persisted user preferences: {}", e);
+                return;
+            }
+        };
+        let mut languages = self.user_languages.lock().await;
+        let mut providers = self.user_providers.lock().await;
+        let mut models = self.user_models.lock().await;
+        for row in &rows {
+            if let Some(lang) = row
+                .language
+                .as_deref()
+                .and_then(|code| code.parse::<Language>().ok())
+            {
+                languages.insert(row.state_key.clone(), lang);
+            }
+            if let Some(provider) = &row.provider {
+                providers.insert(row.state_key.clone(), provider.clone());
+            }
+            if let Some(model) = &row.model {
+                models.insert(row.state_key.clone(), model.clone());
+            }
+        }
+        if !rows.is_empty() {
+            info!("Loaded persisted preferences for {} user(s)", rows.len());
+        }
+    }
+
+    async fn persist_user_preferences(
+        storage: &Arc<Mutex<Storage>>,
+        user_state_key: &str,
+        user_languages: &Arc<Mutex<HashMap<String, Language>>>,
+        user_providers: &Arc<Mutex<HashMap<String, String>>>,
+        user_models: &Arc<Mutex<HashMap<String, String>>>,
+    ) {
+        let language = user_languages
+            .lock()
+            .await
+            .get(user_state_key)
+            .map(|lang| lang.to_string());
+        let provider = user_providers.lock().await.get(user_state_key).cloned();
+        let model = user_models.lock().await.get(user_state_key).cloned();
+        if let Err(e) = storage.lock().await.upsert_user_preferences(
+            user_state_key,
+            language.as_deref(),
+            provider.as_deref(),
+            model.as_deref(),
+        ) {
+            warn!(
+                "Failed to persist user preferences for {}: {}",
+                user_state_key, e
+            );
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_admin_prefs_command(
+        text: &str,
+        config: &Config,
+        account_tag: Option<&str>,
+        permission: PermissionLevel,
+        bot_context: &BotContext,
+        storage: &Arc<Mutex<Storage>>,
+        user_languages: &Arc<Mutex<HashMap<String, Language>>>,
+        user_providers: &Arc<Mutex<HashMap<String, String>>>,
+        user_models: &Arc<Mutex<HashMap<String, String>>>,
+    ) -> String {
+        if permission != PermissionLevel::Admin {
+            return "Admin only command.".to_string();
+        }
+        let usage = "Usage: /admin prefs <user_id|@username> [language <code>|provider <name>|model <name|reset>|reset]";
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        let Some(target) = parts.get(2) else {
+            return usage.to_string();
+        };
+        let user_id = match Self::resolve_admin_target_user_id(
+            target,
+            config,
+            account_tag,
+            bot_context,
+        )
+        .await
+        {
+            Ok(id) => id,
+            Err(err) => return err,
+        };
+        let key = Self::user_state_key(account_tag, Some(&user_id.to_string()), None);
+        let value = parts
+            .get(4..)
+            .map(|rest| rest.join(" "))
+            .unwrap_or_default();
+
+        let response = match parts.get(3).map(|v| v.to_ascii_lowercase()).as_deref() {
+            None => {
+                let language = user_languages.lock().await.get(&key).copied();
+                let provider = user_providers.lock().await.get(&key).cloned();
+                let model = user_models.lock().await.get(&key).cloned();
+                return format!(
+                    "⚙️ Preferences for {}\nLanguage: {}\nProvider: {}\nModel: {}",
+                    user_id,
+                    language
+                        .map(|lang| lang.to_string())
+                        .unwrap_or_else(|| "(default)".to_string()),
+                    provider.unwrap_or_else(|| format!(
+                        "(default: {})",
+                        config.providers.default_provider
+                    )),
+                    model.unwrap_or_else(|| "(provider default)".to_string())
+                );
+            }
+            Some("language") | Some("lang") => match value.parse::<Language>() {
+                Ok(lang) => {
+                    user_languages.lock().await.insert(key.clone(), lang);
+                    format!("✅ Language for {} set to '{}'.", user_id, lang)
+                }
+                Err(err) => return format!("❌ {}", err),
+            },
+            Some("provider") => {
+                if !config.providers.providers.iter().any(|p| p.name == value) {
+                    return format!("❌ Provider '{}' not found.", value);
+                }
+                user_providers
+                    .lock()
+                    .await
+                    .insert(key.clone(), value.clone());
+                format!("✅ Provider for {} set to '{}'.", user_id, value)
+            }
+            Some("model") if !value.is_empty() => {
+                if value.eq_ignore_ascii_case("reset") {
+                    user_models.lock().await.remove(&key);
+                    format!("✅ Model for {} reset to default.", user_id)
+                } else {
+                    user_models.lock().await.insert(key.clone(), value.clone());
+                    format!("✅ Model for {} set to '{}'.", user_id, value)
+                }
+            }
+            Some("reset") | Some("clear") => {
+                user_languages.lock().await.remove(&key);
+                user_providers.lock().await.remove(&key);
+                user_models.lock().await.remove(&key);
+                format!("✅ Preferences for {} reset to defaults.", user_id)
+            }
+            _ => return usage.to_string(),
+        };
+        Self::persist_user_preferences(storage, &key, user_languages, user_providers, user_models)
+            .await;
+        response
+    }
+
     async fn handle_model_chat_command(
         text: &str,
         user_state_key: &str,
@@ -8825,12 +13164,20 @@ impl MasixRuntime {
         format!("{}***{}", &trimmed[..4], &trimmed[trimmed.len() - 4..])
     }
 
-    async fn handle_mcp_chat_command(text: &str, config: &Config) -> String {
+    async fn handle_mcp_chat_command(
+        text: &str,
+        config: &Config,
+        mcp_client: &Option<Arc<Mutex<McpClient>>>,
+    ) -> String {
         let rest = text.strip_prefix("/mcp").unwrap_or("").trim();
 
         let mcp = config.mcp.as_ref();
         let is_enabled = mcp.map(|m| m.enabled).unwrap_or(false);
         let servers = mcp.map(|m| m.servers.as_slice()).unwrap_or(&[]);
+        let statuses = match mcp_client {
+            Some(client) => client.lock().await.server_statuses().await,
+            None => Vec::new(),
+        };
 
         if rest.is_empty() || rest.eq_ignore_ascii_case("help") {
             let mut lines = vec![
@@ -8844,7 +13191,18 @@ impl MasixRuntime {
                 lines.push(String::new());
                 lines.push("Configured servers:".to_string());
                 for s in servers {
-                    lines.push(format!("  • {} ({} {:?})", s.name, s.command, s.args));
+                    lines.push(format!("  • {} ({})", s.name, s.endpoint_label()));
+                }
+            }
+
+            if is_enabled && !servers.is_empty() {
+                lines.push(String::new());
+                lines.push("Runtime:".to_string());
+                for s in servers {
+                    match statuses.iter().find(|status| status.name == s.name) {
+                        Some(status) => lines.extend(Self::format_mcp_server_status(status, 3)),
+                        None => lines.push(format!("⚪ {} — not started", s.name)),
+                    }
                 }
             }
 
@@ -8853,6 +13211,8 @@ impl MasixRuntime {
             lines.push("  masix config mcp list".to_string());
             lines.push("  masix config mcp add <name> <cmd> [args]".to_string());
             lines.push("  masix config mcp remove <name>".to_string());
+            lines.push(String::new());
+            lines.push("Chat: /mcp list, /mcp templates, /prompts, /resources".to_string());
 
             lines.join("\n")
         } else if rest.eq_ignore_ascii_case("list") {
@@ -8864,7 +13224,25 @@ impl MasixRuntime {
                 let mut lines = vec!["📋 *MCP Servers*".to_string(), String::new()];
                 for s in servers {
                     lines.push(format!("• {}", s.name));
-                    lines.push(format!("  Command: {} {:?}", s.command, s.args));
+                    lines.push(format!("  Endpoint: {}", s.endpoint_label()));
+                }
+                lines.join("\n")
+            }
+        } else if rest.eq_ignore_ascii_case("templates") {
+            let templates = match mcp_client {
+                Some(client) => client.lock().await.list_all_resource_templates().await,
+                None => Vec::new(),
+            };
+            if templates.is_empty() {
+                "📋 No MCP resource templates.".to_string()
+            } else {
+                let mut lines = vec!["📋 *MCP Resource Templates*".to_string(), String::new()];
+                for (server, template) in templates {
+                    lines.push(format!("• {}: {}", server, template.name));
+                    lines.push(format!("  URI: {}", template.uri_template));
+                    if let Some(description) = template.description {
+                        lines.push(format!("  {}", description));
+                    }
                 }
                 lines.join("\n")
             }
@@ -8873,30 +13251,286 @@ impl MasixRuntime {
         }
     }
 
-    async fn handle_tools_chat_command(mcp_client: &Option<Arc<Mutex<McpClient>>>) -> String {
-        let tools = Self::get_mcp_tools(mcp_client).await;
-        if tools.is_empty() {
-            return "⚠️ Nessun tool esposto in runtime.".to_string();
+    fn parse_prompt_arguments(raw: &str) -> Result<HashMap<String, String>, String> {
+        let mut arguments = HashMap::new();
+        for token in raw.split_whitespace() {
+            let Some((key, value)) = token.split_once('=') else {
+                return Err(format!("Argomento non valido '{}': usa key=value.", token));
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(format!("Argomento non valido '{}': chiave vuota.", token));
+            }
+            arguments.insert(key.to_string(), value.to_string());
         }
+        Ok(arguments)
+    }
 
-        let mut names: Vec<String> = tools.iter().map(|t| t.function.name.clone()).collect();
-        names.sort();
-        names.dedup();
+    /// Flatten prompt messages into a single user turn; non-user roles are labelled.
+    fn render_prompt_messages(result: &PromptResult) -> String {
+        result
+            .messages
+            .iter()
+            .filter_map(|message| {
+                let text = message.text()?.trim();
+                if text.is_empty() {
+                    None
+                } else if message.role == "user" {
+                    Some(text.to_string())
+                } else {
+                    Some(format!("[{}] {}", message.role, text))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    async fn handle_prompt_command(
+        text: &str,
+        mcp_client: &Option<Arc<Mutex<McpClient>>>,
+        runtime_tool_access: &RuntimeToolAccess,
+        admin_only_modules: &HashSet<String>,
+        permission: PermissionLevel,
+    ) -> Option<PromptCommandOutcome> {
+        let list_only = text == "/prompts";
+        if !list_only && text != "/prompt" && !text.starts_with("/prompt ") {
+            return None;
+        }
+        if !runtime_tool_access.is_enabled() {
+            return Some(PromptCommandOutcome::Reply(
+                "⚠️ Prompt MCP non disponibili per questo utente.".to_string(),
+            ));
+        }
+        let Some(client) = mcp_client else {
+            return Some(PromptCommandOutcome::Reply(
+                "❌ MCP is disabled.".to_string(),
+            ));
+        };
+        let visible: Vec<_> = client
+            .lock()
+            .await
+            .list_all_prompts()
+            .await
+            .into_iter()
+            .filter(|(server, prompt)| {
+                permission == PermissionLevel::Admin
+                    || !is_admin_only_tool(
+                        &format!("{}_{}", server, prompt.name),
+                        admin_only_modules,
+                    )
+            })
+            .collect();
+
+        let rest = text.strip_prefix("/prompt").unwrap_or("").trim();
+        if list_only || rest.is_empty() {
+            if visible.is_empty() {
+                return Some(PromptCommandOutcome::Reply(
+                    "📋 Nessun prompt MCP disponibile.".to_string(),
+                ));
+            }
+            let mut lines = vec!["📋 *MCP Prompts*".to_string(), String::new()];
+            for (server, prompt) in &visible {
+                let arguments = prompt
+                    .arguments
+                    .iter()
+                    .map(|arg| {
+                        if arg.required {
+                            format!("{}=…", arg.name)
+                        } else {
+                            format!("[{}=…]", arg.name)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                lines.push(format!(
+                    "• {} {} {}",
+                    server,
+                    prompt.name,
+                    arguments.join(" ")
+                ));
+                if let Some(description) = prompt.description.as_deref() {
+                    lines.push(format!("  {}", description));
+                }
+            }
+            lines.push(String::new());
+            lines.push("Usage: /prompt <server> <name> [key=value ...]".to_string());
+            return Some(PromptCommandOutcome::Reply(lines.join("\n")));
+        }
+
+        let mut parts = rest.splitn(3, char::is_whitespace);
+        let server_name = parts.next().unwrap_or("");
+        let Some(prompt_name) = parts.next().filter(|name| !name.is_empty()) else {
+            return Some(PromptCommandOutcome::Reply(
+                "Usage: /prompt <server> <name> [key=value ...]".to_string(),
+            ));
+        };
+        let arguments = match Self::parse_prompt_arguments(parts.next().unwrap_or("")) {
+            Ok(arguments) => arguments,
+            Err(e) => return Some(PromptCommandOutcome::Reply(e)),
+        };
+        let Some((_, prompt)) = visible
+            .iter()
+            .find(|(server, prompt)| server == server_name && prompt.name == prompt_name)
+        else {
+            return Some(PromptCommandOutcome::Reply(format!(
+                "❌ Prompt '{}' non trovato su '{}'. Usa /prompts.",
+                prompt_name, server_name
+            )));
+        };
+        let missing: Vec<&str> = prompt
+            .arguments
+            .iter()
+            .filter(|arg| arg.required && !arguments.contains_key(&arg.name))
+            .map(|arg| arg.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Some(PromptCommandOutcome::Reply(format!(
+                "❌ Argomenti mancanti: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let server = client.lock().await.server(server_name);
+        let result = match server {
+            Some(server) => server.get_prompt(prompt_name, &arguments).await,
+            None => Err(anyhow!("Server not found: {}", server_name)),
+        };
+        match result {
+            Ok(result) => {
+                let expanded = Self::render_prompt_messages(&result);
+                if expanded.is_empty() {
+                    Some(PromptCommandOutcome::Reply(format!(
+                        "⚠️ Il prompt '{}' non contiene testo.",
+                        prompt_name
+                    )))
+                } else {
+                    info!("Expanded MCP prompt {}/{}", server_name, prompt_name);
+                    Some(PromptCommandOutcome::Expand(expanded))
+                }
+            }
+            Err(e) => Some(PromptCommandOutcome::Reply(format!(
+                "❌ Prompt '{}' failed: {}",
+                prompt_name, e
+            ))),
+        }
+    }
+
+    async fn handle_resources_command(
+        text: &str,
+        mcp_client: &Option<Arc<Mutex<McpClient>>>,
+        runtime_tool_access: &RuntimeToolAccess,
+        admin_only_modules: &HashSet<String>,
+        permission: PermissionLevel,
+    ) -> Option<String> {
+        if text != "/resources" && !text.starts_with("/resources ") {
+            return None;
+        }
+        if !runtime_tool_access.is_enabled() {
+            return Some("⚠️ Risorse MCP non disponibili per questo utente.".to_string());
+        }
+        let Some(client) = mcp_client else {
+            return Some("❌ MCP is disabled.".to_string());
+        };
+        let rest = text.strip_prefix("/resources").unwrap_or("").trim();
+
+        if rest == "read" || rest.starts_with("read ") {
+            let mut parts = rest["read".len()..].split_whitespace();
+            let (Some(server), Some(uri)) = (parts.next(), parts.next()) else {
+                return Some("Usage: /resources read <server> <uri>".to_string());
+            };
+            if permission != PermissionLevel::Admin
+                && is_admin_only_server(server, admin_only_modules)
+            {
+                return Some("Admin only command.".to_string());
+            }
+            let mcp_server = client.lock().await.server(server);
+            let contents = match mcp_server {
+                Some(mcp_server) => mcp_server.read_resource_contents(uri).await,
+                None => Err(anyhow!("Server not found: {}", server)),
+            };
+            return Some(match contents {
+                Ok(contents) => Self::render_mcp_resource_contents(&contents, None)
+                    .await
+                    .unwrap_or_else(|e| format!("❌ {}", e)),
+                Err(e) => format!("❌ Resource read failed: {}", e),
+            });
+        }
+
+        let server_filter = (!rest.is_empty()).then_some(rest);
+        let resources: Vec<_> = client
+            .lock()
+            .await
+            .list_all_resources()
+            .await
+            .into_iter()
+            .filter(|(server, _)| server_filter.is_none_or(|name| name == server))
+            .filter(|(server, _)| {
+                permission == PermissionLevel::Admin
+                    || !is_admin_only_server(server, admin_only_modules)
+            })
+            .collect();
+        if resources.is_empty() {
+            return Some("📋 Nessuna risorsa MCP disponibile.".to_string());
+        }
+        let mut lines = vec!["📋 *MCP Resources*".to_string(), String::new()];
+        for (server, resource) in resources.iter().take(MCP_RESOURCE_LIST_LIMIT) {
+            lines.push(format!("• {}: {}", server, resource.name));
+            lines.push(format!("  {}", resource.uri));
+        }
+        if resources.len() > MCP_RESOURCE_LIST_LIMIT {
+            lines.push(format!(
+                "… {} more",
+                resources.len() - MCP_RESOURCE_LIST_LIMIT
+            ));
+        }
+        lines.push(String::new());
+        lines.push("Usage: /resources [server] | /resources read <server> <uri>".to_string());
+        Some(lines.join("\n"))
+    }
+
+    async fn handle_tools_chat_command(
+        tool_registry: &ToolRegistry,
+        tool_validation: &ToolValidationMetrics,
+    ) -> String {
+        let catalog = tool_registry.catalog().await;
+        if catalog.tools().is_empty() {
+            return "⚠️ Nessun tool esposto in runtime.".to_string();
+        }
 
-        let builtins = names.iter().filter(|name| is_builtin_tool(name)).count();
-        let mcp_count = names.len().saturating_sub(builtins);
+        let mut tools: Vec<&RegisteredTool> = catalog.tools().iter().collect();
+        tools.sort_by(|a, b| a.name().cmp(b.name()));
+        let count = |source: ToolSource| tools.iter().filter(|t| t.source == source).count();
+        let plugins = count(ToolSource::Plugin);
 
         let mut lines = vec![
             "🧰 *Runtime Tools*".to_string(),
             String::new(),
-            format!("Totale: {}", names.len()),
-            format!("Built-in: {}", builtins),
-            format!("MCP: {}", mcp_count),
-            String::new(),
-            "Tool names:".to_string(),
+            format!("Totale: {}", tools.len()),
+            format!("Built-in: {}", count(ToolSource::Builtin)),
+            format!("MCP: {}", count(ToolSource::Mcp)),
         ];
-        for name in names {
-            lines.push(format!("• {}", name));
+        if plugins > 0 {
+            lines.push(format!("Plugin: {}", plugins));
+        }
+        lines.push(String::new());
+        lines.push("Tool names:".to_string());
+        for tool in tools {
+            let mut tags = vec![format!("costo {}", tool.metadata.cost.as_str())];
+            if tool.metadata.side_effecting {
+                tags.push("effetti esterni".to_string());
+            }
+            if tool.metadata.admin_only {
+                tags.push("solo admin".to_string());
+            }
+            lines.push(format!("• {} ({})", tool.name(), tags.join(", ")));
+        }
+
+        let failures = tool_validation.snapshot();
+        if !failures.is_empty() {
+            lines.push(String::new());
+            lines.push("Argomenti rifiutati (schema):".to_string());
+            for (name, count) in failures {
+                lines.push(format!("• {}: {}", name, count));
+            }
         }
         lines.join("\n")
     }
@@ -8906,15 +13540,15 @@ impl MasixRuntime {
         account_tag: Option<&str>,
         permission: PermissionLevel,
         channel: &str,
-        mcp_client: &Option<Arc<Mutex<McpClient>>>,
-        admin_only_modules: &HashSet<String>,
+        tool_registry: &ToolRegistry,
     ) -> String {
         let runtime_access =
             Self::runtime_tool_access_for_message(config, account_tag, channel, permission);
         let tools = if runtime_access.is_enabled() {
-            let all = Self::get_mcp_tools(mcp_client).await;
-            let filtered = runtime_access.filter_tools(all);
-            Self::filter_visible_tools_by_permission(filtered, admin_only_modules, permission)
+            tool_registry
+                .catalog()
+                .await
+                .visible_definitions(permission, &runtime_access)
         } else {
             Vec::new()
         };
@@ -8948,11 +13582,13 @@ impl MasixRuntime {
 
         lines.push(String::new());
         lines.push(
-            "commands: /help /whoiam /provider /model /cron /termux /capabilities".to_string(),
+            "commands: /help /stop /whoiam /session /facts /kb /prompts /resources /provider /model /cron /termux /capabilities"
+                .to_string(),
         );
         if permission == PermissionLevel::Admin {
-            lines
-                .push("admin_commands: /admin /groups /send /plugin /mcp /tools /exec".to_string());
+            lines.push(
+                "admin_commands: /admin /groups /send /plugin /mcp /tools /exec /jobs".to_string(),
+            );
         }
 
         lines.join("\n")
@@ -8998,20 +13634,6 @@ impl MasixRuntime {
             || text.starts_with("contacts ")
     }
 
-    fn filter_visible_tools_by_permission(
-        tools: Vec<ToolDefinition>,
-        admin_only_modules: &HashSet<String>,
-        permission: PermissionLevel,
-    ) -> Vec<ToolDefinition> {
-        if permission == PermissionLevel::Admin {
-            return tools;
-        }
-        tools
-            .into_iter()
-            .filter(|tool| !is_admin_only_tool(&tool.function.name, admin_only_modules))
-            .collect()
-    }
-
     fn should_stream_telegram_response(
         config: &Config,
         account_tag: Option<&str>,
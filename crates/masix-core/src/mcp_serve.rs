@@ -0,0 +1,204 @@
+//! `masix mcp serve`
+//!
+//! Exposes a configured subset of the builtin tools (cron, memory, Telegram
+//! send, chat context) to other local agents over MCP stdio. Every call runs
+//! with the identity and permission level set in `[mcp.serve]` and is audited
+//! under that identity.
+
+use crate::builtin_tools::get_builtin_tool_definitions;
+use crate::tool_validation::{self, ToolValidationMetrics};
+use crate::{BotContext, MasixRuntime};
+use anyhow::{anyhow, Result};
+use masix_config::{Config, McpServeConfig, PermissionLevel};
+use masix_ipc::{Envelope, MessageKind, OutboundMessage};
+use masix_mcp::server::ToolHandler;
+use masix_mcp::{Tool, ToolContent, ToolResult};
+use masix_storage::Storage;
+use masix_telegram::TelegramAdapter;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn};
+
+pub(crate) struct McpServeHandler {
+    pub(crate) config: Config,
+    pub(crate) storage: Arc<Mutex<Storage>>,
+    pub(crate) bot_context: BotContext,
+    pub(crate) account_tag: Option<String>,
+    pub(crate) user_id: i64,
+    pub(crate) envelope: Envelope,
+    pub(crate) permission: PermissionLevel,
+    pub(crate) tools: Vec<Tool>,
+    /// Delivers `telegram_send` directly; the daemon's adapter is not reachable from here.
+    pub(crate) telegram: Option<TelegramAdapter>,
+    pub(crate) tool_validation: ToolValidationMetrics,
+}
+
+impl McpServeHandler {
+    /// Builtin definitions for the tools listed in `serve.tools`, in MCP form.
+    pub(crate) fn served_tools(serve: &McpServeConfig) -> Vec<Tool> {
+        get_builtin_tool_definitions()
+            .into_iter()
+            .filter(|tool| serve.tools.contains(&tool.function.name))
+            .map(|tool| Tool {
+                name: tool.function.name,
+                description: tool.function.description,
+                input_schema: tool.function.parameters,
+            })
+            .collect()
+    }
+
+    /// Context the tools see for a call: a Telegram message from the configured identity.
+    pub(crate) fn identity_envelope(
+        account_tag: Option<&str>,
+        chat_id: Option<i64>,
+        user_id: i64,
+    ) -> Envelope {
+        let envelope = Envelope::new(
+            "telegram",
+            MessageKind::Message {
+                from: format!("mcp:{}", user_id),
+                text: String::new(),
+            },
+        )
+        .with_payload(serde_json::json!({
+            "account_tag": account_tag,
+            "from_user_id": user_id,
+            "source": "mcp_serve",
+        }));
+        match chat_id {
+            Some(chat_id) => envelope.with_chat_id(chat_id),
+            None => envelope,
+        }
+    }
+
+    async fn dispatch(&self, name: &str, arguments: serde_json::Value) -> Result<String> {
+        let account_tag = self.account_tag.as_deref();
+        match name {
+            "cron" => {
+                MasixRuntime::execute_cron_tool(
+                    arguments,
+                    &self.storage,
+                    &self.envelope,
+                    account_tag,
+                    self.permission,
+                )
+                .await
+            }
+            "memory_read" => {
+                MasixRuntime::execute_memory_read_tool(
+                    arguments,
+                    &self.bot_context,
+                    account_tag,
+                    self.user_id,
+                    self.permission,
+                )
+                .await
+            }
+            "memory_write" => {
+                MasixRuntime::execute_memory_write_tool(
+                    arguments,
+                    &self.bot_context,
+                    account_tag,
+                    self.user_id,
+                    self.permission,
+                    MasixRuntime::self_memory_edit_allowed(&self.config, account_tag),
+                )
+                .await
+            }
+            "chat_context" => {
+                MasixRuntime::execute_chat_context_tool(
+                    arguments,
+                    &self.envelope,
+                    &self.config,
+                    account_tag,
+                    &self.bot_context,
+                )
+                .await
+            }
+            "telegram_send" => self.telegram_send(arguments).await,
+            other => Err(anyhow!("Tool '{}' is not served", other)),
+        }
+    }
+
+    /// Audit a call under the served identity, like chat tool calls.
+    async fn audit(&self, name: &str, arguments: serde_json::Value, outcome: &str, result: &str) {
+        MasixRuntime::append_tool_call_audit(
+            &self.storage,
+            &self.envelope,
+            self.account_tag.as_deref(),
+            name,
+            outcome,
+            MasixRuntime::tool_call_audit_details(&self.envelope, arguments, result),
+        )
+        .await;
+    }
+
+    async fn telegram_send(&self, arguments: serde_json::Value) -> Result<String> {
+        let (sender, mut receiver) = broadcast::channel::<OutboundMessage>(4);
+        let reply = MasixRuntime::execute_telegram_send_tool(
+            arguments,
+            Some(&sender),
+            &self.envelope,
+            self.account_tag.as_deref(),
+            self.permission,
+        )
+        .await?;
+        let Ok(message) = receiver.try_recv() else {
+            // Rejected before sending (permission, arguments): the reply says why.
+            return Ok(reply);
+        };
+        let adapter = self
+            .telegram
+            .as_ref()
+            .ok_or_else(|| anyhow!("telegram_send unavailable: no Telegram account configured"))?;
+        adapter
+            .send_message(message.chat_id, &message.text, None, None)
+            .await?;
+        Ok(reply)
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for McpServeHandler {
+    async fn list_tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    async fn call_tool(&self, name: &str, arguments: serde_json::Value) -> Result<ToolResult> {
+        let schema = self
+            .tools
+            .iter()
+            .find(|tool| tool.name == name)
+            .map(|tool| &tool.input_schema);
+        if let Some(schema) = schema {
+            if let Err(reason) = tool_validation::validate_arguments(schema, &arguments) {
+                let failures = self.tool_validation.record_failure(name);
+                warn!(
+                    "MCP serve: rejected call to '{}' (validation failures: {}): {}",
+                    name, failures, reason
+                );
+                let text = tool_validation::format_rejection(name, &reason, Some(schema));
+                self.audit(name, arguments, "invalid_arguments", &text)
+                    .await;
+                return Ok(ToolResult {
+                    content: vec![ToolContent::Text { text }],
+                    is_error: true,
+                });
+            }
+        }
+
+        info!("MCP serve: calling '{}'", name);
+        let text = match self.dispatch(name, arguments.clone()).await {
+            Ok(text) => text,
+            Err(e) => {
+                self.audit(name, arguments, "error", &e.to_string()).await;
+                return Err(e);
+            }
+        };
+        self.audit(name, arguments, "ok", &text).await;
+        Ok(ToolResult {
+            content: vec![ToolContent::Text { text }],
+            is_error: false,
+        })
+    }
+}
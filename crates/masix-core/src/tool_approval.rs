@@ -0,0 +1,518 @@
+//! Human-in-the-loop approval for tool calls
+//!
+//! Tools listed in `[core.tool_approval].requires_confirmation` are held
+//! before dispatch: the account admins receive a card with the exact
+//! arguments and two inline buttons, and the turn waits for the callback
+//! (`approve:<id>:y|n`), the timeout or `/stop`. Calls whose arguments do
+//! not fit on a card are refused rather than shown in part. Card and replies
+//! use the recipient's menu language.
+
+use masix_ipc::InlineButton;
+use masix_telegram::menu::Language;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+
+pub(crate) const APPROVAL_CALLBACK_PREFIX: &str = masix_ipc::TOOL_APPROVAL_CALLBACK_PREFIX;
+/// Arguments an admin can review on one card (Telegram caps messages at 4096 chars).
+const MAX_CARD_ARGUMENT_CHARS: usize = 3000;
+
+/// Admin click on an approval card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ApprovalDecision {
+    pub approved: bool,
+    pub approver: i64,
+}
+
+/// How a held tool call ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ApprovalOutcome {
+    Approved {
+        approver: i64,
+    },
+    Denied {
+        approver: i64,
+    },
+    TimedOut,
+    Stopped,
+    /// No admin could be asked (non-Telegram channel or no admins configured).
+    Unavailable,
+    /// The arguments do not fit on a card, so no admin could see them in full.
+    TooLarge,
+}
+
+impl ApprovalOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Approved { .. } => "approved",
+            Self::Denied { .. } => "denied",
+            Self::TimedOut => "timed_out",
+            Self::Stopped => "stopped",
+            Self::Unavailable => "unavailable",
+            Self::TooLarge => "too_large",
+        }
+    }
+
+    pub fn approver(&self) -> Option<i64> {
+        match self {
+            Self::Approved { approver } | Self::Denied { approver } => Some(*approver),
+            _ => None,
+        }
+    }
+
+    /// Result handed back to the model in place of the tool output.
+    pub fn tool_message(&self, tool_name: &str) -> String {
+        match self {
+            Self::Approved { .. } => format!("Call to '{}' approved by an admin.", tool_name),
+            Self::Denied { .. } => format!(
+                "Call to '{}' was rejected by an admin. Do not retry it in this turn.",
+                tool_name
+            ),
+            Self::TimedOut => format!(
+                "Call to '{}' was not approved before the timeout. Do not retry it in this turn.",
+                tool_name
+            ),
+            Self::Stopped => format!("Call to '{}' cancelled by the user.", tool_name),
+            Self::Unavailable => format!(
+                "Call to '{}' requires admin approval, but no admin can be reached on this channel.",
+                tool_name
+            ),
+            Self::TooLarge => format!(
+                "Call to '{}' was refused: its arguments are too long to show an admin for approval (max {} chars). Do not retry it in this turn.",
+                tool_name, MAX_CARD_ARGUMENT_CHARS
+            ),
+        }
+    }
+
+    /// Final reply of a turn aborted by this outcome.
+    pub fn user_message(&self, tool_name: &str, lang: Language) -> String {
+        let template = match self {
+            Self::Approved { .. } | Self::Stopped => return String::new(),
+            Self::Denied { .. } => localized(
+                lang,
+                [
+                    "❌ Operation cancelled: `{tool}` was not approved by an admin.",
+                    "❌ Operación cancelada: un admin no aprobó `{tool}`.",
+                    "❌ 操作已取消：管理员未批准 `{tool}`。",
+                    "❌ Операция отменена: админ не одобрил `{tool}`.",
+                    "❌ Operazione annullata: `{tool}` non approvato da un admin.",
+                ],
+            ),
+            Self::TimedOut => localized(
+                lang,
+                [
+                    "⌛ Operation cancelled: no approval for `{tool}` in time.",
+                    "⌛ Operación cancelada: `{tool}` no se aprobó a tiempo.",
+                    "⌛ 操作已取消：`{tool}` 未在时限内获得批准。",
+                    "⌛ Операция отменена: `{tool}` не одобрен вовремя.",
+                    "⌛ Operazione annullata: nessuna approvazione per `{tool}` entro il tempo limite.",
+                ],
+            ),
+            Self::Unavailable => localized(
+                lang,
+                [
+                    "🔐 `{tool}` needs admin approval, but no admin can be reached.",
+                    "🔐 `{tool}` requiere la aprobación de un admin, pero no hay ninguno disponible.",
+                    "🔐 `{tool}` 需要管理员批准，但无法联系到管理员。",
+                    "🔐 `{tool}` требует одобрения админа, но ни один админ недоступен.",
+                    "🔐 `{tool}` richiede l'approvazione di un admin, ma nessun admin è raggiungibile.",
+                ],
+            ),
+            Self::TooLarge => localized(
+                lang,
+                [
+                    "🔐 `{tool}` refused: its arguments are too long to show an admin for approval.",
+                    "🔐 `{tool}` rechazado: sus argumentos son demasiado largos para mostrarlos a un admin.",
+                    "🔐 `{tool}` 已拒绝：参数过长，无法完整展示给管理员审批。",
+                    "🔐 `{tool}` отклонён: аргументы слишком длинные, чтобы показать их админу.",
+                    "🔐 `{tool}` rifiutato: argomenti troppo lunghi per mostrarli a un admin.",
+                ],
+            ),
+        };
+        template.replace("{tool}", tool_name)
+    }
+}
+
+/// Pick the `[en, es, zh, ru, it]` variant for `lang`.
+fn localized(lang: Language, texts: [&'static str; 5]) -> &'static str {
+    let [en, es, zh, ru, it] = texts;
+    match lang {
+        Language::English => en,
+        Language::Spanish => es,
+        Language::Chinese => zh,
+        Language::Russian => ru,
+        Language::Italian => it,
+    }
+}
+
+/// Notice to the requesting chat while the call waits for an admin.
+pub(crate) fn pending_notice(tool_name: &str, timeout_secs: u64, lang: Language) -> String {
+    localized(
+        lang,
+        [
+            "⏳ `{tool}` needs admin approval (expires in {secs}s).",
+            "⏳ `{tool}` requiere la aprobación de un admin (caduca en {secs}s).",
+            "⏳ `{tool}` 需要管理员批准（{secs} 秒后过期）。",
+            "⏳ `{tool}` требует одобрения админа (истекает через {secs} с).",
+            "⏳ `{tool}` richiede l'approvazione di un admin (scade tra {secs}s).",
+        ],
+    )
+    .replace("{tool}", tool_name)
+    .replace("{secs}", &timeout_secs.to_string())
+}
+
+/// Card text after an admin click; `tool_name` is `None` when the request was
+/// unknown, expired or already handled.
+pub(crate) fn decision_text(
+    tool_name: Option<&str>,
+    approved: bool,
+    approver: i64,
+    lang: Language,
+) -> String {
+    let template = match tool_name {
+        Some(_) if approved => localized(
+            lang,
+            [
+                "✅ `{tool}` approved by {admin}.",
+                "✅ `{tool}` aprobado por {admin}.",
+                "✅ `{tool}` 已由 {admin} 批准。",
+                "✅ `{tool}` одобрен {admin}.",
+                "✅ `{tool}` approvato da {admin}.",
+            ],
+        ),
+        Some(_) => localized(
+            lang,
+            [
+                "❌ `{tool}` rejected by {admin}.",
+                "❌ `{tool}` rechazado por {admin}.",
+                "❌ `{tool}` 已被 {admin} 拒绝。",
+                "❌ `{tool}` отклонён {admin}.",
+                "❌ `{tool}` rifiutato da {admin}.",
+            ],
+        ),
+        None => localized(
+            lang,
+            [
+                "⌛ Request expired or already handled.",
+                "⌛ Solicitud caducada o ya gestionada.",
+                "⌛ 请求已过期或已处理。",
+                "⌛ Запрос истёк или уже обработан.",
+                "⌛ Richiesta scaduta o già gestita.",
+            ],
+        ),
+    };
+    template
+        .replace("{tool}", tool_name.unwrap_or_default())
+        .replace("{admin}", &approver.to_string())
+}
+
+/// Toast for a click by someone who may not approve tool calls.
+pub(crate) fn admin_only_notice(lang: Language) -> &'static str {
+    localized(
+        lang,
+        [
+            "🔐 Only admins can approve tool calls.",
+            "🔐 Solo los admins pueden aprobar llamadas a herramientas.",
+            "🔐 只有管理员可以批准工具调用。",
+            "🔐 Одобрять вызовы инструментов могут только админы.",
+            "🔐 Solo gli admin possono approvare le chiamate ai tool.",
+        ],
+    )
+}
+
+struct PendingApproval {
+    account_tag: Option<String>,
+    tool_name: String,
+    respond: oneshot::Sender<ApprovalDecision>,
+}
+
+/// Tool calls waiting for an admin decision, shared by all inbound workers.
+pub(crate) struct ToolApprovalGate {
+    pending: Mutex<HashMap<String, PendingApproval>>,
+    /// Distinguishes ids across restarts so a stale card cannot hit a new request.
+    epoch: u64,
+    next_id: AtomicU64,
+}
+
+impl Default for ToolApprovalGate {
+    fn default() -> Self {
+        let epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() & 0xffff_ffff)
+            .unwrap_or_default();
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            epoch,
+            next_id: AtomicU64::new(1),
+        }
+    }
+}
+
+impl ToolApprovalGate {
+    /// Hold a call and return its approval id with the receiver for the decision.
+    pub fn register(
+        &self,
+        account_tag: Option<&str>,
+        tool_name: &str,
+    ) -> (String, oneshot::Receiver<ApprovalDecision>) {
+        let id = format!(
+            "{:x}{:x}",
+            self.epoch,
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+        let (respond, receiver) = oneshot::channel();
+        self.lock().insert(
+            id.clone(),
+            PendingApproval {
+                account_tag: account_tag.map(|tag| tag.to_string()),
+                tool_name: tool_name.to_string(),
+                respond,
+            },
+        );
+        (id, receiver)
+    }
+
+    /// Drop a request that timed out or was stopped.
+    pub fn cancel(&self, id: &str) {
+        self.lock().remove(id);
+    }
+
+    /// Deliver an admin decision. Returns the tool name, or `None` when the
+    /// request is unknown, already handled or belongs to another bot account.
+    pub fn resolve(
+        &self,
+        id: &str,
+        account_tag: Option<&str>,
+        decision: ApprovalDecision,
+    ) -> Option<String> {
+        let mut pending = self.lock();
+        if pending.get(id)?.account_tag.as_deref() != account_tag {
+            return None;
+        }
+        let request = pending.remove(id)?;
+        request.respond.send(decision).ok()?;
+        Some(request.tool_name)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, PendingApproval>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Split `approve:<id>:y|n` into the approval id and the decision.
+pub(crate) fn parse_callback(data: &str) -> Option<(&str, bool)> {
+    let (id, answer) = data
+        .strip_prefix(APPROVAL_CALLBACK_PREFIX)?
+        .rsplit_once(':')?;
+    if id.is_empty() {
+        return None;
+    }
+    match answer {
+        "y" => Some((id, true)),
+        "n" => Some((id, false)),
+        _ => None,
+    }
+}
+
+/// Arguments as shown on the card, or `None` when they do not fit: an admin
+/// must see everything they approve.
+pub(crate) fn card_arguments(arguments: &serde_json::Value) -> Option<String> {
+    let rendered =
+        serde_json::to_string_pretty(arguments).unwrap_or_else(|_| arguments.to_string());
+    (rendered.chars().count() <= MAX_CARD_ARGUMENT_CHARS).then_some(rendered)
+}
+
+/// Card text and keyboard sent to an admin for a held call; `arguments` comes
+/// from [`card_arguments`].
+pub(crate) fn approval_card(
+    id: &str,
+    tool_name: &str,
+    arguments: &str,
+    requester: &str,
+    chat_id: Option<i64>,
+    timeout_secs: u64,
+    lang: Language,
+) -> (String, Vec<Vec<InlineButton>>) {
+    let origin = match chat_id {
+        Some(chat_id) => format!("{} (chat {})", requester, chat_id),
+        None => requester.to_string(),
+    };
+    let [title, requested_by, arguments_label, expires, approve, reject] = match lang {
+        Language::English => [
+            "🔐 Approval required",
+            "Requested by",
+            "Arguments",
+            "Expires in {secs}s.",
+            "✅ Approve",
+            "❌ Reject",
+        ],
+        Language::Spanish => [
+            "🔐 Aprobación requerida",
+            "Solicitado por",
+            "Argumentos",
+            "Caduca en {secs}s.",
+            "✅ Aprobar",
+            "❌ Rechazar",
+        ],
+        Language::Chinese => [
+            "🔐 需要批准",
+            "请求者",
+            "参数",
+            "{secs} 秒后过期。",
+            "✅ 批准",
+            "❌ 拒绝",
+        ],
+        Language::Russian => [
+            "🔐 Требуется одобрение",
+            "Запросил",
+            "Аргументы",
+            "Истекает через {secs} с.",
+            "✅ Одобрить",
+            "❌ Отклонить",
+        ],
+        Language::Italian => [
+            "🔐 Approvazione richiesta",
+            "Richiesto da",
+            "Argomenti",
+            "Scade tra {secs}s.",
+            "✅ Approva",
+            "❌ Rifiuta",
+        ],
+    };
+    let text = format!(
+        "{}\nTool: `{}`\n{}: {}\n{}:\n```\n{}\n```\n{}",
+        title,
+        tool_name,
+        requested_by,
+        origin,
+        arguments_label,
+        arguments,
+        expires.replace("{secs}", &timeout_secs.to_string())
+    );
+    let keyboard = vec![vec![
+        InlineButton {
+            text: approve.to_string(),
+            callback_data: format!("{}{}:y", APPROVAL_CALLBACK_PREFIX, id),
+        },
+        InlineButton {
+            text: reject.to_string(),
+            callback_data: format!("{}{}:n", APPROVAL_CALLBACK_PREFIX, id),
+        },
+    ]];
+    (text, keyboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decisions_reach_the_waiting_call_once() {
+        let gate = ToolApprovalGate::default();
+        let (id, mut receiver) = gate.register(Some("bot1"), "exec");
+        let (other, _other_rx) = gate.register(Some("bot1"), "write_file");
+        assert_ne!(id, other);
+        assert_eq!(gate.lock().len(), 2);
+
+        let decision = ApprovalDecision {
+            approved: true,
+            approver: 42,
+        };
+        assert_eq!(gate.resolve(&id, Some("bot2"), decision), None);
+        assert_eq!(
+            gate.resolve(&id, Some("bot1"), decision).as_deref(),
+            Some("exec")
+        );
+        assert_eq!(receiver.try_recv().unwrap(), decision);
+        assert_eq!(gate.resolve(&id, Some("bot1"), decision), None);
+
+        gate.cancel(&other);
+        assert_eq!(gate.lock().len(), 0);
+        assert_eq!(gate.resolve(&other, Some("bot1"), decision), None);
+    }
+
+    #[test]
+    fn card_carries_arguments_and_callbacks() {
+        let arguments = card_arguments(&json!({"command": "rm -rf build"})).unwrap();
+        let (text, keyboard) = approval_card(
+            "abc1",
+            "exec",
+            &arguments,
+            "user 7",
+            Some(-100),
+            120,
+            Language::English,
+        );
+        assert!(text.starts_with("🔐 Approval required"));
+        assert!(text.contains("`exec`"));
+        assert!(text.contains("\"command\": \"rm -rf build\""));
+        assert!(text.contains("user 7 (chat -100)"));
+        let buttons = &keyboard[0];
+        assert_eq!(
+            parse_callback(&buttons[0].callback_data),
+            Some(("abc1", true))
+        );
+        assert_eq!(
+            parse_callback(&buttons[1].callback_data),
+            Some(("abc1", false))
+        );
+        assert!(buttons.iter().all(|b| b.callback_data.len() <= 64));
+
+        assert_eq!(parse_callback("approve::y"), None);
+        assert_eq!(parse_callback("approve:abc1:maybe"), None);
+        assert_eq!(parse_callback("menu:admin"), None);
+
+        // Oversized arguments are never shown in part.
+        assert_eq!(card_arguments(&json!({"content": "x".repeat(5000)})), None);
+        let fits = card_arguments(&json!({"content": "x".repeat(2900)})).unwrap();
+        let (card, keyboard) = approval_card(
+            "abc2",
+            "write_file",
+            &fits,
+            "user 7",
+            None,
+            60,
+            Language::Italian,
+        );
+        assert!(card.contains(&"x".repeat(2900)));
+        assert!(card.contains("Scade tra 60s."));
+        assert_eq!(keyboard[0][0].text, "✅ Approva");
+        assert!(card.chars().count() < 4096);
+    }
+
+    #[test]
+    fn replies_follow_the_recipient_language() {
+        let denied = ApprovalOutcome::Denied { approver: 1 };
+        assert_eq!(
+            denied.user_message("exec", Language::English),
+            "❌ Operation cancelled: `exec` was not approved by an admin."
+        );
+        assert!(denied
+            .user_message("exec", Language::Italian)
+            .contains("non approvato"));
+        assert_eq!(
+            ApprovalOutcome::Stopped.user_message("exec", Language::Spanish),
+            ""
+        );
+        assert!(ApprovalOutcome::TooLarge
+            .tool_message("write_file")
+            .contains("too long"));
+        assert_eq!(
+            pending_notice("exec", 30, Language::Russian),
+            "⏳ `exec` требует одобрения админа (истекает через 30 с)."
+        );
+        assert_eq!(
+            decision_text(Some("exec"), true, 42, Language::English),
+            "✅ `exec` approved by 42."
+        );
+        assert_eq!(
+            decision_text(None, true, 42, Language::Italian),
+            "⌛ Richiesta scaduta o già gestita."
+        );
+    }
+}
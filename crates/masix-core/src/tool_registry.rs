@@ -0,0 +1,523 @@
+//! Unified tool registry.
+//!
+//! Builtins, MCP servers, `wasm_tool` plugins and tools registered by an
+//! embedding application are all [`ToolProvider`]s. Each turn the registry
+//! snapshots their tools into a [`ToolCatalog`], which resolves exposed
+//! names, carries per-tool metadata and applies the one permission check used
+//! both for listing tools to the model and for executing its calls.
+
+use crate::builtin_tools::{get_builtin_tool_definitions, is_builtin_tool};
+use crate::{BotContext, MasixRuntime, RuntimeToolAccess};
+use anyhow::{anyhow, Result};
+use masix_config::{Config, PermissionLevel};
+use masix_exec::ExecPolicy;
+use masix_ipc::{Envelope, OutboundMessage};
+use masix_mcp::{McpCallOptions, McpClient, McpServer};
+use masix_providers::{FunctionDefinition, ImagePart, ToolDefinition};
+use masix_storage::Storage;
+use masix_telegram::menu::Language;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+use tracing::warn;
+
+/// Relative cost of one call, shown by `/tools`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToolCost {
+    /// Local and bounded (file reads, memory, interpreter with fuel).
+    Low,
+    /// Network or subprocess work.
+    Medium,
+    /// Long-running or billed work.
+    High,
+}
+
+impl ToolCost {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolMetadata {
+    /// Hidden from and refused to non-admin senders.
+    pub admin_only: bool,
+    /// Changes state outside the conversation (files, messages, processes).
+    pub side_effecting: bool,
+    pub cost: ToolCost,
+}
+
+impl Default for ToolMetadata {
+    /// Unknown tools are assumed to act on the outside world.
+    fn default() -> Self {
+        Self {
+            admin_only: false,
+            side_effecting: true,
+            cost: ToolCost::Medium,
+        }
+    }
+}
+
+/// Result of a tool call: text for the model plus images shown to the user.
+#[derive(Debug, Clone, Default)]
+pub struct ToolOutput {
+    pub text: String,
+    pub images: Vec<ImagePart>,
+}
+
+impl From<String> for ToolOutput {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            images: Vec::new(),
+        }
+    }
+}
+
+/// Per-call runtime state handed to providers.
+#[derive(Clone, Copy)]
+pub struct ToolCallContext<'a> {
+    pub(crate) outbound_sender: Option<&'a broadcast::Sender<OutboundMessage>>,
+    pub(crate) exec_policy: &'a ExecPolicy,
+    pub(crate) workdir: &'a Path,
+    pub(crate) storage: &'a Arc<Mutex<Storage>>,
+    pub(crate) envelope: &'a Envelope,
+    pub(crate) account_tag: Option<&'a str>,
+    pub(crate) vision_analysis: Option<&'a str>,
+    pub(crate) config: &'a Config,
+    pub(crate) bot_context: &'a BotContext,
+    pub(crate) permission: PermissionLevel,
+    pub(crate) admin_only_modules: &'a HashSet<String>,
+    pub(crate) mcp_client: &'a Option<Arc<Mutex<McpClient>>>,
+    pub(crate) mcp_call: &'a McpCallOptions,
+    /// Menu language of the requester.
+    pub(crate) language: Language,
+    pub(crate) user_languages: &'a Arc<Mutex<HashMap<String, Language>>>,
+}
+
+impl<'a> ToolCallContext<'a> {
+    /// Workdir of the bot handling the message.
+    pub fn workdir(&self) -> &'a Path {
+        self.workdir
+    }
+
+    pub fn permission(&self) -> PermissionLevel {
+        self.permission
+    }
+
+    /// Inbound message that triggered the call.
+    pub fn envelope(&self) -> &'a Envelope {
+        self.envelope
+    }
+
+    pub fn account_tag(&self) -> Option<&'a str> {
+        self.account_tag
+    }
+}
+
+#[async_trait::async_trait]
+pub trait ToolProvider: Send + Sync {
+    /// Prefix of the exposed tool names, `{namespace}_{tool}`.
+    fn namespace(&self) -> &str;
+    async fn list_tools(&self) -> Result<Vec<ToolDefinition>>;
+    async fn call_tool(&self, tool_name: &str, arguments: serde_json::Value) -> Result<String>;
+
+    fn tool_metadata(&self, _tool_name: &str) -> ToolMetadata {
+        ToolMetadata::default()
+    }
+
+    /// Same as `call_tool`, with the runtime state of the calling turn.
+    async fn call_tool_with(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        _ctx: &ToolCallContext<'_>,
+    ) -> Result<ToolOutput> {
+        Ok(self.call_tool(tool_name, arguments).await?.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolSource {
+    Builtin,
+    Mcp,
+    Plugin,
+}
+
+#[derive(Clone)]
+struct RegisteredProvider {
+    provider: Arc<dyn ToolProvider>,
+    source: ToolSource,
+}
+
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    providers: Vec<RegisteredProvider>,
+}
+
+impl ToolRegistry {
+    /// Registry with the builtin tools; `mcp_enabled` keeps the MCP resource tools.
+    pub(crate) fn with_builtins(mcp_enabled: bool) -> Self {
+        let mut registry = Self::default();
+        registry.providers.push(RegisteredProvider {
+            provider: Arc::new(BuiltinToolProvider { mcp_enabled }),
+            source: ToolSource::Builtin,
+        });
+        registry
+    }
+
+    pub(crate) fn register_mcp_server(&mut self, server: Arc<McpServer>, admin_only: bool) {
+        let provider = Arc::new(McpServerToolProvider { server, admin_only });
+        if let Err(e) = self.check_namespace(provider.namespace()) {
+            warn!("MCP server tools not registered: {}", e);
+            return;
+        }
+        self.providers.push(RegisteredProvider {
+            provider,
+            source: ToolSource::Mcp,
+        });
+    }
+
+    /// Registers plugin or application tools under their namespace.
+    pub fn register(&mut self, provider: Arc<dyn ToolProvider>) -> Result<()> {
+        self.check_namespace(provider.namespace())?;
+        self.providers.push(RegisteredProvider {
+            provider,
+            source: ToolSource::Plugin,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn plugin_providers(&self) -> impl Iterator<Item = Arc<dyn ToolProvider>> + '_ {
+        self.providers
+            .iter()
+            .filter(|p| p.source == ToolSource::Plugin)
+            .map(|p| p.provider.clone())
+    }
+
+    fn check_namespace(&self, namespace: &str) -> Result<()> {
+        validate_namespace(namespace)?;
+        if self
+            .providers
+            .iter()
+            .any(|p| p.provider.namespace() == namespace)
+        {
+            anyhow::bail!("tool namespace '{}' is already registered", namespace);
+        }
+        Ok(())
+    }
+
+    /// Current tools of every provider. Exposed names that collide keep the
+    /// builtin, then the provider with the longest namespace, then the one
+    /// registered first.
+    pub async fn catalog(&self) -> ToolCatalog {
+        let mut tools: Vec<RegisteredTool> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (provider_index, entry) in self.providers.iter().enumerate() {
+            let namespace = entry.provider.namespace();
+            let listed = match entry.provider.list_tools().await {
+                Ok(listed) => listed,
+                Err(e) => {
+                    warn!("Tool provider '{}' failed to list tools: {}", namespace, e);
+                    continue;
+                }
+            };
+            for mut definition in listed {
+                let local_name = definition.function.name.clone();
+                let metadata = entry.provider.tool_metadata(&local_name);
+                definition.function.name = exposed_tool_name(namespace, &local_name);
+                let tool = RegisteredTool {
+                    definition,
+                    local_name,
+                    namespace: namespace.to_string(),
+                    source: entry.source,
+                    metadata,
+                    provider: provider_index,
+                };
+                match index.get(&tool.definition.function.name).copied() {
+                    None => {
+                        index.insert(tool.definition.function.name.clone(), tools.len());
+                        tools.push(tool);
+                    }
+                    Some(existing) => {
+                        let kept = &tools[existing];
+                        let replaces = kept.source != ToolSource::Builtin
+                            && tool.namespace.len() > kept.namespace.len();
+                        let (winner, shadowed) = if replaces {
+                            (&tool.namespace, &kept.namespace)
+                        } else {
+                            (&kept.namespace, &tool.namespace)
+                        };
+                        warn!(
+                            "Tool name collision on '{}': keeping namespace '{}', ignoring '{}'",
+                            tool.definition.function.name, winner, shadowed
+                        );
+                        if replaces {
+                            tools[existing] = tool;
+                        }
+                    }
+                }
+            }
+        }
+        ToolCatalog {
+            tools,
+            index,
+            providers: self.providers.iter().map(|p| p.provider.clone()).collect(),
+        }
+    }
+}
+
+fn validate_namespace(namespace: &str) -> Result<()> {
+    let valid = !namespace.is_empty()
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        anyhow::bail!(
+            "invalid tool namespace '{}': use letters, digits, '_' or '-'",
+            namespace
+        );
+    }
+    Ok(())
+}
+
+fn exposed_tool_name(namespace: &str, tool_name: &str) -> String {
+    if namespace.is_empty() {
+        tool_name.to_string()
+    } else {
+        format!("{}_{}", namespace, tool_name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegisteredTool {
+    /// Definition advertised to the model, under the exposed name.
+    pub definition: ToolDefinition,
+    /// Name known to the provider.
+    pub local_name: String,
+    pub namespace: String,
+    pub source: ToolSource,
+    pub metadata: ToolMetadata,
+    provider: usize,
+}
+
+impl RegisteredTool {
+    pub fn name(&self) -> &str {
+        &self.definition.function.name
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolDenial {
+    /// Excluded by the role/tool allowlist of the sender.
+    Policy,
+    AdminOnly,
+}
+
+impl ToolDenial {
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::Policy => "Tool execution denied by role/tool policy.",
+            Self::AdminOnly => "Tool execution denied: this tool requires admin privileges.",
+        }
+    }
+}
+
+/// Tools available for one turn.
+#[derive(Default)]
+pub struct ToolCatalog {
+    tools: Vec<RegisteredTool>,
+    index: HashMap<String, usize>,
+    providers: Vec<Arc<dyn ToolProvider>>,
+}
+
+impl ToolCatalog {
+    pub fn tools(&self) -> &[RegisteredTool] {
+        &self.tools
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegisteredTool> {
+        self.index.get(name).map(|&i| &self.tools[i])
+    }
+
+    /// The single permission gate for listing and executing a tool.
+    pub(crate) fn check_access(
+        &self,
+        name: &str,
+        permission: PermissionLevel,
+        access: &RuntimeToolAccess,
+    ) -> std::result::Result<(), ToolDenial> {
+        if !access.allows_tool(name) {
+            return Err(ToolDenial::Policy);
+        }
+        let admin_only = self.get(name).is_some_and(|tool| tool.metadata.admin_only);
+        if admin_only && permission != PermissionLevel::Admin {
+            return Err(ToolDenial::AdminOnly);
+        }
+        Ok(())
+    }
+
+    /// Definitions the sender may see and call.
+    pub(crate) fn visible_definitions(
+        &self,
+        permission: PermissionLevel,
+        access: &RuntimeToolAccess,
+    ) -> Vec<ToolDefinition> {
+        self.tools
+            .iter()
+            .filter(|tool| self.check_access(tool.name(), permission, access).is_ok())
+            .map(|tool| tool.definition.clone())
+            .collect()
+    }
+
+    pub async fn call(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+        ctx: &ToolCallContext<'_>,
+    ) -> Result<ToolOutput> {
+        let tool = self
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown tool: {}", name))?;
+        self.providers[tool.provider]
+            .call_tool_with(&tool.local_name, arguments, ctx)
+            .await
+    }
+}
+
+/// Builtin tools, exposed without a namespace prefix.
+struct BuiltinToolProvider {
+    mcp_enabled: bool,
+}
+
+#[async_trait::async_trait]
+impl ToolProvider for BuiltinToolProvider {
+    fn namespace(&self) -> &str {
+        ""
+    }
+
+    async fn list_tools(&self) -> Result<Vec<ToolDefinition>> {
+        let mut tools = get_builtin_tool_definitions();
+        if !self.mcp_enabled {
+            tools.retain(|tool| !tool.function.name.starts_with("mcp_resource_"));
+        }
+        Ok(tools)
+    }
+
+    async fn call_tool(&self, tool_name: &str, _arguments: serde_json::Value) -> Result<String> {
+        Err(anyhow!(
+            "Builtin tool '{}' needs the runtime context of a turn",
+            tool_name
+        ))
+    }
+
+    fn tool_metadata(&self, tool_name: &str) -> ToolMetadata {
+        debug_assert!(is_builtin_tool(tool_name));
+        let side_effecting = matches!(
+            tool_name,
+            "exec"
+                | "exec_bg"
+                | "termux"
+                | "write_file"
+                | "memory_write"
+                | "cron"
+                | "telegram_send"
+                | "admin_acl"
+                | "intent"
+        );
+        let cost = match tool_name {
+            "exec" | "exec_bg" | "termux" | "intent" | "web_fetch" | "mcp_resource_list"
+            | "mcp_resource_read" => ToolCost::Medium,
+            _ => ToolCost::Low,
+        };
+        // Builtins enforce their own per-tool permission model.
+        ToolMetadata {
+            admin_only: false,
+            side_effecting,
+            cost,
+        }
+    }
+
+    async fn call_tool_with(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        ctx: &ToolCallContext<'_>,
+    ) -> Result<ToolOutput> {
+        MasixRuntime::execute_builtin_tool_call(tool_name, arguments, ctx)
+            .await
+            .map(ToolOutput::from)
+    }
+}
+
+/// One MCP server; its tools are exposed as `{server}_{tool}`.
+struct McpServerToolProvider {
+    server: Arc<McpServer>,
+    admin_only: bool,
+}
+
+#[async_trait::async_trait]
+impl ToolProvider for McpServerToolProvider {
+    fn namespace(&self) -> &str {
+        self.server.name()
+    }
+
+    async fn list_tools(&self) -> Result<Vec<ToolDefinition>> {
+        Ok(self
+            .server
+            .list_tools()
+            .await?
+            .into_iter()
+            .map(|tool| ToolDefinition {
+                tool_type: "function".to_string(),
+                function: FunctionDefinition {
+                    name: tool.name,
+                    description: tool.description,
+                    parameters: tool.input_schema,
+                },
+            })
+            .collect())
+    }
+
+    async fn call_tool(&self, tool_name: &str, arguments: serde_json::Value) -> Result<String> {
+        let mut images = Vec::new();
+        MasixRuntime::call_mcp_server_tool(
+            &self.server,
+            tool_name,
+            arguments,
+            &McpCallOptions::default(),
+            &mut images,
+        )
+        .await
+    }
+
+    fn tool_metadata(&self, _tool_name: &str) -> ToolMetadata {
+        ToolMetadata {
+            admin_only: self.admin_only,
+            ..ToolMetadata::default()
+        }
+    }
+
+    async fn call_tool_with(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        ctx: &ToolCallContext<'_>,
+    ) -> Result<ToolOutput> {
+        let mut images = Vec::new();
+        let text = MasixRuntime::call_mcp_server_tool(
+            &self.server,
+            tool_name,
+            arguments,
+            ctx.mcp_call,
+            &mut images,
+        )
+        .await?;
+        Ok(ToolOutput { text, images })
+    }
+}
@@ -0,0 +1,167 @@
+//! Tool argument validation
+//!
+//! Checks the arguments produced by the model against the JSON Schema that
+//! was advertised for the tool (builtin `parameters` or MCP `inputSchema`)
+//! before the call is dispatched, and counts the rejections per tool.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Maximum number of schema violations reported back to the model.
+const MAX_REPORTED_ERRORS: usize = 5;
+/// Schemas longer than this are not echoed back in the error message.
+const MAX_ECHOED_SCHEMA_CHARS: usize = 1500;
+
+/// Parse the raw argument string of a tool call.
+///
+/// An empty string is treated as `{}` (some providers send it for tools
+/// without parameters); anything else must be valid JSON.
+pub fn parse_arguments(raw: &str) -> Result<Value, String> {
+    if raw.trim().is_empty() {
+        return Ok(Value::Object(Default::default()));
+    }
+    serde_json::from_str(raw).map_err(|e| format!("arguments are not valid JSON ({})", e))
+}
+
+/// Validate `arguments` against `schema`.
+///
+/// Missing, empty or unusable schemas accept everything: a broken schema
+/// published by an MCP server must not make its tool uncallable.
+pub fn validate_arguments(schema: &Value, arguments: &Value) -> Result<(), String> {
+    if schema_is_permissive(schema) {
+        return Ok(());
+    }
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(e) => {
+            tracing::debug!("Skipping argument validation, invalid schema: {}", e);
+            return Ok(());
+        }
+    };
+
+    let mut violations = Vec::new();
+    let mut total = 0usize;
+    for error in validator.iter_errors(arguments) {
+        total += 1;
+        if violations.len() < MAX_REPORTED_ERRORS {
+            let path = error.instance_path().to_string();
+            let path = if path.is_empty() { "/" } else { path.as_str() };
+            violations.push(format!("{}: {}", path, error));
+        }
+    }
+    if violations.is_empty() {
+        return Ok(());
+    }
+    if total > violations.len() {
+        violations.push(format!("(+{} more)", total - violations.len()));
+    }
+    Err(violations.join("; "))
+}
+
+/// Message returned to the model in place of the tool result, so it can
+/// fix the call and retry.
+pub fn format_rejection(tool_name: &str, reason: &str, schema: Option<&Value>) -> String {
+    let mut message = format!(
+        "Invalid arguments for tool '{}': {}. Fix the arguments and call the tool again.",
+        tool_name, reason
+    );
+    if let Some(schema) = schema.filter(|schema| !schema_is_permissive(schema)) {
+        let rendered = schema.to_string();
+        if rendered.chars().count() <= MAX_ECHOED_SCHEMA_CHARS {
+            message.push_str(&format!("\nExpected schema: {}", rendered));
+        }
+    }
+    message
+}
+
+fn schema_is_permissive(schema: &Value) -> bool {
+    match schema {
+        Value::Null | Value::Bool(true) => true,
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+/// Per-tool counters of calls rejected by argument validation.
+#[derive(Debug, Default)]
+pub struct ToolValidationMetrics {
+    failures: Mutex<BTreeMap<String, u64>>,
+}
+
+impl ToolValidationMetrics {
+    /// Count one rejected call and return the running total for the tool.
+    pub fn record_failure(&self, tool_name: &str) -> u64 {
+        let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+        let count = failures.entry(tool_name.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn arguments_are_checked_against_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "limit": { "type": "integer", "minimum": 1 }
+            },
+            "required": ["query"]
+        });
+
+        assert!(validate_arguments(&schema, &json!({"query": "rust", "limit": 3})).is_ok());
+
+        let missing = validate_arguments(&schema, &json!({"limit": 3})).unwrap_err();
+        assert!(missing.contains("\"query\" is a required property"));
+
+        let wrong = validate_arguments(&schema, &json!({"query": 7, "limit": 0})).unwrap_err();
+        assert!(wrong.contains("/query"));
+        assert!(wrong.contains("/limit"));
+
+        // Missing or broken schemas never block a call.
+        assert!(validate_arguments(&Value::Null, &json!({"x": 1})).is_ok());
+        assert!(validate_arguments(&json!({}), &json!({"x": 1})).is_ok());
+        assert!(validate_arguments(&json!({"type": 12}), &json!({"x": 1})).is_ok());
+
+        for tool in crate::builtin_tools::get_builtin_tool_definitions() {
+            assert!(
+                jsonschema::validator_for(&tool.function.parameters).is_ok(),
+                "builtin tool '{}' has an invalid parameter schema",
+                tool.function.name
+            );
+        }
+    }
+
+    #[test]
+    fn rejection_message_and_metrics() {
+        assert!(parse_arguments("").unwrap().is_object());
+        assert!(parse_arguments("{\"a\":").is_err());
+
+        let schema = json!({"type": "object", "required": ["path"]});
+        let message = format_rejection("read_file", "/: \"path\" is missing", Some(&schema));
+        assert!(message.starts_with("Invalid arguments for tool 'read_file'"));
+        assert!(message.contains("Expected schema: "));
+        assert!(!format_rejection("x", "bad", Some(&json!({}))).contains("Expected schema"));
+
+        let metrics = ToolValidationMetrics::default();
+        assert_eq!(metrics.record_failure("read_file"), 1);
+        assert_eq!(metrics.record_failure("read_file"), 2);
+        assert_eq!(metrics.record_failure("web_search"), 1);
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.get("read_file"), Some(&2));
+        assert_eq!(snapshot.get("web_search"), Some(&1));
+    }
+}
@@ -0,0 +1,211 @@
+//! `wasm_tool` plugins exposed as a [`ToolProvider`].
+//!
+//! Installed packages with `package_type = "wasm_tool"` are compiled once at
+//! startup; each call runs on a blocking thread in a fresh wasmi instance
+//! with the fuel/memory limits and capability grants from `[plugins.wasm]`.
+
+use crate::{ToolCallContext, ToolCost, ToolMetadata, ToolOutput, ToolProvider};
+use anyhow::{anyhow, Result};
+use masix_config::{PluginsConfig, WasmPluginsConfig};
+use masix_providers::{FunctionDefinition, ToolDefinition};
+use masix_wasm::{WasmCapabilities, WasmLimits, WasmTool};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub(crate) const WASM_TOOL_PACKAGE_TYPE: &str = "wasm_tool";
+
+pub struct WasmToolProvider {
+    plugin_id: String,
+    namespace: String,
+    tool: Arc<WasmTool>,
+    timeout: Duration,
+    metadata: ToolMetadata,
+}
+
+impl WasmToolProvider {
+    /// Compiles the module at `module_path`. Host capabilities come from
+    /// `config.grants[plugin_id]`; key/value state lives under
+    /// `<data_dir>/plugins/state/<plugin_id>/kv.json`.
+    pub fn load(
+        plugin_id: &str,
+        module_path: &Path,
+        config: &WasmPluginsConfig,
+        data_dir: &Path,
+    ) -> Result<Self> {
+        let grant = config.grants.get(plugin_id).cloned().unwrap_or_default();
+        let metadata = ToolMetadata {
+            admin_only: false,
+            side_effecting: grant.files || grant.kv || !grant.http_allow.is_empty(),
+            cost: if grant.http_allow.is_empty() {
+                ToolCost::Low
+            } else {
+                ToolCost::Medium
+            },
+        };
+        let capabilities = WasmCapabilities {
+            http_allow: grant.http_allow,
+            files: grant.files,
+            kv: grant.kv,
+        };
+        let limits = WasmLimits {
+            fuel: config.fuel,
+            max_memory_bytes: config.max_memory_mb as usize * 1024 * 1024,
+            timeout: Duration::from_secs(config.timeout_secs),
+        };
+        let kv_path = data_dir
+            .join("plugins")
+            .join("state")
+            .join(plugin_id)
+            .join("kv.json");
+        let tool = WasmTool::from_file(module_path, limits, capabilities, Some(kv_path))?;
+        Ok(Self {
+            plugin_id: plugin_id.to_string(),
+            namespace: format!("plugin_{}", plugin_id.replace('-', "_")),
+            tool: Arc::new(tool),
+            timeout: Duration::from_secs(config.timeout_secs),
+            metadata,
+        })
+    }
+
+    /// Hide the tools from non-admin senders (`admin_only` plugins).
+    pub fn admin_only(mut self, admin_only: bool) -> Self {
+        self.metadata.admin_only = admin_only;
+        self
+    }
+
+    async fn run(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        workdir: Option<&Path>,
+    ) -> Result<String> {
+        let tool = Arc::clone(&self.tool);
+        let name = tool_name.to_string();
+        let files_root = workdir.map(|dir| dir.join("plugins").join(&self.plugin_id));
+        // The guest itself stops at its deadline (checked on every host call);
+        // this only bounds how long the turn waits for the blocking thread.
+        let task = tokio::task::spawn_blocking(move || {
+            tool.call(&name, &arguments, files_root.as_deref())
+        });
+        let result = tokio::time::timeout(self.timeout, task)
+            .await
+            .map_err(|_| {
+                anyhow!(
+                    "wasm tool '{}' timed out after {}s",
+                    tool_name,
+                    self.timeout.as_secs()
+                )
+            })?
+            .map_err(|e| anyhow!("wasm tool '{}' panicked: {}", tool_name, e))??;
+        if result.is_error {
+            Ok(format!("Tool error: {}", result.text))
+        } else {
+            Ok(result.text)
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolProvider for WasmToolProvider {
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    async fn list_tools(&self) -> Result<Vec<ToolDefinition>> {
+        Ok(self
+            .tool
+            .tools()
+            .iter()
+            .map(|tool| ToolDefinition {
+                tool_type: "function".to_string(),
+                function: FunctionDefinition {
+                    name: tool.name.clone(),
+                    description: tool.description.clone(),
+                    parameters: tool.parameters.clone(),
+                },
+            })
+            .collect())
+    }
+
+    async fn call_tool(&self, tool_name: &str, arguments: serde_json::Value) -> Result<String> {
+        self.run(tool_name, arguments, None).await
+    }
+
+    fn tool_metadata(&self, _tool_name: &str) -> ToolMetadata {
+        self.metadata
+    }
+
+    async fn call_tool_with(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        ctx: &ToolCallContext<'_>,
+    ) -> Result<ToolOutput> {
+        self.run(tool_name, arguments, Some(ctx.workdir()))
+            .await
+            .map(ToolOutput::from)
+    }
+}
+
+/// Providers for every enabled `wasm_tool` plugin in `plugins/installed.json`.
+/// A module that fails to load is skipped with a warning.
+pub(crate) fn load_wasm_tool_providers(
+    data_dir: &Path,
+    plugins_config: Option<&PluginsConfig>,
+) -> Vec<Arc<dyn ToolProvider>> {
+    #[derive(serde::Deserialize, Default)]
+    struct PluginRegistry {
+        #[serde(default)]
+        plugins: Vec<InstalledWasmPlugin>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct InstalledWasmPlugin {
+        plugin_id: String,
+        install_path: PathBuf,
+        #[serde(default)]
+        enabled: bool,
+        #[serde(default)]
+        package_type: Option<String>,
+        #[serde(default)]
+        admin_only: bool,
+    }
+
+    let registry_path = data_dir.join("plugins").join("installed.json");
+    let Some(registry) = std::fs::read_to_string(&registry_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<PluginRegistry>(&content).ok())
+    else {
+        return Vec::new();
+    };
+
+    let defaults = WasmPluginsConfig::default();
+    let config = plugins_config.map(|p| &p.wasm).unwrap_or(&defaults);
+    let mut providers: Vec<Arc<dyn ToolProvider>> = Vec::new();
+    for plugin in registry
+        .plugins
+        .into_iter()
+        .filter(|p| p.enabled && p.package_type.as_deref() == Some(WASM_TOOL_PACKAGE_TYPE))
+    {
+        match WasmToolProvider::load(&plugin.plugin_id, &plugin.install_path, config, data_dir) {
+            Ok(provider) => {
+                let provider = provider.admin_only(plugin.admin_only);
+                info!(
+                    "Loaded wasm tool plugin '{}' ({} tools)",
+                    plugin.plugin_id,
+                    provider.tool.tools().len()
+                );
+                providers.push(Arc::new(provider));
+            }
+            Err(e) => warn!(
+                "Skipping wasm tool plugin '{}' ({}): {}",
+                plugin.plugin_id,
+                plugin.install_path.display(),
+                e
+            ),
+        }
+    }
+    providers
+}
@@ -279,6 +279,9 @@ impl CronExecutor {
                                 edit_message_id: None,
                                 inline_keyboard: None,
                                 chat_action: None,
+                                photo: None,
+                                delivered: None,
+                                callback_answer: None,
                             };
 
                             if let Err(e) = outbound_sender.send(msg).await {
@@ -0,0 +1,757 @@
+//! Background exec jobs.
+//!
+//! `ExecJobs::start` checks a command exactly like `run_command` and then runs
+//! it without waiting: stdout and stderr go interleaved to `<dir>/<id>.log`
+//! and the job list to `<dir>/jobs.json`, so finished jobs stay listed after
+//! a restart. Jobs still running when the process stopped are reported as
+//! `interrupted`. Every process of a job leads its own process group, so a
+//! kill or timeout also reaches the children it forked.
+
+use crate::{ensure_allowed, parse_pipeline, pipeline, ExecMode, ExecPolicy};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+use tokio::time::{timeout, Duration};
+
+const JOBS_STATE_FILE: &str = "jobs.json";
+const LOG_TRUNCATED_MARKER: &[u8] = b"\n...[log truncated]\n";
+/// How long a finished job waits for the rest of its output before what is
+/// left of its process groups is killed; a leftover process holding the log
+/// pipe must not keep the job `running`.
+const LOG_DRAIN_GRACE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsPolicy {
+    pub enabled: bool,
+    pub max_running: usize,
+    /// Wall-clock limit; the job is killed afterwards.
+    pub max_runtime_secs: u64,
+    /// Output kept per job; the rest is discarded.
+    pub max_log_bytes: u64,
+    /// Finished jobs (and logs) kept for `/jobs`.
+    pub keep_finished: usize,
+    /// How often the chat message of a running job is refreshed.
+    pub update_interval_secs: u64,
+}
+
+impl Default for JobsPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_running: 4,
+            max_runtime_secs: 3600,
+            max_log_bytes: 1024 * 1024,
+            keep_finished: 50,
+            update_interval_secs: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Exited,
+    Killed,
+    TimedOut,
+    /// Could not be started or waited for.
+    Failed,
+    /// Still running when the previous process stopped.
+    Interrupted,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Exited => "exited",
+            Self::Killed => "killed",
+            Self::TimedOut => "timed_out",
+            Self::Failed => "failed",
+            Self::Interrupted => "interrupted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: u64,
+    pub command: String,
+    pub account_tag: Option<String>,
+    pub chat_id: Option<i64>,
+    pub started_by: String,
+    /// Unix seconds.
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub status: JobStatus,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+impl JobInfo {
+    pub fn is_running(&self) -> bool {
+        self.status == JobStatus::Running
+    }
+
+    /// Seconds since start, or the total runtime once finished.
+    pub fn elapsed_secs(&self) -> u64 {
+        self.finished_at
+            .unwrap_or_else(now_unix_secs)
+            .saturating_sub(self.started_at)
+    }
+}
+
+/// Who started a job and which chat it reports to.
+#[derive(Debug, Clone, Default)]
+pub struct JobOwner {
+    pub account_tag: Option<String>,
+    pub chat_id: Option<i64>,
+    pub started_by: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JobsState {
+    next_id: u64,
+    jobs: Vec<JobInfo>,
+}
+
+#[derive(Debug, Default)]
+struct JobsInner {
+    state: JobsState,
+    kill_switches: HashMap<u64, oneshot::Sender<()>>,
+}
+
+enum JobEnd {
+    Waited(std::io::Result<Option<ExitStatus>>),
+    TimedOut,
+    Killed,
+}
+
+#[derive(Debug)]
+pub struct ExecJobs {
+    dir: PathBuf,
+    inner: Mutex<JobsInner>,
+}
+
+impl ExecJobs {
+    /// Jobs persisted in `dir` (created on the first start). An unreadable
+    /// state file starts an empty list.
+    pub fn load(dir: PathBuf) -> Self {
+        let mut state: JobsState = std::fs::read_to_string(dir.join(JOBS_STATE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let mut interrupted = false;
+        for job in state.jobs.iter_mut().filter(|job| job.is_running()) {
+            job.status = JobStatus::Interrupted;
+            job.finished_at = Some(now_unix_secs());
+            interrupted = true;
+        }
+        let jobs = Self {
+            dir,
+            inner: Mutex::new(JobsInner {
+                state,
+                kill_switches: HashMap::new(),
+            }),
+        };
+        if interrupted {
+            let _ = jobs.persist(&jobs.lock().state);
+        }
+        jobs
+    }
+
+    pub fn log_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.log", id))
+    }
+
+    /// Check and start `raw_command` in the background.
+    pub fn start(
+        self: &Arc<Self>,
+        policy: &ExecPolicy,
+        mode: ExecMode,
+        raw_command: &str,
+        workdir: &Path,
+        level: Option<&str>,
+        owner: JobOwner,
+    ) -> Result<JobInfo> {
+        if !policy.jobs.enabled {
+            bail!("Background exec jobs are disabled (exec.jobs.enabled)");
+        }
+        ensure_allowed(policy, mode, raw_command, level)?;
+        std::fs::create_dir_all(&self.dir)?;
+
+        let job = {
+            let mut inner = self.lock();
+            let running = inner
+                .state
+                .jobs
+                .iter()
+                .filter(|job| job.is_running())
+                .count();
+            if running >= policy.jobs.max_running.max(1) {
+                bail!(
+                    "{} background jobs are already running (limit {})",
+                    running,
+                    policy.jobs.max_running.max(1)
+                );
+            }
+            inner.state.next_id += 1;
+            let job = JobInfo {
+                id: inner.state.next_id,
+                command: raw_command.trim().to_string(),
+                account_tag: owner.account_tag,
+                chat_id: owner.chat_id,
+                started_by: owner.started_by,
+                started_at: now_unix_secs(),
+                finished_at: None,
+                status: JobStatus::Running,
+                exit_code: None,
+                error: None,
+            };
+            inner.state.jobs.push(job.clone());
+            self.persist(&inner.state)?;
+            job
+        };
+
+        let spawned = std::fs::File::create(self.log_path(job.id))
+            .map_err(anyhow::Error::from)
+            .and_then(|log| {
+                let (reader, writer) = std::io::pipe()?;
+                let children = spawn_job(policy, mode, raw_command, workdir, writer)?;
+                Ok((children, reader, log))
+            });
+        let (children, reader, log) = match spawned {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                self.finish(
+                    job.id,
+                    JobStatus::Failed,
+                    None,
+                    Some(e.to_string()),
+                    policy.jobs.keep_finished,
+                );
+                return Err(e);
+            }
+        };
+
+        let (kill_tx, kill_rx) = oneshot::channel();
+        self.lock().kill_switches.insert(job.id, kill_tx);
+        let max_log_bytes = policy.jobs.max_log_bytes;
+        let log_task = tokio::task::spawn_blocking(move || copy_capped(reader, log, max_log_bytes));
+        let jobs = Arc::clone(self);
+        let max_runtime = Duration::from_secs(policy.jobs.max_runtime_secs.max(1));
+        let keep_finished = policy.jobs.keep_finished;
+        let id = job.id;
+        tokio::spawn(async move {
+            // Ids are gone once a child is reaped; its group may still have members.
+            let groups: Vec<u32> = children.iter().filter_map(Child::id).collect();
+            let end = supervise(children, &groups, kill_rx, max_runtime).await;
+            let mut log_task = log_task;
+            let drained = timeout(LOG_DRAIN_GRACE, &mut log_task).await.is_ok();
+            // Background descendants die with the job, however it ended.
+            pipeline::kill_process_groups(&groups);
+            if !drained {
+                let _ = timeout(LOG_DRAIN_GRACE, log_task).await;
+            }
+            let (status, exit_code, error) = match end {
+                JobEnd::Waited(Ok(status)) => (
+                    JobStatus::Exited,
+                    status.and_then(|status| status.code()),
+                    None,
+                ),
+                JobEnd::Waited(Err(e)) => (JobStatus::Failed, None, Some(e.to_string())),
+                JobEnd::TimedOut => (JobStatus::TimedOut, None, None),
+                JobEnd::Killed => (JobStatus::Killed, None, None),
+            };
+            jobs.finish(id, status, exit_code, error, keep_finished);
+        });
+        Ok(job)
+    }
+
+    pub fn kill(&self, id: u64) -> Result<()> {
+        let mut inner = self.lock();
+        if !inner.state.jobs.iter().any(|job| job.id == id) {
+            bail!("Job #{} not found", id);
+        }
+        let switch = inner
+            .kill_switches
+            .remove(&id)
+            .ok_or_else(|| anyhow!("Job #{} is not running", id))?;
+        let _ = switch.send(());
+        Ok(())
+    }
+
+    pub fn get(&self, id: u64) -> Option<JobInfo> {
+        self.lock()
+            .state
+            .jobs
+            .iter()
+            .find(|job| job.id == id)
+            .cloned()
+    }
+
+    /// All known jobs, oldest first.
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.lock().state.jobs.clone()
+    }
+
+    /// Last `max_bytes` of the job output, starting at a line boundary.
+    pub fn tail(&self, id: u64, max_bytes: usize) -> Result<String> {
+        if self.get(id).is_none() {
+            bail!("Job #{} not found", id);
+        }
+        let mut file = std::fs::File::open(self.log_path(id))
+            .map_err(|e| anyhow!("No output for job #{}: {}", id, e))?;
+        let len = file.metadata()?.len();
+        let start = len.saturating_sub(max_bytes as u64);
+        file.seek(SeekFrom::Start(start))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let text = String::from_utf8_lossy(&bytes);
+        if start > 0 {
+            if let Some((_, rest)) = text.split_once('\n') {
+                return Ok(rest.to_string());
+            }
+        }
+        Ok(text.into_owned())
+    }
+
+    fn finish(
+        &self,
+        id: u64,
+        status: JobStatus,
+        exit_code: Option<i32>,
+        error: Option<String>,
+        keep_finished: usize,
+    ) {
+        let mut inner = self.lock();
+        inner.kill_switches.remove(&id);
+        if let Some(job) = inner.state.jobs.iter_mut().find(|job| job.id == id) {
+            job.status = status;
+            job.exit_code = exit_code;
+            job.error = error;
+            job.finished_at = Some(now_unix_secs());
+        }
+        let finished = inner
+            .state
+            .jobs
+            .iter()
+            .filter(|job| !job.is_running())
+            .count();
+        let mut excess = finished.saturating_sub(keep_finished);
+        let mut pruned = Vec::new();
+        inner.state.jobs.retain(|job| {
+            if excess > 0 && !job.is_running() {
+                excess -= 1;
+                pruned.push(job.id);
+                false
+            } else {
+                true
+            }
+        });
+        for id in pruned {
+            let _ = std::fs::remove_file(self.log_path(id));
+        }
+        let _ = self.persist(&inner.state);
+    }
+
+    fn persist(&self, state: &JobsState) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(JOBS_STATE_FILE);
+        let tmp = self.dir.join(format!("{}.tmp", JOBS_STATE_FILE));
+        std::fs::write(&tmp, serde_json::to_string_pretty(state)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, JobsInner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Start the processes of a job writing to `output`: the sandboxed pipeline
+/// in pipeline mode, otherwise the single command as `run_command` would.
+fn spawn_job(
+    policy: &ExecPolicy,
+    mode: ExecMode,
+    raw_command: &str,
+    workdir: &Path,
+    output: std::io::PipeWriter,
+) -> Result<Vec<Child>> {
+    if mode == ExecMode::Base && policy.pipeline.enabled {
+        let pipeline = parse_pipeline(raw_command)?;
+        let stderr = output.try_clone()?;
+        return pipeline::spawn_pipeline(policy, &pipeline, workdir, output, stderr);
+    }
+
+    let tokens = shlex::split(raw_command).ok_or_else(|| anyhow!("Invalid command syntax"))?;
+    let (program, args) = tokens
+        .split_first()
+        .ok_or_else(|| anyhow!("Missing command"))?;
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(workdir)
+        .stdin(Stdio::null())
+        .stdout(output.try_clone()?)
+        .stderr(output)
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+    let child = command
+        .spawn()
+        .map_err(|e| anyhow!("Failed to execute '{}': {}", program, e))?;
+    Ok(vec![child])
+}
+
+async fn supervise(
+    mut children: Vec<Child>,
+    groups: &[u32],
+    kill: oneshot::Receiver<()>,
+    max_runtime: Duration,
+) -> JobEnd {
+    let end = {
+        let wait_all = async {
+            let mut last_status = None;
+            for child in &mut children {
+                last_status = Some(child.wait().await?);
+            }
+            Ok::<_, std::io::Error>(last_status)
+        };
+        tokio::select! {
+            waited = timeout(max_runtime, wait_all) => match waited {
+                Ok(result) => JobEnd::Waited(result),
+                Err(_) => JobEnd::TimedOut,
+            },
+            _ = kill => JobEnd::Killed,
+        }
+    };
+    if !matches!(end, JobEnd::Waited(_)) {
+        pipeline::kill_process_groups(groups);
+        for child in &mut children {
+            let _ = child.start_kill();
+        }
+        for child in &mut children {
+            let _ = child.wait().await;
+        }
+    }
+    end
+}
+
+/// Copy to EOF, keeping at most `max_bytes` (the rest is drained so writers never block).
+fn copy_capped(mut reader: impl Read, mut log: std::fs::File, max_bytes: u64) {
+    let mut written = 0u64;
+    let mut truncated = false;
+    let mut buffer = [0u8; 8192];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => {
+                let room = max_bytes.saturating_sub(written).min(read as u64) as usize;
+                if room > 0 && log.write_all(&buffer[..room]).is_ok() {
+                    written += room as u64;
+                }
+                if room < read && !truncated {
+                    truncated = true;
+                    let _ = log.write_all(LOG_TRUNCATED_MARKER);
+                }
+            }
+        }
+    }
+}
+
+fn now_unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "masix-exec-jobs-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_unix_secs()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn policy() -> ExecPolicy {
+        let mut policy = ExecPolicy {
+            enabled: true,
+            allow_base: true,
+            ..ExecPolicy::default()
+        };
+        policy.jobs.enabled = true;
+        policy
+    }
+
+    async fn wait_finished(jobs: &ExecJobs, id: u64) -> JobInfo {
+        for _ in 0..100 {
+            let job = jobs.get(id).unwrap();
+            if !job.is_running() {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("job {} still running", id);
+    }
+
+    #[tokio::test]
+    async fn jobs_run_in_background_and_persist() {
+        let workdir = test_dir("workdir");
+        std::fs::write(workdir.join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+        let dir = test_dir("state");
+        let jobs = Arc::new(ExecJobs::load(dir.clone()));
+        let mut policy = policy();
+        let owner = JobOwner {
+            chat_id: Some(7),
+            started_by: "42".to_string(),
+            ..JobOwner::default()
+        };
+
+        let job = jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "wc -l notes.txt",
+                &workdir,
+                Some("admin"),
+                owner.clone(),
+            )
+            .unwrap();
+        assert_eq!(job.id, 1);
+        let done = wait_finished(&jobs, job.id).await;
+        assert_eq!(done.status, JobStatus::Exited);
+        assert_eq!(done.exit_code, Some(0));
+        assert!(jobs.tail(job.id, 1024).unwrap().contains("3 notes.txt"));
+
+        // Refused like a foreground command.
+        assert!(jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "rm -rf x",
+                &workdir,
+                None,
+                owner.clone()
+            )
+            .is_err());
+
+        let follow = jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "tail -f notes.txt",
+                &workdir,
+                None,
+                owner.clone(),
+            )
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(jobs.get(follow.id).unwrap().is_running());
+        assert!(jobs.tail(follow.id, 1024).unwrap().contains("three"));
+
+        // A second process sees the running job as interrupted.
+        let reloaded = ExecJobs::load(dir.clone());
+        assert_eq!(
+            reloaded.get(follow.id).unwrap().status,
+            JobStatus::Interrupted
+        );
+        assert_eq!(reloaded.get(1).unwrap().exit_code, Some(0));
+
+        jobs.kill(follow.id).unwrap();
+        assert_eq!(
+            wait_finished(&jobs, follow.id).await.status,
+            JobStatus::Killed
+        );
+        assert!(jobs.kill(follow.id).is_err());
+
+        policy.jobs.max_runtime_secs = 1;
+        policy.jobs.keep_finished = 2;
+        let slow = jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "tail -f notes.txt",
+                &workdir,
+                None,
+                owner,
+            )
+            .unwrap();
+        assert_eq!(
+            wait_finished(&jobs, slow.id).await.status,
+            JobStatus::TimedOut
+        );
+        // Only the two most recent finished jobs (and their logs) are kept.
+        let ids: Vec<u64> = jobs.list().iter().map(|job| job.id).collect();
+        assert_eq!(ids, vec![follow.id, slow.id]);
+        assert!(!jobs.log_path(1).exists());
+
+        let _ = std::fs::remove_dir_all(&workdir);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Processes whose command line is exactly `argv`.
+    fn processes_running(argv: &[&str]) -> usize {
+        let expected = argv.join("\0") + "\0";
+        std::fs::read_dir("/proc")
+            .unwrap()
+            .filter_map(|entry| std::fs::read(entry.ok()?.path().join("cmdline")).ok())
+            .filter(|cmdline| cmdline == expected.as_bytes())
+            .count()
+    }
+
+    #[tokio::test]
+    async fn kill_and_timeout_reach_forked_children() {
+        let workdir = test_dir("forks");
+        // Unique name, so the process scan only sees this test's children.
+        let file = format!("forked-{}.txt", std::process::id());
+        std::fs::write(workdir.join(&file), "line\n").unwrap();
+        let jobs = Arc::new(ExecJobs::load(workdir.join("jobs")));
+        let mut policy = policy();
+        // `timeout` runs `tail` as its child, which inherits the log pipe.
+        policy.base_allowlist.push("timeout".to_string());
+        // `--pid` ends a leaked `tail` together with the test process.
+        let pid_arg = format!("--pid={}", std::process::id());
+        let command = format!("timeout 60 tail -f {} {}", pid_arg, file);
+
+        let job = jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                &command,
+                &workdir,
+                None,
+                JobOwner::default(),
+            )
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(processes_running(&["tail", "-f", &pid_arg, &file]), 1);
+        jobs.kill(job.id).unwrap();
+        assert_eq!(wait_finished(&jobs, job.id).await.status, JobStatus::Killed);
+        assert_eq!(processes_running(&["tail", "-f", &pid_arg, &file]), 0);
+
+        policy.jobs.max_runtime_secs = 1;
+        let job = jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                &command,
+                &workdir,
+                None,
+                JobOwner::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            wait_finished(&jobs, job.id).await.status,
+            JobStatus::TimedOut
+        );
+        assert_eq!(processes_running(&["tail", "-f", &pid_arg, &file]), 0);
+
+        // A leader that exits normally does not leave a daemonized child
+        // holding the log pipe (and the job `running`).
+        policy.base_allowlist.push("sh".to_string());
+        std::fs::write(
+            workdir.join("daemon.sh"),
+            format!("tail -f {} {} &\necho started\n", pid_arg, file),
+        )
+        .unwrap();
+        let started = std::time::Instant::now();
+        let job = jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "sh daemon.sh",
+                &workdir,
+                None,
+                JobOwner::default(),
+            )
+            .unwrap();
+        let done = wait_finished(&jobs, job.id).await;
+        assert_eq!(done.status, JobStatus::Exited);
+        assert!(started.elapsed() < Duration::from_secs(4));
+        assert!(jobs.tail(job.id, 1024).unwrap().contains("started"));
+        assert_eq!(processes_running(&["tail", "-f", &pid_arg, &file]), 0);
+
+        let _ = std::fs::remove_dir_all(&workdir);
+    }
+
+    #[tokio::test]
+    async fn job_limits_are_enforced() {
+        let workdir = test_dir("limits");
+        std::fs::write(workdir.join("notes.txt"), "x\n").unwrap();
+        let jobs = Arc::new(ExecJobs::load(workdir.join("jobs")));
+        let mut policy = policy();
+        policy.jobs.max_running = 1;
+        policy.jobs.max_log_bytes = 4;
+        std::fs::write(workdir.join("big.txt"), "0123456789\n").unwrap();
+
+        let first = jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "tail -f notes.txt",
+                &workdir,
+                None,
+                JobOwner::default(),
+            )
+            .unwrap();
+        assert!(jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "pwd",
+                &workdir,
+                None,
+                JobOwner::default()
+            )
+            .is_err());
+        jobs.kill(first.id).unwrap();
+        wait_finished(&jobs, first.id).await;
+
+        let big = jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "head big.txt",
+                &workdir,
+                None,
+                JobOwner::default(),
+            )
+            .unwrap();
+        wait_finished(&jobs, big.id).await;
+        assert_eq!(
+            jobs.tail(big.id, 1024).unwrap(),
+            "0123\n...[log truncated]\n"
+        );
+
+        policy.jobs.enabled = false;
+        assert!(jobs
+            .start(
+                &policy,
+                ExecMode::Base,
+                "pwd",
+                &workdir,
+                None,
+                JobOwner::default()
+            )
+            .is_err());
+        let _ = std::fs::remove_dir_all(&workdir);
+    }
+}
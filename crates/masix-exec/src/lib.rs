@@ -2,6 +2,17 @@
 //!
 //! Guarded command execution for chat/runtime, with Termux-specific helpers.
 
+mod jobs;
+mod pipeline;
+mod rules;
+
+pub use jobs::{ExecJobs, JobInfo, JobOwner, JobStatus, JobsPolicy};
+pub use pipeline::{
+    check_pipeline, parse_pipeline, OutputFile, Pipeline, PipelinePolicy, PipelineStage,
+    StderrTarget,
+};
+pub use rules::{CommandVerdict, ExecRule, ExecRuleSet, ExecRules};
+
 use anyhow::{anyhow, bail, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
@@ -56,6 +67,12 @@ pub struct ExecPolicy {
     pub max_output_chars: usize,
     pub base_allowlist: Vec<String>,
     pub termux_allowlist: Vec<String>,
+    /// Sandboxed pipeline mode for base commands (`[exec.pipeline]`).
+    pub pipeline: PipelinePolicy,
+    /// Argument-level allow/deny rules for base commands (`[exec.rules]`).
+    pub rules: ExecRules,
+    /// Background jobs (`/exec --bg`, `[exec.jobs]`).
+    pub jobs: JobsPolicy,
 }
 
 impl Default for ExecPolicy {
@@ -74,6 +91,9 @@ impl Default for ExecPolicy {
                 .iter()
                 .map(|v| v.to_string())
                 .collect(),
+            pipeline: PipelinePolicy::default(),
+            rules: ExecRules::default(),
+            jobs: JobsPolicy::default(),
         }
     }
 }
@@ -114,12 +134,68 @@ impl ExecResult {
     }
 }
 
-pub async fn run_command(
+/// Evaluate `raw_command` against the policy without running it.
+///
+/// `level` is the caller's permission level (`admin`, `user`, `readonly`)
+/// and selects the per-level exec rules. Syntax and path errors are returned
+/// as `Err`; otherwise there is one verdict per command (pipeline stage).
+pub fn check_command(
     policy: &ExecPolicy,
     mode: ExecMode,
     raw_command: &str,
-    workdir: &Path,
-) -> Result<ExecResult> {
+    level: Option<&str>,
+) -> Result<Vec<CommandVerdict>> {
+    if mode == ExecMode::Base && policy.pipeline.enabled {
+        let pipeline = parse_pipeline(raw_command)?;
+        check_pipeline(policy, &pipeline)?;
+        let allowlist: Vec<&String> = policy
+            .base_allowlist
+            .iter()
+            .chain(policy.pipeline.allowlist.iter())
+            .collect();
+        return Ok(pipeline
+            .stages
+            .iter()
+            .map(|stage| {
+                rules::evaluate(
+                    &policy.rules,
+                    level,
+                    &allowlist,
+                    &stage.program,
+                    &stage.args,
+                )
+            })
+            .collect());
+    }
+
+    let tokens = shlex::split(raw_command).ok_or_else(|| anyhow!("Invalid command syntax"))?;
+    let Some((command, args)) = tokens.split_first() else {
+        bail!("Missing command");
+    };
+    for arg in args {
+        validate_argument(arg)?;
+    }
+
+    let verdict = match mode {
+        ExecMode::Base => {
+            let allowlist: Vec<&String> = policy.base_allowlist.iter().collect();
+            rules::evaluate(&policy.rules, level, &allowlist, command, args)
+        }
+        ExecMode::Termux => {
+            let allowlist: Vec<&String> = policy.termux_allowlist.iter().collect();
+            rules::evaluate(&ExecRules::default(), level, &allowlist, command, args)
+        }
+    };
+    Ok(vec![verdict])
+}
+
+/// Everything `run_command` refuses before starting a process.
+fn ensure_allowed(
+    policy: &ExecPolicy,
+    mode: ExecMode,
+    raw_command: &str,
+    level: Option<&str>,
+) -> Result<()> {
     if !policy.enabled {
         bail!("Exec module is disabled");
     }
@@ -134,25 +210,30 @@ pub async fn run_command(
         bail!("Not running in Termux environment");
     }
 
-    let tokens = shlex::split(raw_command).ok_or_else(|| anyhow!("Invalid command syntax"))?;
-    if tokens.is_empty() {
-        bail!("Missing command");
+    for verdict in check_command(policy, mode, raw_command, level)? {
+        if !verdict.allowed {
+            bail!("{}", verdict.reason);
+        }
     }
+    Ok(())
+}
 
-    let command = tokens[0].clone();
-    let args = tokens[1..].to_vec();
+pub async fn run_command(
+    policy: &ExecPolicy,
+    mode: ExecMode,
+    raw_command: &str,
+    workdir: &Path,
+    level: Option<&str>,
+) -> Result<ExecResult> {
+    ensure_allowed(policy, mode, raw_command, level)?;
 
-    for arg in &args {
-        validate_argument(arg)?;
+    if mode == ExecMode::Base && policy.pipeline.enabled {
+        return pipeline::run_pipeline(policy, raw_command, workdir).await;
     }
 
-    let allowlist = match mode {
-        ExecMode::Base => &policy.base_allowlist,
-        ExecMode::Termux => &policy.termux_allowlist,
-    };
-    if !allowlist.iter().any(|item| item == &command) {
-        bail!("Command '{}' is not in allowlist", command);
-    }
+    let tokens = shlex::split(raw_command).ok_or_else(|| anyhow!("Invalid command syntax"))?;
+    let command = tokens[0].clone();
+    let args = tokens[1..].to_vec();
 
     let mut process = Command::new(&command);
     process
@@ -1042,6 +1123,50 @@ mod tests {
         assert!(validate_argument("a|b").is_err());
     }
 
+    #[test]
+    fn check_command_applies_rules_per_level() {
+        let mut policy = ExecPolicy::default();
+        policy.rules.default = ExecRuleSet::parse(&[], &["ls -R **".to_string()]).unwrap();
+        policy.rules.levels.insert(
+            "admin".to_string(),
+            ExecRuleSet::parse(
+                &[
+                    "git status".to_string(),
+                    "git log --oneline -n *".to_string(),
+                ],
+                &[],
+            )
+            .unwrap(),
+        );
+        let check = |cmd: &str, level: Option<&str>| {
+            check_command(&policy, ExecMode::Base, cmd, level).unwrap()[0].clone()
+        };
+
+        assert!(check("ls -la", Some("user")).allowed);
+        assert!(!check("ls -R", Some("user")).allowed);
+        assert!(!check("git status", Some("user")).allowed);
+        let verdict = check("git log --oneline -n 5", Some("admin"));
+        assert!(verdict.allowed);
+        assert_eq!(verdict.rule.as_deref(), Some("git log --oneline -n *"));
+        assert!(!check("ls -la", Some("admin")).allowed);
+        assert!(!check("ls -R", Some("admin")).allowed);
+        assert!(check_command(&policy, ExecMode::Base, "git log ../x", Some("admin")).is_err());
+
+        policy.pipeline.enabled = true;
+        let verdicts = check_command(
+            &policy,
+            ExecMode::Base,
+            "ls -la | grep rs | rm -rf build",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            verdicts.iter().map(|v| v.allowed).collect::<Vec<_>>(),
+            vec![true, true, false]
+        );
+        assert_eq!(verdicts[2].reason, "Command 'rm' is not in allowlist");
+    }
+
     #[test]
     fn render_boot_script_includes_start_command() {
         let script =
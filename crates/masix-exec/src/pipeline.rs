@@ -0,0 +1,1099 @@
+//! Sandboxed pipeline mode for base exec commands.
+//!
+//! With `[exec.pipeline]` enabled, a base command is parsed as a restricted
+//! pipeline (`a | b`, `< in`, `> out`, `>> out`, `2> err`, `2>&1`) and run
+//! without a shell: no variables, globbing or command substitution. Every
+//! stage must pass the allowlist or exec rules and is started with a cleared
+//! environment, rlimits, `no_new_privs`, a Landlock ruleset (system paths and
+//! `read_only_paths` readable, the workdir writable) and, unless network is
+//! allowed, a private user + network namespace (the last two on Linux only).
+
+use crate::{truncate_output, ExecPolicy, ExecResult};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{PipeWriter, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::{Child, Command};
+use tokio::time::{timeout, timeout_at, Duration, Instant};
+
+const DEFAULT_PIPELINE_ALLOWLIST: &[&str] = &[
+    "cat", "grep", "sort", "uniq", "cut", "tr", "nl", "tac", "stat",
+];
+const DEFAULT_PIPELINE_MAX_STAGES: usize = 6;
+const DEFAULT_PIPELINE_MAX_CPU_SECS: u64 = 10;
+const DEFAULT_PIPELINE_MAX_MEMORY_MB: u64 = 512;
+const DEFAULT_PIPELINE_MAX_FILE_SIZE_MB: u64 = 16;
+/// Extra time to collect output once the stage groups have been killed.
+const OUTPUT_DRAIN_GRACE: Duration = Duration::from_secs(2);
+
+/// Paths every stage may read and execute from, besides `read_only_paths`.
+/// Missing entries are skipped.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const SYSTEM_READ_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/etc/alternatives",
+    "/etc/ld.so.cache",
+    "/etc/ld.so.conf",
+    "/etc/ld.so.conf.d",
+    "/etc/localtime",
+    "/etc/passwd",
+    "/etc/group",
+    "/etc/nsswitch.conf",
+    "/proc",
+    "/sys/devices/system/cpu",
+    "/dev/urandom",
+    "/nix/store",
+    "/data/data/com.termux/files/usr",
+];
+
+/// Parent variables passed to pipeline stages; `HOME` and `TMPDIR` point at the workdir.
+const PIPELINE_ENV_PASSTHROUGH: &[&str] = &["PATH", "LANG", "LC_ALL", "TZ"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelinePolicy {
+    pub enabled: bool,
+    /// Programs allowed only inside the sandbox, on top of `base_allowlist`.
+    pub allowlist: Vec<String>,
+    /// Absolute paths stages may read (files or directories).
+    pub read_only_paths: Vec<PathBuf>,
+    pub max_stages: usize,
+    /// RLIMIT_CPU per stage, seconds.
+    pub max_cpu_secs: u64,
+    /// RLIMIT_AS per stage, MiB.
+    pub max_memory_mb: u64,
+    /// RLIMIT_FSIZE: largest file a stage may write, MiB.
+    pub max_file_size_mb: u64,
+    pub allow_network: bool,
+    pub landlock: bool,
+}
+
+impl Default for PipelinePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowlist: DEFAULT_PIPELINE_ALLOWLIST
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            read_only_paths: Vec::new(),
+            max_stages: DEFAULT_PIPELINE_MAX_STAGES,
+            max_cpu_secs: DEFAULT_PIPELINE_MAX_CPU_SECS,
+            max_memory_mb: DEFAULT_PIPELINE_MAX_MEMORY_MB,
+            max_file_size_mb: DEFAULT_PIPELINE_MAX_FILE_SIZE_MB,
+            allow_network: false,
+            landlock: true,
+        }
+    }
+}
+
+/// Where a stage sends its stderr.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StderrTarget {
+    File {
+        path: String,
+        append: bool,
+    },
+    /// `2>&1`
+    Stdout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputFile {
+    pub path: String,
+    pub append: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineStage {
+    pub program: String,
+    pub args: Vec<String>,
+    pub stderr: Option<StderrTarget>,
+}
+
+/// A parsed command: stages joined by pipes, an optional input file for the
+/// first stage and an optional output file for the last one.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Pipeline {
+    pub stages: Vec<PipelineStage>,
+    pub stdin: Option<String>,
+    pub stdout: Option<OutputFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Pipe,
+    Redirect(RedirectOp),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RedirectOp {
+    In,
+    Out { append: bool },
+    Err { append: bool },
+    ErrToOut,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    fn flush(tokens: &mut Vec<Token>, word: &mut String, in_word: &mut bool, quoted: &mut bool) {
+        if *in_word {
+            tokens.push(Token::Word(std::mem::take(word)));
+        }
+        *in_word = false;
+        *quoted = false;
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => flush(&mut tokens, &mut word, &mut in_word, &mut quoted),
+            '\'' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => bail!("Unterminated double quote"),
+                        },
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated double quote"),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                quoted = true;
+                word.push(chars.next().ok_or_else(|| anyhow!("Trailing backslash"))?);
+            }
+            '|' => {
+                flush(&mut tokens, &mut word, &mut in_word, &mut quoted);
+                if chars.peek() == Some(&'|') {
+                    bail!("'||' is not supported in exec pipelines");
+                }
+                tokens.push(Token::Pipe);
+            }
+            '<' => {
+                flush(&mut tokens, &mut word, &mut in_word, &mut quoted);
+                tokens.push(Token::Redirect(RedirectOp::In));
+            }
+            '>' => {
+                let stderr = in_word && !quoted && word == "2";
+                if stderr {
+                    word.clear();
+                    in_word = false;
+                } else {
+                    flush(&mut tokens, &mut word, &mut in_word, &mut quoted);
+                }
+                let append = chars.next_if_eq(&'>').is_some();
+                let op = if chars.next_if_eq(&'&').is_some() {
+                    if stderr && !append && chars.next_if_eq(&'1').is_some() {
+                        RedirectOp::ErrToOut
+                    } else {
+                        bail!("Only '2>&1' is supported among descriptor redirections");
+                    }
+                } else if stderr {
+                    RedirectOp::Err { append }
+                } else {
+                    RedirectOp::Out { append }
+                };
+                tokens.push(Token::Redirect(op));
+            }
+            ';' | '&' | '`' | '(' | ')' | '$' => {
+                bail!("'{}' is not supported in exec pipelines", c)
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    flush(&mut tokens, &mut word, &mut in_word, &mut quoted);
+    Ok(tokens)
+}
+
+/// Parse `raw_command` into pipeline stages and redirections.
+pub fn parse_pipeline(raw_command: &str) -> Result<Pipeline> {
+    let mut pipeline = Pipeline::default();
+    let mut words: Vec<String> = Vec::new();
+    let mut stderr: Option<StderrTarget> = None;
+    let mut pending_stdout: Option<OutputFile> = None;
+    let mut tokens = tokenize(raw_command)?.into_iter();
+
+    fn finish_stage(
+        words: &mut Vec<String>,
+        stderr: &mut Option<StderrTarget>,
+        pipeline: &mut Pipeline,
+    ) -> Result<()> {
+        if words.is_empty() {
+            bail!("Missing command");
+        }
+        let mut args = std::mem::take(words);
+        let program = args.remove(0);
+        pipeline.stages.push(PipelineStage {
+            program,
+            args,
+            stderr: stderr.take(),
+        });
+        Ok(())
+    }
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => words.push(word),
+            Token::Pipe => {
+                if pending_stdout.is_some() {
+                    bail!("Only the last stage can redirect its output to a file");
+                }
+                finish_stage(&mut words, &mut stderr, &mut pipeline)?;
+            }
+            Token::Redirect(RedirectOp::ErrToOut) => stderr = Some(StderrTarget::Stdout),
+            Token::Redirect(op) => {
+                let Some(Token::Word(path)) = tokens.next() else {
+                    bail!("Redirection needs a file name");
+                };
+                match op {
+                    RedirectOp::In => {
+                        if !pipeline.stages.is_empty() {
+                            bail!("Only the first stage can read its input from a file");
+                        }
+                        pipeline.stdin = Some(path);
+                    }
+                    RedirectOp::Out { append } => {
+                        pending_stdout = Some(OutputFile { path, append })
+                    }
+                    RedirectOp::Err { append } => {
+                        stderr = Some(StderrTarget::File { path, append })
+                    }
+                    RedirectOp::ErrToOut => unreachable!(),
+                }
+            }
+        }
+    }
+    finish_stage(&mut words, &mut stderr, &mut pipeline)?;
+    pipeline.stdout = pending_stdout;
+    Ok(pipeline)
+}
+
+/// Validate stage count and paths against the pipeline policy before anything
+/// runs. Programs are checked per stage by `check_command`.
+pub fn check_pipeline(policy: &ExecPolicy, pipeline: &Pipeline) -> Result<()> {
+    let config = &policy.pipeline;
+    if pipeline.stages.len() > config.max_stages.max(1) {
+        bail!(
+            "Pipeline has {} stages, the limit is {}",
+            pipeline.stages.len(),
+            config.max_stages.max(1)
+        );
+    }
+    for stage in &pipeline.stages {
+        if stage.program.contains('/') {
+            bail!("Programs must be given by name, not path");
+        }
+        for arg in &stage.args {
+            check_pipeline_argument(arg, &config.read_only_paths)?;
+        }
+        if let Some(StderrTarget::File { path, .. }) = &stage.stderr {
+            check_output_path(path)?;
+        }
+    }
+    if let Some(path) = &pipeline.stdin {
+        check_pipeline_argument(path, &config.read_only_paths)?;
+    }
+    if let Some(output) = &pipeline.stdout {
+        check_output_path(&output.path)?;
+    }
+    Ok(())
+}
+
+/// Arguments may name absolute paths only inside `read_only_paths`
+/// (`--opt=/path` included); `..` components are refused everywhere.
+fn check_pipeline_argument(arg: &str, read_only_paths: &[PathBuf]) -> Result<()> {
+    if arg.contains('\0') {
+        bail!("Argument contains null byte");
+    }
+    let value = arg.split_once('=').map(|(_, value)| value);
+    for candidate in std::iter::once(arg).chain(value) {
+        if has_parent_component(candidate) {
+            bail!("Path traversal is not allowed");
+        }
+        if candidate.starts_with('/')
+            && candidate != "/dev/null"
+            && !read_only_paths
+                .iter()
+                .any(|allowed| Path::new(candidate).starts_with(allowed))
+        {
+            bail!(
+                "Path '{}' is outside exec.pipeline.read_only_paths",
+                candidate
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Output files live in the workdir (or are `/dev/null`).
+fn check_output_path(path: &str) -> Result<()> {
+    if path == "/dev/null" {
+        return Ok(());
+    }
+    if path.is_empty() || path.starts_with('/') || has_parent_component(path) {
+        bail!("Output files must be relative paths inside the workdir");
+    }
+    Ok(())
+}
+
+fn has_parent_component(value: &str) -> bool {
+    Path::new(value)
+        .components()
+        .any(|component| component == Component::ParentDir)
+}
+
+/// Where a stage writes its stdout; cloned for `2>&1`.
+enum Sink {
+    Pipe(std::io::PipeWriter),
+    File(std::fs::File),
+}
+
+impl Sink {
+    fn stdio(&self) -> std::io::Result<Stdio> {
+        Ok(match self {
+            Self::Pipe(writer) => writer.try_clone()?.into(),
+            Self::File(file) => file.try_clone()?.into(),
+        })
+    }
+}
+
+/// Redirect files are opened by masix itself, outside the sandbox: resolve
+/// symlinks in the parent directories, require the result under one of
+/// `roots`, and refuse a symlink as the last component.
+fn resolve_redirect(workdir: &Path, path: &str, roots: &[PathBuf]) -> Result<PathBuf> {
+    let target = workdir.join(path);
+    let (Some(parent), Some(name)) = (target.parent(), target.file_name()) else {
+        bail!("Invalid redirect path '{}'", path);
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| anyhow!("Cannot open '{}': {}", path, e))?;
+    let beneath = roots.iter().any(|root| {
+        root.canonicalize()
+            .is_ok_and(|root| parent.starts_with(root))
+    });
+    if !beneath {
+        bail!("Redirect '{}' resolves outside the allowed paths", path);
+    }
+    let resolved = parent.join(name);
+    if resolved
+        .symlink_metadata()
+        .is_ok_and(|meta| meta.file_type().is_symlink())
+    {
+        bail!("Redirect '{}' is a symlink", path);
+    }
+    Ok(resolved)
+}
+
+fn nofollow(options: &mut std::fs::OpenOptions) -> &mut std::fs::OpenOptions {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_NOFOLLOW);
+    }
+    options
+}
+
+fn open_input(policy: &PipelinePolicy, workdir: &Path, path: &str) -> Result<std::fs::File> {
+    let roots: Vec<PathBuf> = std::iter::once(workdir.to_path_buf())
+        .chain(policy.read_only_paths.iter().cloned())
+        .collect();
+    let target = resolve_redirect(workdir, path, &roots)?;
+    nofollow(std::fs::OpenOptions::new().read(true))
+        .open(&target)
+        .map_err(|e| anyhow!("Cannot open '{}': {}", path, e))
+}
+
+fn open_output(workdir: &Path, path: &str, append: bool) -> Result<std::fs::File> {
+    let target = resolve_redirect(workdir, path, &[workdir.to_path_buf()])?;
+    nofollow(
+        std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append),
+    )
+    .open(&target)
+    .map_err(|e| anyhow!("Cannot open '{}' for writing: {}", path, e))
+}
+
+/// Start every stage of a checked pipeline. The last stage writes to
+/// `stdout`/`stderr` unless redirected; the writers are dropped on return, so
+/// their readers see EOF once the children exit.
+pub(crate) fn spawn_pipeline(
+    policy: &ExecPolicy,
+    pipeline: &Pipeline,
+    workdir: &Path,
+    stdout_writer: PipeWriter,
+    stderr_writer: PipeWriter,
+) -> Result<Vec<Child>> {
+    let env = pipeline_env(workdir);
+    let mut next_stdin: Stdio = match &pipeline.stdin {
+        Some(path) => open_input(&policy.pipeline, workdir, path)?.into(),
+        None => Stdio::null(),
+    };
+
+    let mut children: Vec<Child> = Vec::with_capacity(pipeline.stages.len());
+    for (index, stage) in pipeline.stages.iter().enumerate() {
+        let last = index + 1 == pipeline.stages.len();
+        let (sink, following) = if !last {
+            let (reader, writer) = std::io::pipe()?;
+            (Sink::Pipe(writer), Some(reader))
+        } else if let Some(output) = &pipeline.stdout {
+            let file = if output.path == "/dev/null" {
+                std::fs::OpenOptions::new().write(true).open("/dev/null")?
+            } else {
+                open_output(workdir, &output.path, output.append)?
+            };
+            (Sink::File(file), None)
+        } else {
+            (Sink::Pipe(stdout_writer.try_clone()?), None)
+        };
+        let stderr: Stdio = match &stage.stderr {
+            None => stderr_writer.try_clone()?.into(),
+            Some(StderrTarget::Stdout) => sink.stdio()?,
+            Some(StderrTarget::File { path, .. }) if path == "/dev/null" => Stdio::null(),
+            Some(StderrTarget::File { path, append }) => {
+                open_output(workdir, path, *append)?.into()
+            }
+        };
+
+        let mut command = Command::new(&stage.program);
+        command
+            .args(&stage.args)
+            .current_dir(workdir)
+            .env_clear()
+            .envs(env.iter().cloned())
+            .stdin(std::mem::replace(&mut next_stdin, Stdio::null()))
+            .stdout(sink.stdio()?)
+            .stderr(stderr)
+            .kill_on_drop(true);
+        // One group per stage, so background jobs can kill what a stage forks.
+        #[cfg(unix)]
+        command.process_group(0);
+        apply_sandbox(&mut command, &policy.pipeline, workdir)?;
+        let child = command.spawn().map_err(|e| {
+            anyhow!(
+                "Failed to start '{}' in the exec sandbox: {}",
+                stage.program,
+                e
+            )
+        })?;
+        children.push(child);
+        if let Some(reader) = following {
+            next_stdin = reader.into();
+        }
+    }
+    Ok(children)
+}
+
+pub(crate) async fn run_pipeline(
+    policy: &ExecPolicy,
+    raw_command: &str,
+    workdir: &Path,
+) -> Result<ExecResult> {
+    let pipeline = parse_pipeline(raw_command)?;
+    check_pipeline(policy, &pipeline)?;
+    let display = raw_command.trim().to_string();
+
+    let (stdout_reader, stdout_writer) = std::io::pipe()?;
+    let (stderr_reader, stderr_writer) = std::io::pipe()?;
+    let mut children = spawn_pipeline(policy, &pipeline, workdir, stdout_writer, stderr_writer)?;
+    // Ids are gone once a child is reaped; its group may still have members.
+    let groups: Vec<u32> = children.iter().filter_map(Child::id).collect();
+
+    let max_len = policy.max_output_chars.max(256);
+    let max_bytes = (max_len + 1) * 4;
+    let stdout_task = tokio::task::spawn_blocking(move || read_capped(stdout_reader, max_bytes));
+    let stderr_task = tokio::task::spawn_blocking(move || read_capped(stderr_reader, max_bytes));
+
+    let wait_all = async {
+        let mut last_status = None;
+        for child in &mut children {
+            last_status = Some(child.wait().await?);
+        }
+        Ok::<_, std::io::Error>(last_status)
+    };
+    let deadline = Instant::now() + Duration::from_secs(policy.timeout_secs.max(1));
+    let waited = timeout_at(deadline, wait_all).await;
+    let status = match waited {
+        Ok(result) => result.map_err(|e| anyhow!("Failed to wait for pipeline: {}", e))?,
+        Err(_) => {
+            kill_process_groups(&groups);
+            for child in &mut children {
+                let _ = child.start_kill();
+            }
+            return Ok(ExecResult {
+                command: display,
+                exit_code: -1,
+                stdout: String::new(),
+                stderr: String::new(),
+                timed_out: true,
+            });
+        }
+    };
+
+    // A background descendant can hold the pipes open: read until the deadline,
+    // then kill whatever is left in the stage groups so the readers reach EOF.
+    let output = async {
+        (
+            stdout_task.await.unwrap_or_default(),
+            stderr_task.await.unwrap_or_default(),
+        )
+    };
+    tokio::pin!(output);
+    let drained = timeout_at(deadline, &mut output).await;
+    kill_process_groups(&groups);
+    let (stdout, stderr) = match drained {
+        Ok(output) => output,
+        Err(_) => timeout(OUTPUT_DRAIN_GRACE, output)
+            .await
+            .unwrap_or_default(),
+    };
+    Ok(ExecResult {
+        command: display,
+        exit_code: status.and_then(|status| status.code()).unwrap_or(-1),
+        stdout: truncate_output(&String::from_utf8_lossy(&stdout), max_len),
+        stderr: truncate_output(&String::from_utf8_lossy(&stderr), max_len),
+        timed_out: false,
+    })
+}
+
+/// SIGKILL every process group in `groups` (one per stage or job process).
+#[cfg(unix)]
+pub(crate) fn kill_process_groups(groups: &[u32]) {
+    for &group in groups {
+        if let Ok(group) = libc::pid_t::try_from(group) {
+            // SAFETY: plain syscall on a process group id we created.
+            unsafe {
+                libc::killpg(group, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_process_groups(_groups: &[u32]) {}
+
+/// Read to EOF, keeping at most `max_bytes` (the rest is drained so writers never block).
+fn read_capped(mut reader: impl Read, max_bytes: usize) -> Vec<u8> {
+    let mut kept = Vec::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => {
+                let room = max_bytes.saturating_sub(kept.len());
+                kept.extend_from_slice(&buffer[..read.min(room)]);
+            }
+        }
+    }
+    kept
+}
+
+fn pipeline_env(workdir: &Path) -> Vec<(String, String)> {
+    let mut env: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, _)| PIPELINE_ENV_PASSTHROUGH.contains(&key.as_str()))
+        .collect();
+    let workdir = workdir.display().to_string();
+    env.push(("HOME".to_string(), workdir.clone()));
+    env.push(("TMPDIR".to_string(), workdir));
+    env
+}
+
+#[cfg(unix)]
+fn apply_sandbox(command: &mut Command, policy: &PipelinePolicy, workdir: &Path) -> Result<()> {
+    let mib = |value: u64| value.saturating_mul(1024 * 1024);
+    let limits: Vec<(_, libc::rlim_t)> = [
+        (libc::RLIMIT_CPU, policy.max_cpu_secs),
+        (libc::RLIMIT_AS, mib(policy.max_memory_mb)),
+        (libc::RLIMIT_FSIZE, mib(policy.max_file_size_mb)),
+    ]
+    .into_iter()
+    .filter(|(_, value)| *value > 0)
+    .map(|(resource, value)| (resource, value as libc::rlim_t))
+    .collect();
+
+    #[cfg(target_os = "linux")]
+    let mut ruleset = if policy.landlock {
+        Some(landlock_ruleset(policy, workdir)?)
+    } else {
+        None
+    };
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = workdir;
+        if policy.landlock || !policy.allow_network {
+            bail!(
+                "exec.pipeline needs Linux for landlock and network isolation; \
+                 set landlock = false and allow_network = true to run with rlimits only"
+            );
+        }
+    }
+
+    let isolate_network = !policy.allow_network;
+    // Keep the caller's uid/gid inside the new user namespace so files the
+    // stages create in the workdir stay owned by the runtime user.
+    #[cfg(target_os = "linux")]
+    let id_maps = {
+        // SAFETY: getuid/getgid cannot fail.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        [
+            (c"/proc/self/setgroups", b"deny".to_vec()),
+            (
+                c"/proc/self/uid_map",
+                format!("{0} {0} 1", uid).into_bytes(),
+            ),
+            (
+                c"/proc/self/gid_map",
+                format!("{0} {0} 1", gid).into_bytes(),
+            ),
+        ]
+    };
+    // SAFETY: the closure runs in the forked child before exec and only
+    // issues raw syscalls on data prepared by the parent.
+    unsafe {
+        command.pre_exec(move || {
+            #[cfg(target_os = "linux")]
+            if isolate_network {
+                if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                for (path, content) in &id_maps {
+                    write_proc_file(path, content)?;
+                }
+            }
+            for (resource, value) in &limits {
+                let limit = libc::rlimit {
+                    rlim_cur: *value,
+                    rlim_max: *value,
+                };
+                if libc::setrlimit(*resource, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(ruleset) = ruleset.take() {
+                ruleset.restrict_self().map_err(std::io::Error::other)?;
+            }
+            let _ = isolate_network;
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+/// `open` + `write` without allocating, for use between fork and exec.
+#[cfg(target_os = "linux")]
+unsafe fn write_proc_file(path: &std::ffi::CStr, content: &[u8]) -> std::io::Result<()> {
+    let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let written = libc::write(fd, content.as_ptr().cast(), content.len());
+    let result = if written == content.len() as isize {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    };
+    libc::close(fd);
+    result
+}
+
+#[cfg(not(unix))]
+fn apply_sandbox(_command: &mut Command, _policy: &PipelinePolicy, _workdir: &Path) -> Result<()> {
+    bail!("exec.pipeline is not supported on this platform")
+}
+
+/// Read-only system paths and `read_only_paths`, read-write workdir. Refuses
+/// to run when the kernel has no Landlock at all, and warns once when it only
+/// enforces part of the requested access rights.
+#[cfg(target_os = "linux")]
+fn landlock_ruleset(policy: &PipelinePolicy, workdir: &Path) -> Result<landlock::RulesetCreated> {
+    use landlock::{
+        path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr, ABI,
+    };
+
+    let abi = ABI::V5;
+    match landlock_abi_version() {
+        0 => bail!(
+            "exec.pipeline: this kernel does not support Landlock; \
+             set landlock = false to run stages without the filesystem sandbox"
+        ),
+        version if version < 5 => {
+            static WARNED: std::sync::Once = std::sync::Once::new();
+            WARNED.call_once(|| {
+                tracing::warn!(
+                    "exec.pipeline: kernel Landlock ABI v{} < v5, newer access rights are not enforced",
+                    version
+                )
+            });
+        }
+        _ => {}
+    }
+    let readable: Vec<PathBuf> = SYSTEM_READ_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .chain(policy.read_only_paths.iter().cloned())
+        .filter(|path| path.exists())
+        .collect();
+    let writable = [workdir.to_path_buf(), PathBuf::from("/dev/null")];
+    let ruleset = Ruleset::default()
+        .handle_access(AccessFs::from_all(abi))?
+        .create()?
+        .add_rules(path_beneath_rules(readable, AccessFs::from_read(abi)))?
+        .add_rules(path_beneath_rules(writable, AccessFs::from_all(abi)))?;
+    Ok(ruleset)
+}
+
+/// Landlock ABI version of the running kernel, 0 when Landlock is unavailable.
+#[cfg(target_os = "linux")]
+fn landlock_abi_version() -> i64 {
+    static VERSION: std::sync::OnceLock<i64> = std::sync::OnceLock::new();
+    *VERSION.get_or_init(|| {
+        // SAFETY: version query (flag 1), no pointers are dereferenced.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<u8>(),
+                0usize,
+                1u32,
+            )
+        };
+        version.max(0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_pipes_redirects_and_quotes() {
+        let pipeline =
+            parse_pipeline("grep -c 'a|b' < app.log 2>&1 | sort -r >> counts.txt").unwrap();
+        assert_eq!(pipeline.stdin.as_deref(), Some("app.log"));
+        assert_eq!(
+            pipeline.stdout,
+            Some(OutputFile {
+                path: "counts.txt".to_string(),
+                append: true
+            })
+        );
+        assert_eq!(pipeline.stages.len(), 2);
+        assert_eq!(pipeline.stages[0].program, "grep");
+        assert_eq!(pipeline.stages[0].args, words(&["-c", "a|b"]));
+        assert_eq!(pipeline.stages[0].stderr, Some(StderrTarget::Stdout));
+        assert_eq!(pipeline.stages[1].args, words(&["-r"]));
+
+        let quoted = parse_pipeline(r#"grep "say \"hi\"" '2>' x\ y"#).unwrap();
+        assert_eq!(quoted.stages[0].args, words(&["say \"hi\"", "2>", "x y"]));
+
+        for rejected in [
+            "ls; rm x",
+            "ls && pwd",
+            "ls || pwd",
+            "echo $(id)",
+            "echo $HOME",
+            "ls | ",
+            "ls > out | wc",
+            "ls | wc < in",
+            "ls >&2",
+            "grep 'open",
+        ] {
+            assert!(parse_pipeline(rejected).is_err(), "{}", rejected);
+        }
+    }
+
+    #[test]
+    fn check_enforces_paths() {
+        let mut policy = ExecPolicy::default();
+        policy.pipeline.read_only_paths = vec![PathBuf::from("/var/log")];
+        let check = |cmd: &str| check_pipeline(&policy, &parse_pipeline(cmd).unwrap());
+
+        assert!(check("ls -la | grep -c rs | sort").is_ok());
+        assert!(check("grep error /var/log/syslog > errors.txt 2> /dev/null").is_ok());
+        assert!(check("grep 'a..b' notes.txt").is_ok());
+        assert!(check("sort --output=/var/log/x notes.txt").is_ok());
+
+        assert!(check("/bin/ls").is_err());
+        assert!(check("cat /etc/shadow").is_err());
+        assert!(check("cat ../secret").is_err());
+        assert!(check("cat /var/log/../../etc/shadow").is_err());
+        assert!(check("sort --output=/etc/motd x").is_err());
+        assert!(check("ls > /tmp/out").is_err());
+        assert!(check("ls 2> ../err").is_err());
+        assert!(check("cat < /etc/shadow").is_err());
+
+        policy.pipeline.max_stages = 2;
+        let check = |cmd: &str| check_pipeline(&policy, &parse_pipeline(cmd).unwrap());
+        assert!(check("ls | sort | uniq").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn runs_stages_connected_by_pipes() {
+        let workdir = std::env::temp_dir().join(format!(
+            "masix-exec-pipeline-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("clock")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(
+            workdir.join("app.log"),
+            "ok\nerror a\nok\nerror b\nerror a\n",
+        )
+        .unwrap();
+        let mut policy = ExecPolicy {
+            enabled: true,
+            allow_base: true,
+            ..ExecPolicy::default()
+        };
+        policy.pipeline.enabled = true;
+        // Namespaces and Landlock depend on the host kernel; the plumbing is what is tested here.
+        policy.pipeline.allow_network = true;
+        policy.pipeline.landlock = false;
+
+        let result = run_pipeline(
+            &policy,
+            "grep error < app.log | sort | uniq -c | sort -rn > summary.txt",
+            &workdir,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.exit_code, 0, "{:?}", result);
+        let summary = std::fs::read_to_string(workdir.join("summary.txt")).unwrap();
+        let lines: Vec<&str> = summary.lines().map(str::trim).collect();
+        assert_eq!(lines, vec!["2 error a", "1 error b"]);
+
+        let result = run_pipeline(&policy, "cat missing.txt 2>&1 | wc -l", &workdir)
+            .await
+            .unwrap();
+        assert_eq!(result.stdout.trim(), "1");
+        assert!(result.stderr.is_empty());
+
+        std::fs::remove_dir_all(&workdir).ok();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn redirects_do_not_follow_symlinks_out_of_the_workdir() {
+        let root = std::env::temp_dir().join(format!(
+            "masix-exec-redirect-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("clock")
+                .as_nanos()
+        ));
+        let workdir = root.join("work");
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(root.join("outside.txt"), "secret\n").unwrap();
+        std::os::unix::fs::symlink(root.join("outside.txt"), workdir.join("link")).unwrap();
+        std::os::unix::fs::symlink(&root, workdir.join("up")).unwrap();
+        std::fs::write(workdir.join("notes.txt"), "local\n").unwrap();
+        std::os::unix::fs::symlink(workdir.join("notes.txt"), workdir.join("inner")).unwrap();
+        let mut policy = ExecPolicy {
+            enabled: true,
+            allow_base: true,
+            ..ExecPolicy::default()
+        };
+        policy.pipeline.enabled = true;
+        // The parent opens redirects, so no sandbox is needed to reproduce an escape.
+        policy.pipeline.allow_network = true;
+        policy.pipeline.landlock = false;
+
+        for command in [
+            "cat < link",
+            "date > link",
+            "date >> link",
+            "ls 2> link",
+            "cat < up/outside.txt",
+            "date > up/new.txt",
+            "cat < inner",
+        ] {
+            let error = run_pipeline(&policy, command, &workdir).await.unwrap_err();
+            assert!(
+                error.to_string().contains("symlink") || error.to_string().contains("outside"),
+                "{}: {}",
+                command,
+                error
+            );
+        }
+        assert_eq!(
+            std::fs::read_to_string(root.join("outside.txt")).unwrap(),
+            "secret\n"
+        );
+        assert!(!root.join("new.txt").exists());
+
+        let result = run_pipeline(&policy, "cat < notes.txt > copy.txt", &workdir)
+            .await
+            .unwrap();
+        assert_eq!(result.exit_code, 0, "{:?}", result);
+        assert_eq!(
+            std::fs::read_to_string(workdir.join("copy.txt")).unwrap(),
+            "local\n"
+        );
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    /// Processes whose command line is exactly `argv`.
+    #[cfg(target_os = "linux")]
+    fn processes_running(argv: &[&str]) -> usize {
+        let expected = argv.join("\0") + "\0";
+        std::fs::read_dir("/proc")
+            .unwrap()
+            .filter_map(|entry| std::fs::read(entry.ok()?.path().join("cmdline")).ok())
+            .filter(|cmdline| cmdline == expected.as_bytes())
+            .count()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn background_descendants_are_killed_and_cannot_hold_the_output() {
+        let workdir = std::env::temp_dir().join(format!(
+            "masix-exec-background-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("clock")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&workdir).unwrap();
+        // Unique name, so the process scan only sees this test's children.
+        let file = format!("held-{}.txt", std::process::id());
+        std::fs::write(workdir.join(&file), "").unwrap();
+        let mut policy = ExecPolicy {
+            enabled: true,
+            allow_base: true,
+            timeout_secs: 2,
+            ..ExecPolicy::default()
+        };
+        policy.pipeline.enabled = true;
+        policy.pipeline.allow_network = true;
+        policy.pipeline.landlock = false;
+        // The background `tail` inherits stdout; `--pid` ends a leak with the test process.
+        let pid_arg = format!("--pid={}", std::process::id());
+        let tail = ["tail", "-f", pid_arg.as_str(), file.as_str()];
+
+        let started = std::time::Instant::now();
+        let command = format!("sh -c '{} & echo started'", tail.join(" "));
+        let result = run_pipeline(&policy, &command, &workdir).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2) + OUTPUT_DRAIN_GRACE * 2);
+        assert!(!result.timed_out);
+        assert_eq!(result.stdout.trim(), "started");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(processes_running(&tail), 0);
+
+        let command = format!("sh -c '{} & wait'", tail.join(" "));
+        let result = run_pipeline(&policy, &command, &workdir).await.unwrap();
+        assert!(result.timed_out);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(processes_running(&tail), 0);
+
+        std::fs::remove_dir_all(&workdir).ok();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn sandbox_isolates_identity_and_filesystem() {
+        let root = std::env::temp_dir().join(format!(
+            "masix-exec-sandbox-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("clock")
+                .as_nanos()
+        ));
+        let workdir = root.join("work");
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(root.join("outside.txt"), "secret\n").unwrap();
+        std::os::unix::fs::symlink(root.join("outside.txt"), workdir.join("link")).unwrap();
+        std::fs::write(workdir.join("names.txt"), "b\na\n").unwrap();
+        let mut policy = ExecPolicy {
+            enabled: true,
+            allow_base: true,
+            ..ExecPolicy::default()
+        };
+        policy.pipeline.enabled = true;
+        policy.pipeline.read_only_paths = vec![PathBuf::from("/proc")];
+        let landlock_active = landlock_abi_version() > 0;
+        policy.pipeline.landlock = landlock_active;
+
+        let result = match run_pipeline(&policy, "cat /proc/self/uid_map", &workdir).await {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("skipping: user namespaces unavailable ({})", e);
+                std::fs::remove_dir_all(&root).ok();
+                return;
+            }
+        };
+        // SAFETY: getuid cannot fail.
+        let uid = unsafe { libc::getuid() };
+        let mapping: Vec<&str> = result.stdout.split_whitespace().collect();
+        assert_eq!(
+            mapping,
+            vec![uid.to_string(), uid.to_string(), "1".to_string()]
+        );
+
+        let result = run_pipeline(&policy, "sort -o sorted.txt names.txt", &workdir)
+            .await
+            .unwrap();
+        assert_eq!(result.exit_code, 0, "{:?}", result);
+        assert_eq!(
+            std::fs::read_to_string(workdir.join("sorted.txt")).unwrap(),
+            "a\nb\n"
+        );
+
+        if landlock_active {
+            // The argument check cannot see through the symlink; Landlock does.
+            let result = run_pipeline(&policy, "cat link", &workdir).await.unwrap();
+            assert_ne!(result.exit_code, 0);
+            assert!(!result.stdout.contains("secret"));
+        }
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
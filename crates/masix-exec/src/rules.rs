@@ -0,0 +1,199 @@
+//! Argument-level exec rules.
+//!
+//! The rule grammar lives in `masix-config` (see [`ExecRule`]); this module
+//! groups rules per permission level and evaluates commands against them.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub use masix_config::ExecRule;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecRuleSet {
+    pub allow: Vec<ExecRule>,
+    pub deny: Vec<ExecRule>,
+}
+
+impl ExecRuleSet {
+    pub fn parse(allow: &[String], deny: &[String]) -> Result<Self> {
+        let parse = |entries: &[String]| {
+            entries
+                .iter()
+                .map(|entry| ExecRule::parse(entry))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            allow: parse(allow)?,
+            deny: parse(deny)?,
+        })
+    }
+}
+
+/// Rules for base commands. Without allow rules for a level, the program
+/// name allowlist applies; deny rules always win.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecRules {
+    /// Applies to every permission level.
+    pub default: ExecRuleSet,
+    /// Per level (`admin`, `user`, `readonly`): non-empty `allow` replaces
+    /// the default allow rules, `deny` adds to the default deny rules.
+    pub levels: HashMap<String, ExecRuleSet>,
+}
+
+impl ExecRules {
+    pub fn is_empty(&self) -> bool {
+        self.default.allow.is_empty()
+            && self.default.deny.is_empty()
+            && self
+                .levels
+                .values()
+                .all(|set| set.allow.is_empty() && set.deny.is_empty())
+    }
+
+    /// Effective allow and deny rules for `level` (`None`: default set only).
+    fn for_level(&self, level: Option<&str>) -> (&[ExecRule], Vec<&ExecRule>) {
+        let level_set = level.and_then(|level| self.levels.get(level));
+        let allow = match level_set {
+            Some(set) if !set.allow.is_empty() => &set.allow,
+            _ => &self.default.allow,
+        };
+        let deny = self
+            .default
+            .deny
+            .iter()
+            .chain(level_set.into_iter().flat_map(|set| set.deny.iter()))
+            .collect();
+        (allow, deny)
+    }
+}
+
+/// Outcome for one command (one pipeline stage).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandVerdict {
+    pub command: String,
+    pub allowed: bool,
+    /// The deny or allow rule that decided, `None` for the program allowlist.
+    pub rule: Option<String>,
+    pub reason: String,
+}
+
+pub(crate) fn evaluate(
+    rules: &ExecRules,
+    level: Option<&str>,
+    allowlist: &[&String],
+    program: &str,
+    args: &[String],
+) -> CommandVerdict {
+    let command = crate::join_for_display(program, args);
+    let (allow, deny) = rules.for_level(level);
+    if let Some(rule) = deny.iter().find(|rule| rule.matches(program, args)) {
+        return CommandVerdict {
+            reason: format!(
+                "Command '{}' is denied by exec rule `{}`",
+                command,
+                rule.as_str()
+            ),
+            command,
+            allowed: false,
+            rule: Some(rule.as_str().to_string()),
+        };
+    }
+    if !allow.is_empty() {
+        return match allow.iter().find(|rule| rule.matches(program, args)) {
+            Some(rule) => CommandVerdict {
+                reason: format!("allowed by exec rule `{}`", rule.as_str()),
+                command,
+                allowed: true,
+                rule: Some(rule.as_str().to_string()),
+            },
+            None => CommandVerdict {
+                reason: format!("Command '{}' matches no exec allow rule", command),
+                command,
+                allowed: false,
+                rule: None,
+            },
+        };
+    }
+    if allowlist.iter().any(|item| item.as_str() == program) {
+        CommandVerdict {
+            reason: "allowed by program allowlist".to_string(),
+            command,
+            allowed: true,
+            rule: None,
+        }
+    } else {
+        CommandVerdict {
+            reason: format!("Command '{}' is not in allowlist", program),
+            command,
+            allowed: false,
+            rule: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(command: &str) -> (String, Vec<String>) {
+        let mut tokens = shlex::split(command).unwrap();
+        let program = tokens.remove(0);
+        (program, tokens)
+    }
+
+    #[test]
+    fn deny_overrides_allow_and_levels_refine_the_default() {
+        let rules = ExecRules {
+            default: ExecRuleSet::parse(
+                &["git status".to_string(), "git log **".to_string()],
+                &["git log -p **".to_string()],
+            )
+            .unwrap(),
+            levels: HashMap::from([
+                (
+                    "admin".to_string(),
+                    ExecRuleSet::parse(&["git **".to_string()], &["git push **".to_string()])
+                        .unwrap(),
+                ),
+                (
+                    "readonly".to_string(),
+                    ExecRuleSet::parse(&[], &["* **".to_string()]).unwrap(),
+                ),
+            ]),
+        };
+        let check = |level: Option<&str>, command: &str| {
+            let (program, args) = argv(command);
+            evaluate(&rules, level, &[], &program, &args)
+        };
+
+        assert!(check(Some("user"), "git log --oneline").allowed);
+        assert!(!check(Some("user"), "git log -p HEAD").allowed);
+        assert!(!check(Some("user"), "git commit -m x").allowed);
+        assert!(!check(None, "git diff").allowed);
+
+        let admin = check(Some("admin"), "git commit -m x");
+        assert!(admin.allowed);
+        assert_eq!(admin.rule.as_deref(), Some("git **"));
+        let push = check(Some("admin"), "git push origin main");
+        assert!(!push.allowed);
+        assert_eq!(push.rule.as_deref(), Some("git push **"));
+        assert!(!check(Some("admin"), "git log -p").allowed);
+
+        assert!(!check(Some("readonly"), "git status").allowed);
+
+        // Without allow rules the program allowlist decides.
+        let deny_only = ExecRules {
+            default: ExecRuleSet::parse(&[], &["ls -R **".to_string()]).unwrap(),
+            levels: HashMap::new(),
+        };
+        let ls = "ls".to_string();
+        let (program, args) = argv("ls -la");
+        assert!(evaluate(&deny_only, None, &[&ls], &program, &args).allowed);
+        let (program, args) = argv("ls -R .");
+        assert!(!evaluate(&deny_only, None, &[&ls], &program, &args).allowed);
+        let (program, args) = argv("whoami");
+        let verdict = evaluate(&deny_only, None, &[&ls], &program, &args);
+        assert_eq!(verdict.reason, "Command 'whoami' is not in allowlist");
+    }
+}
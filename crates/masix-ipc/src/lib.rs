@@ -4,8 +4,9 @@
 
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, oneshot};
 
 static NEXT_TRACE_COUNTER: AtomicU64 = AtomicU64::new(1);
 
@@ -73,6 +74,49 @@ pub struct OutboundMessage {
     pub edit_message_id: Option<i64>,
     pub inline_keyboard: Option<Vec<Vec<InlineButton>>>,
     pub chat_action: Option<String>,
+    /// Image to send instead of a text message; `text` becomes its caption.
+    pub photo: Option<OutboundPhoto>,
+    /// Told the id of the message showing `text`, so it can be edited later.
+    pub delivered: Option<DeliveryReceipt>,
+    /// Answers a button press; with an empty `text` nothing else is sent.
+    pub callback_answer: Option<CallbackAnswer>,
+}
+
+/// Callback data of tool approval buttons. Adapters leave these queries to the
+/// runtime, which answers them with the decision.
+pub const TOOL_APPROVAL_CALLBACK_PREFIX: &str = "approve:";
+
+/// Reply to a callback query: stops the button's loading state, with an optional toast.
+#[derive(Debug, Clone)]
+pub struct CallbackAnswer {
+    pub query_id: String,
+    pub text: Option<String>,
+}
+
+/// One-shot report of the channel message id an outbound message ended up in.
+#[derive(Debug, Clone)]
+pub struct DeliveryReceipt(Arc<Mutex<Option<oneshot::Sender<i64>>>>);
+
+impl DeliveryReceipt {
+    pub fn new() -> (Self, oneshot::Receiver<i64>) {
+        let (sender, receiver) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(sender)))), receiver)
+    }
+
+    /// Only the first call is reported; adapters that cannot tell the id
+    /// simply drop the receipt.
+    pub fn deliver(&self, message_id: i64) {
+        let sender = self.0.lock().ok().and_then(|mut sender| sender.take());
+        if let Some(sender) = sender {
+            let _ = sender.send(message_id);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboundPhoto {
+    pub mime_type: String,
+    pub data: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -172,6 +216,15 @@ impl Default for EventBus {
 mod tests {
     use super::*;
 
+    #[test]
+    fn delivery_receipt_reports_first_id_only() {
+        let (receipt, mut receiver) = DeliveryReceipt::new();
+        let copy = receipt.clone();
+        copy.deliver(42);
+        receipt.deliver(43);
+        assert_eq!(receiver.try_recv().unwrap(), 42);
+    }
+
     #[test]
     fn envelope_has_schema_version() {
         let env = Envelope::new(
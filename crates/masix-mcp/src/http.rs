@@ -0,0 +1,1119 @@
+//! Remote MCP transports: Streamable HTTP and the legacy HTTP+SSE variant.
+
+use crate::{fail_pending, IncomingSender, PendingMap};
+use anyhow::{anyhow, bail, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{StatusCode, Url};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+use tracing::{debug, error, warn};
+
+const SESSION_HEADER: &str = "mcp-session-id";
+const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+const ERROR_BODY_PREVIEW_CHARS: usize = 200;
+
+/// Remote endpoint settings for the HTTP transports.
+#[derive(Debug, Clone, Default)]
+pub struct McpHttpOptions {
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub auth_token: Option<String>,
+    /// Use the legacy HTTP+SSE transport instead of Streamable HTTP.
+    pub legacy_sse: bool,
+}
+
+/// Legacy transport: responses come back on the long-lived GET event stream.
+struct LegacyChannel {
+    endpoint: Url,
+    pending: PendingMap,
+    /// Set once the event stream ends; no response can arrive after that.
+    closed: Arc<AtomicBool>,
+}
+
+pub(crate) struct HttpLink {
+    name: String,
+    client: reqwest::Client,
+    url: Url,
+    headers: HeaderMap,
+    session_id: Mutex<Option<String>>,
+    protocol_version: Mutex<Option<String>>,
+    /// Last `initialize` request, replayed when the server drops the session.
+    handshake: Mutex<Option<Value>>,
+    /// Serializes session re-initialization across concurrent requests.
+    reinitializing: Mutex<()>,
+    legacy: Option<LegacyChannel>,
+    incoming: IncomingSender,
+}
+
+impl HttpLink {
+    pub(crate) async fn connect(
+        name: &str,
+        options: &McpHttpOptions,
+        incoming: IncomingSender,
+    ) -> Result<Self> {
+        let url = Url::parse(options.url.trim())
+            .map_err(|e| anyhow!("Invalid MCP url '{}': {}", options.url, e))?;
+        let headers = build_headers(options)?;
+        let client = reqwest::Client::builder().build()?;
+
+        let legacy = if options.legacy_sse {
+            Some(open_legacy_stream(name, &client, &url, &headers, incoming.clone()).await?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            client,
+            url,
+            headers,
+            session_id: Mutex::new(None),
+            protocol_version: Mutex::new(None),
+            handshake: Mutex::new(None),
+            reinitializing: Mutex::new(()),
+            legacy,
+            incoming,
+        })
+    }
+
+    pub(crate) async fn set_protocol_version(&self, version: &str) {
+        *self.protocol_version.lock().await = Some(version.to_string());
+    }
+
+    /// A legacy link whose event stream ended can no longer receive responses.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.legacy
+            .as_ref()
+            .is_some_and(|legacy| legacy.closed.load(Ordering::SeqCst))
+    }
+
+    pub(crate) async fn request(&self, id: u64, payload: &Value) -> Result<Value> {
+        if payload.get("method").and_then(|v| v.as_str()) == Some("initialize") {
+            *self.handshake.lock().await = Some(payload.clone());
+        }
+        if let Some(legacy) = &self.legacy {
+            let (tx, rx) = oneshot::channel::<Value>();
+            legacy.pending.lock().await.insert(id, tx);
+            // Checked after the insert so a close racing with it still drains this id.
+            if self.is_closed() {
+                legacy.pending.lock().await.remove(&id);
+                bail!("MCP server '{}' SSE stream closed", self.name);
+            }
+            if let Err(e) = self.post(&legacy.endpoint, payload).await {
+                legacy.pending.lock().await.remove(&id);
+                return Err(e);
+            }
+            return rx.await.map_err(|_| {
+                anyhow!(
+                    "MCP response channel closed (server='{}', id={})",
+                    self.name,
+                    id
+                )
+            });
+        }
+
+        let response = self.post(&self.url, payload).await?;
+        self.read_response(response, id).await
+    }
+
+    async fn read_response(&self, response: reqwest::Response, id: u64) -> Result<Value> {
+        if is_event_stream(response.headers()) {
+            self.read_streamed_response(response, id).await
+        } else {
+            let body: Value = response.json().await?;
+            select_response(body, id).ok_or_else(|| {
+                anyhow!(
+                    "MCP server '{}' returned no response for id {}",
+                    self.name,
+                    id
+                )
+            })
+        }
+    }
+
+    pub(crate) async fn notify(&self, payload: &Value) -> Result<()> {
+        let target = self
+            .legacy
+            .as_ref()
+            .map(|legacy| &legacy.endpoint)
+            .unwrap_or(&self.url);
+        self.post(target, payload).await.map(|_| ())
+    }
+
+    pub(crate) async fn forget(&self, id: u64) {
+        if let Some(legacy) = &self.legacy {
+            legacy.pending.lock().await.remove(&id);
+        }
+    }
+
+    /// POST within the current session. A 404 for a session we sent means the
+    /// server dropped it: redo `initialize` once and retry the request.
+    async fn post(&self, target: &Url, payload: &Value) -> Result<reqwest::Response> {
+        let session = self.session_id.lock().await.clone();
+        let response = self.send(target, payload).await?;
+        let Some(expired) = session.filter(|_| response.status() == StatusCode::NOT_FOUND) else {
+            return self.ensure_success(response).await;
+        };
+        if payload.get("method").and_then(|v| v.as_str()) == Some("initialize") {
+            return self.ensure_success(response).await;
+        }
+        warn!(
+            "MCP server '{}' expired session {}; re-initializing",
+            self.name, expired
+        );
+        self.reinitialize(&expired).await?;
+        let response = self.send(target, payload).await?;
+        self.ensure_success(response).await
+    }
+
+    /// Replay the recorded handshake unless another request already did.
+    async fn reinitialize(&self, expired: &str) -> Result<()> {
+        let _guard = self.reinitializing.lock().await;
+        {
+            let mut session = self.session_id.lock().await;
+            if session.as_deref() != Some(expired) {
+                return Ok(());
+            }
+            *session = None;
+        }
+        let Some(handshake) = self.handshake.lock().await.clone() else {
+            bail!(
+                "MCP server '{}' dropped the session before initialize",
+                self.name
+            );
+        };
+        let id = handshake.get("id").and_then(|v| v.as_u64()).unwrap_or(0);
+        let response = self.send(&self.url, &handshake).await?;
+        let response = self.ensure_success(response).await?;
+        let reply = self.read_response(response, id).await?;
+        if let Some(error) = reply.get("error") {
+            bail!(
+                "MCP server '{}' refused re-initialization: {:?}",
+                self.name,
+                error
+            );
+        }
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        let response = self.send(&self.url, &initialized).await?;
+        self.ensure_success(response).await?;
+        Ok(())
+    }
+
+    async fn send(&self, target: &Url, payload: &Value) -> Result<reqwest::Response> {
+        let mut request = self
+            .client
+            .post(target.clone())
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(payload);
+        if let Some(session) = self.session_id.lock().await.clone() {
+            request = request.header(SESSION_HEADER, session);
+        }
+        if let Some(version) = self.protocol_version.lock().await.clone() {
+            request = request.header(PROTOCOL_VERSION_HEADER, version);
+        }
+
+        let response = request.send().await?;
+        if let Some(session) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            *self.session_id.lock().await = Some(session.to_string());
+        }
+        Ok(response)
+    }
+
+    async fn ensure_success(&self, response: reqwest::Response) -> Result<reqwest::Response> {
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let preview: String = body.chars().take(ERROR_BODY_PREVIEW_CHARS).collect();
+            bail!(
+                "MCP server '{}' HTTP {}: {}",
+                self.name,
+                status,
+                preview.trim()
+            );
+        }
+        Ok(response)
+    }
+
+    /// Streamable HTTP may answer a POST with an SSE stream; the response is the
+    /// event carrying our id, anything before it is a server notification.
+    async fn read_streamed_response(
+        &self,
+        mut response: reqwest::Response,
+        id: u64,
+    ) -> Result<Value> {
+        let mut parser = SseParser::default();
+        while let Some(chunk) = response.chunk().await? {
+            for event in parser.push(&chunk) {
+                let Ok(message) = serde_json::from_str::<Value>(&event.data) else {
+                    debug!("MCP server '{}' sent non-JSON SSE data", self.name);
+                    continue;
+                };
+                if message.get("method").is_some() {
+                    let _ = self.incoming.send(message);
+                } else if let Some(found) = select_response(message, id) {
+                    return Ok(found);
+                }
+            }
+        }
+        Err(anyhow!(
+            "MCP server '{}' closed the event stream before answering id {}",
+            self.name,
+            id
+        ))
+    }
+}
+
+fn build_headers(options: &McpHttpOptions) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for (key, value) in &options.headers {
+        let name = HeaderName::from_bytes(key.trim().as_bytes())
+            .map_err(|e| anyhow!("Invalid MCP header name '{}': {}", key, e))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|e| anyhow!("Invalid MCP header value for '{}': {}", key, e))?;
+        headers.insert(name, value);
+    }
+    if let Some(token) = options
+        .auth_token
+        .as_deref()
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+    {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|e| anyhow!("Invalid MCP auth token: {}", e))?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    Ok(headers)
+}
+
+fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.starts_with("text/event-stream"))
+        .unwrap_or(false)
+}
+
+/// Pick the JSON-RPC response for `id` out of a single message or a batch.
+fn select_response(message: Value, id: u64) -> Option<Value> {
+    match message {
+        Value::Array(items) => items.into_iter().find_map(|item| select_response(item, id)),
+        message => {
+            let matches = message.get("id").and_then(|v| v.as_u64()) == Some(id)
+                && (message.get("result").is_some() || message.get("error").is_some());
+            matches.then_some(message)
+        }
+    }
+}
+
+/// Open the GET event stream and wait for the `endpoint` event that names the POST URL.
+async fn open_legacy_stream(
+    name: &str,
+    client: &reqwest::Client,
+    url: &Url,
+    headers: &HeaderMap,
+    incoming: IncomingSender,
+) -> Result<LegacyChannel> {
+    let response = client
+        .get(url.clone())
+        .headers(headers.clone())
+        .header(ACCEPT, "text/event-stream")
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        bail!(
+            "MCP server '{}' refused SSE stream: HTTP {}",
+            name,
+            response.status()
+        );
+    }
+
+    let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+    let closed = Arc::new(AtomicBool::new(false));
+    let (endpoint_tx, endpoint_rx) = oneshot::channel::<String>();
+    tokio::spawn(route_legacy_events(
+        name.to_string(),
+        response,
+        endpoint_tx,
+        Arc::clone(&pending),
+        Arc::clone(&closed),
+        incoming,
+    ));
+
+    let endpoint = endpoint_rx
+        .await
+        .map_err(|_| anyhow!("MCP server '{}' closed SSE stream before `endpoint`", name))?;
+    let endpoint = url
+        .join(endpoint.trim())
+        .map_err(|e| anyhow!("MCP server '{}' sent invalid endpoint: {}", name, e))?;
+    // The POSTs carry the configured auth headers, so never follow the server elsewhere.
+    if endpoint.origin() != url.origin() {
+        bail!(
+            "MCP server '{}' sent endpoint {} outside the configured origin",
+            name,
+            endpoint
+        );
+    }
+    debug!("MCP server '{}' SSE endpoint: {}", name, endpoint);
+    Ok(LegacyChannel {
+        endpoint,
+        pending,
+        closed,
+    })
+}
+
+/// Route stream events until the stream ends, then mark the link closed (the
+/// server reports `failed`) and fail every waiting request.
+async fn route_legacy_events(
+    server_name: String,
+    mut response: reqwest::Response,
+    endpoint_tx: oneshot::Sender<String>,
+    pending: PendingMap,
+    closed: Arc<AtomicBool>,
+    incoming: IncomingSender,
+) {
+    let mut endpoint_tx = Some(endpoint_tx);
+    let mut parser = SseParser::default();
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => {
+                error!("MCP server '{}' SSE stream closed", server_name);
+                break;
+            }
+            Err(e) => {
+                error!("MCP server '{}' SSE read error: {}", server_name, e);
+                break;
+            }
+        };
+        for event in parser.push(&chunk) {
+            if event.event == "endpoint" {
+                if let Some(tx) = endpoint_tx.take() {
+                    let _ = tx.send(event.data);
+                }
+                continue;
+            }
+            let Ok(message) = serde_json::from_str::<Value>(&event.data) else {
+                debug!("MCP server '{}' sent non-JSON SSE data", server_name);
+                continue;
+            };
+            if message.get("method").is_some() {
+                let _ = incoming.send(message);
+            } else if let Some(id) = message.get("id").and_then(|v| v.as_u64()) {
+                let sender = { pending.lock().await.remove(&id) };
+                if let Some(tx) = sender {
+                    let _ = tx.send(message);
+                }
+            }
+        }
+    }
+    closed.store(true, Ordering::SeqCst);
+    fail_pending(&server_name, &pending).await;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// Incremental `text/event-stream` parser; chunks may split lines anywhere.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            let line = String::from_utf8_lossy(&line);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event: self.event.take().unwrap_or_else(|| "message".to_string()),
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+                self.event = None;
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line.as_ref(), ""),
+            };
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{McpHttpOptions, SseEvent, SseParser};
+    use crate::{McpCallLimits, McpCallOptions, McpServer, McpServerState};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+
+    struct StandInRequest {
+        method: String,
+        path: String,
+        headers: HashMap<String, String>,
+        body: Value,
+    }
+
+    async fn read_request(stream: &mut BufReader<TcpStream>) -> StandInRequest {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts.next().unwrap_or_default().to_string();
+        let mut headers = HashMap::new();
+        loop {
+            let mut header = String::new();
+            stream.read_line(&mut header).await.unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = header.split_once(':') {
+                headers.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+        let length: usize = headers
+            .get("content-length")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0u8; length];
+        stream.read_exact(&mut body).await.unwrap();
+        StandInRequest {
+            method,
+            path,
+            headers,
+            body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+        }
+    }
+
+    async fn respond(
+        stream: &mut BufReader<TcpStream>,
+        status: &str,
+        extra: &str,
+        content_type: &str,
+        body: &str,
+    ) {
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n",
+            status,
+            content_type,
+            body.len(),
+            extra
+        );
+        let inner = stream.get_mut();
+        inner.write_all(head.as_bytes()).await.unwrap();
+        inner.write_all(body.as_bytes()).await.unwrap();
+        inner.shutdown().await.ok();
+    }
+
+    fn result_for(request: &StandInRequest) -> Value {
+        let result = match request.body["method"].as_str().unwrap_or_default() {
+            "initialize" if request.path == "/v2" => json!({
+                "protocolVersion": request.body["params"]["protocolVersion"],
+                "capabilities": { "tools": { "listChanged": true }, "prompts": {} }
+            }),
+            "initialize" => json!({ "protocolVersion": "2024-11-05", "capabilities": {} }),
+            "prompts/list" => match request.body["params"]["cursor"].as_str() {
+                None => json!({
+                    "prompts": [{ "name": "review", "arguments": [{ "name": "lang", "required": true }] }],
+                    "nextCursor": "page-2"
+                }),
+                Some(_) => json!({ "prompts": [{ "name": "summary" }] }),
+            },
+            "prompts/get" => json!({
+                "messages": [{
+                    "role": "user",
+                    "content": {
+                        "type": "text",
+                        "text": format!(
+                            "{}|{}",
+                            request.body["params"]["arguments"]["lang"].as_str().unwrap_or_default(),
+                            request.headers.get("mcp-protocol-version").cloned().unwrap_or_default()
+                        )
+                    }
+                }]
+            }),
+            "tools/list" => json!({
+                "tools": [{ "name": "echo", "description": "Echo", "input_schema": {} }]
+            }),
+            "tools/call" => json!({
+                "content": [{
+                    "type": "text",
+                    "text": format!(
+                        "{}|{}",
+                        request.headers.get("authorization").cloned().unwrap_or_default(),
+                        request.headers.get("mcp-session-id").cloned().unwrap_or_default()
+                    )
+                }]
+            }),
+            _ => json!({}),
+        };
+        json!({ "jsonrpc": "2.0", "id": request.body["id"], "result": result })
+    }
+
+    /// Streamable HTTP stand-in: JSON for most calls, an SSE stream for tools/list.
+    /// Returns the endpoint and a counter of `tools/list` requests served.
+    async fn spawn_streamable_server() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let tool_lists = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&tool_lists);
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let counter = Arc::clone(&counter);
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(socket);
+                    let request = read_request(&mut stream).await;
+                    if request.body.get("id").is_none() {
+                        respond(&mut stream, "202 Accepted", "", "application/json", "").await;
+                        return;
+                    }
+                    let response = result_for(&request).to_string();
+                    match request.body["method"].as_str() {
+                        Some("initialize") => {
+                            respond(
+                                &mut stream,
+                                "200 OK",
+                                "Mcp-Session-Id: session-1\r\n",
+                                "application/json",
+                                &response,
+                            )
+                            .await
+                        }
+                        Some("tools/list") => {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            let body = format!(
+                                ": keepalive\r\nevent: message\r\ndata: {}\r\n\r\ndata: {}\r\n\r\n",
+                                json!({ "jsonrpc": "2.0", "method": "notifications/progress" }),
+                                response
+                            );
+                            respond(&mut stream, "200 OK", "", "text/event-stream", &body).await
+                        }
+                        _ => {
+                            respond(&mut stream, "200 OK", "", "application/json", &response).await
+                        }
+                    }
+                });
+            }
+        });
+        (format!("http://{}/mcp", addr), tool_lists)
+    }
+
+    /// Legacy SSE stand-in: responses are pushed on the GET stream, POSTs get 202.
+    /// The `slow` tool only reports progress and never answers. Returns the
+    /// endpoint and the client notifications received.
+    async fn spawn_legacy_server() -> (String, mpsc::UnboundedReceiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (events_tx, events_rx) = mpsc::unbounded_channel::<String>();
+        let events_rx = Arc::new(tokio::sync::Mutex::new(Some(events_rx)));
+        let (notified_tx, notified_rx) = mpsc::unbounded_channel::<Value>();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let events_tx = events_tx.clone();
+                let events_rx = Arc::clone(&events_rx);
+                let notified_tx = notified_tx.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(socket);
+                    let request = read_request(&mut stream).await;
+                    if request.method == "GET" && request.path == "/sse" {
+                        let mut rx = events_rx.lock().await.take().unwrap();
+                        let inner = stream.get_mut();
+                        inner
+                            .write_all(
+                                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nevent: endpoint\ndata: /messages?session=1\n\n",
+                            )
+                            .await
+                            .unwrap();
+                        while let Some(message) = rx.recv().await {
+                            let frame = format!("event: message\ndata: {}\n\n", message);
+                            if inner.write_all(frame.as_bytes()).await.is_err() {
+                                break;
+                            }
+                        }
+                        return;
+                    }
+                    assert_eq!(request.path, "/messages?session=1");
+                    let params = &request.body["params"];
+                    if request.body["method"] == "tools/call" && params["name"] == "slow" {
+                        let progress = json!({
+                            "jsonrpc": "2.0",
+                            "method": "notifications/progress",
+                            "params": {
+                                "progressToken": params["_meta"]["progressToken"],
+                                "progress": 1,
+                                "total": 4,
+                                "message": "indexing"
+                            }
+                        });
+                        events_tx.send(progress.to_string()).unwrap();
+                    } else if request.body["method"] == "tools/call" {
+                        let changed = json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" });
+                        events_tx.send(changed.to_string()).unwrap();
+                        events_tx.send(result_for(&request).to_string()).unwrap();
+                    } else if request.body.get("id").is_some() {
+                        events_tx.send(result_for(&request).to_string()).unwrap();
+                    } else {
+                        let _ = notified_tx.send(request.body.clone());
+                    }
+                    respond(&mut stream, "202 Accepted", "", "text/plain", "").await;
+                });
+            }
+        });
+        (format!("http://{}/sse", addr), notified_rx)
+    }
+
+    fn first_text(result: &crate::ToolResult) -> String {
+        match result.content.first() {
+            Some(crate::ToolContent::Text { text }) => text.clone(),
+            other => panic!("unexpected content: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sse_parser_handles_split_chunks_and_multiline_data() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"event: endpoint\r\nda").is_empty());
+        assert_eq!(
+            parser.push(b"ta: /messages\r\n\r\n: ping\n\ndata: a\ndata: b\n\n"),
+            vec![
+                SseEvent {
+                    event: "endpoint".to_string(),
+                    data: "/messages".to_string()
+                },
+                SseEvent {
+                    event: "message".to_string(),
+                    data: "a\nb".to_string()
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn streamable_http_server_lists_and_calls_tools() {
+        let (url, _) = spawn_streamable_server().await;
+        let server = McpServer::connect(
+            "remote".to_string(),
+            McpHttpOptions {
+                url,
+                auth_token: Some("secret".to_string()),
+                ..Default::default()
+            },
+            5,
+            5,
+        )
+        .await
+        .expect("connect");
+
+        let tools = server.list_tools().await.expect("tools");
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "echo");
+
+        let result = server.call_tool("echo", json!({})).await.expect("call");
+        assert_eq!(first_text(&result), "Bearer secret|session-1");
+    }
+
+    #[tokio::test]
+    async fn tool_catalog_is_cached_until_refreshed() {
+        let (url, tool_lists) = spawn_streamable_server().await;
+        let connect = |ttl| {
+            let url = url.clone();
+            async move {
+                McpServer::connect(
+                    "cached".to_string(),
+                    McpHttpOptions {
+                        url,
+                        ..Default::default()
+                    },
+                    5,
+                    5,
+                )
+                .await
+                .expect("connect")
+                .with_tool_cache_ttl(ttl)
+            }
+        };
+
+        let cached = connect(300).await;
+        cached.list_tools().await.expect("tools");
+        cached.list_tools().await.expect("tools");
+        assert_eq!(tool_lists.load(Ordering::SeqCst), 1);
+        let revision = cached.tools_revision();
+        cached.refresh_tools().await.expect("refresh");
+        assert_eq!(tool_lists.load(Ordering::SeqCst), 2);
+        assert!(cached.tools_revision() > revision);
+
+        let uncached = connect(0).await;
+        uncached.list_tools().await.expect("tools");
+        uncached.list_tools().await.expect("tools");
+        assert_eq!(tool_lists.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn legacy_sse_server_routes_responses_from_event_stream() {
+        let (url, _) = spawn_legacy_server().await;
+        let server = McpServer::connect(
+            "legacy".to_string(),
+            McpHttpOptions {
+                url,
+                headers: HashMap::from([("X-Api-Key".to_string(), "k".to_string())]),
+                legacy_sse: true,
+                ..Default::default()
+            },
+            5,
+            5,
+        )
+        .await
+        .expect("connect");
+
+        let server = Arc::new(server);
+        let (_client, shutdown) = tokio::sync::watch::channel(());
+        tokio::spawn(Arc::clone(&server).handle_server_messages(shutdown));
+
+        let tools = server.list_tools().await.expect("tools");
+        assert_eq!(tools[0].name, "echo");
+        let cached_revision = server.tools_revision();
+        let result = server.call_tool("echo", json!({})).await.expect("call");
+        assert_eq!(first_text(&result), "|");
+
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+        while server.tools_revision() == cached_revision {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "list_changed not handled"
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn legacy_sse_endpoint_on_another_origin_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let posts = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::clone(&posts);
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(socket);
+                let request = read_request(&mut stream).await;
+                if request.method != "GET" {
+                    seen.fetch_add(1, Ordering::SeqCst);
+                    continue;
+                }
+                let frame = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nevent: endpoint\ndata: http://localhost:{}/messages\n\n",
+                    addr.port()
+                );
+                stream.get_mut().write_all(frame.as_bytes()).await.unwrap();
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+        });
+
+        let error = McpServer::connect(
+            "legacy".to_string(),
+            McpHttpOptions {
+                url: format!("http://{}/sse", addr),
+                headers: HashMap::from([("Authorization".to_string(), "Bearer t".to_string())]),
+                legacy_sse: true,
+                ..Default::default()
+            },
+            5,
+            5,
+        )
+        .await
+        .err()
+        .expect("foreign endpoint must be refused");
+        assert!(error.to_string().contains("outside the configured origin"));
+        assert_eq!(posts.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn slow_calls_report_progress_and_are_cancelled_on_timeout_or_request() {
+        let (url, mut notified) = spawn_legacy_server().await;
+        let server = McpServer::connect(
+            "legacy".to_string(),
+            McpHttpOptions {
+                url,
+                legacy_sse: true,
+                ..Default::default()
+            },
+            5,
+            5,
+        )
+        .await
+        .expect("connect")
+        .with_call_limits(McpCallLimits {
+            tool_timeouts: HashMap::from([("slow".to_string(), 1)]),
+            max_in_flight: Some(1),
+        });
+        let server = Arc::new(server);
+        let (_client, shutdown) = tokio::sync::watch::channel(());
+        tokio::spawn(Arc::clone(&server).handle_server_messages(shutdown));
+        let next_cancel = |notified: &mut mpsc::UnboundedReceiver<Value>| loop {
+            let message = notified.try_recv().expect("cancel notice");
+            if message["method"] == "notifications/cancelled" {
+                return message["params"].clone();
+            }
+        };
+
+        // Per-tool timeout: the server is told to drop the request.
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+        let started = std::time::Instant::now();
+        let err = server
+            .call_tool_with(
+                "slow",
+                json!({}),
+                McpCallOptions {
+                    progress: Some(progress_tx),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timeout after 1s"), "{}", err);
+        assert!(started.elapsed() < std::time::Duration::from_secs(4));
+        let update = progress_rx.recv().await.expect("progress");
+        assert_eq!((update.tool.as_str(), update.progress), ("slow", 1.0));
+        assert_eq!(update.total, Some(4.0));
+        assert_eq!(update.message.as_deref(), Some("indexing"));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert_eq!(next_cancel(&mut notified)["reason"], "timeout");
+
+        // Caller cancellation, while a second call waits for the only slot.
+        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+        let slow = {
+            let server = Arc::clone(&server);
+            tokio::spawn(async move {
+                server
+                    .call_tool_with(
+                        "slow",
+                        json!({}),
+                        McpCallOptions {
+                            cancel: Some(cancel_rx),
+                            ..Default::default()
+                        },
+                    )
+                    .await
+            })
+        };
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let queued = {
+            let server = Arc::clone(&server);
+            tokio::spawn(async move { server.call_tool("echo", json!({})).await })
+        };
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(!queued.is_finished(), "max_in_flight not enforced");
+
+        cancel_tx.send(true).unwrap();
+        let err = slow.await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("cancelled"), "{}", err);
+        let echoed = queued.await.unwrap().expect("queued call");
+        assert_eq!(first_text(&echoed), "|");
+        assert_eq!(next_cancel(&mut notified)["reason"], "cancelled by user");
+    }
+
+    #[tokio::test]
+    async fn negotiated_server_pages_prompts_and_gates_capabilities() {
+        let (url, _) = spawn_streamable_server().await;
+        let url = url.replace("/mcp", "/v2");
+        let server = McpServer::connect(
+            "v2".to_string(),
+            McpHttpOptions {
+                url,
+                ..Default::default()
+            },
+            5,
+            5,
+        )
+        .await
+        .expect("connect");
+        assert_eq!(
+            server.protocol_version().await.as_deref(),
+            Some(crate::LATEST_PROTOCOL_VERSION)
+        );
+
+        let prompts = server.list_prompts().await.expect("prompts");
+        let names: Vec<&str> = prompts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["review", "summary"]);
+        assert!(prompts[0].arguments[0].required);
+
+        let arguments = HashMap::from([("lang".to_string(), "rust".to_string())]);
+        let prompt = server.get_prompt("review", &arguments).await.expect("get");
+        assert_eq!(
+            prompt.messages[0].text(),
+            Some(format!("rust|{}", crate::LATEST_PROTOCOL_VERSION).as_str())
+        );
+
+        // No `resources` capability advertised: templates are not requested.
+        assert!(server
+            .list_resource_templates()
+            .await
+            .expect("templates")
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn expired_session_is_reinitialized_and_the_request_retried() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let live = Arc::new(tokio::sync::Mutex::new(None::<String>));
+        let initializes = Arc::new(AtomicUsize::new(0));
+        let (server_live, server_initializes) = (Arc::clone(&live), Arc::clone(&initializes));
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(socket);
+                let request = read_request(&mut stream).await;
+                if request.body["method"] == "initialize" {
+                    let session = format!(
+                        "session-{}",
+                        server_initializes.fetch_add(1, Ordering::SeqCst) + 1
+                    );
+                    *server_live.lock().await = Some(session.clone());
+                    let extra = format!("Mcp-Session-Id: {}\r\n", session);
+                    let body = result_for(&request).to_string();
+                    respond(&mut stream, "200 OK", &extra, "application/json", &body).await;
+                } else if request.headers.get("mcp-session-id") != server_live.lock().await.as_ref()
+                {
+                    respond(&mut stream, "404 Not Found", "", "text/plain", "no session").await;
+                } else if request.body.get("id").is_none() {
+                    respond(&mut stream, "202 Accepted", "", "application/json", "").await;
+                } else {
+                    let body = result_for(&request).to_string();
+                    respond(&mut stream, "200 OK", "", "application/json", &body).await;
+                }
+            }
+        });
+
+        let server = McpServer::connect(
+            "expiring".to_string(),
+            McpHttpOptions {
+                url: format!("http://{}/mcp", addr),
+                ..Default::default()
+            },
+            5,
+            5,
+        )
+        .await
+        .expect("connect");
+        let result = server.call_tool("echo", json!({})).await.expect("call");
+        assert_eq!(first_text(&result), "|session-1");
+
+        // The server restarts and forgets every session.
+        *live.lock().await = None;
+        let result = server.call_tool("echo", json!({})).await.expect("retried");
+        assert_eq!(first_text(&result), "|session-2");
+        assert_eq!(initializes.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn closed_legacy_stream_fails_the_server_and_waiting_calls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (events_tx, events_rx) = mpsc::unbounded_channel::<Option<String>>();
+        let events_rx = Arc::new(tokio::sync::Mutex::new(Some(events_rx)));
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let events_tx = events_tx.clone();
+                let events_rx = Arc::clone(&events_rx);
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(socket);
+                    let request = read_request(&mut stream).await;
+                    if request.method == "GET" {
+                        let mut rx = events_rx.lock().await.take().unwrap();
+                        let inner = stream.get_mut();
+                        inner
+                            .write_all(
+                                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nevent: endpoint\ndata: /messages\n\n",
+                            )
+                            .await
+                            .unwrap();
+                        // `None` ends the stream.
+                        while let Some(Some(message)) = rx.recv().await {
+                            let frame = format!("event: message\ndata: {}\n\n", message);
+                            inner.write_all(frame.as_bytes()).await.unwrap();
+                        }
+                        inner.shutdown().await.ok();
+                        return;
+                    }
+                    match request.body["method"].as_str() {
+                        Some("initialize") => {
+                            let _ = events_tx.send(Some(result_for(&request).to_string()));
+                        }
+                        // Never answered: the stream closes while the call waits.
+                        Some("tools/call") => {
+                            let _ = events_tx.send(None);
+                        }
+                        _ => {}
+                    }
+                    respond(&mut stream, "202 Accepted", "", "text/plain", "").await;
+                });
+            }
+        });
+
+        let server = McpServer::connect(
+            "legacy".to_string(),
+            McpHttpOptions {
+                url: format!("http://{}/sse", addr),
+                legacy_sse: true,
+                ..Default::default()
+            },
+            30,
+            5,
+        )
+        .await
+        .expect("connect");
+        assert_eq!(server.state().await, McpServerState::Running);
+
+        let started = std::time::Instant::now();
+        let error = server
+            .call_tool("echo", json!({}))
+            .await
+            .expect_err("stream closed");
+        assert!(error.to_string().contains("disconnected"), "{}", error);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert_eq!(server.state().await, McpServerState::Failed);
+        assert_eq!(server.status().await.state, McpServerState::Failed);
+        assert!(server.list_tools().await.is_err());
+    }
+}
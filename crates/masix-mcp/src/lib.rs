@@ -1,24 +1,47 @@
 //! Masix MCP Client
 //!
-//! Model Context Protocol client for tool integration
+//! Model Context Protocol client for tool integration, plus a small stdio server
+
+mod http;
+mod sandbox;
+pub mod server;
+
+pub use http::McpHttpOptions;
+pub use sandbox::McpSandbox;
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::Command;
-use tokio::sync::{oneshot, Mutex};
-use tokio::time::{timeout, Duration};
-use tracing::{debug, info, warn};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, oneshot, watch, Mutex, OwnedSemaphorePermit, Semaphore};
+use tokio::time::{timeout, timeout_at, Duration};
+use tracing::{debug, error, info, warn};
 
 type PendingMap = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+/// Requests and notifications the server sends on its own initiative.
+type IncomingSender = mpsc::UnboundedSender<Value>;
+/// Progress listeners keyed by the token sent in `_meta.progressToken` (the request id).
+type ProgressListeners = Mutex<HashMap<u64, (String, mpsc::UnboundedSender<McpProgress>)>>;
+
+/// Version offered in `initialize`; the server may answer with any of the supported ones.
+pub const LATEST_PROTOCOL_VERSION: &str = "2025-06-18";
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+/// Upper bound on cursor pages fetched by a single list call.
+const MAX_LIST_PAGES: usize = 50;
+/// Default lifetime of a cached `tools/list` result.
+pub const DEFAULT_TOOL_CACHE_TTL_SECS: u64 = 300;
 const CIRCUIT_FAILURE_THRESHOLD: u32 = 3;
 const CIRCUIT_OPEN_SECS: u64 = 30;
+const STDERR_TAIL_LINES: usize = 20;
+/// A process that stayed up this long gets its full restart budget back.
+const RESTART_BUDGET_RESET_SECS: u64 = 600;
 
 #[derive(Debug, Default)]
 struct CircuitState {
@@ -29,7 +52,9 @@ struct CircuitState {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tool {
     pub name: String,
+    #[serde(default)]
     pub description: String,
+    #[serde(default, alias = "inputSchema")]
     pub input_schema: Value,
 }
 
@@ -38,6 +63,76 @@ pub struct Resource {
     pub uri: String,
     pub name: String,
     pub description: Option<String>,
+    #[serde(default, rename = "mimeType")]
+    pub mime_type: Option<String>,
+}
+
+/// One entry of a `resources/read` result: either `text` or base64 `blob`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(default, rename = "mimeType")]
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub blob: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTemplate {
+    #[serde(rename = "uriTemplate")]
+    pub uri_template: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, rename = "mimeType")]
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prompt {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptMessage {
+    pub role: String,
+    pub content: Value,
+}
+
+impl PromptMessage {
+    /// Text of the message, or the embedded text of a resource content block.
+    pub fn text(&self) -> Option<&str> {
+        match self.content.get("type").and_then(|v| v.as_str()) {
+            Some("text") => self.content.get("text").and_then(|v| v.as_str()),
+            Some("resource") => self.content["resource"]
+                .get("text")
+                .and_then(|v| v.as_str()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptResult {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<PromptMessage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,16 +147,169 @@ pub enum ToolContent {
     #[serde(rename = "text")]
     Text { text: String },
     #[serde(rename = "image")]
-    Image { data: String, mime_type: String },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType", alias = "mime_type")]
+        mime_type: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpServerState {
+    Running,
+    Restarting,
+    Failed,
+}
+
+impl McpServerState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Restarting => "restarting",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Point-in-time view of a supervised server, for `/mcp` and `masix status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerStatus {
+    pub name: String,
+    pub state: McpServerState,
+    pub pid: Option<u32>,
+    pub restart_count: u32,
+    pub tool_count: Option<usize>,
+    pub last_exit: Option<String>,
+    pub last_stderr: Vec<String>,
+    #[serde(default)]
+    pub protocol_version: Option<String>,
+}
+
+/// Per-server limits on outgoing requests.
+#[derive(Debug, Clone, Default)]
+pub struct McpCallLimits {
+    /// `tools/call` timeout overrides by tool name, in seconds.
+    pub tool_timeouts: HashMap<String, u64>,
+    /// Requests allowed in flight at once; `None` is unlimited.
+    pub max_in_flight: Option<usize>,
+}
+
+/// A `notifications/progress` update for a running tool call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpProgress {
+    pub server: String,
+    pub tool: String,
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: Option<String>,
+}
+
+/// Caller-side hooks for a single `tools/call`.
+#[derive(Debug, Clone, Default)]
+pub struct McpCallOptions {
+    /// Abandons the call once it reads `true`; the server gets `notifications/cancelled`.
+    pub cancel: Option<watch::Receiver<bool>>,
+    /// Receives the server's progress notifications; asks for them via `_meta.progressToken`.
+    pub progress: Option<mpsc::UnboundedSender<McpProgress>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct McpRestartPolicy {
+    pub max_restarts: u32,
+    pub backoff_secs: u64,
+    pub backoff_max_secs: u64,
+}
+
+impl McpRestartPolicy {
+    /// Exponential backoff before restart attempt `attempt` (0-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.backoff_secs.max(1);
+        let secs = base
+            .saturating_mul(1u64 << attempt.min(16))
+            .min(self.backoff_max_secs.max(base));
+        Duration::from_secs(secs)
+    }
+}
+
+impl Default for McpRestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            backoff_secs: 1,
+            backoff_max_secs: 60,
+        }
+    }
+}
+
+struct LaunchSpec {
+    command: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    startup_timeout_secs: u64,
+    sandbox: Option<McpSandbox>,
+}
+
+/// Pipes of the current child process; replaced on every restart.
+#[derive(Clone)]
+struct Connection {
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: PendingMap,
+    pid: Option<u32>,
+}
+
+/// Outcome of the `initialize` handshake.
+#[derive(Debug, Clone)]
+struct Negotiated {
+    protocol_version: String,
+    capabilities: Value,
+}
+
+/// Last `tools/list` result, served until it expires or is invalidated.
+struct ToolCache {
+    tools: Vec<Tool>,
+    fetched_at: Instant,
+}
+
+#[derive(Debug)]
+struct Supervision {
+    state: McpServerState,
+    restart_count: u32,
+    tool_count: Option<usize>,
+    last_exit: Option<String>,
+}
+
+/// Local child process speaking JSON-RPC over stdin/stdout.
+struct StdioLink {
+    launch: LaunchSpec,
+    connection: Mutex<Connection>,
+    child: Mutex<Option<Child>>,
+}
+
+enum Link {
+    Stdio(StdioLink),
+    Http(http::HttpLink),
 }
 
 pub struct McpServer {
     name: String,
-    stdin: Arc<Mutex<tokio::process::ChildStdin>>,
+    link: Link,
     request_id: Arc<Mutex<u64>>,
-    pending: PendingMap,
     timeout_secs: u64,
     circuit: Arc<Mutex<CircuitState>>,
+    supervision: Mutex<Supervision>,
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    negotiated: Mutex<Option<Negotiated>>,
+    incoming_tx: IncomingSender,
+    incoming_rx: Mutex<Option<mpsc::UnboundedReceiver<Value>>>,
+    /// Bumped whenever the cached tool catalog is replaced or dropped.
+    tools_revision: AtomicU64,
+    tool_cache: Mutex<Option<ToolCache>>,
+    tool_cache_ttl: Duration,
+    tool_timeouts: HashMap<String, u64>,
+    /// Request slots when `max_in_flight` is set.
+    in_flight: Option<Arc<Semaphore>>,
+    progress_listeners: ProgressListeners,
 }
 
 impl McpServer {
@@ -72,15 +320,161 @@ impl McpServer {
         env: std::collections::HashMap<String, String>,
         timeout_secs: u64,
         startup_timeout_secs: u64,
+        sandbox: Option<McpSandbox>,
     ) -> Result<Self> {
-        info!("Starting MCP server '{}' ({})", name, command);
+        match &sandbox {
+            Some(sandbox) => info!(
+                "Starting MCP server '{}' ({}) sandbox: {}",
+                name,
+                command,
+                sandbox.summary()
+            ),
+            None => info!("Starting MCP server '{}' ({})", name, command),
+        }
 
-        let mut child = Command::new(command)
-            .args(&args)
-            .envs(&env)
+        let launch = LaunchSpec {
+            command,
+            args,
+            env,
+            startup_timeout_secs,
+            sandbox,
+        };
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let incoming = mpsc::unbounded_channel();
+        let (connection, child) = Self::spawn_process(&name, &launch, &stderr_tail, &incoming.0)?;
+
+        let link = Link::Stdio(StdioLink {
+            launch,
+            connection: Mutex::new(connection),
+            child: Mutex::new(Some(child)),
+        });
+        let server = Self::with_link(name, link, timeout_secs, stderr_tail, incoming);
+
+        // Initialize MCP connection
+        match timeout(
+            Duration::from_secs(startup_timeout_secs),
+            server.initialize(),
+        )
+        .await
+        {
+            Ok(result) => result?,
+            Err(_) => {
+                return Err(anyhow!(
+                    "MCP server '{}' startup timeout after {}s",
+                    server.name,
+                    startup_timeout_secs
+                ))
+            }
+        }
+
+        Ok(server)
+    }
+
+    /// Connect to a remote server over Streamable HTTP (or legacy SSE) and run `initialize`.
+    pub async fn connect(
+        name: String,
+        options: McpHttpOptions,
+        timeout_secs: u64,
+        startup_timeout_secs: u64,
+    ) -> Result<Self> {
+        info!("Connecting to MCP server '{}' ({})", name, options.url);
+
+        let startup = async {
+            let incoming = mpsc::unbounded_channel();
+            let link = http::HttpLink::connect(&name, &options, incoming.0.clone()).await?;
+            let server = Self::with_link(
+                name.clone(),
+                Link::Http(link),
+                timeout_secs,
+                Arc::new(Mutex::new(VecDeque::new())),
+                incoming,
+            );
+            server.initialize().await?;
+            Ok::<_, anyhow::Error>(server)
+        };
+        match timeout(Duration::from_secs(startup_timeout_secs), startup).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow!(
+                "MCP server '{}' startup timeout after {}s",
+                name,
+                startup_timeout_secs
+            )),
+        }
+    }
+
+    fn with_link(
+        name: String,
+        link: Link,
+        timeout_secs: u64,
+        stderr_tail: Arc<Mutex<VecDeque<String>>>,
+        incoming: (IncomingSender, mpsc::UnboundedReceiver<Value>),
+    ) -> Self {
+        Self {
+            name,
+            link,
+            request_id: Arc::new(Mutex::new(1)),
+            timeout_secs,
+            circuit: Arc::new(Mutex::new(CircuitState::default())),
+            supervision: Mutex::new(Supervision {
+                state: McpServerState::Running,
+                restart_count: 0,
+                tool_count: None,
+                last_exit: None,
+            }),
+            stderr_tail,
+            negotiated: Mutex::new(None),
+            incoming_tx: incoming.0,
+            incoming_rx: Mutex::new(Some(incoming.1)),
+            tools_revision: AtomicU64::new(0),
+            tool_cache: Mutex::new(None),
+            tool_cache_ttl: Duration::from_secs(DEFAULT_TOOL_CACHE_TTL_SECS),
+            tool_timeouts: HashMap::new(),
+            in_flight: None,
+            progress_listeners: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long `list_tools` may serve the cached catalog; 0 queries the server every time.
+    pub fn with_tool_cache_ttl(mut self, ttl_secs: u64) -> Self {
+        self.tool_cache_ttl = Duration::from_secs(ttl_secs);
+        self
+    }
+
+    pub fn with_call_limits(mut self, limits: McpCallLimits) -> Self {
+        self.tool_timeouts = limits.tool_timeouts;
+        self.in_flight = limits
+            .max_in_flight
+            .map(|slots| Arc::new(Semaphore::new(slots.max(1))));
+        self
+    }
+
+    /// Timeout for `tools/call` of `tool`: its override, else the server default.
+    pub fn tool_timeout(&self, tool: &str) -> Duration {
+        Duration::from_secs(
+            self.tool_timeouts
+                .get(tool)
+                .copied()
+                .unwrap_or(self.timeout_secs),
+        )
+    }
+
+    fn spawn_process(
+        name: &str,
+        launch: &LaunchSpec,
+        stderr_tail: &Arc<Mutex<VecDeque<String>>>,
+        incoming: &IncomingSender,
+    ) -> Result<(Connection, Child)> {
+        let mut command = Command::new(&launch.command);
+        if let Some(sandbox) = &launch.sandbox {
+            sandbox.apply(&mut command)?;
+        }
+        let mut child = command
+            .args(&launch.args)
+            .envs(&launch.env)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
+            .kill_on_drop(true)
             .spawn()?;
 
         let stdin = child
@@ -97,36 +491,275 @@ impl McpServer {
             .ok_or_else(|| anyhow!("Failed to get stderr"))?;
 
         let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
-        Self::spawn_stdout_router(name.clone(), stdout, Arc::clone(&pending));
-        Self::spawn_stderr_logger(name.clone(), stderr);
-
-        let server = Self {
-            name,
+        Self::spawn_stdout_router(
+            name.to_string(),
+            stdout,
+            Arc::clone(&pending),
+            incoming.clone(),
+        );
+        Self::spawn_stderr_logger(name.to_string(), stderr, Arc::clone(stderr_tail));
+
+        let connection = Connection {
+            pid: child.id(),
             stdin: Arc::new(Mutex::new(stdin)),
-            request_id: Arc::new(Mutex::new(1)),
             pending,
-            timeout_secs,
-            circuit: Arc::new(Mutex::new(CircuitState::default())),
         };
+        Ok((connection, child))
+    }
 
-        // Initialize MCP connection
-        match timeout(
-            Duration::from_secs(startup_timeout_secs),
-            server.initialize(),
+    /// Wait for the child to exit and restart it with exponential backoff until
+    /// the restart budget is spent, then mark the server as failed. Runs even
+    /// with `max_restarts = 0`, so an exit is still reported as `Failed`.
+    /// Stops, killing the child, once the owning client is dropped.
+    async fn supervise(self: Arc<Self>, policy: McpRestartPolicy, shutdown: watch::Receiver<()>) {
+        let Link::Stdio(stdio) = &self.link else {
+            return;
+        };
+        let mut attempts: u32 = 0;
+        loop {
+            let Some(mut child) = stdio.child.lock().await.take() else {
+                return;
+            };
+            let started = Instant::now();
+            let exit = tokio::select! {
+                status = child.wait() => match status {
+                    Ok(status) => status.to_string(),
+                    Err(e) => format!("wait failed: {}", e),
+                },
+                _ = client_dropped(shutdown.clone()) => {
+                    debug!("MCP server '{}' stopped with its client", self.name);
+                    let _ = child.kill().await;
+                    return;
+                }
+            };
+            drop(child);
+            warn!("MCP server '{}' exited ({})", self.name, exit);
+            if started.elapsed() >= Duration::from_secs(RESTART_BUDGET_RESET_SECS) {
+                attempts = 0;
+            }
+            self.supervision.lock().await.last_exit = Some(exit);
+
+            loop {
+                if attempts >= policy.max_restarts {
+                    error!(
+                        "MCP server '{}' marked failed after {} restart attempt(s)",
+                        self.name, attempts
+                    );
+                    self.supervision.lock().await.state = McpServerState::Failed;
+                    return;
+                }
+                let delay = policy.backoff(attempts);
+                attempts += 1;
+                self.supervision.lock().await.state = McpServerState::Restarting;
+                info!(
+                    "Restarting MCP server '{}' in {}s (attempt {}/{})",
+                    self.name,
+                    delay.as_secs(),
+                    attempts,
+                    policy.max_restarts
+                );
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = client_dropped(shutdown.clone()) => return,
+                }
+                match self.respawn(stdio).await {
+                    Ok(()) => break,
+                    Err(e) => {
+                        warn!("MCP server '{}' restart failed: {}", self.name, e);
+                        self.supervision.lock().await.last_exit =
+                            Some(format!("restart failed: {}", e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Start a fresh process, redo the `initialize` handshake and refresh the tool list.
+    async fn respawn(&self, stdio: &StdioLink) -> Result<()> {
+        let (connection, mut child) = Self::spawn_process(
+            &self.name,
+            &stdio.launch,
+            &self.stderr_tail,
+            &self.incoming_tx,
+        )?;
+        *stdio.connection.lock().await = connection;
+        *self.circuit.lock().await = CircuitState::default();
+
+        let handshake = async {
+            self.initialize().await?;
+            self.fetch_tools().await
+        };
+        let tools = match timeout(
+            Duration::from_secs(stdio.launch.startup_timeout_secs),
+            handshake,
         )
         .await
         {
-            Ok(result) => result?,
+            Ok(Ok(tools)) => tools,
+            Ok(Err(e)) => {
+                let _ = child.kill().await;
+                return Err(e);
+            }
             Err(_) => {
+                let _ = child.kill().await;
                 return Err(anyhow!(
-                    "MCP server '{}' startup timeout after {}s",
-                    server.name,
-                    startup_timeout_secs
-                ))
+                    "startup timeout after {}s",
+                    stdio.launch.startup_timeout_secs
+                ));
             }
+        };
+
+        *stdio.child.lock().await = Some(child);
+        self.store_tools(&tools).await;
+        let mut supervision = self.supervision.lock().await;
+        supervision.state = McpServerState::Running;
+        supervision.restart_count = supervision.restart_count.saturating_add(1);
+        info!(
+            "MCP server '{}' restarted ({} tools, restart #{})",
+            self.name,
+            tools.len(),
+            supervision.restart_count
+        );
+        Ok(())
+    }
+
+    pub async fn status(&self) -> McpServerStatus {
+        let state = self.state().await;
+        let supervision = self.supervision.lock().await;
+        let pid = match &self.link {
+            Link::Stdio(stdio) if state == McpServerState::Running => {
+                stdio.connection.lock().await.pid
+            }
+            _ => None,
+        };
+        McpServerStatus {
+            name: self.name.clone(),
+            state,
+            pid,
+            restart_count: supervision.restart_count,
+            tool_count: supervision.tool_count,
+            last_exit: supervision.last_exit.clone(),
+            last_stderr: self.stderr_tail.lock().await.iter().cloned().collect(),
+            protocol_version: self.protocol_version().await,
+        }
+    }
+
+    pub async fn protocol_version(&self) -> Option<String> {
+        self.negotiated
+            .lock()
+            .await
+            .as_ref()
+            .map(|negotiated| negotiated.protocol_version.clone())
+    }
+
+    pub fn tools_revision(&self) -> u64 {
+        self.tools_revision.load(Ordering::SeqCst)
+    }
+
+    /// Whether the server advertised `capability` during `initialize`.
+    async fn server_supports(&self, capability: &str) -> bool {
+        match self.negotiated.lock().await.as_ref() {
+            Some(negotiated) => negotiated.capabilities.get(capability).is_some(),
+            None => true,
         }
+    }
 
-        Ok(server)
+    /// Answer server-initiated requests and react to notifications until the
+    /// link closes or the owning client is dropped.
+    async fn handle_server_messages(self: Arc<Self>, shutdown: watch::Receiver<()>) {
+        let Some(mut incoming) = self.incoming_rx.lock().await.take() else {
+            return;
+        };
+        loop {
+            let message = tokio::select! {
+                message = incoming.recv() => message,
+                _ = client_dropped(shutdown.clone()) => None,
+            };
+            let Some(message) = message else {
+                break;
+            };
+            let method = message
+                .get("method")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown")
+                .to_string();
+            let Some(id) = message.get("id").cloned() else {
+                self.handle_notification(&method, &message).await;
+                continue;
+            };
+            let reply = match method.as_str() {
+                "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+                "roots/list" => json!({ "jsonrpc": "2.0", "id": id, "result": { "roots": [] } }),
+                _ => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": format!("Method not found: {}", method) }
+                }),
+            };
+            if let Err(e) = self.send_message(reply).await {
+                debug!(
+                    "MCP server '{}' reply to '{}' failed: {}",
+                    self.name, method, e
+                );
+            }
+        }
+    }
+
+    async fn handle_notification(&self, method: &str, message: &Value) {
+        match method {
+            "notifications/tools/list_changed" => {
+                info!("MCP server '{}' tool list changed", self.name);
+                if let Err(e) = self.refresh_tools().await {
+                    self.invalidate_tools().await;
+                    warn!("MCP server '{}' tool refresh failed: {}", self.name, e);
+                }
+            }
+            "notifications/resources/list_changed" | "notifications/prompts/list_changed" => {
+                info!("MCP server '{}' sent {}", self.name, method);
+            }
+            "notifications/progress" => {
+                let params = &message["params"];
+                let Some(token) = params.get("progressToken").and_then(|v| v.as_u64()) else {
+                    return;
+                };
+                if let Some((tool, listener)) = self.progress_listeners.lock().await.get(&token) {
+                    let _ = listener.send(McpProgress {
+                        server: self.name.clone(),
+                        tool: tool.clone(),
+                        progress: params["progress"].as_f64().unwrap_or(0.0),
+                        total: params["total"].as_f64(),
+                        message: params["message"].as_str().map(str::to_string),
+                    });
+                }
+            }
+            "notifications/message" => {
+                let params = &message["params"];
+                info!(
+                    "MCP log [{}] {}: {}",
+                    self.name,
+                    params["level"].as_str().unwrap_or("info"),
+                    params["data"]
+                );
+            }
+            _ => debug!(
+                "MCP server '{}' notification/event: method={}",
+                self.name, method
+            ),
+        }
+    }
+
+    pub async fn state(&self) -> McpServerState {
+        match &self.link {
+            Link::Http(link) if link.is_closed() => McpServerState::Failed,
+            _ => self.supervision.lock().await.state,
+        }
+    }
+
+    async fn ensure_available(&self) -> Result<()> {
+        match self.state().await {
+            McpServerState::Running => Ok(()),
+            state => Err(anyhow!("MCP server '{}' is {}", self.name, state.as_str())),
+        }
     }
 
     async fn initialize(&self) -> Result<()> {
@@ -137,61 +770,204 @@ impl McpServer {
             "id": id,
             "method": "initialize",
             "params": {
-                "protocolVersion": "2024-11-05",
-                "capabilities": {},
+                "protocolVersion": LATEST_PROTOCOL_VERSION,
+                "capabilities": {
+                    "roots": { "listChanged": false }
+                },
                 "clientInfo": {
                     "name": "masix",
-                    "version": "0.1.0"
+                    "version": env!("CARGO_PKG_VERSION")
                 }
             }
         });
 
-        self.send_request(request).await?;
+        let response = self.send_request(request).await?;
+        let result = &response["result"];
+        let protocol_version = result
+            .get("protocolVersion")
+            .and_then(|v| v.as_str())
+            .unwrap_or("2024-11-05");
+        if !SUPPORTED_PROTOCOL_VERSIONS.contains(&protocol_version) {
+            return Err(anyhow!(
+                "MCP server '{}' requires unsupported protocol version '{}' (supported: {})",
+                self.name,
+                protocol_version,
+                SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+            ));
+        }
+        if let Link::Http(link) = &self.link {
+            link.set_protocol_version(protocol_version).await;
+        }
+        debug!(
+            "MCP server '{}' negotiated protocol {}",
+            self.name, protocol_version
+        );
+        *self.negotiated.lock().await = Some(Negotiated {
+            protocol_version: protocol_version.to_string(),
+            capabilities: result.get("capabilities").cloned().unwrap_or(json!({})),
+        });
 
         // Send initialized notification
         let initialized = serde_json::json!({
             "jsonrpc": "2.0",
             "method": "notifications/initialized"
         });
-        self.send_notification(initialized).await?;
+        self.send_message(initialized).await?;
 
         Ok(())
     }
 
-    pub async fn list_tools(&self) -> Result<Vec<Tool>> {
+    /// Liveness probe for the healthcheck. Any answer counts, including
+    /// "method not found" from servers without `ping`; the tool cache is left
+    /// to its TTL and `list_changed`.
+    pub async fn ping(&self) -> Result<()> {
+        self.ensure_available().await?;
         let id = self.next_id().await;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": "ping" });
+        let response = match timeout(
+            Duration::from_secs(self.timeout_secs),
+            self.exchange(id, &request),
+        )
+        .await
+        {
+            Ok(response) => response?,
+            Err(_) => {
+                self.abandon(id, &request, "timeout").await;
+                return Err(anyhow!(
+                    "MCP ping timeout after {}s (server='{}')",
+                    self.timeout_secs,
+                    self.name
+                ));
+            }
+        };
+        match response.get("error") {
+            Some(error) if error["code"].as_i64() != Some(-32601) => {
+                Err(anyhow!("MCP ping to '{}' failed: {:?}", self.name, error))
+            }
+            _ => Ok(()),
+        }
+    }
 
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "method": "tools/list",
-            "params": {}
+    /// Tool catalog, served from cache while it is younger than the TTL.
+    pub async fn list_tools(&self) -> Result<Vec<Tool>> {
+        self.ensure_available().await?;
+        if !self.tool_cache_ttl.is_zero() {
+            if let Some(cache) = self.tool_cache.lock().await.as_ref() {
+                if cache.fetched_at.elapsed() < self.tool_cache_ttl {
+                    return Ok(cache.tools.clone());
+                }
+            }
+        }
+        self.refresh_tools().await
+    }
+
+    /// Query `tools/list` and replace the cached catalog.
+    pub async fn refresh_tools(&self) -> Result<Vec<Tool>> {
+        self.ensure_available().await?;
+        let tools = self.fetch_tools().await?;
+        self.store_tools(&tools).await;
+        Ok(tools)
+    }
+
+    async fn store_tools(&self, tools: &[Tool]) {
+        *self.tool_cache.lock().await = Some(ToolCache {
+            tools: tools.to_vec(),
+            fetched_at: Instant::now(),
         });
+        self.tools_revision.fetch_add(1, Ordering::SeqCst);
+        self.supervision.lock().await.tool_count = Some(tools.len());
+    }
 
-        let response = self.send_request(request).await?;
+    async fn invalidate_tools(&self) {
+        *self.tool_cache.lock().await = None;
+        self.tools_revision.fetch_add(1, Ordering::SeqCst);
+    }
 
-        let tools = response["result"]
-            .get("tools")
-            .and_then(|t| serde_json::from_value(t.clone()).ok())
-            .unwrap_or_default();
+    async fn fetch_tools(&self) -> Result<Vec<Tool>> {
+        self.list_paginated("tools/list", "tools").await
+    }
 
-        Ok(tools)
+    /// Run a `*/list` method, following `nextCursor` until the last page.
+    async fn list_paginated<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        key: &str,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_LIST_PAGES {
+            let id = self.next_id().await;
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": params
+            });
+
+            let response = self.send_request(request).await?;
+            let result = &response["result"];
+            if let Some(page) = result.get(key).and_then(|v| v.as_array()) {
+                items.extend(
+                    page.iter()
+                        .filter_map(|item| serde_json::from_value(item.clone()).ok()),
+                );
+            }
+            match result.get("nextCursor").and_then(|v| v.as_str()) {
+                Some(next) if !next.is_empty() && cursor.as_deref() != Some(next) => {
+                    cursor = Some(next.to_string());
+                }
+                _ => return Ok(items),
+            }
+        }
+        warn!(
+            "MCP server '{}' {} exceeded {} pages; list truncated",
+            self.name, method, MAX_LIST_PAGES
+        );
+        Ok(items)
     }
 
     pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<ToolResult> {
+        self.call_tool_with(name, arguments, McpCallOptions::default())
+            .await
+    }
+
+    /// `tools/call` with the tool's own timeout, cancellation and progress reporting.
+    pub async fn call_tool_with(
+        &self,
+        name: &str,
+        arguments: Value,
+        options: McpCallOptions,
+    ) -> Result<ToolResult> {
+        self.ensure_available().await?;
         let id = self.next_id().await;
 
+        let mut params = serde_json::json!({
+            "name": name,
+            "arguments": arguments
+        });
+        if let Some(listener) = options.progress {
+            params["_meta"] = json!({ "progressToken": id });
+            self.progress_listeners
+                .lock()
+                .await
+                .insert(id, (name.to_string(), listener));
+        }
         let request = serde_json::json!({
             "jsonrpc": "2.0",
             "id": id,
             "method": "tools/call",
-            "params": {
-                "name": name,
-                "arguments": arguments
-            }
+            "params": params
         });
 
-        let response = self.send_request(request).await?;
+        let response = self
+            .send_request_with(request, self.tool_timeout(name), options.cancel)
+            .await;
+        self.progress_listeners.lock().await.remove(&id);
+        let response = response?;
 
         let result = response["result"].clone();
         let is_error = result
@@ -212,26 +988,63 @@ impl McpServer {
     }
 
     pub async fn list_resources(&self) -> Result<Vec<Resource>> {
+        self.ensure_available().await?;
+        if !self.server_supports("resources").await {
+            return Ok(Vec::new());
+        }
+        self.list_paginated("resources/list", "resources").await
+    }
+
+    pub async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>> {
+        self.ensure_available().await?;
+        if !self.server_supports("resources").await {
+            return Ok(Vec::new());
+        }
+        self.list_paginated("resources/templates/list", "resourceTemplates")
+            .await
+    }
+
+    pub async fn list_prompts(&self) -> Result<Vec<Prompt>> {
+        self.ensure_available().await?;
+        if !self.server_supports("prompts").await {
+            return Ok(Vec::new());
+        }
+        self.list_paginated("prompts/list", "prompts").await
+    }
+
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<PromptResult> {
+        self.ensure_available().await?;
         let id = self.next_id().await;
 
         let request = serde_json::json!({
             "jsonrpc": "2.0",
             "id": id,
-            "method": "resources/list",
-            "params": {}
+            "method": "prompts/get",
+            "params": {
+                "name": name,
+                "arguments": arguments
+            }
         });
 
         let response = self.send_request(request).await?;
-
-        let resources = response["result"]
-            .get("resources")
-            .and_then(|r| serde_json::from_value(r.clone()).ok())
-            .unwrap_or_default();
-
-        Ok(resources)
+        Ok(serde_json::from_value(response["result"].clone())?)
     }
 
     pub async fn read_resource(&self, uri: &str) -> Result<String> {
+        let contents = self.read_resource_contents(uri).await?;
+        Ok(contents
+            .into_iter()
+            .next()
+            .and_then(|c| c.text)
+            .unwrap_or_default())
+    }
+
+    pub async fn read_resource_contents(&self, uri: &str) -> Result<Vec<ResourceContents>> {
+        self.ensure_available().await?;
         let id = self.next_id().await;
 
         let request = serde_json::json!({
@@ -247,51 +1060,69 @@ impl McpServer {
 
         let contents = response["result"]
             .get("contents")
-            .and_then(|c| c.as_array())
-            .and_then(|arr| arr.first())
-            .and_then(|c| c["text"].as_str())
-            .unwrap_or("")
-            .to_string();
+            .and_then(|c| serde_json::from_value(c.clone()).ok())
+            .unwrap_or_default();
 
         Ok(contents)
     }
 
     async fn send_request(&self, request: Value) -> Result<Value> {
+        self.send_request_with(request, Duration::from_secs(self.timeout_secs), None)
+            .await
+    }
+
+    /// Send a request and wait for its response. `limit` covers the wait for a
+    /// free slot as well; on timeout or cancellation the server is told to stop.
+    async fn send_request_with(
+        &self,
+        request: Value,
+        limit: Duration,
+        cancel: Option<watch::Receiver<bool>>,
+    ) -> Result<Value> {
         self.check_circuit().await?;
 
         let id = request
             .get("id")
             .and_then(|v| v.as_u64())
             .ok_or_else(|| anyhow!("MCP request missing numeric id"))?;
-
-        let (tx, rx) = oneshot::channel::<Value>();
-        self.pending.lock().await.insert(id, tx);
-
-        if let Err(e) = self.send_json_line(&request).await {
-            self.pending.lock().await.remove(&id);
-            self.mark_failure().await;
-            return Err(e);
-        }
-
-        let response = match timeout(Duration::from_secs(self.timeout_secs), rx).await {
-            Ok(Ok(value)) => value,
-            Ok(Err(_)) => {
-                self.mark_failure().await;
-                return Err(anyhow!(
-                    "MCP response channel closed (server='{}', id={})",
+        let deadline = tokio::time::Instant::now() + limit;
+        let cancelled = cancellation(cancel);
+        tokio::pin!(cancelled);
+
+        let _slot = tokio::select! {
+            slot = self.acquire_slot(deadline) => slot.map_err(|_| {
+                anyhow!(
+                    "MCP server '{}' busy: no request slot freed within {}s",
                     self.name,
-                    id
-                ));
+                    limit.as_secs()
+                )
+            })?,
+            _ = &mut cancelled => {
+                return Err(anyhow!("MCP request cancelled (server='{}', id={})", self.name, id));
             }
-            Err(_) => {
-                self.pending.lock().await.remove(&id);
-                self.mark_failure().await;
-                return Err(anyhow!(
-                    "MCP request timeout after {}s (server='{}', id={})",
-                    self.timeout_secs,
-                    self.name,
-                    id
-                ));
+        };
+
+        let response = tokio::select! {
+            outcome = timeout_at(deadline, self.exchange(id, &request)) => match outcome {
+                Ok(Ok(value)) => value,
+                Ok(Err(e)) => {
+                    self.mark_failure().await;
+                    return Err(e);
+                }
+                Err(_) => {
+                    self.abandon(id, &request, "timeout").await;
+                    self.mark_failure().await;
+                    return Err(anyhow!(
+                        "MCP request timeout after {}s (server='{}', id={})",
+                        limit.as_secs(),
+                        self.name,
+                        id
+                    ));
+                }
+            },
+            _ = &mut cancelled => {
+                self.abandon(id, &request, "cancelled by user").await;
+                return Err(anyhow!("MCP request cancelled (server='{}', id={})", self.name, id));
             }
         };
 
@@ -304,6 +1135,74 @@ impl McpServer {
         Ok(response)
     }
 
+    /// Deliver one request over the active link and wait for the matching response.
+    async fn exchange(&self, id: u64, request: &Value) -> Result<Value> {
+        match &self.link {
+            Link::Stdio(stdio) => {
+                let connection = stdio.connection.lock().await.clone();
+                let (tx, rx) = oneshot::channel::<Value>();
+                connection.pending.lock().await.insert(id, tx);
+
+                if let Err(e) = Self::send_json_line(&connection, request).await {
+                    connection.pending.lock().await.remove(&id);
+                    return Err(e);
+                }
+
+                rx.await.map_err(|_| {
+                    anyhow!(
+                        "MCP response channel closed (server='{}', id={})",
+                        self.name,
+                        id
+                    )
+                })
+            }
+            Link::Http(link) => link.request(id, request).await,
+        }
+    }
+
+    /// Wait for one of the `max_in_flight` slots; `Ok(None)` when unlimited.
+    async fn acquire_slot(
+        &self,
+        deadline: tokio::time::Instant,
+    ) -> Result<Option<OwnedSemaphorePermit>, tokio::time::error::Elapsed> {
+        let Some(slots) = &self.in_flight else {
+            return Ok(None);
+        };
+        let permit = timeout_at(deadline, Arc::clone(slots).acquire_owned()).await?;
+        // The semaphore is never closed.
+        Ok(permit.ok())
+    }
+
+    /// Stop waiting for `id` and ask the server to drop the work.
+    async fn abandon(&self, id: u64, request: &Value, reason: &str) {
+        self.forget_pending(id).await;
+        // `initialize` must not be cancelled (MCP spec).
+        if request.get("method").and_then(|v| v.as_str()) == Some("initialize") {
+            return;
+        }
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": id, "reason": reason }
+        });
+        if let Err(e) = self.send_message(notification).await {
+            debug!(
+                "MCP server '{}' cancel notice for id {} failed: {}",
+                self.name, id, e
+            );
+        }
+    }
+
+    async fn forget_pending(&self, id: u64) {
+        match &self.link {
+            Link::Stdio(stdio) => {
+                let connection = stdio.connection.lock().await.clone();
+                connection.pending.lock().await.remove(&id);
+            }
+            Link::Http(link) => link.forget(id).await,
+        }
+    }
+
     async fn check_circuit(&self) -> Result<()> {
         let mut circuit = self.circuit.lock().await;
         if let Some(open_until) = circuit.open_until {
@@ -346,12 +1245,19 @@ impl McpServer {
         }
     }
 
-    async fn send_notification(&self, notification: Value) -> Result<()> {
-        self.send_json_line(&notification).await
+    /// Send a message that expects no response (notification or reply to the server).
+    async fn send_message(&self, message: Value) -> Result<()> {
+        match &self.link {
+            Link::Stdio(stdio) => {
+                let connection = stdio.connection.lock().await.clone();
+                Self::send_json_line(&connection, &message).await
+            }
+            Link::Http(link) => link.notify(&message).await,
+        }
     }
 
-    async fn send_json_line(&self, payload: &Value) -> Result<()> {
-        let mut stdin = self.stdin.lock().await;
+    async fn send_json_line(connection: &Connection, payload: &Value) -> Result<()> {
+        let mut stdin = connection.stdin.lock().await;
         let line = serde_json::to_string(payload)?;
         stdin.write_all(line.as_bytes()).await?;
         stdin.write_all(b"\n").await?;
@@ -374,6 +1280,7 @@ impl McpServer {
         server_name: String,
         stdout: tokio::process::ChildStdout,
         pending: PendingMap,
+        incoming: IncomingSender,
     ) {
         tokio::spawn(async move {
             let mut reader = BufReader::new(stdout);
@@ -403,7 +1310,9 @@ impl McpServer {
                             }
                         };
 
-                        if let Some(id) = message.get("id").and_then(|v| v.as_u64()) {
+                        if message.get("method").is_some() {
+                            let _ = incoming.send(message);
+                        } else if let Some(id) = message.get("id").and_then(|v| v.as_u64()) {
                             let sender = { pending.lock().await.remove(&id) };
                             if let Some(tx) = sender {
                                 let _ = tx.send(message);
@@ -413,15 +1322,6 @@ impl McpServer {
                                     server_name, id
                                 );
                             }
-                        } else {
-                            let method = message
-                                .get("method")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("unknown");
-                            debug!(
-                                "MCP server '{}' notification/event: method={}",
-                                server_name, method
-                            );
                         }
                     }
                     Err(e) => {
@@ -431,20 +1331,15 @@ impl McpServer {
                 }
             }
 
-            let disconnect_error = json!({
-                "error": {
-                    "code": -32000,
-                    "message": format!("MCP server '{}' disconnected", server_name)
-                }
-            });
-            let mut guard = pending.lock().await;
-            for (_, tx) in guard.drain() {
-                let _ = tx.send(disconnect_error.clone());
-            }
+            fail_pending(&server_name, &pending).await;
         });
     }
 
-    fn spawn_stderr_logger(server_name: String, stderr: tokio::process::ChildStderr) {
+    fn spawn_stderr_logger(
+        server_name: String,
+        stderr: tokio::process::ChildStderr,
+        tail: Arc<Mutex<VecDeque<String>>>,
+    ) {
         tokio::spawn(async move {
             let mut reader = BufReader::new(stderr);
             let mut line = String::new();
@@ -457,6 +1352,11 @@ impl McpServer {
                         let trimmed = line.trim();
                         if !trimmed.is_empty() {
                             warn!("MCP stderr [{}]: {}", server_name, trimmed);
+                            let mut tail = tail.lock().await;
+                            if tail.len() >= STDERR_TAIL_LINES {
+                                tail.pop_front();
+                            }
+                            tail.push_back(trimmed.to_string());
                         }
                     }
                     Err(e) => {
@@ -469,17 +1369,51 @@ impl McpServer {
     }
 }
 
+/// Resolves once `cancel` reads `true`; never when there is no receiver or its sender is gone.
+async fn cancellation(cancel: Option<watch::Receiver<bool>>) {
+    if let Some(mut cancel) = cancel {
+        if cancel.wait_for(|cancelled| *cancelled).await.is_ok() {
+            return;
+        }
+    }
+    std::future::pending::<()>().await
+}
+
+/// Resolves once the `McpClient` owning `shutdown`'s sender is dropped.
+async fn client_dropped(mut shutdown: watch::Receiver<()>) {
+    while shutdown.changed().await.is_ok() {}
+}
+
+/// Answer every in-flight request with a disconnect error once the transport is gone.
+async fn fail_pending(server_name: &str, pending: &PendingMap) {
+    let disconnect_error = json!({
+        "error": {
+            "code": -32000,
+            "message": format!("MCP server '{}' disconnected", server_name)
+        }
+    });
+    let mut guard = pending.lock().await;
+    for (_, tx) in guard.drain() {
+        let _ = tx.send(disconnect_error.clone());
+    }
+}
+
 pub struct McpClient {
     servers: Vec<Arc<McpServer>>,
+    /// Never sent on; dropping it with the client stops the background tasks
+    /// of every server, and with them the stdio processes.
+    shutdown: watch::Sender<()>,
 }
 
 impl McpClient {
     pub fn new() -> Self {
         Self {
             servers: Vec::new(),
+            shutdown: watch::channel(()).0,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn add_server(
         &mut self,
         name: String,
@@ -489,20 +1423,84 @@ impl McpClient {
         timeout_secs: u64,
         startup_timeout_secs: u64,
         healthcheck_interval_secs: u64,
+        tool_cache_ttl_secs: u64,
+        restart_policy: McpRestartPolicy,
+        sandbox: Option<McpSandbox>,
+        limits: McpCallLimits,
     ) -> Result<()> {
-        let server =
-            McpServer::start(name, command, args, env, timeout_secs, startup_timeout_secs).await?;
-        let server = Arc::new(server);
+        let server = McpServer::start(
+            name,
+            command,
+            args,
+            env,
+            timeout_secs,
+            startup_timeout_secs,
+            sandbox,
+        )
+        .await?;
+        let server = Arc::new(
+            server
+                .with_tool_cache_ttl(tool_cache_ttl_secs)
+                .with_call_limits(limits),
+        );
+        tokio::spawn(Arc::clone(&server).supervise(restart_policy, self.shutdown.subscribe()));
+        self.register(server, healthcheck_interval_secs).await;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_remote_server(
+        &mut self,
+        name: String,
+        options: McpHttpOptions,
+        timeout_secs: u64,
+        startup_timeout_secs: u64,
+        healthcheck_interval_secs: u64,
+        tool_cache_ttl_secs: u64,
+        limits: McpCallLimits,
+    ) -> Result<()> {
+        let server = McpServer::connect(name, options, timeout_secs, startup_timeout_secs).await?;
+        self.register(
+            Arc::new(
+                server
+                    .with_tool_cache_ttl(tool_cache_ttl_secs)
+                    .with_call_limits(limits),
+            ),
+            healthcheck_interval_secs,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn register(&mut self, server: Arc<McpServer>, healthcheck_interval_secs: u64) {
+        // Warm the tool catalog so the first chat turn does not pay for `tools/list`.
+        if let Err(e) = server.refresh_tools().await {
+            warn!(
+                "MCP server '{}' initial tool list failed: {}",
+                server.name(),
+                e
+            );
+        }
         self.servers.push(Arc::clone(&server));
+        tokio::spawn(Arc::clone(&server).handle_server_messages(self.shutdown.subscribe()));
 
         if healthcheck_interval_secs > 0 {
             let health_server = Arc::clone(&server);
+            let shutdown = self.shutdown.subscribe();
             tokio::spawn(async move {
                 let mut interval =
                     tokio::time::interval(Duration::from_secs(healthcheck_interval_secs));
                 loop {
-                    interval.tick().await;
-                    if let Err(e) = health_server.list_tools().await {
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        _ = client_dropped(shutdown.clone()) => break,
+                    }
+                    match health_server.state().await {
+                        McpServerState::Running => {}
+                        McpServerState::Restarting => continue,
+                        McpServerState::Failed => break,
+                    }
+                    if let Err(e) = health_server.ping().await {
                         warn!(
                             "MCP healthcheck failed for '{}': {}",
                             health_server.name(),
@@ -512,8 +1510,6 @@ impl McpClient {
                 }
             });
         }
-
-        Ok(())
     }
 
     pub async fn list_all_tools(&self) -> Vec<(String, Tool)> {
@@ -530,6 +1526,16 @@ impl McpClient {
         all_tools
     }
 
+    /// Advertised input schema of a tool, served from the catalog cache.
+    pub async fn tool_input_schema(&self, server_name: &str, tool_name: &str) -> Option<Value> {
+        let server = self.servers.iter().find(|s| s.name() == server_name)?;
+        let tools = server.list_tools().await.ok()?;
+        tools
+            .into_iter()
+            .find(|tool| tool.name == tool_name)
+            .map(|tool| tool.input_schema)
+    }
+
     pub async fn call_tool(
         &self,
         server_name: &str,
@@ -545,10 +1551,83 @@ impl McpClient {
         server.call_tool(tool_name, arguments).await
     }
 
+    /// Handle to a registered server, so long calls need not hold the client lock.
+    pub fn server(&self, server_name: &str) -> Option<Arc<McpServer>> {
+        self.servers
+            .iter()
+            .find(|s| s.name() == server_name)
+            .cloned()
+    }
+
     pub fn server_names(&self) -> Vec<String> {
         self.servers.iter().map(|s| s.name().to_string()).collect()
     }
 
+    pub async fn server_statuses(&self) -> Vec<McpServerStatus> {
+        let mut statuses = Vec::with_capacity(self.servers.len());
+        for server in &self.servers {
+            statuses.push(server.status().await);
+        }
+        statuses
+    }
+
+    pub async fn list_all_prompts(&self) -> Vec<(String, Prompt)> {
+        let mut all_prompts = Vec::new();
+
+        for server in &self.servers {
+            if let Ok(prompts) = server.list_prompts().await {
+                for prompt in prompts {
+                    all_prompts.push((server.name().to_string(), prompt));
+                }
+            }
+        }
+
+        all_prompts
+    }
+
+    pub async fn get_prompt(
+        &self,
+        server_name: &str,
+        prompt_name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<PromptResult> {
+        let server = self
+            .servers
+            .iter()
+            .find(|s| s.name() == server_name)
+            .ok_or_else(|| anyhow::anyhow!("Server not found: {}", server_name))?;
+
+        server.get_prompt(prompt_name, arguments).await
+    }
+
+    pub async fn list_all_resource_templates(&self) -> Vec<(String, ResourceTemplate)> {
+        let mut all_templates = Vec::new();
+
+        for server in &self.servers {
+            if let Ok(templates) = server.list_resource_templates().await {
+                for template in templates {
+                    all_templates.push((server.name().to_string(), template));
+                }
+            }
+        }
+
+        all_templates
+    }
+
+    pub async fn read_resource(
+        &self,
+        server_name: &str,
+        uri: &str,
+    ) -> Result<Vec<ResourceContents>> {
+        let server = self
+            .servers
+            .iter()
+            .find(|s| s.name() == server_name)
+            .ok_or_else(|| anyhow::anyhow!("Server not found: {}", server_name))?;
+
+        server.read_resource_contents(uri).await
+    }
+
     pub async fn list_all_resources(&self) -> Vec<(String, Resource)> {
         let mut all_resources = Vec::new();
 
@@ -569,3 +1648,121 @@ impl Default for McpClient {
         Self::new()
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{McpCallLimits, McpClient, McpRestartPolicy, McpServerState};
+    use std::collections::HashMap;
+    use std::time::Instant;
+    use tokio::time::{sleep, Duration};
+
+    /// Stdio server that answers `initialize` and `tools/list`, then exits with
+    /// `$1` unless it is `stay`.
+    const STAND_IN_SERVER: &str = r#"
+while read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"initialize"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"protocolVersion\":\"2024-11-05\",\"capabilities\":{\"tools\":{}},\"serverInfo\":{\"name\":\"stand-in\",\"version\":\"1\"}}}" ;;
+    *'"tools/list"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"tools\":[]}}"
+      [ "$1" = stay ] || exit "$1" ;;
+    *'"ping"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{}}" ;;
+    *'"method"'*'"id"'*|*'"id"'*'"method"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"error\":{\"code\":-32601,\"message\":\"nope\"}}" ;;
+  esac
+done
+"#;
+
+    async fn client_with_stand_in(
+        mode: &str,
+        policy: McpRestartPolicy,
+        healthcheck_interval_secs: u64,
+    ) -> McpClient {
+        let mut client = McpClient::new();
+        client
+            .add_server(
+                "stand-in".to_string(),
+                "sh".to_string(),
+                vec![
+                    "-c".to_string(),
+                    STAND_IN_SERVER.to_string(),
+                    "sh".to_string(),
+                    mode.to_string(),
+                ],
+                HashMap::new(),
+                5,
+                5,
+                healthcheck_interval_secs,
+                300,
+                policy,
+                None,
+                McpCallLimits::default(),
+            )
+            .await
+            .expect("start");
+        client
+    }
+
+    #[tokio::test]
+    async fn exiting_server_is_restarted_with_backoff_then_failed() {
+        let started = Instant::now();
+        let client = client_with_stand_in(
+            "3",
+            McpRestartPolicy {
+                max_restarts: 2,
+                backoff_secs: 1,
+                backoff_max_secs: 60,
+            },
+            0,
+        )
+        .await;
+
+        let status = loop {
+            let status = client.server_statuses().await.remove(0);
+            if status.state == McpServerState::Failed {
+                break status;
+            }
+            assert!(started.elapsed() < Duration::from_secs(30), "{:?}", status);
+            sleep(Duration::from_millis(50)).await;
+        };
+        // Two restarts, waiting 1s then 2s before them.
+        assert_eq!(status.restart_count, 2);
+        assert!(started.elapsed() >= Duration::from_secs(3));
+        assert!(status.last_exit.unwrap().contains('3'));
+        assert_eq!(status.pid, None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dropping_the_client_stops_the_server() {
+        let client = client_with_stand_in("stay", McpRestartPolicy::default(), 0).await;
+        let status = client.server_statuses().await.remove(0);
+        assert_eq!(status.state, McpServerState::Running);
+        let proc_dir = format!("/proc/{}", status.pid.expect("pid"));
+        assert!(std::path::Path::new(&proc_dir).exists());
+
+        drop(client);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while std::path::Path::new(&proc_dir).exists() {
+            assert!(Instant::now() < deadline, "server outlived its client");
+            sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn healthcheck_pings_without_refreshing_the_tool_cache() {
+        let client = client_with_stand_in("stay", McpRestartPolicy::default(), 1).await;
+        let server = client.server("stand-in").expect("server");
+        let revision = server.tools_revision();
+        server.ping().await.expect("ping");
+
+        sleep(Duration::from_millis(2500)).await;
+        assert_eq!(server.tools_revision(), revision);
+        assert_eq!(
+            client.server_statuses().await[0].state,
+            McpServerState::Running
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Process sandbox for stdio MCP servers.
+//!
+//! Applied to the child between `fork` and `exec`: working directory,
+//! environment allowlist, rlimits, `no_new_privs`, a Landlock filesystem
+//! ruleset and an unshared network namespace (the last two on Linux only).
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Default)]
+pub struct McpSandbox {
+    /// Working directory of the server process (created if missing).
+    pub workdir: Option<PathBuf>,
+    /// When set, only these variables are inherited from MasiX; the server's
+    /// own `env` entries are always passed.
+    pub env_allowlist: Option<Vec<String>>,
+    /// RLIMIT_CPU: total CPU seconds before the kernel kills the process.
+    pub max_cpu_secs: Option<u64>,
+    /// RLIMIT_AS, in MiB.
+    pub max_memory_mb: Option<u64>,
+    /// RLIMIT_NOFILE.
+    pub max_open_files: Option<u64>,
+    pub no_new_privileges: bool,
+    /// Landlock: the whole filesystem stays readable, writes are limited to
+    /// `workdir` and `writable_paths`.
+    pub landlock: bool,
+    pub writable_paths: Vec<PathBuf>,
+    /// Run in a fresh user + network namespace (loopback only).
+    pub isolate_network: bool,
+}
+
+impl McpSandbox {
+    /// Short description for logs and status output.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(workdir) = &self.workdir {
+            parts.push(format!("cwd={}", workdir.display()));
+        }
+        if let Some(allowlist) = &self.env_allowlist {
+            parts.push(format!("env={}", allowlist.len()));
+        }
+        if let Some(cpu) = self.max_cpu_secs {
+            parts.push(format!("cpu={}s", cpu));
+        }
+        if let Some(memory) = self.max_memory_mb {
+            parts.push(format!("mem={}MiB", memory));
+        }
+        if let Some(files) = self.max_open_files {
+            parts.push(format!("nofile={}", files));
+        }
+        if self.no_new_privileges {
+            parts.push("no_new_privs".to_string());
+        }
+        if self.landlock {
+            parts.push("landlock".to_string());
+        }
+        if self.isolate_network {
+            parts.push("netns".to_string());
+        }
+        if parts.is_empty() {
+            "none".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+
+    /// Parent variables that survive the allowlist; `None` when every variable is inherited.
+    pub(crate) fn filtered_env(
+        &self,
+        parent: impl Iterator<Item = (String, String)>,
+    ) -> Option<HashMap<String, String>> {
+        let allowlist = self.env_allowlist.as_ref()?;
+        Some(
+            parent
+                .filter(|(key, _)| allowlist.iter().any(|allowed| allowed == key))
+                .collect(),
+        )
+    }
+
+    /// Configure `command` so the spawned child runs inside the sandbox.
+    pub(crate) fn apply(&self, command: &mut Command) -> Result<()> {
+        if let Some(workdir) = &self.workdir {
+            std::fs::create_dir_all(workdir)?;
+            command.current_dir(workdir);
+        }
+        if let Some(env) = self.filtered_env(std::env::vars()) {
+            command.env_clear();
+            command.envs(env);
+        }
+        self.apply_process_limits(command)
+    }
+
+    #[cfg(unix)]
+    fn apply_process_limits(&self, command: &mut Command) -> Result<()> {
+        let limits: Vec<_> = [
+            (libc::RLIMIT_CPU, self.max_cpu_secs),
+            (
+                libc::RLIMIT_AS,
+                self.max_memory_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+            ),
+            (libc::RLIMIT_NOFILE, self.max_open_files),
+        ]
+        .into_iter()
+        .filter_map(|(resource, value)| value.map(|value| (resource, value as libc::rlim_t)))
+        .collect();
+
+        #[cfg(target_os = "linux")]
+        let mut ruleset = if self.landlock {
+            Some(self.landlock_ruleset()?)
+        } else {
+            None
+        };
+        #[cfg(not(target_os = "linux"))]
+        if self.landlock || self.isolate_network {
+            anyhow::bail!("landlock and isolate_network are only available on Linux");
+        }
+
+        let no_new_privileges = self.no_new_privileges;
+        let isolate_network = self.isolate_network;
+        // SAFETY: the closure runs in the forked child before exec and only
+        // issues raw syscalls on data prepared by the parent.
+        unsafe {
+            command.pre_exec(move || {
+                #[cfg(target_os = "linux")]
+                if isolate_network && libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                for (resource, value) in &limits {
+                    let limit = libc::rlimit {
+                        rlim_cur: *value,
+                        rlim_max: *value,
+                    };
+                    if libc::setrlimit(*resource, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                if no_new_privileges && libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                #[cfg(target_os = "linux")]
+                if let Some(ruleset) = ruleset.take() {
+                    ruleset.restrict_self().map_err(std::io::Error::other)?;
+                }
+                let _ = (no_new_privileges, isolate_network);
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn apply_process_limits(&self, _command: &mut Command) -> Result<()> {
+        if self.max_cpu_secs.is_some()
+            || self.max_memory_mb.is_some()
+            || self.max_open_files.is_some()
+            || self.no_new_privileges
+            || self.landlock
+            || self.isolate_network
+        {
+            anyhow::bail!("MCP sandbox limits are not supported on this platform");
+        }
+        Ok(())
+    }
+
+    /// Best-effort ruleset: kernels without Landlock run the server unrestricted.
+    #[cfg(target_os = "linux")]
+    fn landlock_ruleset(&self) -> Result<landlock::RulesetCreated> {
+        use landlock::{
+            path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr, ABI,
+        };
+
+        let abi = ABI::V5;
+        let mut writable: Vec<PathBuf> = self.writable_paths.clone();
+        writable.extend(self.workdir.clone());
+        writable.push(PathBuf::from("/dev/null"));
+        let ruleset = Ruleset::default()
+            .handle_access(AccessFs::from_all(abi))?
+            .create()?
+            .add_rules(path_beneath_rules(["/"], AccessFs::from_read(abi)))?
+            .add_rules(path_beneath_rules(writable, AccessFs::from_all(abi)))?;
+        Ok(ruleset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::McpSandbox;
+
+    #[test]
+    fn env_allowlist_filters_parent_environment() {
+        let parent = vec![
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("OPENAI_API_KEY".to_string(), "secret".to_string()),
+            ("HOME".to_string(), "/home/u".to_string()),
+        ];
+        let open = McpSandbox::default();
+        assert!(open.filtered_env(parent.clone().into_iter()).is_none());
+
+        let sandbox = McpSandbox {
+            env_allowlist: Some(vec!["PATH".to_string(), "HOME".to_string()]),
+            ..McpSandbox::default()
+        };
+        let env = sandbox.filtered_env(parent.into_iter()).expect("filtered");
+        assert_eq!(env.len(), 2);
+        assert!(!env.contains_key("OPENAI_API_KEY"));
+        assert_eq!(open.summary(), "none");
+        assert_eq!(sandbox.summary(), "env=2");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn limits_and_env_apply_to_the_child_process() {
+        let workdir = std::env::temp_dir().join(format!(
+            "masix-mcp-sandbox-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("clock")
+                .as_nanos()
+        ));
+        let sandbox = McpSandbox {
+            workdir: Some(workdir.clone()),
+            env_allowlist: Some(vec!["PATH".to_string()]),
+            max_open_files: Some(64),
+            no_new_privileges: true,
+            ..McpSandbox::default()
+        };
+        let mut command = tokio::process::Command::new("sh");
+        command
+            .args([
+                "-c",
+                "pwd; ulimit -n; echo \"home=${HOME:-unset}\"; grep NoNewPrivs /proc/self/status",
+            ])
+            .stdout(std::process::Stdio::piped());
+        sandbox.apply(&mut command).expect("apply");
+        let output = command.output().await.expect("run");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines[0], workdir.canonicalize().unwrap().to_string_lossy());
+        assert_eq!(lines[1], "64");
+        assert_eq!(lines[2], "home=unset");
+        assert!(lines[3].ends_with('1'), "{}", lines[3]);
+
+        std::fs::remove_dir_all(&workdir).ok();
+    }
+}
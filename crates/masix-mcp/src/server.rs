@@ -0,0 +1,298 @@
+//! Server side of MCP over stdio: lets other agents call tools hosted by MasiX.
+//!
+//! Only the `tools` capability is offered. Requests are answered one at a
+//! time, in the order they arrive.
+
+use crate::{Tool, ToolContent, ToolResult, LATEST_PROTOCOL_VERSION, SUPPORTED_PROTOCOL_VERSIONS};
+use anyhow::Result;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tracing::{debug, warn};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Identity announced in the `initialize` result.
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    pub name: String,
+    pub version: String,
+    pub instructions: Option<String>,
+}
+
+/// Tools served to MCP clients.
+#[async_trait::async_trait]
+pub trait ToolHandler: Send + Sync {
+    async fn list_tools(&self) -> Vec<Tool>;
+    /// Run a tool listed by `list_tools`. Errors are reported to the client
+    /// as a tool result with `isError`.
+    async fn call_tool(&self, name: &str, arguments: Value) -> Result<ToolResult>;
+}
+
+/// Serve MCP on the process stdin/stdout until stdin is closed.
+pub async fn serve_stdio<H: ToolHandler>(info: &ServerInfo, handler: &H) -> Result<()> {
+    serve(info, handler, tokio::io::stdin(), tokio::io::stdout()).await
+}
+
+/// Serve newline-delimited JSON-RPC from `reader`, answering on `writer`.
+pub async fn serve<H, R, W>(info: &ServerInfo, handler: &H, reader: R, mut writer: W) -> Result<()>
+where
+    H: ToolHandler,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(message) => handle_message(info, handler, message).await,
+            Err(e) => Some(error_response(
+                Value::Null,
+                PARSE_ERROR,
+                &format!("Parse error: {}", e),
+            )),
+        };
+        if let Some(response) = response {
+            let mut payload = serde_json::to_vec(&response)?;
+            payload.push(b'\n');
+            writer.write_all(&payload).await?;
+            writer.flush().await?;
+        }
+    }
+    debug!("MCP client closed the input stream");
+    Ok(())
+}
+
+async fn handle_message<H: ToolHandler>(
+    info: &ServerInfo,
+    handler: &H,
+    message: Value,
+) -> Option<Value> {
+    let Some(method) = message.get("method").and_then(|v| v.as_str()) else {
+        if message.is_object()
+            && (message.get("result").is_some() || message.get("error").is_some())
+        {
+            // Replies to requests we never send; nothing to do.
+            return None;
+        }
+        return Some(error_response(
+            message.get("id").cloned().unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "Invalid request",
+        ));
+    };
+    // Notifications (no id) never get a reply.
+    let id = message.get("id").cloned()?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let outcome = match method {
+        "initialize" => Ok(initialize_result(info, &params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => {
+            let tools: Vec<Value> = handler.list_tools().await.iter().map(tool_json).collect();
+            Ok(json!({ "tools": tools }))
+        }
+        "tools/call" => call_tool(handler, &params).await,
+        other => Err((METHOD_NOT_FOUND, format!("Method not found: {}", other))),
+    };
+
+    Some(match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+fn initialize_result(info: &ServerInfo, params: &Value) -> Value {
+    let requested = params
+        .get("protocolVersion")
+        .and_then(|v| v.as_str())
+        .unwrap_or(LATEST_PROTOCOL_VERSION);
+    let protocol_version = if SUPPORTED_PROTOCOL_VERSIONS.contains(&requested) {
+        requested
+    } else {
+        LATEST_PROTOCOL_VERSION
+    };
+    let mut result = json!({
+        "protocolVersion": protocol_version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": info.name, "version": info.version },
+    });
+    if let Some(instructions) = &info.instructions {
+        result["instructions"] = json!(instructions);
+    }
+    result
+}
+
+async fn call_tool<H: ToolHandler>(handler: &H, params: &Value) -> Result<Value, (i64, String)> {
+    let name = params
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| (INVALID_PARAMS, "tools/call requires `name`".to_string()))?;
+    if !handler
+        .list_tools()
+        .await
+        .iter()
+        .any(|tool| tool.name == name)
+    {
+        return Err((INVALID_PARAMS, format!("Unknown tool: {}", name)));
+    }
+    let arguments = match params.get("arguments") {
+        None | Some(Value::Null) => json!({}),
+        Some(arguments) => arguments.clone(),
+    };
+
+    let result = match handler.call_tool(name, arguments).await {
+        Ok(result) => result,
+        Err(e) => {
+            warn!("MCP serve: tool '{}' failed: {}", name, e);
+            ToolResult {
+                content: vec![ToolContent::Text {
+                    text: e.to_string(),
+                }],
+                is_error: true,
+            }
+        }
+    };
+    Ok(json!({ "content": result.content, "isError": result.is_error }))
+}
+
+fn tool_json(tool: &Tool) -> Value {
+    let input_schema = if tool.input_schema.is_object() {
+        tool.input_schema.clone()
+    } else {
+        json!({ "type": "object" })
+    };
+    json!({
+        "name": tool.name,
+        "description": tool.description,
+        "inputSchema": input_schema,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{serve, ServerInfo, ToolHandler};
+    use crate::{Tool, ToolContent, ToolResult};
+    use anyhow::{bail, Result};
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+
+    struct Echo;
+
+    async fn next_reply<R: AsyncBufRead + Unpin>(replies: &mut Lines<R>) -> Value {
+        let line = replies.next_line().await.unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[async_trait::async_trait]
+    impl ToolHandler for Echo {
+        async fn list_tools(&self) -> Vec<Tool> {
+            vec![Tool {
+                name: "echo".to_string(),
+                description: "Echo the text back".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": { "text": { "type": "string" } },
+                    "required": ["text"]
+                }),
+            }]
+        }
+
+        async fn call_tool(&self, _name: &str, arguments: Value) -> Result<ToolResult> {
+            let Some(text) = arguments.get("text").and_then(|v| v.as_str()) else {
+                bail!("missing text");
+            };
+            Ok(ToolResult {
+                content: vec![ToolContent::Text {
+                    text: text.to_string(),
+                }],
+                is_error: false,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn stdio_server_negotiates_lists_and_calls_tools() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server);
+        let info = ServerInfo {
+            name: "masix".to_string(),
+            version: "test".to_string(),
+            instructions: None,
+        };
+        let task =
+            tokio::spawn(async move { serve(&info, &Echo, server_read, server_write).await });
+
+        let (client_read, mut client_write) = tokio::io::split(client);
+        let mut replies = BufReader::new(client_read).lines();
+
+        let messages = [
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+                   "params": {"protocolVersion": "2024-11-05", "capabilities": {}}}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                   "params": {"name": "echo", "arguments": {"text": "ciao"}}}),
+            json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                   "params": {"name": "echo", "arguments": {}}}),
+            json!({"jsonrpc": "2.0", "id": 5, "method": "tools/call",
+                   "params": {"name": "nope"}}),
+            json!({"jsonrpc": "2.0", "id": 6, "method": "resources/list"}),
+        ];
+        for message in messages {
+            let mut line = serde_json::to_vec(&message).unwrap();
+            line.push(b'\n');
+            client_write.write_all(&line).await.unwrap();
+        }
+        client_write.write_all(b"{not json\n").await.unwrap();
+
+        let init = next_reply(&mut replies).await;
+        assert_eq!(init["id"], 1);
+        assert_eq!(init["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(init["result"]["serverInfo"]["name"], "masix");
+        assert!(init["result"]["capabilities"]["tools"].is_object());
+
+        let list = next_reply(&mut replies).await;
+        assert_eq!(list["id"], 2);
+        assert_eq!(list["result"]["tools"][0]["name"], "echo");
+        assert_eq!(
+            list["result"]["tools"][0]["inputSchema"]["required"][0],
+            "text"
+        );
+
+        let call = next_reply(&mut replies).await;
+        assert_eq!(call["result"]["content"][0]["text"], "ciao");
+        assert_eq!(call["result"]["isError"], false);
+
+        let failed = next_reply(&mut replies).await;
+        assert_eq!(failed["result"]["isError"], true);
+        assert_eq!(failed["result"]["content"][0]["text"], "missing text");
+
+        let unknown = next_reply(&mut replies).await;
+        assert_eq!(unknown["error"]["code"], -32602);
+
+        let unsupported = next_reply(&mut replies).await;
+        assert_eq!(unsupported["error"]["code"], -32601);
+
+        let parse_error = next_reply(&mut replies).await;
+        assert_eq!(parse_error["error"]["code"], -32700);
+        assert!(parse_error["id"].is_null());
+
+        drop(client_write);
+        drop(replies);
+        task.await.unwrap().unwrap();
+    }
+}
@@ -21,6 +21,23 @@ pub struct ChatMessage {
     pub tool_call_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// Images attached to the message (e.g. MCP tool screenshots); providers
+    /// translate them into their own image parts and never persist them.
+    #[serde(skip)]
+    pub images: Vec<ImagePart>,
+}
+
+/// Base64 image payload attached to a chat message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePart {
+    pub mime_type: String,
+    pub data: String,
+}
+
+impl ImagePart {
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type, self.data)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,6 +145,7 @@ pub struct OpenAICompatibleProvider {
     api_key: String,
     base_url: String,
     model: String,
+    supports_images: bool,
 }
 
 impl OpenAICompatibleProvider {
@@ -143,9 +161,55 @@ impl OpenAICompatibleProvider {
             api_key,
             base_url: base_url.unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
             model: model.unwrap_or_else(|| "gpt-3.5-turbo".to_string()),
+            supports_images: false,
         }
     }
 
+    /// Forward message images as `image_url` parts (the model must accept vision input).
+    pub fn with_image_input(mut self, enabled: bool) -> Self {
+        self.supports_images = enabled;
+        self
+    }
+
+    /// Tool images go into one `user` message after the run of `tool`
+    /// results: APIs reject anything between the results of one assistant turn.
+    fn convert_messages(&self, messages: &[ChatMessage]) -> Vec<serde_json::Value> {
+        let mut converted = Vec::with_capacity(messages.len());
+        let mut image_parts: Vec<serde_json::Value> = Vec::new();
+        let flush = |converted: &mut Vec<serde_json::Value>, parts: &mut Vec<serde_json::Value>| {
+            if !parts.is_empty() {
+                converted.push(serde_json::json!({
+                    "role": "user",
+                    "content": std::mem::take(parts)
+                }));
+            }
+        };
+        for msg in messages {
+            if msg.role != "tool" {
+                flush(&mut converted, &mut image_parts);
+            }
+            converted.push(serde_json::to_value(msg).unwrap_or(serde_json::Value::Null));
+            if msg.images.is_empty() || !self.supports_images {
+                continue;
+            }
+            image_parts.push(serde_json::json!({
+                "type": "text",
+                "text": format!(
+                    "Image output from tool {}:",
+                    msg.name.as_deref().unwrap_or("call")
+                )
+            }));
+            image_parts.extend(msg.images.iter().map(|image| {
+                serde_json::json!({
+                    "type": "image_url",
+                    "image_url": { "url": image.data_url() }
+                })
+            }));
+        }
+        flush(&mut converted, &mut image_parts);
+        converted
+    }
+
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
@@ -694,7 +758,7 @@ impl Provider for OpenAICompatibleProvider {
         self.request_chat(
             serde_json::json!({
                 "model": model_override.unwrap_or(&self.model),
-                "messages": messages
+                "messages": self.convert_messages(&messages)
             }),
             retry_policy,
         )
@@ -721,7 +785,7 @@ impl Provider for OpenAICompatibleProvider {
         self.request_chat(
             serde_json::json!({
                 "model": model_override.unwrap_or(&self.model),
-                "messages": messages,
+                "messages": self.convert_messages(&messages),
                 "tools": tools,
                 "tool_choice": "auto"
             }),
@@ -810,6 +874,25 @@ impl AnthropicProvider {
                 }
                 "tool" => {
                     if let (Some(tool_id), Some(content)) = (&msg.tool_call_id, &msg.content) {
+                        let content = if msg.images.is_empty() {
+                            serde_json::json!(content)
+                        } else {
+                            let mut blocks = vec![serde_json::json!({
+                                "type": "text",
+                                "text": content
+                            })];
+                            blocks.extend(msg.images.iter().map(|image| {
+                                serde_json::json!({
+                                    "type": "image",
+                                    "source": {
+                                        "type": "base64",
+                                        "media_type": image.mime_type,
+                                        "data": image.data
+                                    }
+                                })
+                            }));
+                            serde_json::Value::Array(blocks)
+                        };
                         anthropic_messages.push(serde_json::json!({
                             "role": "user",
                             "content": [{
@@ -1164,7 +1247,7 @@ impl ProviderRouter {
 
 #[cfg(test)]
 mod tests {
-    use super::{OpenAICompatibleProvider, RetryPolicy};
+    use super::{AnthropicProvider, ChatMessage, ImagePart, OpenAICompatibleProvider, RetryPolicy};
     use reqwest::header::{HeaderMap, HeaderValue};
     use std::time::Duration;
 
@@ -1412,4 +1495,95 @@ mod tests {
             Duration::from_secs(30)
         );
     }
+
+    fn tool_message_with_image() -> ChatMessage {
+        ChatMessage {
+            role: "tool".to_string(),
+            content: Some("[image content: image/png, shown to the user]".to_string()),
+            tool_calls: None,
+            tool_call_id: Some("call_1".to_string()),
+            name: Some("browser_screenshot".to_string()),
+            images: vec![ImagePart {
+                mime_type: "image/png".to_string(),
+                data: "iVBORw0KGgo=".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn openai_tool_images_follow_as_user_parts_only_when_enabled() {
+        let messages = vec![tool_message_with_image()];
+        let provider = OpenAICompatibleProvider::new(
+            "test".to_string(),
+            "key".to_string(),
+            None,
+            Some("model".to_string()),
+        );
+
+        let plain = provider.convert_messages(&messages);
+        assert_eq!(plain.len(), 1);
+        assert!(plain[0].get("images").is_none());
+
+        let converted = provider.with_image_input(true).convert_messages(&messages);
+        assert_eq!(converted.len(), 2);
+        assert_eq!(converted[0]["role"], "tool");
+        assert_eq!(converted[1]["role"], "user");
+        assert_eq!(
+            converted[1]["content"][1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+    }
+
+    #[test]
+    fn openai_tool_images_follow_the_last_tool_result() {
+        let second = ChatMessage {
+            role: "tool".to_string(),
+            content: Some("done".to_string()),
+            tool_calls: None,
+            tool_call_id: Some("call_2".to_string()),
+            name: Some("read_file".to_string()),
+            images: Vec::new(),
+        };
+        let follow_up = ChatMessage {
+            role: "assistant".to_string(),
+            content: Some("Here it is.".to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+            images: Vec::new(),
+        };
+        let messages = vec![tool_message_with_image(), second, follow_up];
+        let provider = OpenAICompatibleProvider::new(
+            "test".to_string(),
+            "key".to_string(),
+            None,
+            Some("model".to_string()),
+        )
+        .with_image_input(true);
+
+        let converted = provider.convert_messages(&messages);
+        let roles: Vec<&str> = converted
+            .iter()
+            .map(|msg| msg["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["tool", "tool", "user", "assistant"]);
+        assert_eq!(converted[1]["tool_call_id"], "call_2");
+        assert_eq!(
+            converted[2]["content"][0]["text"],
+            "Image output from tool browser_screenshot:"
+        );
+        assert_eq!(converted[2]["content"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn anthropic_tool_result_carries_image_blocks() {
+        let (_, converted) =
+            AnthropicProvider::convert_messages_to_anthropic(&[tool_message_with_image()]);
+        let result = &converted[0]["content"][0];
+        assert_eq!(result["type"], "tool_result");
+        assert_eq!(result["content"][0]["type"], "text");
+        assert_eq!(result["content"][1]["type"], "image");
+        assert_eq!(result["content"][1]["source"]["media_type"], "image/png");
+        assert_eq!(result["content"][1]["source"]["data"], "iVBORw0KGgo=");
+    }
 }
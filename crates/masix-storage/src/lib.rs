@@ -2,7 +2,12 @@
 //!
 //! SQLite event persistence with ChaCha20-Poly1305 encryption
 
+mod audit;
+
 use anyhow::{anyhow, Result};
+pub use audit::{
+    redact_secrets, AuditEntry, AuditFilter, AuditRecord, AuditVerification, REDACTED,
+};
 use rusqlite::OptionalExtension;
 use std::path::Path;
 use std::str::FromStr;
@@ -83,10 +88,19 @@ impl Storage {
                 next_run DATETIME,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP
             );
+
+            CREATE TABLE IF NOT EXISTS user_preferences (
+                state_key TEXT PRIMARY KEY,
+                language TEXT,
+                provider TEXT,
+                model TEXT,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            );
             ",
         )?;
 
         Self::ensure_cron_schema(&conn)?;
+        audit::ensure_audit_schema(&conn)?;
 
         Ok(Self { conn })
     }
@@ -143,6 +157,71 @@ impl Storage {
         Ok(offset)
     }
 
+    /// Store the preferences for a user state key; a row with no values is removed.
+    pub fn upsert_user_preferences(
+        &self,
+        state_key: &str,
+        language: Option<&str>,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<()> {
+        if language.is_none() && provider.is_none() && model.is_none() {
+            self.delete_user_preferences(state_key)?;
+            return Ok(());
+        }
+        self.conn.execute(
+            "INSERT INTO user_preferences (state_key, language, provider, model)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(state_key)
+             DO UPDATE SET language = excluded.language, provider = excluded.provider,
+                           model = excluded.model, updated_at = CURRENT_TIMESTAMP",
+            (state_key, language, provider, model),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_user_preferences(&self, state_key: &str) -> Result<Option<UserPreferences>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT state_key, language, provider, model, updated_at
+             FROM user_preferences WHERE state_key = ?1",
+        )?;
+        let prefs = stmt
+            .query_row([state_key], Self::user_preferences_from_row)
+            .optional()?;
+        Ok(prefs)
+    }
+
+    pub fn list_user_preferences(&self) -> Result<Vec<UserPreferences>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT state_key, language, provider, model, updated_at
+             FROM user_preferences ORDER BY state_key",
+        )?;
+        let rows = stmt.query_map([], Self::user_preferences_from_row)?;
+        let mut prefs = Vec::new();
+        for row in rows {
+            prefs.push(row?);
+        }
+        Ok(prefs)
+    }
+
+    pub fn delete_user_preferences(&self, state_key: &str) -> Result<bool> {
+        let changed = self.conn.execute(
+            "DELETE FROM user_preferences WHERE state_key = ?1",
+            [state_key],
+        )?;
+        Ok(changed > 0)
+    }
+
+    fn user_preferences_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<UserPreferences> {
+        Ok(UserPreferences {
+            state_key: row.get(0)?,
+            language: row.get(1)?,
+            provider: row.get(2)?,
+            model: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn create_cron_job(
         &self,
@@ -426,6 +505,15 @@ pub struct CronJob {
     pub recurring: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct UserPreferences {
+    pub state_key: String,
+    pub language: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub updated_at: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::Storage;
@@ -484,6 +572,34 @@ mod tests {
         assert_eq!(b_jobs[0].account_tag, "bot_b");
     }
 
+    #[test]
+    fn user_preferences_roundtrip_and_clear() {
+        let path = temp_db_path("prefs");
+        let storage = Storage::new(&path).expect("storage init");
+
+        storage
+            .upsert_user_preferences("bot_a::100", Some("it"), None, Some("gpt-4o"))
+            .expect("insert");
+        storage
+            .upsert_user_preferences("bot_a::100", Some("en"), Some("openai"), Some("gpt-4o"))
+            .expect("update");
+        let prefs = storage
+            .get_user_preferences("bot_a::100")
+            .expect("get")
+            .expect("row");
+        assert_eq!(prefs.language.as_deref(), Some("en"));
+        assert_eq!(prefs.provider.as_deref(), Some("openai"));
+        assert_eq!(storage.list_user_preferences().expect("list").len(), 1);
+
+        storage
+            .upsert_user_preferences("bot_a::100", None, None, None)
+            .expect("clear");
+        assert!(storage
+            .get_user_preferences("bot_a::100")
+            .expect("get")
+            .is_none());
+    }
+
     #[test]
     fn migrates_legacy_cron_table_with_default_account_tag() {
         let path = temp_db_path("legacy");
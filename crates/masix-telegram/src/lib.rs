@@ -7,7 +7,7 @@ pub mod menu;
 
 use anyhow::{anyhow, Result};
 use masix_config::TelegramAccount;
-use masix_ipc::{Envelope, EventBus, InlineButton, MessageKind, OutboundMessage};
+use masix_ipc::{Envelope, EventBus, InlineButton, MessageKind, OutboundMessage, OutboundPhoto};
 use reqwest::{Client, ClientBuilder};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -18,6 +18,7 @@ use tokio::sync::broadcast;
 use tracing::{info, warn};
 
 const TELEGRAM_MAX_MESSAGE_LEN: usize = 4096;
+const TELEGRAM_MAX_CAPTION_LEN: usize = 1024;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramUpdate {
@@ -147,6 +148,8 @@ pub struct TelegramUser {
 pub struct TelegramReplyToMessage {
     pub message_id: i64,
     pub from: Option<TelegramUser>,
+    #[serde(default)]
+    pub document: Option<TelegramDocument>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -302,14 +305,16 @@ impl TelegramAdapter {
         Ok(parsed.result)
     }
 
+    /// Returns the id of the last chunk sent.
     pub async fn send_message(
         &self,
         chat_id: i64,
         text: &str,
         reply_to: Option<i64>,
         inline_keyboard: Option<Vec<Vec<InlineButton>>>,
-    ) -> Result<()> {
+    ) -> Result<Option<i64>> {
         let chunks = self.chunk_message(text);
+        let mut last_message_id = None;
 
         for (i, chunk) in chunks.iter().enumerate() {
             let url = format!("{}/sendMessage", self.api_url);
@@ -339,19 +344,22 @@ impl TelegramAdapter {
                 }
             }
 
-            self.send_with_markdown_fallback(&url, payload).await?;
+            let sent = self.send_with_markdown_fallback(&url, payload).await?;
+            last_message_id = Self::result_message_id(&sent).or(last_message_id);
         }
 
-        Ok(())
+        Ok(last_message_id)
     }
 
+    /// Returns the id of the message now showing `text`: `message_id`, or a
+    /// new message when the text is too long to edit in place.
     pub async fn edit_message_text(
         &self,
         chat_id: i64,
         message_id: i64,
         text: &str,
         inline_keyboard: Option<Vec<Vec<InlineButton>>>,
-    ) -> Result<()> {
+    ) -> Result<Option<i64>> {
         // editMessageText cannot be split into chunks: fallback to a new message if too long.
         if text.chars().count() > TELEGRAM_MAX_MESSAGE_LEN {
             return self
@@ -379,7 +387,12 @@ impl TelegramAdapter {
             });
         }
 
-        self.send_with_markdown_fallback(&url, payload).await
+        self.send_with_markdown_fallback(&url, payload).await?;
+        Ok(Some(message_id))
+    }
+
+    fn result_message_id(result: &serde_json::Value) -> Option<i64> {
+        result.get("message_id").and_then(|id| id.as_i64())
     }
 
     pub async fn answer_callback_query(
@@ -411,11 +424,85 @@ impl TelegramAdapter {
         Ok(())
     }
 
+    /// Upload an image with `sendPhoto`; formats Telegram does not render as photos
+    /// go through `sendDocument`.
+    pub async fn send_photo(
+        &self,
+        chat_id: i64,
+        photo: &OutboundPhoto,
+        caption: &str,
+        reply_to: Option<i64>,
+    ) -> Result<()> {
+        let extension = match photo.mime_type.as_str() {
+            "image/png" => Some("png"),
+            "image/jpeg" | "image/jpg" => Some("jpg"),
+            "image/webp" => Some("webp"),
+            _ => None,
+        };
+        let (endpoint, field, file_name) = match extension {
+            Some(ext) => ("sendPhoto", "photo", format!("image.{}", ext)),
+            None => {
+                let subtype = photo.mime_type.rsplit('/').next().unwrap_or("bin");
+                ("sendDocument", "document", format!("image.{}", subtype))
+            }
+        };
+
+        let mut fields = vec![("chat_id", chat_id.to_string())];
+        let caption = caption.trim();
+        if !caption.is_empty() {
+            fields.push((
+                "caption",
+                caption.chars().take(TELEGRAM_MAX_CAPTION_LEN).collect(),
+            ));
+        }
+        if let Some(reply_to_message_id) = reply_to {
+            fields.push(("reply_to_message_id", reply_to_message_id.to_string()));
+        }
+
+        let boundary = format!(
+            "masix-{:x}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default()
+        );
+        let body = multipart_form(
+            &boundary,
+            &fields,
+            (field, &file_name, &photo.mime_type, &photo.data),
+        );
+        let url = format!("{}/{}", self.api_url, endpoint);
+        let resp = self
+            .client
+            .post(&url)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| anyhow!("telegram {} request failed: {}", endpoint, e))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("telegram {} HTTP {}: {}", endpoint, status, body));
+        }
+        let parsed: ApiResponse<serde_json::Value> = resp
+            .json()
+            .await
+            .map_err(|e| anyhow!("telegram {} decode failed: {}", endpoint, e))?;
+        if !parsed.ok {
+            return Err(anyhow!("telegram {} returned ok=false", endpoint));
+        }
+        Ok(())
+    }
+
     async fn send_with_markdown_fallback(
         &self,
         url: &str,
         payload: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<serde_json::Value> {
         let endpoint = url.rsplit('/').next().unwrap_or("telegram");
 
         let first_resp = self
@@ -432,7 +519,7 @@ impl TelegramAdapter {
                 .await
                 .map_err(|e| anyhow!("telegram {} decode failed: {}", endpoint, e))?;
             if parsed.ok {
-                return Ok(());
+                return Ok(parsed.result);
             }
             warn!(
                 "telegram {} returned ok=false with Markdown payload, retrying without parse_mode",
@@ -491,7 +578,7 @@ impl TelegramAdapter {
             return Err(anyhow!("telegram {} fallback returned ok=false", endpoint));
         }
 
-        Ok(())
+        Ok(parsed.result)
     }
 
     async fn send_without_reply_target(
@@ -499,7 +586,7 @@ impl TelegramAdapter {
         url: &str,
         endpoint: &str,
         payload: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<serde_json::Value> {
         let resp = self
             .client
             .post(url)
@@ -530,7 +617,7 @@ impl TelegramAdapter {
             ));
         }
 
-        Ok(())
+        Ok(parsed.result)
     }
 
     fn remove_reply_to_message_id(payload: &mut serde_json::Value) -> bool {
@@ -631,17 +718,21 @@ impl TelegramAdapter {
             { "command": "start", "description": "Open main menu" },
             { "command": "menu", "description": "Show menu" },
             { "command": "new", "description": "Reset conversation" },
+            { "command": "session", "description": "Manage named sessions" },
             { "command": "help", "description": "Show help" },
             { "command": "whoiam", "description": "Show IDs and scope" },
             { "command": "language", "description": "Set language" },
             { "command": "provider", "description": "Manage provider" },
             { "command": "model", "description": "Set model" },
             { "command": "capabilities", "description": "Show live capabilities" },
+            { "command": "prompts", "description": "List MCP prompts" },
+            { "command": "resources", "description": "List MCP resources" },
             { "command": "admin", "description": "Manage ACL and user tools" },
             { "command": "mcp", "description": "Show MCP status" },
             { "command": "tools", "description": "List runtime tools" },
             { "command": "cron", "description": "Manage reminders" },
             { "command": "exec", "description": "Run shell commands" },
+            { "command": "jobs", "description": "Manage background exec jobs" },
             { "command": "termux", "description": "Use Termux tools" }
         ]);
 
@@ -698,6 +789,8 @@ impl TelegramAdapter {
                     .is_some_and(|mime| mime.starts_with("image/"))
                 {
                     Some("[Media: image_document]".to_string())
+                } else if message.document.is_some() {
+                    Some("[Media: document]".to_string())
                 } else if message.video.is_some() {
                     Some("[Media: video]".to_string())
                 } else if message.voice.is_some() {
@@ -785,6 +878,11 @@ impl TelegramAdapter {
                         obj.insert("media".to_string(), media);
                     }
                 }
+                if let Some(reply_media) = Self::extract_reply_media_payload(message) {
+                    if let Some(obj) = payload.as_object_mut() {
+                        obj.insert("reply_media".to_string(), reply_media);
+                    }
+                }
 
                 let envelope = Envelope::new(
                     "telegram",
@@ -887,6 +985,7 @@ impl TelegramAdapter {
                     "caption": caption,
                 }));
             }
+            return Some(Self::document_media_payload(document, caption));
         }
 
         if let Some(video) = &message.video {
@@ -929,6 +1028,34 @@ impl TelegramAdapter {
         None
     }
 
+    fn document_media_payload(
+        document: &TelegramDocument,
+        caption: Option<String>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "kind": "document",
+            "file_id": document.file_id,
+            "mime_type": document.mime_type,
+            "file_name": document.file_name,
+            "file_size": document.file_size,
+            "caption": caption,
+        })
+    }
+
+    /// Document attached to the message being replied to (used by `/kb add`).
+    fn extract_reply_media_payload(message: &TelegramMessage) -> Option<serde_json::Value> {
+        let reply = message.reply_to_message.as_ref()?;
+        let document = reply.document.as_ref()?;
+        let mut payload = Self::document_media_payload(document, None);
+        if let Some(obj) = payload.as_object_mut() {
+            obj.insert(
+                "message_id".to_string(),
+                serde_json::json!(reply.message_id),
+            );
+        }
+        Some(payload)
+    }
+
     async fn handle_callback(&self, callback: &TelegramCallbackQuery) {
         let query_id = &callback.id;
         let chat_id = callback.message.as_ref().map(|m| m.chat.id);
@@ -944,6 +1071,7 @@ impl TelegramAdapter {
             }
         }
 
+        let mut published = false;
         if let Some(event_bus) = &self.event_bus {
             let chat_type = callback
                 .message
@@ -968,13 +1096,19 @@ impl TelegramAdapter {
                 if let Some(message_id) = message_id {
                     envelope = envelope.with_message_id(message_id);
                 }
-                if let Err(e) = event_bus.publish(envelope) {
-                    warn!("Failed to publish callback to event bus: {}", e);
+                match event_bus.publish(envelope) {
+                    Ok(()) => published = true,
+                    Err(e) => warn!("Failed to publish callback to event bus: {}", e),
                 }
             }
         }
 
-        let _ = self.answer_callback_query(query_id, None).await;
+        // The runtime answers approval clicks itself, with the decision as toast.
+        let runtime_answers = published
+            && data.is_some_and(|data| data.starts_with(masix_ipc::TOOL_APPROVAL_CALLBACK_PREFIX));
+        if !runtime_answers {
+            let _ = self.answer_callback_query(query_id, None).await;
+        }
     }
 
     pub async fn run_outbound_handler(&self, mut receiver: broadcast::Receiver<OutboundMessage>) {
@@ -992,6 +1126,18 @@ impl TelegramAdapter {
                         }
                     }
 
+                    if let Some(answer) = &msg.callback_answer {
+                        if let Err(e) = self
+                            .answer_callback_query(&answer.query_id, answer.text.as_deref())
+                            .await
+                        {
+                            warn!("Failed to answer callback query: {}", e);
+                        }
+                        if msg.text.is_empty() {
+                            continue;
+                        }
+                    }
+
                     // Handle chat action (typing, etc.)
                     if let Some(action) = &msg.chat_action {
                         if let Err(e) = self.send_chat_action(msg.chat_id, action).await {
@@ -1000,7 +1146,11 @@ impl TelegramAdapter {
                         continue;
                     }
 
-                    let send_result = if let Some(message_id) = msg.edit_message_id {
+                    let send_result = if let Some(photo) = &msg.photo {
+                        self.send_photo(msg.chat_id, photo, &msg.text, msg.reply_to)
+                            .await
+                            .map(|_| None)
+                    } else if let Some(message_id) = msg.edit_message_id {
                         self.edit_message_text(
                             msg.chat_id,
                             message_id,
@@ -1013,8 +1163,14 @@ impl TelegramAdapter {
                             .await
                     };
 
-                    if let Err(e) = send_result {
-                        warn!("Failed to send outbound message: {}", e);
+                    match send_result {
+                        Ok(Some(message_id)) => {
+                            if let Some(receipt) = &msg.delivered {
+                                receipt.deliver(message_id);
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("Failed to send outbound message: {}", e),
                     }
                 }
                 Err(broadcast::error::RecvError::Closed) => {
@@ -1032,9 +1188,39 @@ impl TelegramAdapter {
     }
 }
 
+/// Encode `multipart/form-data` with text fields and one file part
+/// (`(field, file_name, mime_type, bytes)`).
+fn multipart_form(
+    boundary: &str,
+    fields: &[(&str, String)],
+    file: (&str, &str, &str, &[u8]),
+) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    let (field, file_name, mime_type, bytes) = file;
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            boundary, field, file_name, mime_type
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
 #[cfg(test)]
 mod tests {
-    use super::TelegramAdapter;
+    use super::{multipart_form, TelegramAdapter};
     use masix_config::TelegramAccount;
 
     fn make_adapter() -> TelegramAdapter {
@@ -1048,6 +1234,7 @@ mod tests {
             readonly: vec![],
             isolated: true,
             shared_memory_with: vec![],
+            memory_shares: vec![],
             allow_self_memory_edit: true,
             dm_policy: masix_config::DmPolicy::Allowlist,
             dm_allow_from: vec![],
@@ -1086,6 +1273,19 @@ mod tests {
         assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 4096));
     }
 
+    #[test]
+    fn multipart_form_encodes_fields_and_file() {
+        let body = multipart_form(
+            "b0",
+            &[("chat_id", "42".to_string())],
+            ("photo", "image.png", "image/png", &[0x89, b'P']),
+        );
+        let mut expected = b"--b0\r\nContent-Disposition: form-data; name=\"chat_id\"\r\n\r\n42\r\n--b0\r\nContent-Disposition: form-data; name=\"photo\"; filename=\"image.png\"\r\nContent-Type: image/png\r\n\r\n".to_vec();
+        expected.extend_from_slice(&[0x89, b'P']);
+        expected.extend_from_slice(b"\r\n--b0--\r\n");
+        assert_eq!(body, expected);
+    }
+
     #[test]
     fn remove_reply_to_message_id_when_present() {
         let mut payload = serde_json::json!({
@@ -1102,4 +1302,29 @@ mod tests {
         let body = r#"{"ok":false,"error_code":400,"description":"Bad Request: message to be replied not found"}"#;
         assert!(TelegramAdapter::is_reply_target_missing(body));
     }
+
+    #[test]
+    fn reply_document_is_exposed_as_reply_media() {
+        let message: super::TelegramMessage = serde_json::from_value(serde_json::json!({
+            "message_id": 10,
+            "text": "/kb add",
+            "chat": {"id": 1, "type": "private"},
+            "from": {"id": 7},
+            "reply_to_message": {
+                "message_id": 9,
+                "document": {
+                    "file_id": "doc-1",
+                    "mime_type": "application/pdf",
+                    "file_name": "manual.pdf"
+                }
+            }
+        }))
+        .unwrap();
+        assert!(TelegramAdapter::extract_media_payload(&message).is_none());
+        let reply = TelegramAdapter::extract_reply_media_payload(&message).unwrap();
+        assert_eq!(reply["kind"], "document");
+        assert_eq!(reply["file_id"], "doc-1");
+        assert_eq!(reply["file_name"], "manual.pdf");
+        assert_eq!(reply["message_id"], 9);
+    }
 }
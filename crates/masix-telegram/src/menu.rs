@@ -175,21 +175,21 @@ pub fn language_menu(lang: Language) -> (String, Vec<Vec<InlineButton>>) {
 
 pub fn help_text(lang: Language, is_admin: bool) -> String {
     let mut text = match lang {
-        Language::English => "📚 *Help - Available Commands*\n\n/start - Show main menu\n/menu - Show main menu\n/new - Reset conversation\n/help - Show this help\n/whoiam - Show user/chat IDs\n/language - Change language\n/provider - Manage LLM provider\n/model - Change model\n/cron - Manage reminders\n/termux - Termux tools\n/capabilities - Show live runtime capabilities\n\nJust send a message to chat with me!",
-        Language::Spanish => "📚 *Ayuda - Comandos Disponibles*\n\n/start - Mostrar menú principal\n/menu - Mostrar menú principal\n/new - Reiniciar conversación\n/help - Mostrar esta ayuda\n/whoiam - Mostrar IDs de usuario/chat\n/language - Cambiar idioma\n/provider - Gestionar proveedor LLM\n/model - Cambiar modelo\n/cron - Gestionar recordatorios\n/termux - Herramientas Termux\n/capabilities - Mostrar capacidades runtime en vivo\n\n¡Solo envía un mensaje para chatear conmigo!",
-        Language::Chinese => "📚 *帮助 - 可用命令*\n\n/start - 显示主菜单\n/menu - 显示主菜单\n/new - 重置对话\n/help - 显示帮助\n/whoiam - 查看用户/聊天ID\n/language - 更改语言\n/provider - 管理LLM提供商\n/model - 更改模型\n/cron - 管理提醒\n/termux - Termux工具\n/capabilities - 显示实时运行能力\n\n只需发送消息与我聊天！",
-        Language::Russian => "📚 *Помощь - Доступные команды*\n\n/start - Показать главное меню\n/menu - Показать главное меню\n/new - Сбросить разговор\n/help - Показать помощь\n/whoiam - Показать ID пользователя/чата\n/language - Сменить язык\n/provider - Управление провайдером\n/model - Изменить модель\n/cron - Напоминания\n/termux - Инструменты Termux\n/capabilities - Показать актуальные runtime-возможности\n\nПросто отправьте сообщение, чтобы пообщаться!",
-        Language::Italian => "📚 *Aiuto - Comandi Disponibili*\n\n/start - Mostra menu principale\n/menu - Mostra menu principale\n/new - Resetta conversazione\n/help - Mostra aiuto\n/whoiam - Mostra ID utente/chat\n/language - Cambia lingua\n/provider - Gestisci provider LLM\n/model - Cambia modello\n/cron - Gestisci promemoria\n/termux - Strumenti Termux\n/capabilities - Mostra capability runtime reali\n\nInvia un messaggio per chiacchierare con me!",
+        Language::English => "📚 *Help - Available Commands*\n\n/start - Show main menu\n/menu - Show main menu\n/new - Reset conversation\n/stop - Stop the running request\n/help - Show this help\n/whoiam - Show user/chat IDs\n/language - Change language\n/provider - Manage LLM provider\n/model - Change model\n/cron - Manage reminders\n/termux - Termux tools\n/capabilities - Show live runtime capabilities\n\nJust send a message to chat with me!",
+        Language::Spanish => "📚 *Ayuda - Comandos Disponibles*\n\n/start - Mostrar menú principal\n/menu - Mostrar menú principal\n/new - Reiniciar conversación\n/stop - Detener la solicitud en curso\n/help - Mostrar esta ayuda\n/whoiam - Mostrar IDs de usuario/chat\n/language - Cambiar idioma\n/provider - Gestionar proveedor LLM\n/model - Cambiar modelo\n/cron - Gestionar recordatorios\n/termux - Herramientas Termux\n/capabilities - Mostrar capacidades runtime en vivo\n\n¡Solo envía un mensaje para chatear conmigo!",
+        Language::Chinese => "📚 *帮助 - 可用命令*\n\n/start - 显示主菜单\n/menu - 显示主菜单\n/new - 重置对话\n/stop - 停止当前请求\n/help - 显示帮助\n/whoiam - 查看用户/聊天ID\n/language - 更改语言\n/provider - 管理LLM提供商\n/model - 更改模型\n/cron - 管理提醒\n/termux - Termux工具\n/capabilities - 显示实时运行能力\n\n只需发送消息与我聊天！",
+        Language::Russian => "📚 *Помощь - Доступные команды*\n\n/start - Показать главное меню\n/menu - Показать главное меню\n/new - Сбросить разговор\n/stop - Остановить текущий запрос\n/help - Показать помощь\n/whoiam - Показать ID пользователя/чата\n/language - Сменить язык\n/provider - Управление провайдером\n/model - Изменить модель\n/cron - Напоминания\n/termux - Инструменты Termux\n/capabilities - Показать актуальные runtime-возможности\n\nПросто отправьте сообщение, чтобы пообщаться!",
+        Language::Italian => "📚 *Aiuto - Comandi Disponibili*\n\n/start - Mostra menu principale\n/menu - Mostra menu principale\n/new - Resetta conversazione\n/stop - Interrompi la richiesta in corso\n/help - Mostra aiuto\n/whoiam - Mostra ID utente/chat\n/language - Cambia lingua\n/provider - Gestisci provider LLM\n/model - Cambia modello\n/cron - Gestisci promemoria\n/termux - Strumenti Termux\n/capabilities - Mostra capability runtime reali\n\nInvia un messaggio per chiacchierare con me!",
     }
     .to_string();
 
     if is_admin {
         let admin_block = match lang {
-            Language::English => "\n\n🛡️ *Admin commands*\n/admin - ACL and user tools\n/plugin - Module keys and catalog\n/mcp - MCP status\n/tools - Runtime tools list\n/exec - Run allowlisted shell command",
-            Language::Spanish => "\n\n🛡️ *Comandos admin*\n/admin - ACL y tools de usuario\n/plugin - Claves de módulos y catálogo\n/mcp - Estado MCP\n/tools - Lista tools runtime\n/exec - Ejecutar comando allowlist",
-            Language::Chinese => "\n\n🛡️ *管理员命令*\n/admin - ACL与用户工具\n/plugin - 模块密钥与目录\n/mcp - MCP状态\n/tools - 运行时工具列表\n/exec - 执行白名单命令",
-            Language::Russian => "\n\n🛡️ *Команды администратора*\n/admin - ACL и инструменты пользователей\n/plugin - Ключи модулей и каталог\n/mcp - Статус MCP\n/tools - Список инструментов runtime\n/exec - Выполнить команду из allowlist",
-            Language::Italian => "\n\n🛡️ *Comandi admin*\n/admin - ACL e tool utenti\n/plugin - Chiavi moduli e catalogo\n/mcp - Stato MCP\n/tools - Lista tool runtime\n/exec - Esegui comando allowlist",
+            Language::English => "\n\n🛡️ *Admin commands*\n/admin - ACL and user tools\n/plugin - Module keys and catalog\n/mcp - MCP status\n/tools - Runtime tools list\n/exec - Run allowlisted shell command\n/jobs - Background exec jobs",
+            Language::Spanish => "\n\n🛡️ *Comandos admin*\n/admin - ACL y tools de usuario\n/plugin - Claves de módulos y catálogo\n/mcp - Estado MCP\n/tools - Lista tools runtime\n/exec - Ejecutar comando allowlist\n/jobs - Jobs exec en segundo plano",
+            Language::Chinese => "\n\n🛡️ *管理员命令*\n/admin - ACL与用户工具\n/plugin - 模块密钥与目录\n/mcp - MCP状态\n/tools - 运行时工具列表\n/exec - 执行白名单命令\n/jobs - 后台执行任务",
+            Language::Russian => "\n\n🛡️ *Команды администратора*\n/admin - ACL и инструменты пользователей\n/plugin - Ключи модулей и каталог\n/mcp - Статус MCP\n/tools - Список инструментов runtime\n/exec - Выполнить команду из allowlist\n/jobs - Фоновые задачи exec",
+            Language::Italian => "\n\n🛡️ *Comandi admin*\n/admin - ACL e tool utenti\n/plugin - Chiavi moduli e catalogo\n/mcp - Stato MCP\n/tools - Lista tool runtime\n/exec - Esegui comando allowlist\n/jobs - Job exec in background",
         };
         text.push_str(admin_block);
     }
@@ -199,21 +199,21 @@ pub fn help_text(lang: Language, is_admin: bool) -> String {
 
 pub fn command_list(lang: Language, is_admin: bool) -> String {
     let mut text = match lang {
-        Language::English => "📋 *Commands*\n\n/start - Main menu\n/menu - Main menu\n/new - Reset session\n/help - Help\n/whoiam - Show user/chat IDs\n/language - Language\n/provider - LLM provider\n/model - Change model\n/cron - Reminders\n/termux - Termux tools\n/capabilities - Live runtime capabilities",
-        Language::Spanish => "📋 *Comandos*\n\n/start - Menú principal\n/menu - Menú principal\n/new - Reiniciar sesión\n/help - Ayuda\n/whoiam - Mostrar IDs usuario/chat\n/language - Idioma\n/provider - Proveedor LLM\n/model - Cambiar modelo\n/cron - Recordatorios\n/termux - Herramientas Termux\n/capabilities - Capacidades runtime en vivo",
-        Language::Chinese => "📋 *命令*\n\n/start - 主菜单\n/menu - 主菜单\n/new - 重置会话\n/help - 帮助\n/whoiam - 查看用户/聊天ID\n/language - 语言\n/provider - LLM提供商\n/model - 更改模型\n/cron - 提醒\n/termux - Termux工具\n/capabilities - 实时运行能力",
-        Language::Russian => "📋 *Команды*\n\n/start - Главное меню\n/menu - Главное меню\n/new - Сброс сессии\n/help - Помощь\n/whoiam - Показать ID пользователя/чата\n/language - Язык\n/provider - Провайдер LLM\n/model - Изменить модель\n/cron - Напоминания\n/termux - Инструменты Termux\n/capabilities - Актуальные runtime-возможности",
-        Language::Italian => "📋 *Comandi*\n\n/start - Menu principale\n/menu - Menu principale\n/new - Reset sessione\n/help - Aiuto\n/whoiam - Mostra ID utente/chat\n/language - Lingua\n/provider - Provider LLM\n/model - Cambia modello\n/cron - Promemoria\n/termux - Strumenti Termux\n/capabilities - Capability runtime reali",
+        Language::English => "📋 *Commands*\n\n/start - Main menu\n/menu - Main menu\n/new - Reset session\n/stop - Stop the running request\n/help - Help\n/whoiam - Show user/chat IDs\n/language - Language\n/provider - LLM provider\n/model - Change model\n/cron - Reminders\n/termux - Termux tools\n/capabilities - Live runtime capabilities",
+        Language::Spanish => "📋 *Comandos*\n\n/start - Menú principal\n/menu - Menú principal\n/new - Reiniciar sesión\n/stop - Detener la solicitud en curso\n/help - Ayuda\n/whoiam - Mostrar IDs usuario/chat\n/language - Idioma\n/provider - Proveedor LLM\n/model - Cambiar modelo\n/cron - Recordatorios\n/termux - Herramientas Termux\n/capabilities - Capacidades runtime en vivo",
+        Language::Chinese => "📋 *命令*\n\n/start - 主菜单\n/menu - 主菜单\n/new - 重置会话\n/stop - 停止当前请求\n/help - 帮助\n/whoiam - 查看用户/聊天ID\n/language - 语言\n/provider - LLM提供商\n/model - 更改模型\n/cron - 提醒\n/termux - Termux工具\n/capabilities - 实时运行能力",
+        Language::Russian => "📋 *Команды*\n\n/start - Главное меню\n/menu - Главное меню\n/new - Сброс сессии\n/stop - Остановить текущий запрос\n/help - Помощь\n/whoiam - Показать ID пользователя/чата\n/language - Язык\n/provider - Провайдер LLM\n/model - Изменить модель\n/cron - Напоминания\n/termux - Инструменты Termux\n/capabilities - Актуальные runtime-возможности",
+        Language::Italian => "📋 *Comandi*\n\n/start - Menu principale\n/menu - Menu principale\n/new - Reset sessione\n/stop - Interrompi la richiesta in corso\n/help - Aiuto\n/whoiam - Mostra ID utente/chat\n/language - Lingua\n/provider - Provider LLM\n/model - Cambia modello\n/cron - Promemoria\n/termux - Strumenti Termux\n/capabilities - Capability runtime reali",
     }
     .to_string();
 
     if is_admin {
         let admin_block = match lang {
-            Language::English => "\n/admin - ACL and user tools\n/plugin - Module keys and catalog\n/mcp - MCP status\n/tools - Runtime tools list\n/exec - Run commands",
-            Language::Spanish => "\n/admin - ACL y tools de usuario\n/plugin - Claves de módulos y catálogo\n/mcp - Estado MCP\n/tools - Lista tools runtime\n/exec - Ejecutar comandos",
-            Language::Chinese => "\n/admin - ACL与用户工具\n/plugin - 模块密钥与目录\n/mcp - MCP状态\n/tools - 运行时工具列表\n/exec - 执行命令",
-            Language::Russian => "\n/admin - ACL и инструменты пользователей\n/plugin - Ключи модулей и каталог\n/mcp - Статус MCP\n/tools - Список инструментов runtime\n/exec - Выполнить команды",
-            Language::Italian => "\n/admin - ACL e tool utenti\n/plugin - Chiavi moduli e catalogo\n/mcp - Stato MCP\n/tools - Lista tool runtime\n/exec - Esegui comandi",
+            Language::English => "\n/admin - ACL and user tools\n/plugin - Module keys and catalog\n/mcp - MCP status\n/tools - Runtime tools list\n/exec - Run commands\n/jobs - Background jobs",
+            Language::Spanish => "\n/admin - ACL y tools de usuario\n/plugin - Claves de módulos y catálogo\n/mcp - Estado MCP\n/tools - Lista tools runtime\n/exec - Ejecutar comandos\n/jobs - Jobs en segundo plano",
+            Language::Chinese => "\n/admin - ACL与用户工具\n/plugin - 模块密钥与目录\n/mcp - MCP状态\n/tools - 运行时工具列表\n/exec - 执行命令\n/jobs - 后台任务",
+            Language::Russian => "\n/admin - ACL и инструменты пользователей\n/plugin - Ключи модулей и каталог\n/mcp - Статус MCP\n/tools - Список инструментов runtime\n/exec - Выполнить команды\n/jobs - Фоновые задачи",
+            Language::Italian => "\n/admin - ACL e tool utenti\n/plugin - Chiavi moduli e catalogo\n/mcp - Stato MCP\n/tools - Lista tool runtime\n/exec - Esegui comandi\n/jobs - Job in background",
         };
         text.push_str(admin_block);
     }
@@ -455,6 +455,9 @@ fn action_message(
         edit_message_id: message_id,
         inline_keyboard: keyboard,
         chat_action: None,
+        photo: None,
+        delivered: None,
+        callback_answer: None,
     }
 }
 
@@ -0,0 +1,910 @@
+//! Masix WASM Tools
+//!
+//! Runs `wasm_tool` plugin modules in wasmi with fuel and memory limits and a
+//! capability-based host API.
+//!
+//! Guest ABI (all strings are UTF-8, results are packed as `(ptr << 32) | len`):
+//! - exports `memory`, `masix_alloc(len: i32) -> i32`
+//! - exports `masix_tools() -> i64`: JSON array of
+//!   `{"name", "description", "parameters"}` tool definitions
+//! - exports `masix_call(name_ptr, name_len, args_ptr, args_len) -> i64`:
+//!   `{"ok": <string|json>}` or `{"error": "<message>"}`
+//!
+//! Host imports (module `masix`, each takes `(ptr, len)` and returns a packed
+//! `{"ok": ..}` / `{"error": ..}` envelope written through `masix_alloc`):
+//! - `http_fetch`: `{"url", "method"?, "headers"?, "body"?}` -> `{"status", "body"}`,
+//!   only for hosts in the plugin's HTTP allowlist, redirects are not followed
+//! - `file_read`: relative path -> content; `file_write`: `{"path", "content"}`,
+//!   both confined to the call's files root (the bot workdir scope)
+//! - `kv_get`: key -> value or null; `kv_set`: `{"key", "value"}` (null deletes)
+//! - `log`: message, returns nothing
+//!
+//! A capability that was not granted answers with an `{"error"}` envelope.
+//! Each host call costs `HOST_CALL_FUEL` and fails once the call deadline
+//! (`WasmLimits::timeout`) has passed, so a guest cannot loop on slow imports.
+//! Every call runs in a fresh instance, so guests keep no state between calls
+//! other than what they store through `kv_set`.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+use wasmi::core::TrapCode;
+use wasmi::{
+    Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    TypedFunc,
+};
+
+const HOST_MODULE: &str = "masix";
+const MAX_GUEST_OUTPUT_BYTES: usize = 1024 * 1024;
+const MAX_HTTP_BODY_BYTES: usize = 1024 * 1024;
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_KV_BYTES: usize = 256 * 1024;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(15);
+/// Fuel charged per host import call, on top of the guest's instructions.
+const HOST_CALL_FUEL: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    /// Instructions budget per call (wasmi fuel units).
+    pub fuel: u64,
+    /// Cap for the guest linear memory.
+    pub max_memory_bytes: usize,
+    /// Wall-clock budget per call, enforced at every host call.
+    pub timeout: Duration,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 50_000_000,
+            max_memory_bytes: 32 * 1024 * 1024,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Host API granted to one plugin. Everything is denied by default.
+#[derive(Debug, Clone, Default)]
+pub struct WasmCapabilities {
+    /// Hosts reachable through `http_fetch`; `*.example.com` also matches subdomains.
+    pub http_allow: Vec<String>,
+    pub files: bool,
+    pub kv: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmToolDef {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_parameters")]
+    pub parameters: Value,
+}
+
+fn default_parameters() -> Value {
+    json!({"type": "object", "properties": {}})
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WasmCallResult {
+    pub text: String,
+    pub is_error: bool,
+}
+
+/// A compiled `wasm_tool` module and the tools it exports.
+pub struct WasmTool {
+    engine: Engine,
+    module: Module,
+    tools: Vec<WasmToolDef>,
+    limits: WasmLimits,
+    capabilities: WasmCapabilities,
+    kv_path: Option<PathBuf>,
+}
+
+struct HostState {
+    limits: StoreLimits,
+    capabilities: WasmCapabilities,
+    files_root: Option<PathBuf>,
+    kv_path: Option<PathBuf>,
+    deadline: Instant,
+    timed_out: bool,
+}
+
+impl WasmTool {
+    /// `kv_path` is the JSON file backing `kv_get`/`kv_set` for this plugin.
+    pub fn load(
+        wasm: &[u8],
+        limits: WasmLimits,
+        capabilities: WasmCapabilities,
+        kv_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module =
+            Module::new(&engine, wasm).map_err(|e| anyhow!("Invalid wasm module: {}", e))?;
+        let mut tool = Self {
+            engine,
+            module,
+            tools: Vec::new(),
+            limits,
+            capabilities,
+            kv_path,
+        };
+
+        let (mut store, instance) = tool.instantiate(None)?;
+        let list: TypedFunc<(), i64> = instance
+            .get_typed_func(&store, "masix_tools")
+            .map_err(|e| anyhow!("Module does not export masix_tools: {}", e))?;
+        let packed = list
+            .call(&mut store, ())
+            .map_err(|e| guest_error(&store, e))?;
+        let raw = read_packed(&store, &instance_memory(&store, &instance)?, packed)?;
+        tool.tools = serde_json::from_slice(&raw).context("masix_tools returned invalid JSON")?;
+        Ok(tool)
+    }
+
+    pub fn from_file(
+        path: &Path,
+        limits: WasmLimits,
+        capabilities: WasmCapabilities,
+        kv_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        let wasm = std::fs::read(path)
+            .with_context(|| format!("Cannot read wasm module {}", path.display()))?;
+        Self::load(&wasm, limits, capabilities, kv_path)
+    }
+
+    pub fn tools(&self) -> &[WasmToolDef] {
+        &self.tools
+    }
+
+    /// Runs `tool` in a fresh instance. Blocking: call it from a blocking
+    /// thread. `files_root` scopes `file_read`/`file_write` for this call.
+    pub fn call(
+        &self,
+        tool: &str,
+        arguments: &Value,
+        files_root: Option<&Path>,
+    ) -> Result<WasmCallResult> {
+        if !self.tools.iter().any(|t| t.name == tool) {
+            anyhow::bail!("Unknown wasm tool: {}", tool);
+        }
+        let (mut store, instance) = self.instantiate(files_root.map(Path::to_path_buf))?;
+        let memory = instance_memory(&store, &instance)?;
+        let alloc = guest_alloc(&store, &instance)?;
+        let call: TypedFunc<(i32, i32, i32, i32), i64> = instance
+            .get_typed_func(&store, "masix_call")
+            .map_err(|e| anyhow!("Module does not export masix_call: {}", e))?;
+
+        let args = serde_json::to_vec(arguments)?;
+        let (name_ptr, name_len) = write_guest(&mut store, &memory, &alloc, tool.as_bytes())?;
+        let (args_ptr, args_len) = write_guest(&mut store, &memory, &alloc, &args)?;
+        let packed = call
+            .call(&mut store, (name_ptr, name_len, args_ptr, args_len))
+            .map_err(|e| guest_error(&store, e))?;
+        debug!(
+            "wasm tool '{}' used {} fuel",
+            tool,
+            self.limits
+                .fuel
+                .saturating_sub(store.get_fuel().unwrap_or_default())
+        );
+
+        let raw = read_packed(&store, &memory, packed)?;
+        let envelope: Value =
+            serde_json::from_slice(&raw).context("masix_call returned invalid JSON")?;
+        if let Some(error) = envelope.get("error") {
+            return Ok(WasmCallResult {
+                text: error
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string()),
+                is_error: true,
+            });
+        }
+        let text = match envelope.get("ok") {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+            None => anyhow::bail!("masix_call result has neither 'ok' nor 'error'"),
+        };
+        Ok(WasmCallResult {
+            text,
+            is_error: false,
+        })
+    }
+
+    fn instantiate(
+        &self,
+        files_root: Option<PathBuf>,
+    ) -> Result<(Store<HostState>, wasmi::Instance)> {
+        let state = HostState {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.limits.max_memory_bytes)
+                .instances(1)
+                .memories(1)
+                .tables(4)
+                .trap_on_grow_failure(true)
+                .build(),
+            capabilities: self.capabilities.clone(),
+            files_root,
+            kv_path: self.kv_path.clone(),
+            deadline: Instant::now() + self.limits.timeout,
+            timed_out: false,
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(self.limits.fuel)
+            .map_err(|e| anyhow!("Cannot set fuel: {}", e))?;
+
+        let mut linker = Linker::<HostState>::new(&self.engine);
+        define_host_api(&mut linker)?;
+        let instance = linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| anyhow!("Cannot instantiate wasm module: {}", e))?;
+        Ok((store, instance))
+    }
+}
+
+type HostCall = fn(&mut Caller<'_, HostState>, &[u8]) -> std::result::Result<Value, String>;
+
+fn define_host_api(linker: &mut Linker<HostState>) -> Result<()> {
+    let calls: [(&str, HostCall); 5] = [
+        ("http_fetch", host_http_fetch),
+        ("file_read", host_file_read),
+        ("file_write", host_file_write),
+        ("kv_get", host_kv_get),
+        ("kv_set", host_kv_set),
+    ];
+    for (name, handler) in calls {
+        linker
+            .func_wrap(
+                HOST_MODULE,
+                name,
+                move |mut caller: Caller<'_, HostState>,
+                      ptr: i32,
+                      len: i32|
+                      -> std::result::Result<i64, wasmi::Error> {
+                    enter_host_call(&mut caller)?;
+                    let input = read_caller(&mut caller, ptr, len)?;
+                    let envelope = match handler(&mut caller, &input) {
+                        Ok(value) => json!({ "ok": value }),
+                        Err(error) => json!({ "error": error }),
+                    };
+                    write_caller(&mut caller, envelope.to_string().as_bytes())
+                },
+            )
+            .map_err(|e| anyhow!("Cannot define host function {}: {}", name, e))?;
+    }
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "log",
+            |mut caller: Caller<'_, HostState>,
+             ptr: i32,
+             len: i32|
+             -> std::result::Result<(), wasmi::Error> {
+                enter_host_call(&mut caller)?;
+                let message = read_caller(&mut caller, ptr, len)?;
+                info!("wasm tool: {}", String::from_utf8_lossy(&message));
+                Ok(())
+            },
+        )
+        .map_err(|e| anyhow!("Cannot define host function log: {}", e))?;
+    Ok(())
+}
+
+/// Charge `HOST_CALL_FUEL` and stop the guest once the call deadline passed.
+fn enter_host_call(caller: &mut Caller<'_, HostState>) -> std::result::Result<(), wasmi::Error> {
+    if Instant::now() >= caller.data().deadline {
+        caller.data_mut().timed_out = true;
+        return Err(wasmi::Error::new("call deadline exceeded"));
+    }
+    let fuel = caller.get_fuel().unwrap_or_default();
+    let remaining = fuel.saturating_sub(HOST_CALL_FUEL);
+    caller
+        .set_fuel(remaining)
+        .map_err(|e| wasmi::Error::new(e.to_string()))?;
+    if fuel < HOST_CALL_FUEL {
+        return Err(TrapCode::OutOfFuel.into());
+    }
+    Ok(())
+}
+
+fn host_http_fetch(
+    caller: &mut Caller<'_, HostState>,
+    input: &[u8],
+) -> std::result::Result<Value, String> {
+    #[derive(Deserialize)]
+    struct FetchRequest {
+        url: String,
+        #[serde(default)]
+        method: Option<String>,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        #[serde(default)]
+        body: Option<String>,
+    }
+
+    let request: FetchRequest =
+        serde_json::from_slice(input).map_err(|e| format!("invalid http_fetch request: {}", e))?;
+    if caller.data().capabilities.http_allow.is_empty() {
+        return Err("capability 'http' not granted".to_string());
+    }
+    if !http_url_allowed(&request.url, &caller.data().capabilities.http_allow) {
+        return Err(format!("host not in http allowlist: {}", request.url));
+    }
+
+    let method = request
+        .method
+        .as_deref()
+        .unwrap_or("GET")
+        .parse::<reqwest::Method>()
+        .map_err(|e| format!("invalid method: {}", e))?;
+    let remaining = caller
+        .data()
+        .deadline
+        .saturating_duration_since(Instant::now());
+    let client = reqwest::blocking::Client::builder()
+        .timeout(HTTP_TIMEOUT.min(remaining))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut builder = client.request(method, &request.url);
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    if let Some(body) = request.body {
+        builder = builder.body(body);
+    }
+    let response = builder.send().map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    let mut bytes = Vec::new();
+    response
+        .take(MAX_HTTP_BODY_BYTES as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    let body = String::from_utf8_lossy(&bytes);
+    Ok(json!({ "status": status, "body": body }))
+}
+
+fn host_file_read(
+    caller: &mut Caller<'_, HostState>,
+    input: &[u8],
+) -> std::result::Result<Value, String> {
+    let path = scoped_file_path(caller.data(), &String::from_utf8_lossy(input), false)?;
+    let size = std::fs::metadata(&path).map_err(|e| e.to_string())?.len();
+    if size > MAX_FILE_BYTES {
+        return Err(format!("file larger than {} bytes", MAX_FILE_BYTES));
+    }
+    std::fs::read_to_string(&path)
+        .map(Value::String)
+        .map_err(|e| e.to_string())
+}
+
+fn host_file_write(
+    caller: &mut Caller<'_, HostState>,
+    input: &[u8],
+) -> std::result::Result<Value, String> {
+    #[derive(Deserialize)]
+    struct WriteRequest {
+        path: String,
+        content: String,
+    }
+
+    let request: WriteRequest =
+        serde_json::from_slice(input).map_err(|e| format!("invalid file_write request: {}", e))?;
+    if request.content.len() as u64 > MAX_FILE_BYTES {
+        return Err(format!("content larger than {} bytes", MAX_FILE_BYTES));
+    }
+    let path = scoped_file_path(caller.data(), &request.path, true)?;
+    std::fs::write(&path, &request.content).map_err(|e| e.to_string())?;
+    Ok(json!(request.content.len()))
+}
+
+fn host_kv_get(
+    caller: &mut Caller<'_, HostState>,
+    input: &[u8],
+) -> std::result::Result<Value, String> {
+    let key = String::from_utf8_lossy(input);
+    let store = load_kv(caller.data())?;
+    Ok(store.get(key.as_ref()).cloned().unwrap_or(Value::Null))
+}
+
+fn host_kv_set(
+    caller: &mut Caller<'_, HostState>,
+    input: &[u8],
+) -> std::result::Result<Value, String> {
+    #[derive(Deserialize)]
+    struct SetRequest {
+        key: String,
+        #[serde(default)]
+        value: Value,
+    }
+
+    let request: SetRequest =
+        serde_json::from_slice(input).map_err(|e| format!("invalid kv_set request: {}", e))?;
+    let mut store = load_kv(caller.data())?;
+    if request.value.is_null() {
+        store.remove(&request.key);
+    } else {
+        store.insert(request.key, request.value);
+    }
+    let encoded = serde_json::to_string(&store).map_err(|e| e.to_string())?;
+    if encoded.len() > MAX_KV_BYTES {
+        return Err(format!("kv store larger than {} bytes", MAX_KV_BYTES));
+    }
+    let path = caller
+        .data()
+        .kv_path
+        .as_ref()
+        .ok_or("kv storage unavailable")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, encoded).map_err(|e| e.to_string())?;
+    Ok(Value::Bool(true))
+}
+
+fn load_kv(state: &HostState) -> std::result::Result<BTreeMap<String, Value>, String> {
+    if !state.capabilities.kv {
+        return Err("capability 'kv' not granted".to_string());
+    }
+    let path = state.kv_path.as_ref().ok_or("kv storage unavailable")?;
+    match std::fs::read_to_string(path) {
+        Ok(raw) => serde_json::from_str(&raw).map_err(|e| format!("corrupt kv store: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Resolves `relative` under the files root without following symlinks out
+/// of it; `create_parents` makes missing directories (for `file_write`).
+fn scoped_file_path(
+    state: &HostState,
+    relative: &str,
+    create_parents: bool,
+) -> std::result::Result<PathBuf, String> {
+    if !state.capabilities.files {
+        return Err("capability 'files' not granted".to_string());
+    }
+    let root = state
+        .files_root
+        .as_ref()
+        .ok_or("no workdir available for file access")?;
+    let relative = Path::new(relative.trim());
+    let confined = relative.components().next().is_some()
+        && relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if !confined {
+        return Err(format!(
+            "path must be relative to the plugin workdir: {}",
+            relative.display()
+        ));
+    }
+    let target = root.join(relative);
+    let (Some(parent), Some(name)) = (target.parent(), target.file_name()) else {
+        return Err(format!("invalid path: {}", relative.display()));
+    };
+    if create_parents {
+        std::fs::create_dir_all(root).map_err(|e| e.to_string())?;
+    }
+    let root = root.canonicalize().map_err(|e| e.to_string())?;
+    let escapes = |dir: &Path| -> std::result::Result<bool, String> {
+        Ok(!dir
+            .canonicalize()
+            .map_err(|e| e.to_string())?
+            .starts_with(&root))
+    };
+    let outside = || format!("path escapes the plugin workdir: {}", relative.display());
+    if create_parents {
+        // Check the deepest existing ancestor before creating anything below it.
+        let existing = parent
+            .ancestors()
+            .find(|dir| dir.exists())
+            .unwrap_or(root.as_path());
+        if escapes(existing)? {
+            return Err(outside());
+        }
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    if escapes(parent)? {
+        return Err(outside());
+    }
+    let resolved = parent.canonicalize().map_err(|e| e.to_string())?.join(name);
+    if resolved
+        .symlink_metadata()
+        .is_ok_and(|meta| meta.file_type().is_symlink())
+    {
+        return Err(outside());
+    }
+    Ok(resolved)
+}
+
+/// `example.com` matches only that host, `*.example.com` also its subdomains.
+pub fn http_url_allowed(url: &str, allowlist: &[String]) -> bool {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return false;
+    };
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return false;
+    }
+    let Some(host) = parsed.host_str().map(str::to_ascii_lowercase) else {
+        return false;
+    };
+    allowlist.iter().any(|entry| {
+        let entry = entry.trim().to_ascii_lowercase();
+        match entry.strip_prefix("*.") {
+            Some(domain) => host == domain || host.ends_with(&format!(".{}", domain)),
+            None => host == entry,
+        }
+    })
+}
+
+fn guest_error(store: &Store<HostState>, error: wasmi::Error) -> anyhow::Error {
+    if store.data().timed_out {
+        return anyhow!("wasm tool exceeded its time limit");
+    }
+    match error.as_trap_code() {
+        Some(TrapCode::OutOfFuel) => anyhow!("wasm tool exceeded its fuel limit"),
+        Some(TrapCode::GrowthOperationLimited) => anyhow!("wasm tool exceeded its memory limit"),
+        _ => anyhow!("wasm tool trapped: {}", error),
+    }
+}
+
+fn instance_memory(store: &Store<HostState>, instance: &wasmi::Instance) -> Result<Memory> {
+    instance
+        .get_memory(store, "memory")
+        .ok_or_else(|| anyhow!("Module does not export its memory"))
+}
+
+fn guest_alloc(
+    store: &Store<HostState>,
+    instance: &wasmi::Instance,
+) -> Result<TypedFunc<i32, i32>> {
+    instance
+        .get_typed_func(store, "masix_alloc")
+        .map_err(|e| anyhow!("Module does not export masix_alloc: {}", e))
+}
+
+fn write_guest(
+    store: &mut Store<HostState>,
+    memory: &Memory,
+    alloc: &TypedFunc<i32, i32>,
+    bytes: &[u8],
+) -> Result<(i32, i32)> {
+    let len = i32::try_from(bytes.len()).map_err(|_| anyhow!("input too large"))?;
+    let ptr = alloc
+        .call(&mut *store, len)
+        .map_err(|e| guest_error(store, e))?;
+    memory
+        .write(&mut *store, ptr as u32 as usize, bytes)
+        .map_err(|e| anyhow!("masix_alloc returned an invalid buffer: {}", e))?;
+    Ok((ptr, len))
+}
+
+fn unpack(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+fn read_packed(store: &Store<HostState>, memory: &Memory, packed: i64) -> Result<Vec<u8>> {
+    let (ptr, len) = unpack(packed);
+    if len > MAX_GUEST_OUTPUT_BYTES {
+        anyhow::bail!(
+            "wasm tool output larger than {} bytes",
+            MAX_GUEST_OUTPUT_BYTES
+        );
+    }
+    let mut buffer = vec![0u8; len];
+    memory
+        .read(store, ptr, &mut buffer)
+        .map_err(|e| anyhow!("wasm tool returned an invalid buffer: {}", e))?;
+    Ok(buffer)
+}
+
+fn caller_memory(caller: &Caller<'_, HostState>) -> std::result::Result<Memory, wasmi::Error> {
+    match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => Ok(memory),
+        _ => Err(wasmi::Error::new("module does not export its memory")),
+    }
+}
+
+fn read_caller(
+    caller: &mut Caller<'_, HostState>,
+    ptr: i32,
+    len: i32,
+) -> std::result::Result<Vec<u8>, wasmi::Error> {
+    let len = len as u32 as usize;
+    if len > MAX_GUEST_OUTPUT_BYTES {
+        return Err(wasmi::Error::new("host call input too large"));
+    }
+    let memory = caller_memory(caller)?;
+    let mut buffer = vec![0u8; len];
+    memory
+        .read(&*caller, ptr as u32 as usize, &mut buffer)
+        .map_err(|e| wasmi::Error::new(format!("invalid guest buffer: {}", e)))?;
+    Ok(buffer)
+}
+
+fn write_caller(
+    caller: &mut Caller<'_, HostState>,
+    bytes: &[u8],
+) -> std::result::Result<i64, wasmi::Error> {
+    let alloc = match caller.get_export("masix_alloc") {
+        Some(Extern::Func(func)) => func.typed::<i32, i32>(&*caller)?,
+        _ => return Err(wasmi::Error::new("module does not export masix_alloc")),
+    };
+    let len = i32::try_from(bytes.len()).map_err(|_| wasmi::Error::new("response too large"))?;
+    let ptr = alloc.call(&mut *caller, len)?;
+    let memory = caller_memory(caller)?;
+    memory
+        .write(&mut *caller, ptr as u32 as usize, bytes)
+        .map_err(|e| wasmi::Error::new(format!("invalid guest buffer: {}", e)))?;
+    Ok(((ptr as u32 as i64) << 32) | len as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bump allocator plus a `masix_tools` export listing a single tool.
+    const PRELUDE: &str = r#"
+        (memory (export "memory") 1)
+        (global $next (mut i32) (i32.const 4096))
+        (func (export "masix_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+        (data (i32.const 0) "[{\"name\":\"demo\",\"description\":\"Demo tool\",\"parameters\":{\"type\":\"object\"}}]")
+        (func (export "masix_tools") (result i64)
+            (i64.const 74))
+    "#;
+
+    fn module(body: &str) -> Vec<u8> {
+        wat::parse_str(format!(
+            "(module {} {})",
+            HOST_IMPORTS,
+            PRELUDE.to_string() + body
+        ))
+        .unwrap()
+    }
+
+    const HOST_IMPORTS: &str = r#"
+        (import "masix" "kv_set" (func $kv_set (param i32 i32) (result i64)))
+        (import "masix" "kv_get" (func $kv_get (param i32 i32) (result i64)))
+        (import "masix" "file_write" (func $file_write (param i32 i32) (result i64)))
+        (import "masix" "file_read" (func $file_read (param i32 i32) (result i64)))
+        (import "masix" "log" (func $log (param i32 i32)))
+    "#;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "masix-wasm-{}-{}-{}",
+            name,
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn lists_tools_and_echoes_arguments() {
+        // Returns its arguments: `{"ok": ...}` in, same envelope out.
+        let wasm = module(
+            r#"(func (export "masix_call") (param i32 i32 i32 i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get 2)) (i64.const 32))
+                    (i64.extend_i32_u (local.get 3))))"#,
+        );
+        let tool = WasmTool::load(&wasm, WasmLimits::default(), Default::default(), None).unwrap();
+        assert_eq!(tool.tools().len(), 1);
+        assert_eq!(tool.tools()[0].name, "demo");
+
+        let result = tool.call("demo", &json!({"ok": "pong"}), None).unwrap();
+        assert_eq!(
+            result,
+            WasmCallResult {
+                text: "pong".to_string(),
+                is_error: false
+            }
+        );
+        let failed = tool.call("demo", &json!({"error": "boom"}), None).unwrap();
+        assert!(failed.is_error);
+        assert_eq!(failed.text, "boom");
+        assert!(tool.call("missing", &json!({}), None).is_err());
+    }
+
+    #[test]
+    fn fuel_and_memory_limits_stop_runaway_guests() {
+        let spin = module(
+            r#"(func (export "masix_call") (param i32 i32 i32 i32) (result i64)
+                (loop $forever (br $forever))
+                (i64.const 0))"#,
+        );
+        let limits = WasmLimits {
+            fuel: 100_000,
+            ..WasmLimits::default()
+        };
+        let tool = WasmTool::load(&spin, limits, Default::default(), None).unwrap();
+        let error = tool.call("demo", &json!({}), None).unwrap_err().to_string();
+        assert!(error.contains("fuel"), "{}", error);
+
+        let grow = module(
+            r#"(func (export "masix_call") (param i32 i32 i32 i32) (result i64)
+                (drop (memory.grow (i32.const 64)))
+                (i64.const 0))"#,
+        );
+        let limits = WasmLimits {
+            max_memory_bytes: 2 * 65536,
+            ..WasmLimits::default()
+        };
+        let tool = WasmTool::load(&grow, limits, Default::default(), None).unwrap();
+        let error = tool.call("demo", &json!({}), None).unwrap_err().to_string();
+        assert!(error.contains("memory limit"), "{}", error);
+    }
+
+    #[test]
+    fn looping_host_calls_hit_the_deadline_and_cost_fuel() {
+        let spin = module(
+            r#"(data (i32.const 200) "tick")
+            (func (export "masix_call") (param i32 i32 i32 i32) (result i64)
+                (loop $forever
+                    (call $log (i32.const 200) (i32.const 4))
+                    (br $forever))
+                (i64.const 0))"#,
+        );
+        let limits = WasmLimits {
+            fuel: u64::MAX / 2,
+            timeout: Duration::from_millis(200),
+            ..WasmLimits::default()
+        };
+        let tool = WasmTool::load(&spin, limits, Default::default(), None).unwrap();
+        let started = Instant::now();
+        let error = tool.call("demo", &json!({}), None).unwrap_err().to_string();
+        assert!(error.contains("time limit"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let limits = WasmLimits {
+            fuel: HOST_CALL_FUEL * 5,
+            timeout: Duration::from_secs(60),
+            ..WasmLimits::default()
+        };
+        let tool = WasmTool::load(&spin, limits, Default::default(), None).unwrap();
+        let error = tool.call("demo", &json!({}), None).unwrap_err().to_string();
+        assert!(error.contains("fuel"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_access_does_not_follow_symlinks_out_of_the_root() {
+        let dir = temp_dir("symlink");
+        let root = dir.join("root");
+        let outside = dir.join("outside");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), root.join("link")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("sub")).unwrap();
+        std::fs::write(root.join("own.txt"), "mine").unwrap();
+
+        let files = WasmCapabilities {
+            files: true,
+            ..Default::default()
+        };
+        let read = |path: &str| {
+            let wasm = module(&format!(
+                r#"(data (i32.const 200) "{}")
+                (func (export "masix_call") (param i32 i32 i32 i32) (result i64)
+                    (call $file_read (i32.const 200) (i32.const {})))"#,
+                path,
+                path.len()
+            ));
+            WasmTool::load(&wasm, WasmLimits::default(), files.clone(), None)
+                .unwrap()
+                .call("demo", &json!({}), Some(&root))
+                .unwrap()
+        };
+        assert_eq!(read("own.txt").text, "mine");
+        for escape in ["link", "sub/secret.txt"] {
+            let result = read(escape);
+            assert!(result.is_error, "{}", escape);
+            assert!(result.text.contains("escapes"), "{}", result.text);
+        }
+
+        let write = module(
+            r#"(data (i32.const 200) "{\"path\":\"sub/new/x.txt\",\"content\":\"x\"}")
+            (func (export "masix_call") (param i32 i32 i32 i32) (result i64)
+                (call $file_write (i32.const 200) (i32.const 38)))"#,
+        );
+        let result = WasmTool::load(&write, WasmLimits::default(), files.clone(), None)
+            .unwrap()
+            .call("demo", &json!({}), Some(&root))
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.text.contains("escapes"), "{}", result.text);
+        assert!(!outside.join("new").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn host_capabilities_are_denied_unless_granted() {
+        // kv_set({"key":"n","value":7}) then return kv_get("n") verbatim.
+        let kv = module(
+            r#"(data (i32.const 200) "{\"key\":\"n\",\"value\":7}n")
+            (func (export "masix_call") (param i32 i32 i32 i32) (result i64)
+                (drop (call $kv_set (i32.const 200) (i32.const 21)))
+                (call $kv_get (i32.const 221) (i32.const 1)))"#,
+        );
+        let dir = temp_dir("kv");
+        let kv_path = dir.join("kv.json");
+
+        let denied = WasmTool::load(
+            &kv,
+            WasmLimits::default(),
+            Default::default(),
+            Some(kv_path.clone()),
+        )
+        .unwrap()
+        .call("demo", &json!({}), None)
+        .unwrap();
+        assert!(denied.is_error);
+        assert!(denied.text.contains("'kv' not granted"));
+
+        let granted = WasmCapabilities {
+            kv: true,
+            ..Default::default()
+        };
+        let tool =
+            WasmTool::load(&kv, WasmLimits::default(), granted, Some(kv_path.clone())).unwrap();
+        assert_eq!(tool.call("demo", &json!({}), None).unwrap().text, "7");
+        assert!(std::fs::read_to_string(&kv_path)
+            .unwrap()
+            .contains("\"n\":7"));
+
+        // file_write with a path escaping the workdir is refused.
+        let escape = module(
+            r#"(data (i32.const 200) "{\"path\":\"../out.txt\",\"content\":\"x\"}")
+            (func (export "masix_call") (param i32 i32 i32 i32) (result i64)
+                (call $file_write (i32.const 200) (i32.const 35)))"#,
+        );
+        let files = WasmCapabilities {
+            files: true,
+            ..Default::default()
+        };
+        let result = WasmTool::load(&escape, WasmLimits::default(), files, None)
+            .unwrap()
+            .call("demo", &json!({}), Some(&dir))
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.text.contains("relative to the plugin workdir"));
+        assert!(!dir.parent().unwrap().join("out.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn http_allowlist_matches_exact_hosts_and_wildcard_subdomains() {
+        let allow = vec!["api.example.com".to_string(), "*.weather.io".to_string()];
+        assert!(http_url_allowed("https://api.example.com/v1", &allow));
+        assert!(!http_url_allowed("https://evil.example.com/", &allow));
+        assert!(http_url_allowed("https://eu.weather.io/now", &allow));
+        assert!(http_url_allowed("https://weather.io/", &allow));
+        assert!(!http_url_allowed("https://weather.io.evil.com/", &allow));
+        assert!(!http_url_allowed("file:///etc/passwd", &allow));
+    }
+}
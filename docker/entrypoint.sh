@@ -82,7 +82,12 @@ EOF
 }
 
 ensure_memory_files() {
-  mkdir -p "$MASIX_DATA_DIR" "$MASIX_DATA_DIR/memory/custom"
+  mkdir -p "$MASIX_DATA_DIR" "$MASIX_DATA_DIR/memory/custom" "$MASIX_DATA_DIR/memory/knowledge"
+  for file in /opt/masix/memory/knowledge/*; do
+    [ -f "$file" ] || continue
+    target="$MASIX_DATA_DIR/memory/knowledge/$(basename "$file")"
+    [ -f "$target" ] || cp "$file" "$target"
+  done
   if [ ! -f "$MASIX_DATA_DIR/SOUL.md" ]; then
     cp /opt/masix/memory/SOUL.md "$MASIX_DATA_DIR/SOUL.md"
   fi
@@ -107,6 +112,13 @@ ensure_memory_files() {
   ln -snf "$account_memory_file" "$MASIX_DATA_DIR/MEMORY.current.md"
 }
 
+ingest_knowledge_files() {
+  # Already indexed documents are skipped (content hash), so this is safe on every boot.
+  if ! masix --config "$MASIX_CONFIG_FILE" kb ingest "$MASIX_DATA_DIR/memory/knowledge" >/dev/null 2>&1; then
+    log "Knowledge base ingestion reported errors (see: masix kb ingest)"
+  fi
+}
+
 build_config() {
   : "${MASIX_TELEGRAM_BOT_TOKEN:?MASIX_TELEGRAM_BOT_TOKEN is required}"
   : "${MASIX_ADMIN_IDS:?MASIX_ADMIN_IDS is required}"
@@ -244,6 +256,7 @@ EOF
 main() {
   build_config
   ensure_memory_files
+  ingest_knowledge_files
   log "MasiX Telegram assistant config generated at $MASIX_CONFIG_FILE"
   exec masix start --foreground --config "$MASIX_CONFIG_FILE"
 }